@@ -8,6 +8,7 @@
 
 let wasm = null;
 let paused = false;
+let timeWarp = 1;
 let clockRafId = null;
 let scrubberIntervalId = null;
 
@@ -17,7 +18,7 @@ const TLE_URL = '/api/tle/gnss';
 const TLE_CACHE_KEY = TLE_URL;
 const TLE_TTL_MS = 12 * 60 * 60 * 1000; // 12 hours
 
-const CONSTELLATION_IDS = ['gps', 'glonass', 'galileo', 'beidou', 'qzss', 'navic', 'other'];
+const CONSTELLATION_IDS = ['gps', 'glonass', 'galileo', 'beidou', 'qzss', 'navic', 'other', 'sbas'];
 
 // current observer position (readable by getObserverLatLon)
 let observerLat = CHICAGO_LAT;
@@ -37,17 +38,24 @@ export function initHud(wasmModule) {
   wasm = wasmModule;
 
   wireConstellationToggles();
+  wireOrbitClassToggles();
   wireVisibleOnlyToggle();
   wireElevMaskSlider();
   wireOverlayToggles();
   wireGroundLocation();
+  wireWalkerControls();
+  wireRinexControls();
   wireTimeControls();
+  wireShareButton();
+  const fromLink = applyScenarioFromHash();
+  window.addEventListener('hashchange', applyScenarioFromHash);
   startClockDisplay();
   startScrubberSync();
   startPrnListUpdater();
+  watchTleGeneration();
 
-  // async work — do not block caller
-  fetchAndApplyGeolocation();
+  // async work — do not block caller; a shared link fixes the observer
+  if (!fromLink) fetchAndApplyGeolocation();
   fetchAndInjectTles();
   fetchAndInjectBorders();
 }
@@ -72,6 +80,18 @@ function wireConstellationToggles() {
   });
 }
 
+// ─── orbit-type toggles (BeiDou MEO/IGSO/GEO, QZSS QZO/GEO, NavIC GSO/GEO) ───
+
+function wireOrbitClassToggles() {
+  document.querySelectorAll('input[data-orbit-class]').forEach((el) => {
+    const idx = Number(el.dataset.constIdx);
+    const cls = Number(el.dataset.orbitClass);
+    el.addEventListener('change', () => {
+      if (wasm.toggle_orbit_class) wasm.toggle_orbit_class(idx, cls, el.checked);
+    });
+  });
+}
+
 // ─── visible-only toggle ─────────────────────────────────────────────────────
 
 function wireVisibleOnlyToggle() {
@@ -104,6 +124,8 @@ function wireOverlayToggles() {
     { id: 'toggle-eci-axes', fn: 'set_show_eci_axes' },
     { id: 'toggle-borders', fn: 'set_show_borders' },
     { id: 'toggle-elev-cone', fn: 'set_show_elev_cone' },
+    { id: 'toggle-ground-tracks', fn: 'set_show_ground_tracks' },
+    { id: 'toggle-point-sprites', fn: 'set_point_sprites' },
   ];
   for (const { id, fn } of toggles) {
     const el = document.getElementById(id);
@@ -131,7 +153,7 @@ function wireOverlayToggles() {
 //   NavIC:   "IRNSS-1A"                → I01   (letter suffix A=1, B=2, …)
 // Falls back to prefix + (fallbackN+1) when no number can be extracted.
 export function satShortLabel(name, constellation, fallbackN) {
-  const PREFIXES = ['G', 'R', 'E', 'C', 'J', 'I', 'A']; // A = Augmentation (SBAS/other)
+  const PREFIXES = ['G', 'R', 'E', 'C', 'J', 'I', '?', 'S']; // S = SBAS (RINEX letter)
   const prefix = PREFIXES[constellation] ?? '?';
   if (name) {
     // GPS: "(PRN 25)" → G25
@@ -166,7 +188,7 @@ export function satShortLabel(name, constellation, fallbackN) {
 function startPrnListUpdater() {
   const container = document.getElementById('prn-list');
   if (!container) return;
-  const CONST_COLORS = ['#39ff14', '#ff4444', '#00ffcc', '#ffaa00', '#a050ff', '#ff50a0', '#808080'];
+  const CONST_COLORS = ['#39ff14', '#ff4444', '#00ffcc', '#ffaa00', '#a050ff', '#ff50a0', '#808080', '#ffff78'];
   setInterval(() => {
     let sats;
     try {
@@ -175,22 +197,27 @@ function startPrnListUpdater() {
       return;
     }
     if (!Array.isArray(sats)) return;
-    // Group by constellation
+    // Group by constellation; Walker satellites group by generator
     const byConst = {};
     for (const sat of sats) {
-      const c = sat.constellation;
+      const c = sat.walker ? `w:${sat.walker}` : sat.constellation;
       if (!byConst[c]) byConst[c] = [];
       byConst[c].push(sat);
     }
     let html = '';
     for (const [c, group] of Object.entries(byConst)) {
       const ci = Number(c);
-      const color = CONST_COLORS[ci] || '#808080';
+      const w = group[0].walker;
+      const color = w ? `rgb(${group[0].r},${group[0].g},${group[0].b})` : CONST_COLORS[ci] || '#808080';
       // Sort by elevation descending
       group.sort((a, b) => b.el_deg - a.el_deg);
       const boxes = group
         .map((sat, i) => {
-          const label = satShortLabel(sat.name, ci, i);
+          const label = w
+            ? sat.sv_id == null
+              ? '--'
+              : String(sat.sv_id % 100).padStart(2, '0')
+            : satShortLabel(sat.name, ci, i);
           return `<span style="display:inline-block;background:${color};color:#000;width:18px;height:18px;line-height:18px;text-align:center;font-size:0.6rem;font-weight:600;margin:1px 1px 1px 0;border-radius:2px">${label}</span>`;
         })
         .join('');
@@ -200,6 +227,86 @@ function startPrnListUpdater() {
   }, 1000);
 }
 
+// ─── Walker constellation generator ──────────────────────────────────────────
+
+const WALKER_COLORS = [[255, 255, 255], [255, 200, 120], [120, 200, 255], [200, 255, 120]];
+let walkerCount = 0;
+
+function wireWalkerControls() {
+  const addBtn = document.getElementById('btn-add-walker');
+  const clearBtn = document.getElementById('btn-clear-walkers');
+  if (!addBtn || !wasm.add_walker) return;
+  addBtn.addEventListener('click', () => {
+    const notation = document.getElementById('walker-notation')?.value.trim();
+    const alt = Number(document.getElementById('walker-alt')?.value);
+    const def = {
+      name: `W${walkerCount + 1}`,
+      notation,
+      alt_km: alt,
+      color: WALKER_COLORS[walkerCount % WALKER_COLORS.length],
+    };
+    if (wasm.add_walker(JSON.stringify(def)) < 0) {
+      console.warn('[gnss] invalid Walker constellation', def);
+      return;
+    }
+    walkerCount++;
+  });
+  clearBtn?.addEventListener('click', () => {
+    wasm.clear_walkers();
+    walkerCount = 0;
+  });
+}
+
+// ─── RINEX availability ──────────────────────────────────────────────────────
+//
+// A loaded RINEX 3 observation file is compared epoch by epoch with the
+// predicted sky; get_rinex_availability() is computed once per file in WASM,
+// so the table is only rendered on load.
+
+const RINEX_PREFIXES = ['G', 'R', 'E', 'C', 'J', 'I', '?', 'S'];
+
+function wireRinexControls() {
+  const input = document.getElementById('rinex-file');
+  const clearBtn = document.getElementById('btn-clear-rinex');
+  if (!input || !wasm.load_rinex_obs) return;
+  input.addEventListener('change', async () => {
+    const file = input.files?.[0];
+    if (!file) return;
+    if (wasm.load_rinex_obs(await file.text()) === 0) {
+      console.warn('[gnss-hud] not a RINEX 3 observation file:', file.name);
+      renderRinexAvailability(null);
+      return;
+    }
+    renderRinexAvailability(wasm.get_rinex_availability());
+  });
+  clearBtn?.addEventListener('click', () => {
+    wasm.clear_rinex_obs();
+    input.value = '';
+    renderRinexAvailability(null);
+  });
+}
+
+function renderRinexAvailability(stats) {
+  const container = document.getElementById('rinex-availability');
+  if (!container) return;
+  if (!Array.isArray(stats)) {
+    container.innerHTML = '';
+    return;
+  }
+  const rows = stats
+    .filter((s) => s.epochs_predicted > 0 || s.epochs_tracked > 0)
+    .sort((a, b) => a.constellation - b.constellation || a.sv_id - b.sv_id)
+    .map((s) => {
+      const prn = (RINEX_PREFIXES[s.constellation] ?? '?') + String(s.sv_id).padStart(2, '0');
+      const pct = `${Math.round(s.availability * 100)}%`.padStart(4, '\u00a0');
+      const snr = s.mean_s_dbhz == null ? '' : ` · ${s.mean_s_dbhz.toFixed(1)} dB-Hz`;
+      const title = `predicted ${s.epochs_predicted} · tracked ${s.epochs_tracked} · both ${s.epochs_both}`;
+      return `<div title="${title}">${prn} ${pct}${snr}</div>`;
+    })
+    .join('');
+  container.innerHTML = rows || '<span style="color:#3a6a3a">no predicted sats</span>';
+}
+
 // ─── ground location ─────────────────────────────────────────────────────────
 
 function wireGroundLocation() {
@@ -304,6 +411,11 @@ function sliderToWarp(v) {
   return Math.max(1, Math.round(Math.pow(10, (v * 9) / 100)));
 }
 
+/** Inverse of sliderToWarp. */
+function warpToSlider(w) {
+  return Math.min(100, Math.max(0, (Math.log10(Math.max(1, w)) * 100) / 9));
+}
+
 /** Human-readable time-rate label for a given warp multiplier. */
 function warpLabel(w) {
   if (w < 60) return `${w}×`;
@@ -320,11 +432,13 @@ function wireTimeWarpSlider() {
   if (!slider) return;
   // Push the slider's current HTML default into WASM state immediately
   const initial = sliderToWarp(Number(slider.value));
+  timeWarp = initial;
   wasm.set_time_warp(initial);
   if (label) label.textContent = warpLabel(initial);
   // Update WASM state on every slider change
   slider.addEventListener('input', () => {
     const w = sliderToWarp(Number(slider.value));
+    timeWarp = w;
     wasm.set_time_warp(w);
     if (label) label.textContent = warpLabel(w);
   });
@@ -381,6 +495,216 @@ function startScrubberSync() {
   }, 2000);
 }
 
+// ─── scenarios / shared links ────────────────────────────────────────────────
+//
+// The whole view — observer, clock, masks, toggles, overlays, Walker
+// constellations, camera — round-trips through export_scenario_fragment()
+// as `#scenario=…`. The page always loads its own TLE and border data, so
+// the scenario's data_sources are informational here.
+
+const SCENARIO_HASH_PREFIX = '#scenario=';
+
+function wireShareButton() {
+  const btn = document.getElementById('btn-share');
+  if (!btn || !wasm.export_scenario_fragment) return;
+  btn.addEventListener('click', async () => {
+    const url = shareScenarioLink();
+    try {
+      await navigator.clipboard.writeText(url);
+      flashButton(btn, 'copied');
+    } catch {
+      flashButton(btn, 'in url');
+    }
+  });
+}
+
+function flashButton(btn, text) {
+  const prev = btn.textContent;
+  btn.textContent = text;
+  setTimeout(() => (btn.textContent = prev), 1200);
+}
+
+/**
+ * Put the current view in the address bar (without reloading or adding a
+ * history entry) and return the full link.
+ * @returns {string}
+ */
+export function shareScenarioLink() {
+  const hash = '#' + wasm.export_scenario_fragment();
+  history.replaceState(null, '', hash);
+  return location.href;
+}
+
+/**
+ * Import `#scenario=…` from the address bar, if present, and bring the HUD
+ * controls in line with it. Returns true when a scenario was applied.
+ * @returns {boolean}
+ */
+function applyScenarioFromHash() {
+  if (!location.hash.startsWith(SCENARIO_HASH_PREFIX) || !wasm.import_scenario_fragment) return false;
+  if (!wasm.import_scenario_fragment(location.hash)) {
+    console.warn('[gnss-hud] ignoring invalid scenario link');
+    return false;
+  }
+  syncHudFromScenario(JSON.parse(wasm.export_scenario()));
+  return true;
+}
+
+function syncHudFromScenario(sc) {
+  observerLat = sc.observer.lat_deg;
+  observerLon = sc.observer.lon_deg;
+  locationManuallySet = true;
+  updateLocationInputs(observerLat, observerLon);
+
+  paused = sc.paused;
+  const btnPause = document.getElementById('btn-pause');
+  if (btnPause) btnPause.textContent = paused ? '▶' : '⏸';
+
+  timeWarp = sc.time_warp;
+  const warpSlider = document.getElementById('time-warp-slider');
+  if (warpSlider) warpSlider.value = String(warpToSlider(timeWarp));
+  const warpValue = document.getElementById('time-warp-value');
+  if (warpValue) warpValue.textContent = warpLabel(timeWarp);
+
+  const maskSlider = document.getElementById('elev-mask-slider');
+  if (maskSlider) maskSlider.value = String(sc.elev_mask_deg);
+  const maskValue = document.getElementById('elev-mask-value');
+  if (maskValue) maskValue.textContent = `${sc.elev_mask_deg}°`;
+
+  const setChecked = (id, on) => {
+    const el = document.getElementById(id);
+    if (el) el.checked = on;
+  };
+  CONSTELLATION_IDS.forEach((name, idx) => setChecked(`toggle-${name}`, sc.constellations[idx]));
+  document.querySelectorAll('input[data-orbit-class]').forEach((el) => {
+    el.checked = sc.orbit_classes[Number(el.dataset.constIdx)][Number(el.dataset.orbitClass)];
+  });
+  setChecked('toggle-visible-only', sc.visible_only);
+  setChecked('toggle-inc-rings', sc.overlays.inc_rings);
+  setChecked('toggle-ecef-axes', sc.overlays.ecef_axes);
+  setChecked('toggle-eci-axes', sc.overlays.eci_axes);
+  setChecked('toggle-borders', sc.overlays.borders);
+  setChecked('toggle-elev-cone', sc.overlays.elev_cone);
+  setChecked('toggle-ground-tracks', sc.overlays.ground_tracks);
+  setChecked('toggle-point-sprites', sc.overlays.point_sprites);
+
+  walkerCount = sc.walkers.length;
+}
+
+// ─── propagation worker ──────────────────────────────────────────────────────
+//
+// SGP4 runs in gnss-prop-worker.js; the page requests a position+velocity
+// snapshot slightly ahead of sim time every get_propagation_interval_ms()
+// (longer for bigger catalogues) and WASM Hermite-interpolates between
+// snapshots each frame. One request is in flight at a time, so a single
+// buffer is enough: a SharedArrayBuffer when the page is cross-origin
+// isolated, otherwise a Float64Array transferred back and forth. If the
+// worker can't start, WASM keeps propagating on the main thread.
+//
+// Whatever replaces the store (the TLE fetch, inject_tle_text, a console
+// call) bumps get_tle_generation(); a watcher reloads the worker from
+// get_tle_source() and every message carries the generation it belongs to,
+// so push_position_sample() drops samples of a replaced store.
+
+const PROP_WORKER_URL = '/assets/js/gnss-prop-worker.js';
+const PROP_LEAD_INTERVALS = 2.5; // request this many intervals of sim time ahead
+const PROP_WATCH_MS = 1000; // store-replacement poll
+
+let propWorker = null;
+let propBuf = null;
+let propShared = false;
+let propPending = false;
+let propTimerId = null;
+let propGen = -1; // store generation the worker holds; replies from older ones are ignored
+let propDisabled = false; // the worker failed; stay on the main thread
+
+function watchTleGeneration() {
+  if (!wasm.get_tle_generation) return;
+  setInterval(() => {
+    if (!propDisabled && wasm.get_tle_count() > 0 && wasm.get_tle_generation() !== propGen) {
+      startPropagationWorker();
+    }
+  }, PROP_WATCH_MS);
+}
+
+function startPropagationWorker() {
+  if (propDisabled || typeof Worker === 'undefined' || !wasm.push_position_sample) return;
+  if (!propWorker) {
+    try {
+      propWorker = new Worker(PROP_WORKER_URL, { type: 'module' });
+    } catch (e) {
+      console.warn('[gnss-hud] propagation worker unavailable:', e);
+      return;
+    }
+    propWorker.onmessage = onPropWorkerMessage;
+    propWorker.onerror = (e) => {
+      console.warn('[gnss-hud] propagation worker failed, propagating on main thread:', e.message);
+      stopPropagationWorker();
+    };
+  }
+  propGen = wasm.get_tle_generation();
+  const count = wasm.get_tle_count();
+  clearTimeout(propTimerId);
+  propPending = false;
+  propShared = self.crossOriginIsolated === true && typeof SharedArrayBuffer !== 'undefined';
+  propBuf = new Float64Array(propShared ? new SharedArrayBuffer(count * 6 * 8) : count * 6);
+  propWorker.postMessage({
+    type: 'tles',
+    gen: propGen,
+    text: wasm.get_tle_source(),
+    shared: propShared ? propBuf.buffer : null,
+  });
+}
+
+function stopPropagationWorker() {
+  if (propWorker) propWorker.terminate();
+  propWorker = null;
+  propDisabled = true;
+  clearTimeout(propTimerId);
+  propPending = false;
+  wasm.set_worker_propagation(false);
+}
+
+function onPropWorkerMessage(e) {
+  const msg = e.data;
+  // A reply for a store that has since been replaced: the watcher reloads
+  if (msg.gen !== propGen || msg.gen !== wasm.get_tle_generation()) return;
+  if (msg.type === 'loaded') {
+    if (msg.count !== wasm.get_tle_count()) {
+      console.warn('[gnss-hud] worker record count mismatch — propagating on main thread');
+      stopPropagationWorker();
+      return;
+    }
+    wasm.set_worker_propagation(true);
+    requestPropSample();
+  } else if (msg.type === 'sample') {
+    if (!propShared) propBuf = msg.buf;
+    const half = propBuf.length / 2;
+    wasm.push_position_sample(msg.gen, msg.unixS, propBuf.subarray(0, half), propBuf.subarray(half));
+    propPending = false;
+    propTimerId = setTimeout(requestPropSample, wasm.get_propagation_interval_ms());
+  }
+}
+
+function requestPropSample() {
+  if (!propWorker || propPending) return;
+  // WASM ignores samples while paused; poll until the clock runs again
+  const intervalMs = wasm.get_propagation_interval_ms();
+  if (paused) {
+    propTimerId = setTimeout(requestPropSample, intervalMs);
+    return;
+  }
+  const unixS = wasm.get_sim_epoch() + (timeWarp * PROP_LEAD_INTERVALS * intervalMs) / 1000;
+  propPending = true;
+  if (propShared) {
+    propWorker.postMessage({ type: 'propagate', gen: propGen, unixS });
+  } else {
+    propWorker.postMessage({ type: 'propagate', gen: propGen, unixS, buf: propBuf }, [
+      propBuf.buffer,
+    ]);
+  }
+}
+
 // ─── Country borders fetch ────────────────────────────────────────────────────
 
 const BORDERS_LOCAL_URL = '/assets/data/borders-110m.json';
@@ -393,6 +717,7 @@ async function fetchAndInjectBorders() {
     if (!res.ok) throw new Error(`local borders HTTP ${res.status}`);
     const json = await res.text();
     wasm.inject_borders(json);
+    wasm.set_data_source?.('borders', BORDERS_LOCAL_URL);
   } catch (e) {
     console.warn('[gnss-hud] local borders failed, trying CDN:', e);
     try {
@@ -439,8 +764,10 @@ async function fetchAndInjectTles() {
     if (loaded === 0) {
       throw new Error('TLE JSON parsed to 0 records — likely a bad cache entry');
     }
+    wasm.set_data_source?.('elements', TLE_URL);
     const countStr = loaded > 0 ? ` · ${loaded} sats` : '';
     setPropMode('live', 'live tle', `● SGP4 · live TLE${countStr}`);
+    if (loaded > 0) startPropagationWorker();
   } catch (e) {
     console.error('[gnss-hud] TLE fetch failed:', e);
     setPropMode(
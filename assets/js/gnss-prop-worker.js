@@ -0,0 +1,55 @@
+/**
+ * gnss-prop-worker.js — dedicated Web Worker running SGP4 off the main thread
+ * Started by gnss-hud.js as a module worker. Loads the same wasm module as the
+ * page (its start() returns early when there is no window) and drives a
+ * Propagator. Each sample buffer holds 6·n doubles: ECEF km as
+ * [x0, y0, z0, x1, …] in TLE record order, then ECEF km/s in the same layout.
+ *
+ * Messages in (every message carries the page's `gen` counter, echoed back):
+ *   { type: 'tles', text, shared }      — load the page's get_tle_source() (OMM
+ *                                          JSON or TLE lines); `shared` is a
+ *                                          SharedArrayBuffer to write into, or null
+ *   { type: 'propagate', unixS }        — shared mode: fill the shared buffer
+ *   { type: 'propagate', unixS, buf }   — transfer mode: fill `buf`, transfer it back
+ * Messages out:
+ *   { type: 'loaded', count }
+ *   { type: 'sample', unixS, buf? }
+ */
+
+import init, { Propagator } from '/assets/wasm/gnss-constellation/gnss_constellation.js';
+
+const ready = init();
+let prop = null;
+let shared = null;
+
+/** Position and velocity views over one 6·n sample buffer. */
+function splitSample(buffer) {
+  const half = buffer.byteLength / 2;
+  return [new Float64Array(buffer, 0, half / 8), new Float64Array(buffer, half, half / 8)];
+}
+
+self.onmessage = async (e) => {
+  await ready;
+  const msg = e.data;
+  const gen = msg.gen;
+
+  if (msg.type === 'tles') {
+    if (!prop) prop = new Propagator();
+    const count = prop.load_text(msg.text);
+    shared = msg.shared ? splitSample(msg.shared) : null;
+    self.postMessage({ type: 'loaded', gen, count });
+    return;
+  }
+
+  if (msg.type === 'propagate' && prop) {
+    if (shared) {
+      prop.propagate_into(msg.unixS, shared[0], shared[1]);
+      self.postMessage({ type: 'sample', gen, unixS: msg.unixS });
+    } else {
+      const buf = msg.buf ?? new Float64Array(prop.len() * 6);
+      const [pos, vel] = splitSample(buf.buffer);
+      prop.propagate_into(msg.unixS, pos, vel);
+      self.postMessage({ type: 'sample', gen, unixS: msg.unixS, buf }, [buf.buffer]);
+    }
+  }
+};
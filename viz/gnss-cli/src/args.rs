@@ -0,0 +1,256 @@
+// args.rs — Command-line parsing for the `gnss` tool
+//
+// Responsibilities:
+//   - Parse `gnss <command> [options]` into a `Command` and `Options`
+//   - Parse time arguments (ISO 8601, "YYYY-DDD.FFF", Unix seconds, "now")
+//
+// Hand-rolled to keep the tool dependency-free beyond gnss-core; options
+// accept both `--key value` and `--key=value`.
+
+use gnss_core::tles;
+
+pub const USAGE: &str = "\
+usage: gnss <command> --elements FILE --lat DEG --lon DEG [options]
+
+commands:
+  sky      satellites in view at one epoch, with DOP
+  passes   rise / culmination / set predictions over a window
+  series   time series of visible counts and DOP (--kind dop) or
+           per-satellite az/el (--kind azel)
+
+options:
+  -e, --elements FILE   Celestrak OMM JSON or two/three-line TLE text
+      --lat DEG         observer latitude, degrees north
+      --lon DEG         observer longitude, degrees east
+  -t, --time T          epoch or window start: ISO 8601 (2024-01-15T12:00:00Z),
+                        YYYY-DDD.FFF, Unix seconds, or now (default)
+      --hours H         window length for passes / series (default 24)
+      --step S          sample step in seconds (default 60)
+      --mask DEG        elevation mask in degrees (default 5)
+      --horizon FILE    per-azimuth horizon profile (JSON or CSV), on top of --mask
+      --sat NAME        only satellites whose name or NORAD id contains NAME
+      --kind KIND       series kind: dop (default) or azel
+      --all             sky / azel: include satellites below the mask
+  -f, --format FMT      table (default), csv or json
+  -h, --help            show this help
+";
+
+/// Upper bound on `--hours` × 3600 / `--step`, so a typo cannot start an
+/// effectively endless propagation loop.
+pub const MAX_SAMPLES: f64 = 1_000_000.0;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Sky,
+    Passes,
+    Series,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeriesKind {
+    Dop,
+    AzEl,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    pub elements: String,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    /// `None` = now.
+    pub time_unix: Option<f64>,
+    pub hours: f64,
+    pub step_s: f64,
+    pub mask_deg: f64,
+    pub horizon: Option<String>,
+    pub sat: Option<String>,
+    pub kind: SeriesKind,
+    pub all: bool,
+    pub format: Format,
+}
+
+/// What the command line asked for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parsed {
+    Help,
+    Run(Command, Options),
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+/// Parse the arguments after the program name.
+pub fn parse(args: &[String]) -> Result<Parsed, String> {
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        return Ok(Parsed::Help);
+    }
+    let command = match args[0].as_str() {
+        "sky" => Command::Sky,
+        "passes" => Command::Passes,
+        "series" => Command::Series,
+        other => return Err(format!("unknown command '{other}'")),
+    };
+
+    let mut elements = None;
+    let (mut lat_deg, mut lon_deg) = (None, None);
+    let mut opts = Options {
+        elements: String::new(),
+        lat_deg: 0.0,
+        lon_deg: 0.0,
+        time_unix: None,
+        hours: 24.0,
+        step_s: 60.0,
+        mask_deg: 5.0,
+        horizon: None,
+        sat: None,
+        kind: SeriesKind::Dop,
+        all: false,
+        format: Format::Table,
+    };
+
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        let (key, inline) = match arg.split_once('=') {
+            Some((k, v)) if k.starts_with("--") => (k, Some(v.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        if key == "--all" {
+            opts.all = true;
+            continue;
+        }
+        let value = match inline {
+            Some(v) => v,
+            None => rest.next().cloned().ok_or_else(|| format!("{key} needs a value"))?,
+        };
+        let number = |v: &str| v.parse::<f64>().map_err(|_| format!("{key}: '{v}' is not a number"));
+        match key {
+            "-e" | "--elements" => elements = Some(value),
+            "--lat" => lat_deg = Some(number(&value)?),
+            "--lon" => lon_deg = Some(number(&value)?),
+            "-t" | "--time" => opts.time_unix = parse_time(&value)?,
+            "--hours" => opts.hours = number(&value)?,
+            "--step" => opts.step_s = number(&value)?,
+            "--mask" => opts.mask_deg = number(&value)?,
+            "--horizon" => opts.horizon = Some(value),
+            "--sat" => opts.sat = Some(value),
+            "--kind" => {
+                opts.kind = match value.as_str() {
+                    "dop" => SeriesKind::Dop,
+                    "azel" => SeriesKind::AzEl,
+                    _ => return Err(format!("--kind: expected dop or azel, got '{value}'")),
+                }
+            }
+            "-f" | "--format" => {
+                opts.format = match value.as_str() {
+                    "table" => Format::Table,
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(format!("--format: expected table, csv or json, got '{value}'")),
+                }
+            }
+            _ => return Err(format!("unknown option '{key}'")),
+        }
+    }
+
+    opts.elements = elements.ok_or("--elements is required")?;
+    opts.lat_deg = lat_deg.ok_or("--lat is required")?;
+    opts.lon_deg = lon_deg.ok_or("--lon is required")?;
+    if !(-90.0..=90.0).contains(&opts.lat_deg) {
+        return Err(format!("--lat {} is outside ±90°", opts.lat_deg));
+    }
+    if !(opts.lon_deg.is_finite() && opts.mask_deg.is_finite()) {
+        return Err("--lon and --mask must be finite".to_string());
+    }
+    let finite_positive = |v: f64| v.is_finite() && v > 0.0;
+    if !(finite_positive(opts.step_s) && finite_positive(opts.hours)) {
+        return Err("--step and --hours must be positive and finite".to_string());
+    }
+    if opts.hours * 3600.0 / opts.step_s > MAX_SAMPLES {
+        return Err(format!("--hours {} at --step {} exceeds {MAX_SAMPLES} samples", opts.hours, opts.step_s));
+    }
+    Ok(Parsed::Run(command, opts))
+}
+
+/// Parse a time argument to Unix seconds; `Ok(None)` means now.
+pub fn parse_time(s: &str) -> Result<Option<f64>, String> {
+    if s == "now" {
+        return Ok(None);
+    }
+    if let Ok(unix) = s.parse::<f64>() {
+        return if unix.is_finite() { Ok(Some(unix)) } else { Err(format!("time '{s}' is not finite")) };
+    }
+    let trimmed = s.trim_end_matches('Z');
+    tles::parse_epoch(trimmed)
+        .map(|(_, _, unix)| Some(unix))
+        .ok_or_else(|| format!("cannot parse time '{s}'"))
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    /// Both option spellings parse; defaults fill the rest.
+    #[test]
+    fn test_parse_options() {
+        let Ok(Parsed::Run(cmd, o)) =
+            parse(&args("passes -e gnss.json --lat=41.85 --lon -87.65 --hours 6 --sat G05 -f csv"))
+        else {
+            panic!("expected a command");
+        };
+        assert_eq!(cmd, Command::Passes);
+        assert_eq!(o.elements, "gnss.json");
+        assert_eq!((o.lat_deg, o.lon_deg, o.hours), (41.85, -87.65, 6.0));
+        assert_eq!(o.sat.as_deref(), Some("G05"));
+        assert_eq!(o.format, Format::Csv);
+        assert_eq!((o.step_s, o.mask_deg, o.time_unix), (60.0, 5.0, None));
+    }
+
+    /// Missing required options, unknown commands and bad values are errors.
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&args("sky --lat 1 --lon 2")).is_err());
+        assert!(parse(&args("orbit -e x --lat 1 --lon 2")).is_err());
+        assert!(parse(&args("sky -e x --lat north --lon 2")).is_err());
+        assert!(parse(&args("sky -e x --lat 1 --lon 2 --format xml")).is_err());
+        assert!(parse(&args("sky -e x --lat 1 --lon")).is_err());
+        assert!(parse(&args("passes -e x --lat 1 --lon 2 --hours inf")).is_err());
+        assert!(parse(&args("passes -e x --lat 1 --lon 2 --hours NaN")).is_err());
+        assert!(parse(&args("series -e x --lat 1 --lon 2 --step 1e-9")).is_err());
+        assert!(parse(&args("series -e x --lat 1 --lon 2 --hours 1e12")).is_err());
+        assert!(parse(&args("series -e x --lat 1 --lon 2 --hours 240 --step 1")).is_ok());
+        assert!(parse(&args("sky -e x --lat 1 --lon inf")).is_err());
+        assert!(parse(&args("sky -e x --lat 1 --lon 2 --mask NaN")).is_err());
+        assert!(parse(&args("sky -e x --lat 1 --lon 2 --time NaN")).is_err());
+        assert_eq!(parse(&args("sky --help")), Ok(Parsed::Help));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("now"), Ok(None));
+        assert_eq!(parse_time("1700000000"), Ok(Some(1.7e9)));
+        assert_eq!(parse_time("1970-01-02T00:00:00Z"), Ok(Some(86400.0)));
+        assert_eq!(parse_time("1970-002.5"), Ok(Some(1.5 * 86400.0)));
+        assert!(parse_time("yesterday").is_err());
+        assert!(parse_time("inf").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+// main.rs — `gnss`: headless sky, pass and time-series queries
+//
+// Runs the gnss-core propagation and observer math from the command line
+// for scripts and cron jobs:
+//
+//   gnss sky    -e gnss.json --lat 41.85 --lon -87.65 --time 2024-01-15T12:00:00Z
+//   gnss passes -e gnss.tle  --lat 41.85 --lon -87.65 --hours 12 --sat "PRN 05"
+//   gnss series -e gnss.json --lat 41.85 --lon -87.65 --kind dop --format csv
+//
+// Output goes to stdout; errors go to stderr with exit status 2.
+
+mod args;
+mod report;
+
+use std::process::ExitCode;
+
+use args::{Command, Parsed};
+
+fn main() -> ExitCode {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    let read = |path: &str| std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"));
+    match run(&argv, now_unix(), read) {
+        Ok(out) => {
+            print!("{out}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("gnss: {e}\nrun `gnss --help` for usage");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Parse `argv`, load the files through `read` and return the report text.
+/// `now_unix` stands in for an omitted or "now" `--time`.
+fn run(argv: &[String], now_unix: f64, read: impl Fn(&str) -> Result<String, String>) -> Result<String, String> {
+    let (command, opts) = match args::parse(argv)? {
+        Parsed::Help => return Ok(args::USAGE.to_string()),
+        Parsed::Run(command, opts) => (command, opts),
+    };
+    let elements = read(&opts.elements)?;
+    let horizon = opts.horizon.as_deref().map(&read).transpose()?;
+    let site = report::Site::new(&elements, horizon.as_deref(), &opts)?;
+    let t = opts.time_unix.unwrap_or(now_unix);
+    Ok(match command {
+        Command::Sky => report::sky(&site, &opts, t),
+        Command::Passes => report::passes(&site, &opts, t),
+        Command::Series => report::series(&site, &opts, t),
+    })
+}
+
+fn now_unix() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Epoch of the synthetic element sets: 2024-01-15 12:00 UTC.
+    const EPOCH: f64 = 1_705_320_000.0;
+
+    /// Three-line element set in NORAD column layout for a GPS-like orbit.
+    fn gps_tle(norad: u32, raan: f64, mean_anomaly: f64) -> String {
+        format!(
+            "GPS TEST-{norad}\n\
+             1 {norad:05}U 98001A   24015.50000000  .00000000  00000-0  00000-0 0  9990\n\
+             2 {norad:05} {:8.4} {raan:8.4} 0050000 {:8.4} {mean_anomaly:8.4} {:11.8}    10\n",
+            55.0, 0.0, 2.00561
+        )
+    }
+
+    /// 6 planes × 4 satellites, like the GPS baseline.
+    fn constellation() -> String {
+        (0..24).map(|i| gps_tle(90000 + i, (i / 4) as f64 * 60.0, (i % 4) as f64 * 90.0 + (i / 4) as f64 * 15.0)).collect()
+    }
+
+    fn run_str(cmd: &str) -> Result<String, String> {
+        let argv: Vec<String> = cmd.split_whitespace().map(String::from).collect();
+        run(&argv, EPOCH, |path| match path {
+            "gps.tle" => Ok(constellation()),
+            _ => Err(format!("{path}: not found")),
+        })
+    }
+
+    /// A full constellation leaves several satellites in view with a
+    /// usable DOP; JSON output carries the same figures.
+    #[test]
+    fn test_sky_json() {
+        let out = run_str("sky -e gps.tle --lat 41.85 --lon -87.65 -f json").unwrap();
+        let v: serde_json::Value = serde_json::from_str(&out).unwrap();
+        let visible = v["visible"].as_u64().unwrap();
+        assert!((4..=16).contains(&visible), "visible={visible}");
+        assert!(v["dop"]["pdop"].as_f64().unwrap() < 10.0);
+        assert_eq!(v["satellites"].as_array().unwrap().len() as u64, visible);
+        assert_eq!(v["satellites"][0]["constellation"], "GPS");
+        let els: Vec<f64> = v["satellites"].as_array().unwrap().iter().map(|s| s["el_deg"].as_f64().unwrap()).collect();
+        assert!(els.windows(2).all(|w| w[0] >= w[1]) && els.iter().all(|&e| e >= 5.0));
+    }
+
+    /// A GPS orbit repeats twice a sidereal day, so one satellite yields
+    /// ordered rise / peak / set passes over 24 h.
+    #[test]
+    fn test_passes_csv() {
+        let out = run_str("passes -e gps.tle --lat 41.85 --lon -87.65 --sat 90000 -f csv").unwrap();
+        let mut lines = out.lines();
+        assert!(lines.next().unwrap().starts_with("name,norad_id,constellation,rise_utc"));
+        let rows: Vec<&str> = lines.collect();
+        assert!(!rows.is_empty() && rows.len() <= 4, "{out}");
+        assert!(rows.iter().all(|r| r.starts_with("GPS TEST-90000,90000,GPS,")));
+    }
+
+    /// One row per step, inclusive of both ends; counts match the CSV width.
+    #[test]
+    fn test_series_dop_rows() {
+        let out = run_str("series -e gps.tle --lat 0 --lon 0 --hours 1 --step 600 -f csv").unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 1 + 7);
+        let width = lines[0].split(',').count();
+        assert_eq!(width, 3 + 8 + 5);
+        assert!(lines[1..].iter().all(|l| l.split(',').count() == width));
+        assert!(lines[1].starts_with("2024-01-15T12:00:00Z,1705320000,"));
+    }
+
+    #[test]
+    fn test_errors_surface() {
+        assert!(run_str("sky -e missing.json --lat 0 --lon 0").unwrap_err().contains("not found"));
+        assert!(run_str("sky --lat 0 --lon 0").is_err());
+        assert!(run_str("--help").unwrap().starts_with("usage: gnss"));
+    }
+}
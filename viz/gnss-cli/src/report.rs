@@ -0,0 +1,466 @@
+// report.rs — Sky, pass and time-series reports for the `gnss` tool
+//
+// Responsibilities:
+//   - Load an element file and observer into a `Site` that answers look
+//     angles and mask checks at any epoch
+//   - Build the sky, passes and series reports as rows
+//   - Render rows as an aligned table, CSV or JSON
+//
+// All math comes from gnss-core; this module only samples and formats.
+
+use serde::Serialize;
+
+use gnss_core::coords;
+use gnss_core::dop::{self, Dop};
+use gnss_core::ground::{Observer, EARTH_R_KM};
+use gnss_core::horizon::{self, HorizonMask};
+use gnss_core::passes::{self, Pass};
+use gnss_core::tles::{SatRecord, TleStore, CONSTELLATION_COUNT, CONSTELLATION_NAMES};
+
+use crate::args::{Format, Options, SeriesKind};
+
+// ---------------------------------------------------------------------------
+// Site
+// ---------------------------------------------------------------------------
+
+/// Loaded satellites plus the observer and its masks.
+pub struct Site {
+    store: TleStore,
+    obs_km: [f64; 3],
+    horizon: Option<HorizonMask>,
+    mask_deg: f64,
+    sat_filter: Option<String>,
+}
+
+impl Site {
+    /// `elements` is OMM JSON or TLE text; `horizon` an optional profile.
+    pub fn new(elements: &str, horizon: Option<&str>, opts: &Options) -> Result<Site, String> {
+        let mut store = TleStore::new();
+        let count = if elements.trim_start().starts_with('[') {
+            store.load_from_json(elements)?
+        } else {
+            store.load_from_tle(elements)?
+        };
+        if count == 0 {
+            return Err("element file holds no usable satellites".to_string());
+        }
+        let horizon = match horizon {
+            Some(text) => Some(HorizonMask::parse(text).ok_or("cannot parse horizon profile")?),
+            None => None,
+        };
+        let u = Observer::new(opts.lat_deg, opts.lon_deg).ecef_unit();
+        Ok(Site {
+            store,
+            obs_km: [u[0] * EARTH_R_KM, u[1] * EARTH_R_KM, u[2] * EARTH_R_KM],
+            horizon,
+            mask_deg: opts.mask_deg,
+            sat_filter: opts.sat.as_ref().map(|s| s.to_lowercase()),
+        })
+    }
+
+    /// Records matching `--sat` (all of them without a filter).
+    pub fn sats(&self) -> impl Iterator<Item = &SatRecord> {
+        self.store.records.iter().filter(|r| match &self.sat_filter {
+            Some(f) => r.name.to_lowercase().contains(f) || r.norad_id.to_string().contains(f),
+            None => true,
+        })
+    }
+
+    /// `(az_deg, el_deg, range_km)` of `rec` at `unix_s`.
+    pub fn look(&self, rec: &SatRecord, unix_s: f64) -> (f64, f64, f64) {
+        let sat = coords::teme_to_ecef(TleStore::propagate(rec, unix_s), coords::gmst_rad(unix_s));
+        let (az, el) = coords::az_el(self.obs_km, sat);
+        let d = [sat[0] - self.obs_km[0], sat[1] - self.obs_km[1], sat[2] - self.obs_km[2]];
+        (az, el, (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt())
+    }
+
+    /// Elevation mask at `az_deg`: the horizon profile or `--mask`, whichever is higher.
+    pub fn mask_at(&self, az_deg: f64) -> f64 {
+        horizon::effective_mask_deg(self.horizon.as_ref(), self.mask_deg, az_deg)
+    }
+
+    pub fn is_visible(&self, az_deg: f64, el_deg: f64) -> bool {
+        horizon::is_visible(self.horizon.as_ref(), self.mask_deg, az_deg, el_deg)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Reports
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct SkyRow {
+    name: String,
+    norad_id: u64,
+    constellation: &'static str,
+    orbit: &'static str,
+    az_deg: f64,
+    el_deg: f64,
+    range_km: f64,
+    visible: bool,
+}
+
+#[derive(Serialize)]
+struct SkyReport {
+    time_unix: f64,
+    time_utc: String,
+    lat_deg: f64,
+    lon_deg: f64,
+    mask_deg: f64,
+    visible: usize,
+    dop: Option<Dop>,
+    satellites: Vec<SkyRow>,
+}
+
+/// Satellites in view at `unix_s`, highest first, with DOP over the
+/// visible set.
+pub fn sky(site: &Site, opts: &Options, unix_s: f64) -> String {
+    let mut rows: Vec<SkyRow> = site
+        .sats()
+        .map(|rec| {
+            let (az, el, range_km) = site.look(rec, unix_s);
+            SkyRow {
+                name: rec.name.clone(),
+                norad_id: rec.norad_id,
+                constellation: constellation_name(rec.constellation),
+                orbit: rec.orbit_class.label(rec.constellation),
+                az_deg: az,
+                el_deg: el,
+                range_km,
+                visible: site.is_visible(az, el),
+            }
+        })
+        .filter(|r| opts.all || r.visible)
+        .collect();
+    rows.sort_by(|a, b| b.el_deg.total_cmp(&a.el_deg));
+    let in_view: Vec<(f64, f64)> = rows.iter().filter(|r| r.visible).map(|r| (r.az_deg, r.el_deg)).collect();
+    let report = SkyReport {
+        time_unix: unix_s,
+        time_utc: utc(unix_s),
+        lat_deg: opts.lat_deg,
+        lon_deg: opts.lon_deg,
+        mask_deg: opts.mask_deg,
+        visible: in_view.len(),
+        dop: dop::compute(&in_view),
+        satellites: rows,
+    };
+
+    let headers = ["name", "norad_id", "constellation", "orbit", "az_deg", "el_deg", "range_km", "visible"];
+    let cells: Vec<Vec<String>> = report
+        .satellites
+        .iter()
+        .map(|r| {
+            vec![
+                r.name.clone(),
+                r.norad_id.to_string(),
+                r.constellation.to_string(),
+                r.orbit.to_string(),
+                format!("{:.2}", r.az_deg),
+                format!("{:.2}", r.el_deg),
+                format!("{:.1}", r.range_km),
+                r.visible.to_string(),
+            ]
+        })
+        .collect();
+    match opts.format {
+        Format::Json => json(&report),
+        Format::Csv => csv(&headers, &cells),
+        Format::Table => {
+            let dop = match &report.dop {
+                Some(d) => format!(
+                    "GDOP {:.2}  PDOP {:.2}  HDOP {:.2}  VDOP {:.2}  TDOP {:.2}",
+                    d.gdop, d.pdop, d.hdop, d.vdop, d.tdop
+                ),
+                None => "DOP n/a (fewer than 4 satellites)".to_string(),
+            };
+            format!(
+                "# {}  lat {:.4}  lon {:.4}  mask {:.1}°\n# {} visible  {}\n{}",
+                report.time_utc,
+                report.lat_deg,
+                report.lon_deg,
+                report.mask_deg,
+                report.visible,
+                dop,
+                table(&headers, &cells)
+            )
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PassRow {
+    name: String,
+    norad_id: u64,
+    constellation: &'static str,
+    rise_utc: String,
+    max_el_utc: String,
+    set_utc: String,
+    duration_min: f64,
+    #[serde(flatten)]
+    pass: Pass,
+}
+
+/// Passes of every (filtered) satellite over the window, by rise time.
+pub fn passes(site: &Site, opts: &Options, start_unix: f64) -> String {
+    let end_unix = start_unix + opts.hours * 3600.0;
+    let mut rows: Vec<PassRow> = site
+        .sats()
+        .flat_map(|rec| {
+            let look = |t: f64| {
+                let (az, el, _) = site.look(rec, t);
+                (az, el)
+            };
+            passes::find_passes(look, |az| site.mask_at(az), start_unix, end_unix, opts.step_s)
+                .into_iter()
+                .map(|p| PassRow {
+                    name: rec.name.clone(),
+                    norad_id: rec.norad_id,
+                    constellation: constellation_name(rec.constellation),
+                    rise_utc: utc(p.rise_unix),
+                    max_el_utc: utc(p.max_el_unix),
+                    set_utc: utc(p.set_unix),
+                    duration_min: p.duration_s() / 60.0,
+                    pass: p,
+                })
+        })
+        .collect();
+    rows.sort_by(|a, b| a.pass.rise_unix.total_cmp(&b.pass.rise_unix));
+
+    let headers = [
+        "name", "norad_id", "constellation", "rise_utc", "rise_az_deg", "max_el_utc", "max_el_deg", "set_utc",
+        "set_az_deg", "duration_min",
+    ];
+    // Clipped ends are marked with '<' (up at window start) / '>' (up at end)
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|r| {
+            vec![
+                r.name.clone(),
+                r.norad_id.to_string(),
+                r.constellation.to_string(),
+                format!("{}{}", if r.pass.rise_clipped { "<" } else { "" }, r.rise_utc),
+                format!("{:.1}", r.pass.rise_az_deg),
+                r.max_el_utc.clone(),
+                format!("{:.1}", r.pass.max_el_deg),
+                format!("{}{}", if r.pass.set_clipped { ">" } else { "" }, r.set_utc),
+                format!("{:.1}", r.pass.set_az_deg),
+                format!("{:.1}", r.duration_min),
+            ]
+        })
+        .collect();
+    match opts.format {
+        Format::Json => json(&rows),
+        Format::Csv => csv(&headers, &cells),
+        Format::Table => format!(
+            "# {} → {}  lat {:.4}  lon {:.4}  mask {:.1}°  ({} passes)\n{}",
+            utc(start_unix),
+            utc(end_unix),
+            opts.lat_deg,
+            opts.lon_deg,
+            opts.mask_deg,
+            rows.len(),
+            table(&headers, &cells)
+        ),
+    }
+}
+
+#[derive(Serialize)]
+struct DopRow {
+    time_utc: String,
+    time_unix: f64,
+    visible: usize,
+    visible_by_constellation: [usize; CONSTELLATION_COUNT],
+    dop: Option<Dop>,
+}
+
+#[derive(Serialize)]
+struct AzElRow {
+    time_utc: String,
+    time_unix: f64,
+    name: String,
+    norad_id: u64,
+    constellation: &'static str,
+    az_deg: f64,
+    el_deg: f64,
+    visible: bool,
+}
+
+/// Sample the window every `--step`: visible counts and DOP, or one
+/// az/el row per satellite and epoch.
+pub fn series(site: &Site, opts: &Options, start_unix: f64) -> String {
+    let n = (opts.hours * 3600.0 / opts.step_s).floor() as usize;
+    let times = (0..=n).map(|i| start_unix + i as f64 * opts.step_s);
+    match opts.kind {
+        SeriesKind::Dop => {
+            let rows: Vec<DopRow> = times
+                .map(|t| {
+                    let mut counts = [0usize; CONSTELLATION_COUNT];
+                    let mut in_view = Vec::new();
+                    for rec in site.sats() {
+                        let (az, el, _) = site.look(rec, t);
+                        if site.is_visible(az, el) {
+                            in_view.push((az, el));
+                            if let Some(c) = counts.get_mut(rec.constellation as usize) {
+                                *c += 1;
+                            }
+                        }
+                    }
+                    DopRow {
+                        time_utc: utc(t),
+                        time_unix: t,
+                        visible: in_view.len(),
+                        visible_by_constellation: counts,
+                        dop: dop::compute(&in_view),
+                    }
+                })
+                .collect();
+            let mut headers = vec!["time_utc".to_string(), "time_unix".to_string(), "visible".to_string()];
+            headers.extend(CONSTELLATION_NAMES.iter().map(|c| c.to_lowercase()));
+            headers.extend(["gdop", "pdop", "hdop", "vdop", "tdop"].map(String::from));
+            let cells: Vec<Vec<String>> = rows
+                .iter()
+                .map(|r| {
+                    let mut c = vec![r.time_utc.clone(), format!("{:.0}", r.time_unix), r.visible.to_string()];
+                    c.extend(r.visible_by_constellation.iter().map(|n| n.to_string()));
+                    match &r.dop {
+                        Some(d) => c.extend([d.gdop, d.pdop, d.hdop, d.vdop, d.tdop].map(|x| format!("{x:.2}"))),
+                        None => c.extend(std::iter::repeat_n(String::new(), 5)),
+                    }
+                    c
+                })
+                .collect();
+            let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+            render(opts.format, &headers, &cells, &rows)
+        }
+        SeriesKind::AzEl => {
+            let rows: Vec<AzElRow> = times
+                .flat_map(|t| {
+                    site.sats().filter_map(move |rec| {
+                        let (az, el, _) = site.look(rec, t);
+                        let visible = site.is_visible(az, el);
+                        (opts.all || visible).then(|| AzElRow {
+                            time_utc: utc(t),
+                            time_unix: t,
+                            name: rec.name.clone(),
+                            norad_id: rec.norad_id,
+                            constellation: constellation_name(rec.constellation),
+                            az_deg: az,
+                            el_deg: el,
+                            visible,
+                        })
+                    })
+                })
+                .collect();
+            let headers =
+                ["time_utc", "time_unix", "name", "norad_id", "constellation", "az_deg", "el_deg", "visible"];
+            let cells: Vec<Vec<String>> = rows
+                .iter()
+                .map(|r| {
+                    vec![
+                        r.time_utc.clone(),
+                        format!("{:.0}", r.time_unix),
+                        r.name.clone(),
+                        r.norad_id.to_string(),
+                        r.constellation.to_string(),
+                        format!("{:.2}", r.az_deg),
+                        format!("{:.2}", r.el_deg),
+                        r.visible.to_string(),
+                    ]
+                })
+                .collect();
+            render(opts.format, &headers, &cells, &rows)
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Formatting
+// ---------------------------------------------------------------------------
+
+fn constellation_name(idx: u8) -> &'static str {
+    CONSTELLATION_NAMES.get(idx as usize).copied().unwrap_or("Other")
+}
+
+/// ISO 8601 UTC, whole seconds.
+pub fn utc(unix_s: f64) -> String {
+    chrono::DateTime::from_timestamp(unix_s.round() as i64, 0)
+        .map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_else(|| format!("{unix_s}"))
+}
+
+fn render<T: Serialize>(format: Format, headers: &[&str], cells: &[Vec<String>], rows: &T) -> String {
+    match format {
+        Format::Json => json(rows),
+        Format::Csv => csv(headers, cells),
+        Format::Table => table(headers, cells),
+    }
+}
+
+fn json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default() + "\n"
+}
+
+fn csv(headers: &[&str], cells: &[Vec<String>]) -> String {
+    let field = |f: &str| {
+        if f.contains([',', '"', '\n']) {
+            format!("\"{}\"", f.replace('"', "\"\""))
+        } else {
+            f.to_string()
+        }
+    };
+    let mut out = headers.join(",") + "\n";
+    for row in cells {
+        out += &row.iter().map(|f| field(f)).collect::<Vec<_>>().join(",");
+        out.push('\n');
+    }
+    out
+}
+
+/// Left-aligned columns, two spaces apart, upper-case header.
+fn table(headers: &[&str], cells: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in cells {
+        for (w, c) in widths.iter_mut().zip(row) {
+            *w = (*w).max(c.chars().count());
+        }
+    }
+    let line = |row: Vec<String>| {
+        let padded: Vec<String> = row.iter().zip(&widths).map(|(c, w)| format!("{c:<w$}")).collect();
+        padded.join("  ").trim_end().to_string() + "\n"
+    };
+    let mut out = line(headers.iter().map(|h| h.to_uppercase()).collect());
+    for row in cells {
+        out += &line(row.clone());
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quoting() {
+        let out = csv(&["a", "b"], &[vec!["x,y".into(), "say \"hi\"".into()]]);
+        assert_eq!(out, "a,b\n\"x,y\",\"say \"\"hi\"\"\"\n");
+    }
+
+    #[test]
+    fn test_table_alignment() {
+        let out = table(&["name", "el"], &[vec!["GPS BIIR-2".into(), "45.00".into()], vec!["G1".into(), "5".into()]]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "NAME        EL");
+        assert_eq!(lines[1], "GPS BIIR-2  45.00");
+        assert_eq!(lines[2], "G1          5");
+    }
+
+    #[test]
+    fn test_utc() {
+        assert_eq!(utc(0.0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc(1_700_000_000.4), "2023-11-14T22:13:20Z");
+    }
+}
@@ -0,0 +1,102 @@
+// capture.rs — PNG encoding and pixel comparison for offscreen captures
+//
+// Responsibilities:
+//   - Encode the top-down RGBA rows from `Scene::render_rgba` as PNG
+//   - Fit a requested capture size to the GL texture limit
+//   - Compare two captures for pixel-diff regression tests
+//
+// No GL here: the pixels come from whichever context rendered the scene —
+// the browser canvas or a native surfaceless EGL context (headless.rs).
+
+/// Encode `width` × `height` RGBA8 pixels (top row first) as PNG.
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, String> {
+    if rgba.len() != width as usize * height as usize * 4 {
+        return Err(format!("expected {}×{} RGBA pixels, got {} bytes", width, height, rgba.len()));
+    }
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(rgba).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// Scale a requested capture size down, keeping its aspect ratio, so that
+/// neither edge exceeds `max` pixels (the GL texture limit).
+pub fn fit_size(width: u32, height: u32, max: u32) -> (u32, u32) {
+    let (w, h) = (width.max(1), height.max(1));
+    let longest = w.max(h);
+    if longest <= max {
+        return (w, h);
+    }
+    let scale = max as f64 / longest as f64;
+    let fit = |n: u32| ((n as f64 * scale).round() as u32).clamp(1, max);
+    (fit(w), fit(h))
+}
+
+/// Decode an RGBA8 PNG back to `(width, height, pixels)` — the inverse of
+/// `encode_png`, for loading reference images.
+pub fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    let decoder = png::Decoder::new(bytes);
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!("expected RGBA8, got {:?} {:?}", info.color_type, info.bit_depth));
+    }
+    buf.truncate(info.buffer_size());
+    Ok((info.width, info.height, buf))
+}
+
+/// Fraction of pixels in which any channel differs by more than
+/// `tolerance`. Images of different sizes differ entirely.
+pub fn diff_fraction(a: &[u8], b: &[u8], tolerance: u8) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 1.0;
+    }
+    let differing = a
+        .chunks_exact(4)
+        .zip(b.chunks_exact(4))
+        .filter(|(p, q)| p.iter().zip(q.iter()).any(|(x, y)| x.abs_diff(*y) > tolerance))
+        .count();
+    differing as f64 / (a.len() / 4) as f64
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_round_trip() {
+        let rgba: Vec<u8> = (0..3 * 2 * 4).map(|i| (i * 10) as u8).collect();
+        let png = encode_png(3, 2, &rgba).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        assert_eq!(decode_png(&png).unwrap(), (3, 2, rgba));
+        assert!(encode_png(4, 2, &[0; 24]).is_err());
+    }
+
+    #[test]
+    fn test_fit_size() {
+        assert_eq!(fit_size(1920, 1080, 4096), (1920, 1080));
+        assert_eq!(fit_size(7680, 4320, 4096), (4096, 2304));
+        assert_eq!(fit_size(100, 20000, 4096), (20, 4096));
+        assert_eq!(fit_size(0, 0, 4096), (1, 1));
+    }
+
+    #[test]
+    fn test_diff_fraction() {
+        let a = [10u8, 20, 30, 255, 0, 0, 0, 255];
+        let mut b = a;
+        assert_eq!(diff_fraction(&a, &b, 0), 0.0);
+        b[0] = 13;
+        assert_eq!(diff_fraction(&a, &b, 3), 0.0);
+        assert_eq!(diff_fraction(&a, &b, 2), 0.5);
+        assert_eq!(diff_fraction(&a, &b[..4], 0), 1.0);
+    }
+}
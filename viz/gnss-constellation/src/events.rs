@@ -0,0 +1,321 @@
+// events.rs — Viewer events detected by the render loop
+//
+// Responsibilities:
+//   - Compare each propagation tick's sky against the previous one and
+//     report satellites rising above or setting below the mask, PDOP
+//     crossing a threshold, visible-count changes and UTC day rollovers
+//   - Define the serialisable `ViewerEvent` handed to JS callbacks
+//     (`on_event`) or drained by native front ends (`take_events`)
+//
+// Platform-independent: the JS callback registry lives in lib.rs.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use gnss_core::{dop::Dop, gis, ground::SkySat, tles::CONSTELLATION_COUNT};
+
+/// Default PDOP alarm threshold.
+pub const DEFAULT_PDOP_THRESHOLD: f64 = 6.0;
+
+/// Event kinds accepted by `on_event`; "*" subscribes to all of them.
+pub const EVENT_KINDS: [&str; 6] = ["rise", "set", "dop", "count", "day", "tles_loaded"];
+
+// ---------------------------------------------------------------------------
+// Events
+// ---------------------------------------------------------------------------
+
+/// Serialised as `{ "kind": "rise", … }`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ViewerEvent {
+    /// A satellite became visible (elevation mask, horizon, buildings).
+    Rise(PassEvent),
+    /// A satellite stopped being visible.
+    Set(PassEvent),
+    /// PDOP of the satellites in the fix crossed the threshold; `above` is
+    /// the new side. Fewer than four satellites counts as above.
+    Dop { unix_s: f64, pdop: Option<f64>, threshold: f64, above: bool },
+    /// Visible satellites per constellation index changed.
+    Count { unix_s: f64, visible: [u32; CONSTELLATION_COUNT], total: u32 },
+    /// The sim clock crossed UTC midnight; `date` is the new day.
+    Day { unix_s: f64, date: String },
+    /// A new set of element sets was loaded.
+    TlesLoaded { count: u32 },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PassEvent {
+    pub unix_s: f64,
+    pub name: String,
+    pub norad_id: u64,
+    pub constellation: u8,
+    pub az_deg: f32,
+    pub el_deg: f32,
+}
+
+impl ViewerEvent {
+    /// The `kind` tag, as used by `on_event`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ViewerEvent::Rise(_) => "rise",
+            ViewerEvent::Set(_) => "set",
+            ViewerEvent::Dop { .. } => "dop",
+            ViewerEvent::Count { .. } => "count",
+            ViewerEvent::Day { .. } => "day",
+            ViewerEvent::TlesLoaded { .. } => "tles_loaded",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Detector
+// ---------------------------------------------------------------------------
+
+/// Edge detector over successive sky snapshots. The first `check` after
+/// `new` or `reset` only records a baseline, so switching observer or
+/// loading a catalogue does not announce every satellite as rising.
+#[derive(Clone, Debug)]
+pub struct EventDetector {
+    pub pdop_threshold: f64,
+    primed: bool,
+    /// (NORAD ID, object name) of the satellites visible at the last check;
+    /// Walker slots share NORAD 0 and differ by name.
+    visible: HashSet<(u64, String)>,
+    /// Object name and pass details of each visible satellite at the last
+    /// check, in sky order, for the matching set events.
+    last: Vec<(String, PassEvent)>,
+    dop_above: bool,
+    counts: [u32; CONSTELLATION_COUNT],
+    day: i64,
+}
+
+impl Default for EventDetector {
+    fn default() -> Self {
+        EventDetector {
+            pdop_threshold: DEFAULT_PDOP_THRESHOLD,
+            primed: false,
+            visible: HashSet::new(),
+            last: Vec::new(),
+            dop_above: false,
+            counts: [0; CONSTELLATION_COUNT],
+            day: 0,
+        }
+    }
+}
+
+impl EventDetector {
+    /// Forget the baseline; the next `check` reports nothing.
+    pub fn reset(&mut self) {
+        self.primed = false;
+    }
+
+    /// Compare `sats` (and the fix's `dop`) at `unix_s` with the previous
+    /// check and append what changed to `out`.
+    pub fn check(&mut self, unix_s: f64, sats: &[SkySat], dop: Option<&Dop>, out: &mut Vec<ViewerEvent>) {
+        let visible: Vec<&SkySat> = sats.iter().filter(|s| s.visible).collect();
+        let keys: HashSet<(u64, String)> = visible.iter().map(|s| (s.norad_id, s.object_name.clone())).collect();
+        let mut counts = [0u32; CONSTELLATION_COUNT];
+        for s in &visible {
+            if let Some(c) = counts.get_mut(s.constellation as usize) {
+                *c += 1;
+            }
+        }
+        let pdop = dop.map(|d| d.pdop);
+        let dop_above = pdop.is_none_or(|p| p > self.pdop_threshold);
+        let day = (unix_s / 86400.0).floor() as i64;
+        let pass = |s: &SkySat| PassEvent {
+            unix_s,
+            name: s.name.clone(),
+            norad_id: s.norad_id,
+            constellation: s.constellation,
+            az_deg: s.az_deg,
+            el_deg: s.el_deg,
+        };
+
+        if self.primed {
+            for s in &visible {
+                if !self.visible.contains(&(s.norad_id, s.object_name.clone())) {
+                    out.push(ViewerEvent::Rise(pass(s)));
+                }
+            }
+            // Set events report where the satellite was last seen
+            for (object_name, p) in &self.last {
+                if !keys.contains(&(p.norad_id, object_name.clone())) {
+                    out.push(ViewerEvent::Set(PassEvent { unix_s, ..p.clone() }));
+                }
+            }
+            if dop_above != self.dop_above {
+                out.push(ViewerEvent::Dop { unix_s, pdop, threshold: self.pdop_threshold, above: dop_above });
+            }
+            if counts != self.counts {
+                out.push(ViewerEvent::Count { unix_s, visible: counts, total: counts.iter().sum() });
+            }
+            if day != self.day {
+                // "YYYY-MM-DD" of the new day's midnight
+                let date = gis::iso8601(day as f64 * 86400.0)[..10].to_string();
+                out.push(ViewerEvent::Day { unix_s, date });
+            }
+        }
+
+        self.primed = true;
+        self.visible = keys;
+        self.last = visible.iter().map(|s| (s.object_name.clone(), pass(s))).collect();
+        self.dop_above = dop_above;
+        self.counts = counts;
+        self.day = day;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_core::{classify::OrbitClass, urban::LosState};
+
+    fn sat(norad_id: u64, constellation: u8, el_deg: f32) -> SkySat {
+        SkySat {
+            name: format!("G{norad_id:02}"),
+            object_name: format!("SAT {norad_id}"),
+            norad_id,
+            sv_id: Some(norad_id as u16),
+            prn: None,
+            svn: None,
+            block: None,
+            freq_channel: None,
+            healthy: None,
+            sbas_provider: None,
+            walker: None,
+            constellation,
+            orbit_class: OrbitClass::Meo,
+            orbit_label: "MEO",
+            az_deg: 90.0,
+            el_deg,
+            visible: el_deg >= 5.0,
+            los_state: LosState::Los,
+            c_n0_penalty_db: 0.0,
+            nlos_excess_m: 0.0,
+            r: 0,
+            g: 0,
+            b: 0,
+            c_n0: 45.0,
+            signals: Vec::new(),
+            iono_klobuchar_m: 0.0,
+            iono_nequick_m: 0.0,
+            tropo_m: 0.0,
+        }
+    }
+
+    fn dop(pdop: f64) -> Dop {
+        Dop { gdop: pdop, pdop, hdop: pdop, vdop: pdop, tdop: pdop, n_sats: 4 }
+    }
+
+    /// The first check is a silent baseline; later checks report rises,
+    /// sets, count changes, DOP crossings and midnight.
+    #[test]
+    fn test_detects_edges() {
+        let mut det = EventDetector::default();
+        let mut out = Vec::new();
+        let t = 1_705_276_700.0; // 2024-01-14 23:58:20Z
+        det.check(t, &[sat(1, 0, 30.0), sat(2, 0, 3.0)], Some(&dop(2.0)), &mut out);
+        assert!(out.is_empty());
+
+        det.check(t + 60.0, &[sat(1, 0, 31.0), sat(2, 0, 6.0)], Some(&dop(2.0)), &mut out);
+        assert_eq!(out.len(), 2);
+        assert!(matches!(&out[0], ViewerEvent::Rise(p) if p.norad_id == 2 && p.name == "G02"));
+        assert!(matches!(&out[1], ViewerEvent::Count { total: 2, .. }));
+
+        out.clear();
+        det.check(t + 120.0, &[sat(2, 0, 8.0), sat(3, 2, 2.0)], None, &mut out);
+        let kinds: Vec<&str> = out.iter().map(ViewerEvent::kind).collect();
+        assert_eq!(kinds, ["set", "dop", "count", "day"]);
+        assert!(matches!(&out[0], ViewerEvent::Set(p) if p.name == "G01" && p.el_deg == 31.0));
+        assert!(matches!(&out[1], ViewerEvent::Dop { pdop: None, above: true, .. }));
+        assert!(matches!(&out[3], ViewerEvent::Day { date, .. } if date == "2024-01-15"));
+
+        // Unchanged sky: nothing. After reset: a silent baseline again.
+        out.clear();
+        det.check(t + 180.0, &[sat(2, 0, 9.0)], None, &mut out);
+        assert!(out.is_empty());
+        det.reset();
+        det.check(t + 240.0, &[sat(4, 1, 40.0)], Some(&dop(1.5)), &mut out);
+        assert!(out.is_empty());
+    }
+
+    /// Through the viewer state: a LEO Walker shell rises and sets over an
+    /// hour of ticks, and a catalogue load is announced.
+    #[test]
+    fn test_viewer_queues_events() {
+        crate::set_ground_location(45.0, 10.0);
+        crate::set_sim_epoch(1_705_320_000.0);
+        assert_eq!(crate::add_walker(r#"{"name": "LEO", "notation": "53:72/6/1", "alt_km": 550}"#), 0);
+        let tick = || crate::STATE.with(|s| crate::detect_events(&mut s.borrow_mut()));
+
+        tick();
+        assert!(crate::take_events().is_empty(), "detection is off by default");
+        crate::set_event_detection(true);
+        for minute in 0..60 {
+            crate::set_sim_epoch(1_705_320_000.0 + minute as f64 * 60.0);
+            tick();
+        }
+        let events = crate::take_events();
+        assert!(events.iter().any(|e| matches!(e, ViewerEvent::Rise(p) if p.name.starts_with("LEO"))));
+        assert!(events.iter().any(|e| e.kind() == "set"));
+        assert!(events.iter().any(|e| e.kind() == "count"));
+        assert!(crate::take_events().is_empty());
+
+        let tle = "ISS (ZARYA)
+1 25544U 98067A   24015.50000000  .00016717  00000-0  10270-3 0  9002
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50000000 12345
+";
+        assert_eq!(crate::inject_tle_text(tle), 1);
+        assert_eq!(crate::take_events(), [ViewerEvent::TlesLoaded { count: 1 }]);
+        crate::set_event_detection(false);
+        crate::clear_walkers();
+    }
+
+    /// Hiding every constellation changes the view, not the sky: no sets,
+    /// no count change, and the sky plot data is empty meanwhile.
+    #[test]
+    fn test_toggles_do_not_fire_events() {
+        crate::set_ground_location(45.0, 10.0);
+        crate::set_sim_epoch(1_705_320_000.0);
+        assert_eq!(crate::add_walker(r#"{"name": "LEO", "notation": "53:72/6/1", "alt_km": 550}"#), 0);
+        let tick = || crate::STATE.with(|s| crate::detect_events(&mut s.borrow_mut()));
+        crate::set_event_detection(true);
+        tick();
+        assert!(crate::STATE.with(|s| crate::sky_sats(&s.borrow()).iter().any(|s| s.visible)));
+
+        for c in 0..CONSTELLATION_COUNT as u32 {
+            crate::toggle_constellation(c, false);
+        }
+        tick();
+        assert!(crate::take_events().is_empty());
+        assert!(crate::STATE.with(|s| crate::sky_sats(&s.borrow()).is_empty()));
+
+        for c in 0..CONSTELLATION_COUNT as u32 {
+            crate::toggle_constellation(c, true);
+        }
+        crate::set_event_detection(false);
+        crate::clear_walkers();
+    }
+
+    #[test]
+    fn test_event_json_shape() {
+        let ev = ViewerEvent::TlesLoaded { count: 31 };
+        assert_eq!(serde_json::to_string(&ev).unwrap(), r#"{"kind":"tles_loaded","count":31}"#);
+        let ev = ViewerEvent::Rise(PassEvent {
+            unix_s: 1.0,
+            name: "G05".into(),
+            norad_id: 5,
+            constellation: 0,
+            az_deg: 90.0,
+            el_deg: 5.5,
+        });
+        let json = serde_json::to_string(&ev).unwrap();
+        assert!(json.starts_with(r#"{"kind":"rise","unix_s":1.0,"name":"G05""#), "{json}");
+        assert!(EVENT_KINDS.contains(&ev.kind()));
+    }
+}
@@ -0,0 +1,176 @@
+// headless.rs — Native offscreen rendering without a window
+//
+// Responsibilities:
+//   - Create a surfaceless EGL OpenGL context on the first usable device
+//     (GPU or Mesa llvmpipe), no display server required
+//   - Render the current `STATE` once at a chosen size and camera pose and
+//     return PNG bytes, for tests, reports and the desktop `--png` mode
+//
+// Native only. three-d's own `HeadlessContext` needs glutin 0.29; this uses
+// the glutin 0.30 EGL API that three-d's window already links.
+
+use std::ffi::CStr;
+use std::sync::Arc;
+
+use glutin::api::egl::{context::PossiblyCurrentContext, device::Device, display::Display};
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glutin::context::ContextAttributesBuilder;
+use glutin::prelude::*;
+use three_d::{Context, Viewport};
+
+use crate::{capture, propagate_ecef_into, STATE};
+use crate::scene::{CameraPose, Scene};
+
+/// A current GL context with no window or surface behind it. Derefs to the
+/// three-d `Context`; dropping it releases the EGL context.
+pub struct HeadlessContext {
+    context: Context,
+    _egl: PossiblyCurrentContext,
+}
+
+impl HeadlessContext {
+    /// Make a surfaceless context current on this thread, trying each EGL
+    /// device in turn. Set `LIBGL_ALWAYS_SOFTWARE=1` to force llvmpipe.
+    pub fn new() -> Result<Self, String> {
+        let devices = Device::query_devices().map_err(|e| format!("no EGL devices: {e}"))?;
+        let mut last_err = "no EGL devices".to_string();
+        for device in devices {
+            match Self::on_device(&device) {
+                Ok(ctx) => return Ok(ctx),
+                Err(e) => last_err = e,
+            }
+        }
+        Err(last_err)
+    }
+
+    fn on_device(device: &Device) -> Result<Self, String> {
+        let name = device.name().unwrap_or("EGL device").to_string();
+        let err = |e: glutin::error::Error| format!("{name}: {e}");
+        // SAFETY: no native display is passed; the device comes from EGL itself.
+        let display = unsafe { Display::with_device(device, None) }.map_err(err)?;
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .with_depth_size(24)
+            .build();
+        // SAFETY: the template asks for no native window.
+        let config = unsafe { display.find_configs(template) }
+            .map_err(err)?
+            .next()
+            .ok_or_else(|| format!("{name}: no usable config"))?;
+        // SAFETY: no raw window handle; the config belongs to `display`.
+        let egl = unsafe { display.create_context(&config, &ContextAttributesBuilder::new().build(None)) }
+            .map_err(err)?
+            .make_current_surfaceless()
+            .map_err(err)?;
+        // SAFETY: the context is current on this thread for as long as `egl` lives.
+        let gl = unsafe {
+            three_d::context::Context::from_loader_function_cstr(|s: &CStr| display.get_proc_address(s))
+        };
+        let context = Context::from_gl_context(Arc::new(gl)).map_err(|e| format!("{name}: {e}"))?;
+        Ok(HeadlessContext { context, _egl: egl })
+    }
+}
+
+impl std::ops::Deref for HeadlessContext {
+    type Target = Context;
+    fn deref(&self) -> &Context {
+        &self.context
+    }
+}
+
+/// Render the current `STATE` — sim epoch, observer, overlays, loaded data
+/// — from `pose` into a `width` × `height` PNG.
+pub fn render_png(width: u32, height: u32, pose: CameraPose) -> Result<Vec<u8>, String> {
+    // Positions must match the sim epoch even while paused
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let st = &mut *st;
+        propagate_ecef_into(&st.tle_store, st.sim_epoch, &mut st.sat_ecef_km);
+    });
+    let context = HeadlessContext::new()?;
+    let mut scene = Scene::new(&context, Viewport::new_at_origo(width, height));
+    scene.pose = pose;
+    scene.update(0.0, Viewport::new_at_origo(width, height));
+    capture::encode_png(width, height, &scene.render_rgba(width, height))
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: u32 = 160;
+    const H: u32 = 120;
+
+    /// Render the default scene (Phase-1 constellations) from `pose`.
+    fn render(pose: CameraPose) -> Vec<u8> {
+        crate::set_sim_epoch(1_705_320_000.0);
+        let png = render_png(W, H, pose).expect("headless render");
+        let (w, h, rgba) = capture::decode_png(&png).unwrap();
+        assert_eq!((w, h), (W, H));
+        rgba
+    }
+
+    fn pixel(rgba: &[u8], x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * W + x) * 4) as usize;
+        [rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]]
+    }
+
+    /// The globe fills the centre of a close-up; the corners show the
+    /// near-black background.
+    #[test]
+    fn test_renders_globe() {
+        let rgba = render(CameraPose { az: 0.0, el: 0.0, dist: 3.0 });
+        assert_eq!(pixel(&rgba, 0, 0), [3, 3, 3, 255]);
+        let [r, g, b, a] = pixel(&rgba, W / 2, H / 2 + 5);
+        assert!(g > r && g > b && a == 255, "centre {:?}", [r, g, b]);
+    }
+
+    /// Same state, same pixels — the basis for pixel-diff regression tests.
+    /// Turning on the elevation cone changes the image.
+    #[test]
+    fn test_deterministic_and_overlay_sensitive() {
+        let pose = CameraPose::default();
+        let a = render(pose);
+        let b = render(pose);
+        assert_eq!(capture::diff_fraction(&a, &b, 0), 0.0);
+
+        crate::set_ground_location(30.0, 0.0);
+        crate::set_show_elev_cone(true);
+        let c = render(CameraPose { az: 0.0, el: 0.5, dist: 8.0 });
+        crate::set_show_elev_cone(false);
+        let d = render(CameraPose { az: 0.0, el: 0.5, dist: 8.0 });
+        assert!(capture::diff_fraction(&c, &d, 0) > 0.001);
+    }
+
+    /// Stepped captures advance the epoch by exactly the step, one per
+    /// frame, and render at the requested size rather than the scene's.
+    #[test]
+    fn test_capture_frame_steps_epoch() {
+        let t0 = 1_705_320_000.0;
+        crate::set_sim_epoch(t0);
+        assert!(crate::capture_frame(W, H).is_empty(), "no scene yet");
+
+        let context = HeadlessContext::new().expect("headless context");
+        let scene = Scene::new(&context, Viewport::new_at_origo(32, 32));
+        crate::SCENE.with(|s| *s.borrow_mut() = Some(scene));
+
+        crate::set_frame_step(600.0);
+        let first = crate::capture_frame(W, H);
+        let second = crate::capture_frame(W, H);
+        assert_eq!(crate::get_sim_epoch(), t0 + 1200.0);
+        let (w, h, a) = capture::decode_png(&first).unwrap();
+        let (_, _, b) = capture::decode_png(&second).unwrap();
+        assert_eq!((w, h), (W, H));
+        assert!(capture::diff_fraction(&a, &b, 0) > 0.0, "satellites moved");
+
+        // Wall-clock mode: capturing leaves the epoch alone
+        crate::set_frame_step(0.0);
+        crate::capture_frame(W, H);
+        assert_eq!(crate::get_sim_epoch(), t0 + 1200.0);
+        crate::SCENE.with(|s| s.borrow_mut().take());
+    }
+}
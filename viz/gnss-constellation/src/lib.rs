@@ -1,14 +1,22 @@
-mod coords;
-mod tles;
-mod ground;
+mod worker;
 pub mod borders;
+pub mod capture;
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+pub mod scenario;
+pub mod scene;
 
 use std::cell::RefCell;
-use std::f32::consts::PI;
-use three_d::*;
+use std::collections::{BTreeMap, VecDeque};
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
+use gnss_core::{
+    atmosphere, catalog, classify, coords, czml, dop, gis, ground, horizon, interp, linkbudget, nmea, passes, replay,
+    rinex, satbuf, station, tles, trajectory, urban, walker,
+};
+use classify::{OrbitClass, ORBIT_CLASS_COUNT};
 use ground::Observer;
+use satbuf::SatPositions;
 use tles::TleStore;
 
 // ── State ─────────────────────────────────────────────────────────────────────
@@ -19,28 +27,107 @@ struct GnssState {
     sim_epoch: f64,
     paused: bool,
     visible_only: bool,
-    /// Indexed by tles::CONSTELLATION_* constants (0=GPS … 6=Other).
-    constellation_visible: [bool; 7],
-    /// -1 = none highlighted; 0-6 = one constellation highlighted.
+    /// Indexed by tles::CONSTELLATION_* constants (0=GPS … 6=Other, 7=SBAS).
+    constellation_visible: [bool; tles::CONSTELLATION_COUNT],
+    /// Per-constellation MEO / IGSO / GEO toggles, indexed by `OrbitClass::index`.
+    orbit_class_visible: [[bool; classify::ORBIT_CLASS_COUNT]; tles::CONSTELLATION_COUNT],
+    /// -1 = none highlighted; 0-7 = one constellation highlighted.
     highlighted: i32,
-    /// Most-recent per-satellite ECEF positions (km) from TLE propagation.
-    sat_ecef_km: Vec<(u8, [f64; 3])>,
+    /// Most-recent per-satellite ECEF positions (km) from TLE propagation,
+    /// refilled in place each propagation tick.
+    sat_ecef_km: SatPositions,
+    /// True when satellite positions or the display filters (toggles,
+    /// observer, masks, buildings) changed and the instance buffers must be
+    /// re-bucketed. Cleared by the render loop.
+    instances_dirty: bool,
+    /// Interpolate TLE positions from samples pushed by the propagation
+    /// worker instead of running SGP4 on the render thread.
+    worker_propagation: bool,
+    /// Worker snapshots, reset whenever the TLE set changes.
+    prop_samples: interp::SampleRing,
+    /// Bumped on every store replacement; worker samples carry the
+    /// generation they were propagated for and stale ones are rejected.
+    tle_generation: u32,
+    /// Text `tle_store` was loaded from (OMM JSON or two/three-line sets),
+    /// re-posted to the propagation worker after a replacement.
+    tle_source: String,
+    /// Draw satellites as camera-facing point sprites instead of instanced
+    /// spheres; forced on above `POINT_SPRITE_AUTO` satellites.
+    point_sprites: bool,
     /// Simulation time acceleration (e.g. 120 = 2 min real time per sim second).
     time_warp: f64,
     /// Configurable elevation mask in degrees (default 5.0). Replaces the old hardcoded 5.0.
     elev_mask_deg: f64,
+    /// Optional per-azimuth site horizon, applied on top of `elev_mask_deg`.
+    horizon: Option<horizon::HorizonMask>,
+    /// Optional extruded building footprints for urban-canyon ray casting.
+    urban: Option<urban::UrbanScene>,
+    /// Antenna height above street level for building ray casts, metres.
+    antenna_height_m: f64,
+    /// Broadcast ionospheric coefficients for the per-satellite delay models.
+    atmo: atmosphere::AtmoConfig,
+    /// Receive antenna and noise temperature for the C/N0 link budget.
+    receiver: linkbudget::ReceiverConfig,
+    /// Loaded NMEA log for replay; drives `sim_epoch` and the observer.
+    replay: Option<replay::ReplayLog>,
+    /// Current epoch index into `replay`.
+    replay_idx: usize,
+    /// Moving-observer route; sampled at `sim_epoch` to place the observer.
+    trajectory: Option<trajectory::Trajectory>,
+    /// Vehicle state at `sim_epoch` while a trajectory is loaded.
+    pose: Option<trajectory::Pose>,
+    /// Body-frame antenna mask, degrees above the vehicle's roof plane;
+    /// `None` ignores the attitude.
+    body_mask_deg: Option<f64>,
+    /// NMEA satellite ID per TLE record, then per Walker slot (see
+    /// `sat_sv_ids`); refreshed when the store, the catalog or the Walker
+    /// constellations change, not per frame.
+    sv_ids: Vec<Option<u16>>,
+    /// Loaded RINEX 3 observation file for tracked-vs-predicted comparison.
+    rinex: Option<rinex::RinexObs>,
+    /// `get_rinex_availability` result for `rinex`, filled on first request
+    /// and dropped when the file, the elements or the catalog change.
+    rinex_availability: Option<Vec<rinex::SatAvailability>>,
+    /// NORAD → PRN / SVN / block / channel / health identities.
+    catalog: catalog::Catalog,
+    /// Label sky-plot satellites by PRN ("G05") instead of the object name.
+    prn_labels: bool,
+    /// User-defined Walker constellations, drawn and included in the sky
+    /// data and DOP alongside the TLE or Phase-1 satellites.
+    walkers: Vec<walker::Walker>,
+    /// True when `walkers` changed and the render meshes need rebuilding.
+    walkers_dirty: bool,
+    /// Named observer sites (reference stations) besides `observer`, each
+    /// with its own marker colour and elevation mask.
+    stations: Vec<station::Station>,
+    /// True when `stations` changed and the markers need rebuilding.
+    stations_dirty: bool,
     /// Visibility toggles for overlay objects.
     show_inc_rings: bool,
     show_ecef_axes: bool,
     show_eci_axes: bool,
     show_borders: bool,
     show_elev_cone: bool,
+    show_ground_tracks: bool,
     /// Injected country border JSON. Set by inject_borders(), consumed by render loop.
     borders_json: Option<String>,
     /// True when borders_json was updated but the mesh hasn't been rebuilt yet.
     borders_dirty: bool,
     /// True when the elevation cone mesh needs to be rebuilt.
     cone_needs_rebuild: bool,
+    /// Rise/set, DOP, count and day-rollover detection, run on each
+    /// propagation tick while `event_detection` is on (a JS listener is
+    /// registered, or a native front end asked for it).
+    event_detector: events::EventDetector,
+    event_detection: bool,
+    /// Detected events waiting for dispatch (`on_event`) or `take_events`.
+    event_queue: VecDeque<events::ViewerEvent>,
+    /// Where each bulk data set came from ("elements" → URL or path), so a
+    /// scenario can name its data without embedding it.
+    data_sources: BTreeMap<String, String>,
+    /// Deterministic frame stepping: when > 0 the wall clock no longer moves
+    /// `sim_epoch`; each `capture_frame` advances it by this many seconds.
+    frame_step_s: f64,
     /// Most-recent camera view-projection matrix (column-major, 16 f32s).
     /// Updated every frame. Used by JS for screen-space axis label projection.
     camera_vp: [f32; 16],
@@ -54,19 +141,51 @@ impl Default for GnssState {
             sim_epoch: 0.0,
             paused: false,
             visible_only: false,
-            constellation_visible: [true; 7],
+            constellation_visible: [true; tles::CONSTELLATION_COUNT],
+            orbit_class_visible: [[true; classify::ORBIT_CLASS_COUNT]; tles::CONSTELLATION_COUNT],
             highlighted: -1,
-            sat_ecef_km: Vec::new(),
+            sat_ecef_km: SatPositions::default(),
+            instances_dirty: true,
+            worker_propagation: false,
+            prop_samples: interp::SampleRing::default(),
+            tle_generation: 0,
+            tle_source: String::new(),
+            point_sprites: false,
             time_warp: 120.0,
             elev_mask_deg: 5.0,
+            horizon: None,
+            urban: None,
+            antenna_height_m: 1.5,
+            atmo: atmosphere::AtmoConfig::default(),
+            receiver: linkbudget::ReceiverConfig::default(),
+            replay: None,
+            replay_idx: 0,
+            trajectory: None,
+            pose: None,
+            body_mask_deg: None,
+            sv_ids: Vec::new(),
+            rinex: None,
+            rinex_availability: None,
+            catalog: catalog::Catalog::default(),
+            prn_labels: true,
+            walkers: Vec::new(),
+            walkers_dirty: false,
+            stations: Vec::new(),
+            stations_dirty: false,
             show_inc_rings: true,
             show_ecef_axes: false,
             show_eci_axes: false,
             show_borders: true,
             show_elev_cone: false,
+            show_ground_tracks: true,
             borders_json: None,
             borders_dirty: false,
             cone_needs_rebuild: true,
+            event_detector: events::EventDetector::default(),
+            event_detection: false,
+            event_queue: VecDeque::new(),
+            data_sources: BTreeMap::new(),
+            frame_step_s: 0.0,
             camera_vp: [0.0f32; 16],
         }
     }
@@ -74,6 +193,9 @@ impl Default for GnssState {
 
 thread_local! {
     static STATE: RefCell<GnssState> = RefCell::new(GnssState::default());
+    /// The browser scene, created by `start()`. Kept here rather than in the
+    /// render-loop closure so `capture_frame` can render it between frames.
+    static SCENE: RefCell<Option<scene::Scene>> = const { RefCell::new(None) };
 }
 
 // ── WASM exports ──────────────────────────────────────────────────────────────
@@ -83,14 +205,39 @@ pub fn set_ground_location(lat: f64, lon: f64) {
     STATE.with(|s| {
         let mut st = s.borrow_mut();
         st.observer = Observer::new(lat, lon);
+        // Placing the observer by hand stops it following a route
+        st.trajectory = None;
+        st.pose = None;
         st.cone_needs_rebuild = true;
+        st.instances_dirty = true;
+        // A new site is not a sky full of rising satellites
+        st.event_detector.reset();
     });
 }
 
 #[wasm_bindgen]
 pub fn toggle_constellation(idx: u32, on: bool) {
-    if idx < 7 {
-        STATE.with(|s| s.borrow_mut().constellation_visible[idx as usize] = on);
+    if (idx as usize) < tles::CONSTELLATION_COUNT {
+        STATE.with(|s| {
+            let mut st = s.borrow_mut();
+            st.constellation_visible[idx as usize] = on;
+            st.instances_dirty = true;
+            st.event_detector.reset();
+        });
+    }
+}
+
+/// Show or hide one orbit type of a constellation: `class` 0 = MEO,
+/// 1 = IGSO (QZSS QZO, NavIC GSO), 2 = GEO.
+#[wasm_bindgen]
+pub fn toggle_orbit_class(idx: u32, class: u32, on: bool) {
+    if let (true, Some(oc)) = ((idx as usize) < tles::CONSTELLATION_COUNT, OrbitClass::from_index(class as usize)) {
+        STATE.with(|s| {
+            let mut st = s.borrow_mut();
+            st.orbit_class_visible[idx as usize][oc.index()] = on;
+            st.instances_dirty = true;
+            st.event_detector.reset();
+        });
     }
 }
 
@@ -101,7 +248,11 @@ pub fn set_highlighted_constellation(idx: i32) {
 
 #[wasm_bindgen]
 pub fn set_visible_only(on: bool) {
-    STATE.with(|s| s.borrow_mut().visible_only = on);
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.visible_only = on;
+        st.instances_dirty = true;
+    });
 }
 
 #[wasm_bindgen]
@@ -120,6 +271,109 @@ pub fn set_elev_mask(v: f64) {
         let mut st = s.borrow_mut();
         st.elev_mask_deg = v.clamp(0.0, 89.0);
         st.cone_needs_rebuild = true;
+        st.instances_dirty = true;
+    });
+}
+
+/// Inject GPS broadcast Klobuchar coefficients (4 × alpha, 4 × beta).
+/// Ignored unless both arrays have exactly four entries.
+#[wasm_bindgen]
+pub fn set_klobuchar_coeffs(alpha: Vec<f64>, beta: Vec<f64>) {
+    if let (Ok(alpha), Ok(beta)) = (<[f64; 4]>::try_from(alpha), <[f64; 4]>::try_from(beta)) {
+        STATE.with(|s| s.borrow_mut().atmo.klobuchar = atmosphere::KlobucharCoeffs { alpha, beta });
+    }
+}
+
+/// Inject Galileo broadcast effective-ionisation coefficients (ai0, ai1, ai2).
+#[wasm_bindgen]
+pub fn set_nequick_coeffs(ai0: f64, ai1: f64, ai2: f64) {
+    STATE.with(|s| s.borrow_mut().atmo.nequick = atmosphere::NequickCoeffs { ai0, ai1, ai2 });
+}
+
+/// Select a receive antenna preset: "isotropic", "patch" or "choke_ring".
+/// Unknown names leave the current pattern unchanged.
+#[wasm_bindgen]
+pub fn set_antenna_preset(name: &str) {
+    if let Some(p) = linkbudget::AntennaPattern::preset(name) {
+        STATE.with(|s| s.borrow_mut().receiver.antenna = p);
+    }
+}
+
+/// Inject a custom antenna gain table (dBic at each elevation in degrees).
+#[wasm_bindgen]
+pub fn set_antenna_gain_table(el_deg: Vec<f64>, gain_dbi: Vec<f64>) {
+    if let Some(p) = linkbudget::AntennaPattern::custom(&el_deg, &gain_dbi) {
+        STATE.with(|s| s.borrow_mut().receiver.antenna = p);
+    }
+}
+
+/// Set the receiver system noise temperature in kelvin (default 290 K).
+#[wasm_bindgen]
+pub fn set_receiver_noise_temp(kelvin: f64) {
+    STATE.with(|s| s.borrow_mut().receiver.noise_temp_k = kelvin.max(1.0));
+}
+
+/// Load a site horizon profile: a JSON array of bin elevations (or
+/// `{az, el}` objects), or CSV with one elevation or one `az,el` pair per line.
+/// Returns `false` and keeps the previous profile if parsing fails.
+#[wasm_bindgen]
+pub fn inject_horizon_profile(text: &str) -> bool {
+    match horizon::HorizonMask::parse(text) {
+        Some(mask) => {
+            STATE.with(|s| {
+                let mut st = s.borrow_mut();
+                st.horizon = Some(mask);
+                st.cone_needs_rebuild = true;
+                st.instances_dirty = true;
+            });
+            true
+        }
+        None => false,
+    }
+}
+
+/// Remove the site horizon profile; only the global elevation mask remains.
+#[wasm_bindgen]
+pub fn clear_horizon_profile() {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.horizon = None;
+        st.cone_needs_rebuild = true;
+        st.instances_dirty = true;
+    });
+}
+
+/// Load building footprints (GeoJSON FeatureCollection of Polygon /
+/// MultiPolygon features with a `height` or `building:levels` property).
+/// Returns the number of buildings loaded; 0 leaves no scene active.
+#[wasm_bindgen]
+pub fn inject_buildings(geojson: &str) -> u32 {
+    let scene = urban::UrbanScene::from_geojson(geojson).ok().filter(|u| !u.is_empty());
+    let count = scene.as_ref().map(|u| u.buildings.len() as u32).unwrap_or(0);
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.urban = scene;
+        st.instances_dirty = true;
+    });
+    count
+}
+
+#[wasm_bindgen]
+pub fn clear_buildings() {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.urban = None;
+        st.instances_dirty = true;
+    });
+}
+
+/// Antenna height above street level used for building ray casts (default 1.5 m).
+#[wasm_bindgen]
+pub fn set_antenna_height(m: f64) {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.antenna_height_m = m.max(0.0);
+        st.instances_dirty = true;
     });
 }
 
@@ -128,6 +382,24 @@ pub fn set_show_inc_rings(on: bool) {
     STATE.with(|s| s.borrow_mut().show_inc_rings = on);
 }
 
+/// Draw satellites as point sprites (one camera-facing quad each) rather
+/// than instanced spheres — much cheaper for LEO mega-constellations.
+/// Always on above 5000 satellites.
+#[wasm_bindgen]
+pub fn set_point_sprites(on: bool) {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.point_sprites = on;
+        st.instances_dirty = true;
+    });
+}
+
+/// Sub-satellite ground tracks of the IGSO / QZO / GSO satellites.
+#[wasm_bindgen]
+pub fn set_show_ground_tracks(on: bool) {
+    STATE.with(|s| s.borrow_mut().show_ground_tracks = on);
+}
+
 #[wasm_bindgen]
 pub fn set_show_ecef_axes(on: bool) {
     STATE.with(|s| s.borrow_mut().show_ecef_axes = on);
@@ -161,846 +433,1606 @@ pub fn inject_borders(json: &str) {
     });
 }
 
-/// Returns the number of TLE satellite records currently loaded.
-/// Call after inject_tles() to verify the JSON was successfully parsed.
-/// Returns 0 if inject_tles() has not been called or if the JSON failed to parse.
+/// Merge a satellite identity catalog: a JSON array of `{ norad_id, prn,
+/// svn, block, freq_channel, healthy }` objects (all but `norad_id`
+/// optional; `prn` is RINEX style, e.g. "G05", "R07" for GLONASS slot 7).
+/// Later injections override earlier entries for the same NORAD ID.
+/// Returns the total catalog size, or 0 if the JSON fails to parse.
 #[wasm_bindgen]
-pub fn get_tle_count() -> u32 {
-    STATE.with(|s| s.borrow().tle_store.records.len() as u32)
+pub fn inject_catalog(json: &str) -> u32 {
+    let Ok(cat) = catalog::Catalog::from_json(json) else {
+        return 0;
+    };
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.catalog.merge(cat);
+        st.sv_ids = sat_sv_ids(&st);
+        st.rinex_availability = None;
+        st.catalog.len() as u32
+    })
 }
 
-/// Returns the current camera view-projection matrix as a Vec of 16 f64 values (column-major).
-/// Each frame this is updated by the render loop. Used by JS for screen-space axis label projection.
 #[wasm_bindgen]
-pub fn get_camera_vp_matrix() -> Vec<f64> {
-    STATE.with(|s| s.borrow().camera_vp.iter().map(|&x| x as f64).collect())
+pub fn clear_catalog() {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.catalog = catalog::Catalog::default();
+        st.sv_ids = sat_sv_ids(&st);
+        st.rinex_availability = None;
+    });
 }
 
+/// Label satellites by PRN (default) or by Celestrak object name.
 #[wasm_bindgen]
-pub fn get_sim_epoch() -> f64 {
-    STATE.with(|s| s.borrow().sim_epoch)
+pub fn set_prn_labels(on: bool) {
+    STATE.with(|s| s.borrow_mut().prn_labels = on);
 }
 
+/// Add a Walker Delta / Star constellation from a JSON definition, e.g.
+/// `{ "name": "LEO-PNT", "notation": "87.9:240/12/1", "alt_km": 1100,
+///    "pattern": "star", "color": [255, 255, 255] }`.
+/// Returns its index, or -1 if the definition is invalid.
 #[wasm_bindgen]
-pub fn set_sim_epoch(unix_s: f64) {
-    STATE.with(|s| s.borrow_mut().sim_epoch = unix_s);
+pub fn add_walker(json: &str) -> i32 {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let Ok(w) = walker::Walker::from_json(json, st.sim_epoch) else {
+            return -1;
+        };
+        st.walkers.push(w);
+        st.walkers_dirty = true;
+        st.sv_ids = sat_sv_ids(&st);
+        st.walkers.len() as i32 - 1
+    })
 }
 
 #[wasm_bindgen]
-pub fn inject_tles(json: &str) {
+pub fn remove_walker(idx: u32) {
     STATE.with(|s| {
         let mut st = s.borrow_mut();
-        // Clear previous records so a fresh fetch replaces stale data
-        st.tle_store = TleStore::new();
-        match st.tle_store.load_from_json(json) {
-            Ok(_) => {}
-            Err(_) => {}
+        if (idx as usize) < st.walkers.len() {
+            st.walkers.remove(idx as usize);
+            st.walkers_dirty = true;
+            st.sv_ids = sat_sv_ids(&st);
         }
     });
 }
 
-/// Returns a JS Array of sky-plot entries for the current sim epoch.
-/// Each entry: `{ name, constellation, az_deg, el_deg, r, g, b, c_n0 }`
 #[wasm_bindgen]
-pub fn get_sky_data() -> JsValue {
+pub fn clear_walkers() {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.walkers.clear();
+        st.walkers_dirty = true;
+        st.sv_ids = sat_sv_ids(&st);
+    });
+}
+
+#[wasm_bindgen]
+pub fn set_walker_enabled(idx: u32, on: bool) {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        if let Some(w) = st.walkers.get_mut(idx as usize) {
+            w.enabled = on;
+            st.instances_dirty = true;
+        }
+    });
+}
+
+/// JS Array of the Walker definitions with `enabled`, `satellites`,
+/// `orbit_class` and `period_min`.
+#[wasm_bindgen]
+pub fn get_walkers() -> JsValue {
     STATE.with(|s| {
         let st = s.borrow();
-        // Observer ECEF position in km (unit vector × Earth radius)
-        let u = st.observer.ecef_unit();
-        let obs_km = [u[0] * 6371.0, u[1] * 6371.0, u[2] * 6371.0];
+        let list: Vec<walker::WalkerSummary> = st.walkers.iter().map(|w| w.summary()).collect();
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(&list, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
 
-        let sky_sats: Vec<ground::SkySat> = st
-            .sat_ecef_km
-            .iter()
-            .enumerate()
-            .filter_map(|(sat_idx, (c_idx, pos_km))| {
-                let ci = *c_idx as usize;
-                if !st.constellation_visible.get(ci).copied().unwrap_or(false) {
-                    return None;
-                }
-                let (az, el) = coords::az_el(obs_km, *pos_km);
-                if el < 0.0 {
-                    return None; // below horizon
-                }
-                if st.visible_only && el < st.elev_mask_deg {
-                    return None;
-                }
-                let [r, g, b] = ground::constellation_color(*c_idx);
-                let c_n0 = ground::simulate_c_n0(
-                    el as f32,
-                    st.observer.lat_deg,
-                    st.observer.lon_deg,
-                    st.sim_epoch,
-                    *c_idx,
-                    sat_idx,
-                );
-                // sat_ecef_km is populated in tle_store.records order,
-                // so sat_idx directly indexes the matching TLE record.
-                let name = st.tle_store.records
-                    .get(sat_idx)
-                    .map(|r| r.name.clone())
-                    .unwrap_or_default();
-                Some(ground::SkySat {
-                    name,
-                    constellation: *c_idx,
-                    az_deg: az as f32,
-                    el_deg: el as f32,
-                    r,
-                    g,
-                    b,
-                    c_n0,
-                })
-            })
-            .collect();
+// ── Stations ──────────────────────────────────────────────────────────────────
 
-        ground::sky_plot_jsvalue(&sky_sats)
+/// Add a named observer site from JSON, e.g. `{ "name": "BASE-1",
+/// "lat_deg": 41.85, "lon_deg": -87.65, "color": [255, 200, 0],
+/// "elev_mask_deg": 10 }`. A station with the same name is replaced.
+/// Returns its index, or -1 if the definition is invalid.
+#[wasm_bindgen]
+pub fn add_station(json: &str) -> i32 {
+    let Ok(stn) = station::Station::from_json(json) else {
+        return -1;
+    };
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.stations_dirty = true;
+        match st.stations.iter().position(|o| o.name == stn.name) {
+            Some(i) => {
+                st.stations[i] = stn;
+                i as i32
+            }
+            None => {
+                st.stations.push(stn);
+                st.stations.len() as i32 - 1
+            }
+        }
     })
 }
 
-// ── Phase-1 constellation definitions (Keplerian fallback sim) ────────────────
-
-struct ConstellationDef {
-    rgb: [u8; 3],
-    alt_km: f32,
-    inc_deg: f32,
-    planes: u32,
-    sats_per_plane: u32,
-    raan_spacing_deg: f32,
-    raan_offset_deg: f32,
-}
-
-const SATS: &[ConstellationDef] = &[
-    ConstellationDef { rgb: [57,  255, 20],  alt_km: 20200.0, inc_deg: 55.0, planes: 6, sats_per_plane: 4,  raan_spacing_deg: 60.0,  raan_offset_deg: 0.0  }, // GPS
-    ConstellationDef { rgb: [255, 68,  68],  alt_km: 19130.0, inc_deg: 64.8, planes: 3, sats_per_plane: 8,  raan_spacing_deg: 120.0, raan_offset_deg: 15.0 }, // GLONASS
-    ConstellationDef { rgb: [0,   255, 204], alt_km: 23222.0, inc_deg: 56.0, planes: 3, sats_per_plane: 10, raan_spacing_deg: 120.0, raan_offset_deg: 40.0 }, // Galileo
-    ConstellationDef { rgb: [255, 170, 0],   alt_km: 21528.0, inc_deg: 55.0, planes: 3, sats_per_plane: 8,  raan_spacing_deg: 120.0, raan_offset_deg: 80.0 }, // BeiDou
-];
-
-const EARTH_R: f32 = 6371.0;
-const MU: f32 = 398_600.4418;
-const RING_PTS: u32 = 120; // 120 pts/ring — visually solid, half the Mat4 cost of 240
-
-/// J2 secular nodal precession rates (rad/s) per constellation, indexed 0=GPS..3=BeiDou.
-/// dΩ/dt = -3/2 * n * J2 * (R_E/a)² * cos(i)
-/// GPS:     a=26571 km, i=55°  → -7.80e-9 rad/s
-/// GLONASS: a=25501 km, i=64.8°→ -6.69e-9 rad/s
-/// Galileo: a=29593 km, i=56°  → -5.21e-9 rad/s
-/// BeiDou:  a=27899 km, i=55°  → -6.58e-9 rad/s
-const J2_RATES: [f64; 4] = [-7.80e-9, -6.69e-9, -5.21e-9, -6.58e-9];
-
-fn alt_norm(alt_km: f32) -> f32 {
-    (EARTH_R + alt_km) / EARTH_R
-}
-
-fn period_s(alt_km: f32) -> f32 {
-    let a = EARTH_R + alt_km;
-    2.0 * PI * (a * a * a / MU).sqrt()
-}
-
-/// Keplerian position in normalised scene units (Earth radius = 1.0).
-/// Convention: Z = north pole, equatorial plane = XY.
-/// inc tilts the orbital plane from equatorial (rotation around X / line-of-nodes).
-/// raan rotates the ascending node around Z (correct J2 precession axis).
-fn kpos(r: f32, inc: f32, raan: f32, m: f32) -> Vec3 {
-    let xo = r * m.cos(); // radial in orbital plane
-    let yo = r * m.sin(); // along-track in orbital plane
-    // Apply inclination (rotation around X axis)
-    let (x1, y1, z1) = (xo, yo * inc.cos(), yo * inc.sin());
-    // Apply RAAN (rotation around Z axis — the north pole)
-    vec3(
-        x1 * raan.cos() - y1 * raan.sin(),
-        x1 * raan.sin() + y1 * raan.cos(),
-        z1,
-    )
-}
-
-struct SatState {
-    r: f32, inc: f32, rsp: f32, roff: f32, mm: f32,
-    planes: u32, sats_per_plane: u32,
-}
-
-// ── Constellation colours (per-constellation material colour in TLE mode) ─────
-
-const CONST_COLORS: [[u8; 3]; 7] = [
-    [57, 255, 20],    // GPS      (0) — neon green
-    [255, 68, 68],    // GLONASS  (1) — red
-    [0, 255, 204],    // Galileo  (2) — cyan
-    [255, 170, 0],    // BeiDou   (3) — orange
-    [160, 80, 255],   // QZSS     (4) — violet
-    [255, 80, 160],   // NavIC    (5) — magenta
-    [128, 128, 128],  // Other    (6) — grey
-];
-
-
-/// Builds a small cone tower pointing outward from the Earth surface at obs_n.
-/// Apex at 1.10 × Earth radius, base ring at 1.00 (surface), radius 0.025.
-fn build_observer_tower(obs_n: Vec3) -> CpuMesh {
-    let n = 8i32;
-    let tip_r = 1.10f32;
-    let base_r = 0.025f32;
-
-    let up_ref = if obs_n.z.abs() < 0.9 { vec3(0.0f32, 0.0, 1.0) } else { vec3(1.0f32, 0.0, 0.0) };
-    let e1 = obs_n.cross(up_ref).normalize();
-    let e2 = obs_n.cross(e1).normalize();
-
-    let tip = obs_n * tip_r;
-    let base_center = obs_n;
-
-    let mut verts: Vec<Vec3> = vec![tip]; // index 0 = tip
-    for i in 0..n {
-        let phi = i as f32 * 2.0 * PI / n as f32;
-        verts.push(obs_n + base_r * (phi.cos() * e1 + phi.sin() * e2));
-    }
-    let center_idx = verts.len() as u32;
-    verts.push(base_center);
-
-    let mut idxs: Vec<u32> = Vec::new();
-    // Side triangles: tip → next base → current base (CCW when viewed from outside)
-    for i in 1..=(n as u32) {
-        let next = if i == n as u32 { 1 } else { i + 1 };
-        idxs.push(0);     // tip
-        idxs.push(next);
-        idxs.push(i);
-    }
-    // Base cap: base_center → current → next (CCW from below, i.e., inward normal)
-    for i in 1..=(n as u32) {
-        let next = if i == n as u32 { 1 } else { i + 1 };
-        idxs.push(center_idx);
-        idxs.push(i);
-        idxs.push(next);
-    }
-
-    CpuMesh {
-        positions: Positions::F32(verts),
-        indices: Indices::U32(idxs),
-        ..Default::default()
-    }
+/// Remove the station called `name`; `false` if there is none.
+#[wasm_bindgen]
+pub fn remove_station(name: &str) -> bool {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let before = st.stations.len();
+        st.stations.retain(|o| o.name != name);
+        st.stations_dirty = true;
+        st.stations.len() < before
+    })
 }
 
-/// Builds a solid cone from the observer surface point to GPS altitude (4.17 scene units).
-/// The cone boundary is at elevation angle `elev_deg` above the local horizon.
-/// Returns a filled triangle-fan CpuMesh.
-fn build_elev_cone(obs_n: Vec3, elev_deg: f64) -> CpuMesh {
-    const N: i32 = 64;
-    const GPS_ALT_SCENE: f32 = 4.17; // (6371 + 20200) / 6371
+#[wasm_bindgen]
+pub fn clear_stations() {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.stations.clear();
+        st.stations_dirty = true;
+    });
+}
 
-    let el_rad = (elev_deg as f32).to_radians();
-    let sin_el = el_rad.sin();
-    let cos_el = el_rad.cos();
+/// JS Array of the station definitions.
+#[wasm_bindgen]
+pub fn get_stations() -> JsValue {
+    STATE.with(|s| {
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(&s.borrow().stations, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
 
-    let up_ref = if obs_n.z.abs() < 0.9 { vec3(0.0f32, 0.0, 1.0) } else { vec3(1.0f32, 0.0, 0.0) };
-    let e1 = obs_n.cross(up_ref).normalize();
-    let e2 = obs_n.cross(e1).normalize();
+/// The site called `name`: a station, or the primary observer for "".
+fn site_named<'a>(st: &'a GnssState, name: &str) -> Option<Site<'a>> {
+    if name.is_empty() {
+        return Some(Site::Primary(&st.observer));
+    }
+    st.stations.iter().find(|o| o.name == name).map(Site::Station)
+}
 
-    // Apex at Earth surface
-    let mut verts: Vec<Vec3> = vec![obs_n];
+/// `get_sky_data` as seen from station `name` with its own mask ("" for the
+/// primary observer). NULL if there is no such station.
+#[wasm_bindgen]
+pub fn get_sky_data_for(name: &str) -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let Some(site) = site_named(&st, name) else {
+            return JsValue::NULL;
+        };
+        let mut sats = sky_sats_for(&st, site, &st.sat_ecef_km, st.sim_epoch);
+        if st.visible_only {
+            sats.retain(|s| s.visible);
+        }
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(&sats, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
 
-    // Rim: ray from apex in direction `dir`, intersect sphere of radius GPS_ALT_SCENE
-    // Ray: P(t) = obs_n + t*dir, |P|² = GPS_ALT_SCENE²
-    // t² + 2t*(obs_n·dir) + 1 - GPS_ALT_SCENE² = 0
-    // obs_n·dir = sin_el (since dir = sin_el*obs_n + cos_el*(cos_phi*e1+sin_phi*e2))
-    // t = -sin_el + sqrt(sin_el² - 1 + GPS_ALT_SCENE²)
-    let discriminant = sin_el * sin_el - 1.0 + GPS_ALT_SCENE * GPS_ALT_SCENE;
-    let t_rim = if discriminant >= 0.0 { -sin_el + discriminant.sqrt() } else { GPS_ALT_SCENE };
+/// `get_dop` for station `name` ("" for the primary observer). NULL if
+/// there is no such station or fewer than four satellites are usable.
+#[wasm_bindgen]
+pub fn get_dop_for(name: &str) -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let Some(site) = site_named(&st, name) else {
+            return JsValue::NULL;
+        };
+        let sats = sky_sats_for(&st, site, &st.sat_ecef_km, st.sim_epoch);
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        used_dop(&sats)
+            .and_then(|d| serde::Serialize::serialize(&d, &serializer).ok())
+            .unwrap_or(JsValue::NULL)
+    })
+}
 
-    for i in 0..N {
-        let phi = i as f32 * 2.0 * PI / N as f32;
-        let dir = (sin_el * obs_n + cos_el * (phi.cos() * e1 + phi.sin() * e2)).normalize();
-        verts.push(obs_n + t_rim * dir);
-    }
+/// Common view between sites `a` and `b` (station names, "" for the primary
+/// observer) over the next `hours` of sim time, sampled every `step_s`
+/// (default 60 s): the baseline length and, per satellite, the windows in
+/// which it is above both masks at once. The primary observer's horizon
+/// profile applies, its buildings do not. NULL if a name is unknown.
+#[wasm_bindgen]
+pub fn get_common_view(a: &str, b: &str, hours: f64, step_s: f64) -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let (Some(site_a), Some(site_b)) = (site_named(&st, a), site_named(&st, b)) else {
+            return JsValue::NULL;
+        };
+        let start_unix = st.sim_epoch;
+        let end_unix = start_unix + hours.clamp(0.0, 24.0 * 14.0) * 3600.0;
+        let step_s = if step_s > 0.0 { step_s } else { 60.0 };
+        let report = station::CommonViewReport {
+            a: a.to_string(),
+            b: b.to_string(),
+            baseline_km: station::baseline_km(&site_a.observer(), &site_b.observer()),
+            start_unix,
+            end_unix,
+            satellites: common_view(&st, site_a, site_b, start_unix, end_unix, step_s),
+        };
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(&report, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
 
-    // Triangle fan: apex(0) → rim[i] → rim[next]
-    let mut idxs: Vec<u32> = Vec::new();
-    for i in 1..=(N as u32) {
-        let next = if i == N as u32 { 1 } else { i + 1 };
-        idxs.push(0);
-        idxs.push(i);
-        idxs.push(next);
-    }
+/// Per-satellite common-view windows of every shown TLE satellite and
+/// enabled Walker slot, ordered by the start of the first window.
+fn common_view(
+    st: &GnssState,
+    a: Site,
+    b: Site,
+    start_unix: f64,
+    end_unix: f64,
+    step_s: f64,
+) -> Vec<station::CommonView> {
+    let obs_km = |site: Site| {
+        let u = site.observer().ecef_unit();
+        [u[0] * 6371.0, u[1] * 6371.0, u[2] * 6371.0]
+    };
+    let (a_km, b_km) = (obs_km(a), obs_km(b));
+    let windows = |ecef: &dyn Fn(f64) -> [f64; 3]| {
+        let pa = passes::find_passes(
+            |t| coords::az_el(a_km, ecef(t)),
+            |az| a.mask_deg(st, az),
+            start_unix,
+            end_unix,
+            step_s,
+        );
+        if pa.is_empty() {
+            return Vec::new();
+        }
+        let pb = passes::find_passes(
+            |t| coords::az_el(b_km, ecef(t)),
+            |az| b.mask_deg(st, az),
+            start_unix,
+            end_unix,
+            step_s,
+        );
+        station::common_windows(&pa, &pb)
+    };
 
-    CpuMesh {
-        positions: Positions::F32(verts),
-        indices: Indices::U32(idxs),
-        ..Default::default()
-    }
+    let mut out: Vec<station::CommonView> = shown_sats(st)
+        .into_iter()
+        .filter_map(|sat| station::CommonView::new(sat.name, sat.norad_id, sat.constellation, windows(&sat.ecef_km)))
+        .collect();
+    out.sort_by(|x, y| x.windows[0].start_unix.total_cmp(&y.windows[0].start_unix));
+    out
 }
 
-// ── Entry point ───────────────────────────────────────────────────────────────
+// ── GIS export ────────────────────────────────────────────────────────────────
 
-#[wasm_bindgen(start)]
-pub fn start() {
-    // Seed sim epoch to current wall-clock time
-    STATE.with(|s| s.borrow_mut().sim_epoch = js_sys::Date::now() / 1000.0);
+/// KML of the next `hours` of sim time, sampled every `step_s` (default
+/// 300 s): the observer and stations, each shown satellite's time-stamped
+/// ground track, its coverage footprint above the elevation mask at the
+/// current epoch, and the ground-track arcs of its passes over the observer,
+/// in constellation colours. An observer following a trajectory is written
+/// as its route, and passes are found from the moving antenna. Opens in
+/// Google Earth, QGIS and ArcGIS.
+#[wasm_bindgen]
+pub fn export_kml(hours: f64, step_s: f64) -> String {
+    STATE.with(|s| gis_export(&s.borrow(), hours, step_s).to_kml())
+}
 
-    let canvas = web_sys::window()
-        .expect("no window")
-        .document()
-        .expect("no document")
-        .get_element_by_id("gnss-canvas")
-        .expect("no #gnss-canvas")
-        .dyn_into::<web_sys::HtmlCanvasElement>()
-        .expect("not a canvas");
+/// `export_kml` as GPX: observer and station waypoints (a track for an
+/// observer on a route), one track per ground track and per pass. GPX has no polygons, so no footprints.
+#[wasm_bindgen]
+pub fn export_gpx(hours: f64, step_s: f64) -> String {
+    STATE.with(|s| gis_export(&s.borrow(), hours, step_s).to_gpx())
+}
 
-    let window = Window::new(WindowSettings {
-        title: "gnss constellation".to_string(),
-        canvas: Some(canvas),
-        ..Default::default()
-    })
-    .expect("window");
+/// Geometry of every shown TLE satellite and enabled Walker slot from
+/// `sim_epoch`, with passes over the primary observer and its masks.
+fn gis_export(st: &GnssState, hours: f64, step_s: f64) -> gis::GisExport {
+    let start_unix = st.sim_epoch;
+    let end_unix = start_unix + hours.clamp(0.0, 24.0 * 14.0) * 3600.0;
+    let step_s = if step_s > 0.0 { step_s } else { 300.0 };
+    let site = Site::Primary(&st.observer);
+
+    let sats = shown_sats(st)
+        .into_iter()
+        .map(|sat| {
+            let ecef = &sat.ecef_km;
+            let passes = passes::find_passes(
+                |t| coords::az_el(site.ecef_km_at(st, t), ecef(t)),
+                |az| site.mask_deg(st, az),
+                start_unix,
+                end_unix,
+                step_s,
+            );
+            gis::SatGeometry {
+                track: gis::ground_track(ecef, start_unix, end_unix, step_s),
+                footprint: gis::footprint(ecef(start_unix), st.elev_mask_deg, gis::FOOTPRINT_POINTS),
+                passes: gis::pass_arcs(ecef, &passes, step_s.min(60.0)),
+                name: sat.name,
+                norad_id: sat.norad_id,
+                constellation: sat.constellation,
+            }
+        })
+        .collect();
+    gis::GisExport {
+        title: format!("GNSS geometry from {}", gis::iso8601(start_unix)),
+        start_unix,
+        end_unix,
+        sites: gis_sites(st, start_unix, end_unix, step_s),
+        sats,
+    }
+}
 
-    let context = window.gl();
-
-    // Spherical camera state: azimuth (longitude), elevation (latitude), distance
-    let mut cam_az: f64  = 0.3;   // radians, initial horizontal angle
-    let mut cam_el: f64  = 0.42;  // radians, initial elevation (~24°)
-    let mut cam_dist: f64 = 19.0; // scene units — pulled back to show outer QZSS/NavIC/BeiDou rings
-
-    let mut camera = Camera::new_perspective(
-        window.viewport(),
-        vec3(
-            (cam_el.cos() * cam_az.cos()) as f32,
-            (cam_el.cos() * cam_az.sin()) as f32,
-            cam_el.sin() as f32,
-        ) * cam_dist as f32,
-        vec3(0.0f32, 0.0, 0.0),
-        vec3(
-            (-cam_el.sin() * cam_az.cos()) as f32,
-            (-cam_el.sin() * cam_az.sin()) as f32,
-            cam_el.cos() as f32,
-        ),
-        degrees(42.0),
-        0.1,
-        200.0,
-    );
-
-    // Earth sphere (radius = 1.0 scene units)
-    let earth = Gm::new(
-        Mesh::new(&context, &CpuMesh::sphere(32)),
-        ColorMaterial { color: Srgba::new(8, 20, 8, 255), ..Default::default() },
-    );
-
-    // Equatorial ring
-    let eq_ring = Gm::new(
-        Mesh::new(&context, &CpuMesh::circle(128)),
-        ColorMaterial { color: Srgba::new(20, 60, 20, 255), ..Default::default() },
-    );
-
-    // Lat/lon graticule — 15° grid dots on Earth surface
-    let grid_dot_mesh = CpuMesh::sphere(2);
-    let grid_dot_scale = Mat4::from_scale(0.007f32);
-    let mut grid_xforms: Vec<Mat4> = Vec::new();
-    for lat_i in -5i32..=5 {          // −75° to +75° in 15° steps
-        let lat = (lat_i as f32 * 15.0).to_radians();
-        for lon_i in 0..24i32 {        // 0° to 345° in 15° steps
-            let lon = (lon_i as f32 * 15.0).to_radians();
-            let x = lat.cos() * lon.cos();
-            let y = lat.cos() * lon.sin();
-            let z = lat.sin();
-            grid_xforms.push(Mat4::from_translation(vec3(x, y, z)) * grid_dot_scale);
-        }
-    }
-    // Polar caps
-    grid_xforms.push(Mat4::from_translation(vec3(0.0f32, 0.0, 1.0)) * grid_dot_scale);
-    grid_xforms.push(Mat4::from_translation(vec3(0.0f32, 0.0, -1.0)) * grid_dot_scale);
-    let graticule = Gm::new(
-        InstancedMesh::new(
-            &context,
-            &Instances { transformations: grid_xforms, ..Default::default() },
-            &grid_dot_mesh,
-        ),
-        ColorMaterial { color: Srgba::new(100, 100, 100, 255), ..Default::default() },
-    );
-
-    // Ground observer marker — cone tower pointing outward from Earth surface
-    // Rebuilt each frame when observer changes. Start with a placeholder at (0,1,0).
-    let obs_init = vec3(0.0f32, 1.0, 0.0);
-    let tower_mesh = build_observer_tower(obs_init);
-    let mut ground_marker = Gm::new(
-        Mesh::new(&context, &tower_mesh),
-        ColorMaterial { color: Srgba::new(255, 240, 60, 255), ..Default::default() },
-    );
-    let mut prev_obs_n = obs_init;
-
-    // ── Keplerian Phase-1 orbit rings + satellite meshes ──────────────────────
-    let ring_dot  = CpuMesh::sphere(3);
-    let sat_dot   = CpuMesh::sphere(4);
-    let ring_scale = Mat4::from_scale(0.013); // slightly larger for solid-ring appearance
-    let sat_scale  = Mat4::from_scale(0.06);  // 2× size for visibility
-
-    let mut orbit_gms: Vec<Gm<InstancedMesh, ColorMaterial>> = Vec::new();
-    let mut sat_gms:   Vec<Gm<InstancedMesh, ColorMaterial>> = Vec::new();
-    let mut states:    Vec<SatState>                         = Vec::new();
-
-    for def in SATS {
-        let r    = alt_norm(def.alt_km);
-        let inc  = def.inc_deg.to_radians();
-        let rsp  = def.raan_spacing_deg.to_radians();
-        let roff = def.raan_offset_deg.to_radians();
-        let mm   = 2.0 * PI / period_s(def.alt_km);
-
-        let ring_xforms: Vec<Mat4> = (0..def.planes)
-            .flat_map(|p| {
-                let raan = roff + p as f32 * rsp;
-                (0..RING_PTS).map(move |i| {
-                    let a = i as f32 * 2.0 * PI / RING_PTS as f32;
-                    Mat4::from_translation(kpos(r, inc, raan, a)) * ring_scale
-                })
+/// The primary observer and the stations, for GIS and CZML export. An
+/// observer following a trajectory carries its route sampled every `step_s`
+/// over the window.
+fn gis_sites(st: &GnssState, start_unix: f64, end_unix: f64, step_s: f64) -> Vec<gis::GisSite> {
+    let route = st.trajectory.as_ref().map_or_else(Vec::new, |tr| {
+        gis::sample_times(start_unix, end_unix, step_s)
+            .into_iter()
+            .filter_map(|t| tr.sample(t))
+            .map(|p| {
+                let alt_km = p.alt_m / 1000.0;
+                gis::TrackPoint { unix_s: p.unix_s, lat_deg: p.lat_deg, lon_deg: p.lon_deg, alt_km }
             })
-            .collect();
-
-        let ring_col = Srgba::new(def.rgb[0] / 3, def.rgb[1] / 3, def.rgb[2] / 3, 255);
-        orbit_gms.push(Gm::new(
-            InstancedMesh::new(&context, &Instances { transformations: ring_xforms, ..Default::default() }, &ring_dot),
-            ColorMaterial { color: ring_col, ..Default::default() },
-        ));
+            .collect()
+    });
+    let mut sites = vec![gis::GisSite {
+        name: "Observer".to_string(),
+        lat_deg: st.observer.lat_deg,
+        lon_deg: st.observer.lon_deg,
+        color: [255, 240, 60], // the scene's observer tower
+        track: route,
+    }];
+    sites.extend(st.stations.iter().map(|stn| gis::GisSite {
+        name: stn.name.clone(),
+        lat_deg: stn.lat_deg,
+        lon_deg: stn.lon_deg,
+        color: stn.color,
+        track: Vec::new(),
+    }));
+    sites
+}
 
-        let n = def.planes * def.sats_per_plane;
-        let sat_col = Srgba::new(def.rgb[0], def.rgb[1], def.rgb[2], 255);
-        sat_gms.push(Gm::new(
-            InstancedMesh::new(&context, &Instances { transformations: vec![Mat4::identity(); n as usize], ..Default::default() }, &sat_dot),
-            ColorMaterial { color: sat_col, ..Default::default() },
-        ));
-        states.push(SatState { r, inc, rsp, roff, mm, planes: def.planes, sats_per_plane: def.sats_per_plane });
-    }
+/// CZML scene of the next `hours` of sim time for Cesium and other 3D
+/// globes: each shown satellite sampled every `step_s` (default 60 s) in
+/// the Earth-fixed frame, or GCRF (CZML `INERTIAL`) when `inertial`, with
+/// constellation-coloured points, labels and paths; the observer (moving
+/// along its route when following a trajectory) and stations; and access
+/// lines shown while a satellite is above each site's mask. The clock plays at the current time warp.
+#[wasm_bindgen]
+pub fn export_czml(hours: f64, step_s: f64, inertial: bool) -> String {
+    STATE.with(|s| czml_export(&s.borrow(), hours, step_s, inertial).to_json())
+}
 
-    // ── TLE-mode satellite meshes — one Gm per constellation ─────────────────
-    let mut tle_sat_gms: Vec<Gm<InstancedMesh, ColorMaterial>> = CONST_COLORS
-        .iter()
-        .map(|rgb| {
-            Gm::new(
-                InstancedMesh::new(
-                    &context,
-                    &Instances { transformations: vec![Mat4::from_scale(0.0)], ..Default::default() },
-                    &sat_dot,
-                ),
-                ColorMaterial { color: Srgba::new(rgb[0], rgb[1], rgb[2], 255), ..Default::default() },
-            )
-        })
+fn czml_export(st: &GnssState, hours: f64, step_s: f64, inertial: bool) -> czml::CzmlDocument {
+    let start_unix = st.sim_epoch;
+    let end_unix = start_unix + hours.clamp(0.0, 24.0 * 14.0) * 3600.0;
+    let step_s = if step_s > 0.0 { step_s } else { 60.0 };
+    let sites: Vec<Site> = std::iter::once(Site::Primary(&st.observer))
+        .chain(st.stations.iter().map(Site::Station))
         .collect();
 
-    // ── ECEF reference frame axes — static dot lines ─────────────────────────
-    let axis_dot_mesh = CpuMesh::sphere(2);
-    let axis_dot_scale = Mat4::from_scale(0.010f32);
-    let n_axis_dots: i32 = 30;
-    let axis_max_r = 2.5f32;
-
-    let ecef_gm_x = {
-        let dir = vec3(1.0f32, 0.0, 0.0);
-        let xforms: Vec<Mat4> = (1..=n_axis_dots)
-            .map(|i| {
-                let t = i as f32 * axis_max_r / n_axis_dots as f32;
-                Mat4::from_translation(dir * t) * axis_dot_scale
-            })
-            .collect();
-        Gm::new(
-            InstancedMesh::new(&context, &Instances { transformations: xforms, ..Default::default() }, &axis_dot_mesh),
-            ColorMaterial { color: Srgba::new(255, 80, 80, 255), ..Default::default() },
-        )
-    };
-    let ecef_gm_y = {
-        let dir = vec3(0.0f32, 1.0, 0.0);
-        let xforms: Vec<Mat4> = (1..=n_axis_dots)
-            .map(|i| {
-                let t = i as f32 * axis_max_r / n_axis_dots as f32;
-                Mat4::from_translation(dir * t) * axis_dot_scale
-            })
-            .collect();
-        Gm::new(
-            InstancedMesh::new(&context, &Instances { transformations: xforms, ..Default::default() }, &axis_dot_mesh),
-            ColorMaterial { color: Srgba::new(80, 255, 80, 255), ..Default::default() },
-        )
-    };
-    let ecef_gm_z = {
-        let dir = vec3(0.0f32, 0.0, 1.0);
-        let xforms: Vec<Mat4> = (1..=n_axis_dots)
-            .map(|i| {
-                let t = i as f32 * axis_max_r / n_axis_dots as f32;
-                Mat4::from_translation(dir * t) * axis_dot_scale
-            })
+    let mut sats = Vec::new();
+    let mut access = Vec::new();
+    for (i, sat) in shown_sats(st).into_iter().enumerate() {
+        let ecef = &sat.ecef_km;
+        for (j, site) in sites.iter().enumerate() {
+            let intervals = passes::find_passes(
+                |t| coords::az_el(site.ecef_km_at(st, t), ecef(t)),
+                |az| site.mask_deg(st, az),
+                start_unix,
+                end_unix,
+                step_s,
+            )
+            .iter()
+            .map(|p| (p.rise_unix, p.set_unix))
             .collect();
-        Gm::new(
-            InstancedMesh::new(&context, &Instances { transformations: xforms, ..Default::default() }, &axis_dot_mesh),
-            ColorMaterial { color: Srgba::new(80, 80, 255, 255), ..Default::default() },
-        )
-    };
-
-    // ── ECI axes — placeholder transforms, updated each frame based on GMST ──
-    let mut eci_gm_x = Gm::new(
-        InstancedMesh::new(&context, &Instances { transformations: vec![Mat4::from_scale(0.0)], ..Default::default() }, &axis_dot_mesh),
-        ColorMaterial { color: Srgba::new(255, 160, 80, 255), ..Default::default() }, // orange
-    );
-    let mut eci_gm_y = Gm::new(
-        InstancedMesh::new(&context, &Instances { transformations: vec![Mat4::from_scale(0.0)], ..Default::default() }, &axis_dot_mesh),
-        ColorMaterial { color: Srgba::new(160, 80, 255, 255), ..Default::default() }, // purple
-    );
-    let eci_gm_z = {
-        // ECI Z = ECEF Z, light blue
-        let dir = vec3(0.0f32, 0.0, 1.0);
-        let xforms: Vec<Mat4> = (1..=n_axis_dots)
-            .map(|i| {
-                let t = i as f32 * axis_max_r / n_axis_dots as f32;
-                Mat4::from_translation(dir * t) * axis_dot_scale
+            access.push(czml::Access { site: j, sat: i, intervals });
+        }
+        let samples = gis::sample_times(start_unix, end_unix, step_s)
+            .into_iter()
+            .map(|t| {
+                let pos = ecef(t);
+                if inertial {
+                    (t, coords::teme_to_gcrf(coords::ecef_to_teme(pos, coords::gmst_rad(t)), t))
+                } else {
+                    (t, pos)
+                }
             })
             .collect();
-        Gm::new(
-            InstancedMesh::new(&context, &Instances { transformations: xforms, ..Default::default() }, &axis_dot_mesh),
-            ColorMaterial { color: Srgba::new(80, 160, 255, 200), ..Default::default() },
-        )
-    };
-
-    // ── Elevation cone — solid filled cone mesh, rebuilt when dirty ───────────
-    let mut elev_cone_gm: Option<Gm<Mesh, ColorMaterial>> = None;
-
-    // ── Country borders — built lazily when inject_borders() is called ────────
-    let mut borders_gm: Option<Gm<Mesh, ColorMaterial>> = None;
-
-    // Capture simulation epoch at render-loop start — used as J2 precession reference.
-    let epoch_zero = STATE.with(|s| s.borrow().sim_epoch);
+        let id = if sat.norad_id > 0 { sat.norad_id.to_string() } else { sat.name.clone() };
+        sats.push(czml::CzmlSat { id, name: sat.name, constellation: sat.constellation, samples });
+    }
+    czml::CzmlDocument {
+        name: format!("GNSS scene from {}", gis::iso8601(start_unix)),
+        start_unix,
+        end_unix,
+        multiplier: st.time_warp.max(1.0),
+        frame: if inertial { czml::Frame::Inertial } else { czml::Frame::Fixed },
+        sites: gis_sites(st, start_unix, end_unix, step_s),
+        primary_site: Some(0),
+        sats,
+        access,
+    }
+}
 
-    // Ring dirty-check: skip Mat4 rebuild when sim is paused (epoch constant).
-    let mut last_ring_epoch: f64 = f64::NEG_INFINITY;
-    // SGP4 throttle: propagate at most every 100 ms wall-clock (~10 Hz).
-    let mut prop_timer_ms: f64 = 100.0; // start at threshold so first frame propagates
+/// A satellite to export: identity plus its ECEF position (km) at any time.
+struct ShownSat<'a> {
+    name: String,
+    norad_id: u64,
+    constellation: u8,
+    ecef_km: Box<dyn Fn(f64) -> [f64; 3] + 'a>,
+}
 
-    window.render_loop(move |mut frame_input| {
-        // ── 1. Advance sim clock ──────────────────────────────────────────
-        let paused = STATE.with(|s| s.borrow().paused);
-        if !paused {
-            let warp = STATE.with(|s| s.borrow().time_warp);
-            STATE.with(|s| {
-                s.borrow_mut().sim_epoch += frame_input.elapsed_time / 1000.0 * warp;
+/// Every TLE record and enabled Walker slot whose constellation and orbit
+/// type are shown, for window-long computations (common view, export).
+fn shown_sats(st: &GnssState) -> Vec<ShownSat<'_>> {
+    let mut out = Vec::new();
+    for rec in st.tle_store.records.iter().filter(|r| class_shown(st, r.constellation, r.orbit_class)) {
+        out.push(ShownSat {
+            name: rec.name.clone(),
+            norad_id: rec.norad_id,
+            constellation: rec.constellation,
+            ecef_km: Box::new(move |t| coords::teme_to_ecef(TleStore::propagate(rec, t), coords::gmst_rad(t))),
+        });
+    }
+    for wk in st.walkers.iter().filter(|w| w.enabled && class_shown(st, w.def.constellation, w.orbit_class())) {
+        for i in 0..wk.slots.len() {
+            out.push(ShownSat {
+                name: wk.slot_label(i),
+                norad_id: 0,
+                constellation: wk.def.constellation,
+                ecef_km: Box::new(move |t| wk.slot_ecef(i, t)),
             });
         }
-        let sim_epoch = STATE.with(|s| s.borrow().sim_epoch);
-
-        prop_timer_ms += frame_input.elapsed_time;
-
-        // ── 2. Spherical camera — mouse/scroll → azimuth/elevation/distance ──
-        for event in frame_input.events.iter_mut() {
-            match event {
-                Event::MouseMotion { delta, button, handled, .. } => {
-                    if *handled { continue; }
-                    if button.is_some() {
-                        // Any button drag: horizontal → azimuth, vertical → elevation
-                        cam_az -= delta.0 as f64 * 0.004;
-                        cam_el  = (cam_el + delta.1 as f64 * 0.004).clamp(-1.55, 1.55);
-                        *handled = true;
-                    }
-                }
-                Event::MouseWheel { delta, handled, .. } => {
-                    if *handled { continue; }
-                    cam_dist = (cam_dist * (1.0 - delta.1 as f64 * 0.08)).clamp(1.5, 30.0);
-                    *handled = true;
-                }
-                _ => {}
-            }
-        }
-        // Recompute camera position/orientation from spherical state
-        let cam_pos = vec3(
-            (cam_el.cos() * cam_az.cos()) as f32,
-            (cam_el.cos() * cam_az.sin()) as f32,
-            cam_el.sin() as f32,
-        ) * cam_dist as f32;
-        // Spherical "north": dPos/d(el), always perpendicular to view direction
-        let cam_up = vec3(
-            (-cam_el.sin() * cam_az.cos()) as f32,
-            (-cam_el.sin() * cam_az.sin()) as f32,
-            cam_el.cos() as f32,
-        );
-        camera.set_view(cam_pos, vec3(0.0f32, 0.0, 0.0), cam_up);
-        camera.set_viewport(frame_input.viewport);
-
-        // Store camera VP matrix for JS axis label projection
-        {
-            let vp = camera.projection() * camera.view();
-            let arr: [f32; 16] = [
-                vp.x.x, vp.x.y, vp.x.z, vp.x.w,
-                vp.y.x, vp.y.y, vp.y.z, vp.y.w,
-                vp.z.x, vp.z.y, vp.z.z, vp.z.w,
-                vp.w.x, vp.w.y, vp.w.z, vp.w.w,
-            ];
-            STATE.with(|s| s.borrow_mut().camera_vp = arr);
-        }
-
-        // ── 3. Read display state snapshot ───────────────────────────────
-        let (has_tles, cv, highlighted, visible_only, elev_mask, show_inc_rings, show_ecef_axes,
-             show_eci_axes, show_borders, show_elev_cone, borders_dirty) = STATE.with(|s| {
-            let st = s.borrow();
-            (
-                !st.tle_store.is_empty(),
-                st.constellation_visible,
-                st.highlighted,
-                st.visible_only,
-                st.elev_mask_deg,
-                st.show_inc_rings,
-                st.show_ecef_axes,
-                st.show_eci_axes,
-                st.show_borders,
-                st.show_elev_cone,
-                st.borders_dirty,
-            )
-        });
-        let cone_dirty = STATE.with(|s| s.borrow().cone_needs_rebuild);
-
-        // ── 4. Update Keplerian orbit ring colours + J2 precession ───────
-        for (ci, og) in orbit_gms.iter_mut().enumerate() {
-            let base = SATS[ci].rgb;
-            og.material.color = if !cv[ci] {
-                Srgba::new(0, 0, 0, 255)
-            } else if highlighted != -1 && highlighted != ci as i32 {
-                Srgba::new(base[0] / 10, base[1] / 10, base[2] / 10, 255)
-            } else {
-                Srgba::new(base[0] / 3, base[1] / 3, base[2] / 3, 255)
-            };
+    }
+    out
+}
 
-            // Ring transforms are pure functions of sim_epoch. Skip rebuild when
-            // paused to avoid thousands of wasted Mat4 computations per frame.
-            if (sim_epoch - last_ring_epoch).abs() > 1e-9 {
-                let def = &SATS[ci];
-                let r   = alt_norm(def.alt_km);
-                let inc = def.inc_deg.to_radians();
-                let rsp = def.raan_spacing_deg.to_radians();
-                let raan_base = def.raan_offset_deg.to_radians();
-                let raan_drift = (J2_RATES[ci] * (sim_epoch - epoch_zero)) as f32;
-
-                let ring_xforms: Vec<Mat4> = (0..def.planes)
-                    .flat_map(|p| {
-                        let raan = raan_base + p as f32 * rsp + raan_drift;
-                        (0..RING_PTS).map(move |j| {
-                            let a = j as f32 * 2.0 * std::f32::consts::PI / RING_PTS as f32;
-                            Mat4::from_translation(kpos(r, inc, raan, a)) * ring_scale
-                        })
-                    })
-                    .collect();
-                og.geometry.set_instances(&Instances { transformations: ring_xforms, ..Default::default() });
-            }
-        }
-        // Update dirty-check sentinel after processing all rings for this epoch.
-        if (sim_epoch - last_ring_epoch).abs() > 1e-9 {
-            last_ring_epoch = sim_epoch;
-        }
-
-        // ── 5. Propagate satellites — throttled to 10 Hz ─────────────────
-        if has_tles {
-            let should_propagate = !paused && prop_timer_ms >= 100.0;
-            if should_propagate { prop_timer_ms = 0.0; }
-            let ecef: Vec<(u8, [f64; 3])> = if should_propagate {
-                let all_teme = STATE.with(|s| s.borrow().tle_store.propagate_all(sim_epoch));
-                let gmst = coords::gmst_rad(sim_epoch);
-                let new_ecef: Vec<(u8, [f64; 3])> = all_teme
-                    .iter()
-                    .map(|(c, t)| (*c, coords::teme_to_ecef(*t, gmst)))
-                    .collect();
-                STATE.with(|s| s.borrow_mut().sat_ecef_km = new_ecef.clone());
-                new_ecef
-            } else {
-                STATE.with(|s| s.borrow().sat_ecef_km.clone())
-            };
+/// Returns the number of TLE satellite records currently loaded.
+/// Call after inject_tles() to verify the JSON was successfully parsed.
+/// Returns 0 if inject_tles() has not been called or if the JSON failed to parse.
+#[wasm_bindgen]
+pub fn get_tle_count() -> u32 {
+    STATE.with(|s| s.borrow().tle_store.records.len() as u32)
+}
 
-            // Observer ECEF km for elevation mask
-            let obs_km = {
-                let u = STATE.with(|s| s.borrow().observer.ecef_unit());
-                [u[0] * 6371.0, u[1] * 6371.0, u[2] * 6371.0]
-            };
+/// Returns the current camera view-projection matrix as a Vec of 16 f64 values (column-major).
+/// Each frame this is updated by the render loop. Used by JS for screen-space axis label projection.
+#[wasm_bindgen]
+pub fn get_camera_vp_matrix() -> Vec<f64> {
+    STATE.with(|s| s.borrow().camera_vp.iter().map(|&x| x as f64).collect())
+}
 
-            for ci in 0..CONST_COLORS.len() {
-                let base = CONST_COLORS[ci];
-                tle_sat_gms[ci].material.color = if !cv[ci] {
-                    Srgba::new(0, 0, 0, 255)
-                } else if highlighted != -1 && highlighted != ci as i32 {
-                    Srgba::new(base[0] / 4, base[1] / 4, base[2] / 4, 255)
-                } else {
-                    Srgba::new(base[0], base[1], base[2], 255)
-                };
+#[wasm_bindgen]
+pub fn get_sim_epoch() -> f64 {
+    STATE.with(|s| s.borrow().sim_epoch)
+}
 
-                let mut xf: Vec<Mat4> = if !cv[ci] {
-                    Vec::new()
-                } else {
-                    ecef.iter()
-                        .filter(|(c, _)| *c as usize == ci)
-                        .filter_map(|(_, pos_km)| {
-                            // Health check: skip satellites at implausible altitude (decayed or bad TLE)
-                            let alt_km = (pos_km[0].powi(2) + pos_km[1].powi(2) + pos_km[2].powi(2)).sqrt() - 6371.0;
-                            if alt_km < 100.0 || alt_km > 50_000.0 {
-                                return None;
-                            }
-                            if visible_only {
-                                let (_, el) = coords::az_el(obs_km, *pos_km);
-                                if el < elev_mask { return None; }
-                            }
-                            let s = coords::km_to_scene(*pos_km);
-                            Some(Mat4::from_translation(vec3(s[0], s[1], s[2])) * sat_scale)
-                        })
-                        .collect()
-                };
-                if xf.is_empty() { xf.push(Mat4::from_scale(0.0)); }
-                tle_sat_gms[ci].geometry.set_instances(&Instances { transformations: xf, ..Default::default() });
+#[wasm_bindgen]
+pub fn set_sim_epoch(unix_s: f64) {
+    STATE.with(|s| s.borrow_mut().sim_epoch = unix_s);
+}
+
+/// Deterministic frame stepping for time-lapse capture: with `seconds` > 0
+/// the wall clock stops driving the sim and each `capture_frame` advances
+/// `sim_epoch` by `seconds` instead. 0 returns to wall-clock time.
+#[wasm_bindgen]
+pub fn set_frame_step(seconds: f64) {
+    STATE.with(|s| s.borrow_mut().frame_step_s = if seconds.is_finite() { seconds.max(0.0) } else { 0.0 });
+}
+
+#[wasm_bindgen]
+pub fn get_frame_step() -> f64 {
+    STATE.with(|s| s.borrow().frame_step_s)
+}
+
+/// Render the current scene offscreen at `width` × `height` — independent
+/// of the canvas size, scaled down to fit the GL texture limit — and return
+/// PNG bytes. In frame-stepping mode the sim epoch then advances by the
+/// step, so successive captures are evenly spaced video frames. Empty
+/// before `start()` has created the scene.
+#[wasm_bindgen]
+pub fn capture_frame(width: u32, height: u32) -> Vec<u8> {
+    let png = SCENE.with(|s| {
+        let mut s = s.borrow_mut();
+        let scene = s.as_mut()?;
+        let (w, h) = capture::fit_size(width, height, scene.max_capture_size());
+        scene.update(0.0, three_d::Viewport::new_at_origo(w, h));
+        capture::encode_png(w, h, &scene.render_rgba(w, h)).ok()
+    });
+    if png.is_some() {
+        STATE.with(|s| {
+            let mut st = s.borrow_mut();
+            if st.frame_step_s > 0.0 {
+                st.sim_epoch += st.frame_step_s;
+                sync_replay_observer(&mut st);
+                sync_trajectory_observer(&mut st);
             }
-            // Hide Keplerian dots (rings stay as background decoration)
-            for sg in &mut sat_gms {
-                sg.geometry.set_instances(&Instances {
-                    transformations: vec![Mat4::from_scale(0.0)],
-                    ..Default::default()
-                });
+        });
+    }
+    png.unwrap_or_default()
+}
+
+#[wasm_bindgen]
+pub fn inject_tles(json: &str) {
+    // A parse failure leaves the store empty; get_tle_count() reports 0
+    let mut store = TleStore::new();
+    let _ = store.load_from_json(json);
+    replace_tle_store(store, json);
+}
+
+/// Load two- or three-line element sets (Celestrak / Space-Track text)
+/// in place of the current records. Returns the number loaded; 0 leaves
+/// the store empty.
+#[wasm_bindgen]
+pub fn inject_tle_text(text: &str) -> u32 {
+    let mut store = TleStore::new();
+    let _ = store.load_from_tle(text);
+    replace_tle_store(store, text)
+}
+
+/// Swap in a freshly loaded store so new data replaces stale records.
+fn replace_tle_store(store: TleStore, source: &str) -> u32 {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.tle_store = store;
+        st.tle_generation = st.tle_generation.wrapping_add(1);
+        st.tle_source = source.to_string();
+        st.sv_ids = sat_sv_ids(&st);
+        // Positions must match the new records even while paused
+        st.sat_ecef_km = propagate_ecef(&st.tle_store, st.sim_epoch);
+        let n = st.tle_store.records.len();
+        st.prop_samples.reset(n);
+        st.instances_dirty = true;
+        st.event_detector.reset();
+        st.rinex_availability = None;
+        if st.event_detection {
+            push_event(&mut st, events::ViewerEvent::TlesLoaded { count: n as u32 });
+        }
+        n as u32
+    })
+}
+
+/// Interpolate TLE satellite positions from worker samples (see
+/// `push_position_sample`) instead of propagating on the render thread.
+/// Whenever the samples do not cover the sim epoch — before the first
+/// arrives, after a scrub — the render loop falls back to its own SGP4.
+#[wasm_bindgen]
+pub fn set_worker_propagation(on: bool) {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.worker_propagation = on;
+        if !on {
+            let n = st.tle_store.records.len();
+            st.prop_samples.reset(n);
+        }
+    });
+}
+
+/// Store generation, bumped by every `inject_tles` / `inject_tle_text`.
+/// The worker client reloads the worker when it changes.
+#[wasm_bindgen]
+pub fn get_tle_generation() -> u32 {
+    STATE.with(|s| s.borrow().tle_generation)
+}
+
+/// The text the current store was loaded from, for `Propagator::load_text`.
+#[wasm_bindgen]
+pub fn get_tle_source() -> String {
+    STATE.with(|s| s.borrow().tle_source.clone())
+}
+
+/// Store a worker snapshot: ECEF km as `[x0, y0, z0, x1, …]` in the order
+/// of the injected TLE records (a `Propagator` loaded with `get_tle_source()`
+/// of store `generation`), plus ECEF km/s velocities in the same layout for
+/// Hermite interpolation (or an empty array for linear). Returns false if
+/// the sample was propagated for a replaced store or a length does not
+/// match the loaded record count.
+#[wasm_bindgen]
+pub fn push_position_sample(generation: u32, unix_s: f64, ecef_km: &[f64], ecef_vel_km_s: &[f64]) -> bool {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        generation == st.tle_generation && st.prop_samples.push(unix_s, ecef_km, ecef_vel_km_s)
+    })
+}
+
+/// Wall-clock milliseconds between propagation samples for the loaded
+/// satellite count; the worker client paces its requests with this.
+#[wasm_bindgen]
+pub fn get_propagation_interval_ms() -> f64 {
+    STATE.with(|s| interp::wall_interval_s(s.borrow().tle_store.records.len()) * 1000.0)
+}
+
+/// Returns a JS Array of sky-plot entries for the current sim epoch.
+/// Each entry: `{ name, object_name, norad_id, sv_id, prn, svn, block,
+/// freq_channel, healthy, sbas_provider, walker, constellation, orbit_class,
+/// orbit_label, az_deg, el_deg, visible, los_state,
+/// c_n0_penalty_db, nlos_excess_m, r, g, b, c_n0, signals,
+/// iono_klobuchar_m, iono_nequick_m, tropo_m }`
+#[wasm_bindgen]
+pub fn get_sky_data() -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let mut sats = sky_sats(&st);
+        if st.visible_only {
+            sats.retain(|s| s.visible);
+        }
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(&sats, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
+
+/// Returns one NMEA 0183 epoch (GGA, RMC, GSA, GSV, ZDA) for the current
+/// observer and sim epoch, CRLF-terminated with checksums.
+///
+/// Built from the same per-satellite values as `get_sky_data`: GSV lists
+/// every satellite above the horizon with its link-budget C/N0 (blank when a
+/// building blocks it), GSA lists those passing the elevation/horizon mask.
+#[wasm_bindgen]
+pub fn get_nmea() -> String {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let sats = sky_sats(&st);
+        let nmea_sats = to_nmea_sats(&sats);
+        let dop = used_dop(&sats);
+
+        let fix = nmea::NmeaFix {
+            lat_deg: st.observer.lat_deg,
+            lon_deg: st.observer.lon_deg,
+            alt_m: 0.0,
+            unix_s: st.sim_epoch,
+        };
+        nmea::generate(&fix, &nmea_sats, dop.as_ref())
+    })
+}
+
+/// Returns `{ gdop, pdop, hdop, vdop, tdop, n_sats }` for the satellites
+/// currently passing the elevation/horizon mask, or `null` with fewer than 4.
+#[wasm_bindgen]
+pub fn get_dop() -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let sats = sky_sats(&st);
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        used_dop(&sats)
+            .and_then(|d| serde::Serialize::serialize(&d, &serializer).ok())
+            .unwrap_or(JsValue::NULL)
+    })
+}
+
+/// Load a recorded NMEA log (GGA / RMC / ZDA / GSA / GSV) for replay.
+///
+/// Pauses the clock and jumps to the first epoch: `sim_epoch` and the
+/// observer follow the log from then on, via `replay_step` / `replay_seek`
+/// or by unpausing. Logs without RMC/ZDA dates are placed on the current
+/// sim day. Returns the number of epochs; 0 clears replay.
+#[wasm_bindgen]
+pub fn load_nmea_log(text: &str) -> u32 {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let log = replay::ReplayLog::parse(text, st.sim_epoch);
+        let n = log.epochs.len() as u32;
+        st.replay = (!log.is_empty()).then_some(log);
+        if st.replay.is_some() {
+            st.trajectory = None;
+            st.pose = None;
+            st.paused = true;
+            apply_replay_epoch(&mut st, 0);
+        }
+        n
+    })
+}
+
+#[wasm_bindgen]
+pub fn clear_nmea_log() {
+    STATE.with(|s| s.borrow_mut().replay = None);
+}
+
+// ── Trajectory ────────────────────────────────────────────────────────────────
+
+/// Load a moving-observer route: JSON waypoints, a GPX track or a KML track
+/// (see `trajectory::Trajectory::parse`). From then on the observer follows
+/// the route at `sim_epoch`, with the sky data, visibility and DOP computed
+/// from the interpolated position and altitude; before the first and after
+/// the last waypoint it waits at the end. Replaces any NMEA replay.
+/// Returns the number of waypoints, or 0 (nothing changed) on a parse error.
+#[wasm_bindgen]
+pub fn load_trajectory(text: &str) -> u32 {
+    let Ok(tr) = trajectory::Trajectory::parse(text) else {
+        return 0;
+    };
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let n = tr.waypoints.len() as u32;
+        st.trajectory = Some(tr);
+        st.replay = None;
+        sync_trajectory_observer(&mut st);
+        // The observer jumped to the route
+        st.event_detector.reset();
+        n
+    })
+}
+
+/// Stop following the route; the observer stays where it is, on the ground.
+#[wasm_bindgen]
+pub fn clear_trajectory() {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.trajectory = None;
+        st.pose = None;
+        st.instances_dirty = true;
+    });
+}
+
+/// JS Array of the route's waypoints (to draw it on a map), or NULL.
+#[wasm_bindgen]
+pub fn get_trajectory() -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        st.trajectory
+            .as_ref()
+            .and_then(|t| serde::Serialize::serialize(&t.waypoints, &serializer).ok())
+            .unwrap_or(JsValue::NULL)
+    })
+}
+
+/// The vehicle at `sim_epoch`: `{ lat_deg, lon_deg, alt_m, heading_deg,
+/// pitch_deg, roll_deg, speed_m_s, ... }`, or NULL without a trajectory.
+#[wasm_bindgen]
+pub fn get_trajectory_pose() -> JsValue {
+    STATE.with(|s| {
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        s.borrow()
+            .pose
+            .and_then(|p| serde::Serialize::serialize(&p, &serializer).ok())
+            .unwrap_or(JsValue::NULL)
+    })
+}
+
+/// Body-frame antenna masking on a moving observer: satellites less than
+/// `mask_deg` above the tilted roof plane (from the route's heading, pitch
+/// and roll) are not visible. Negative or NaN turns it off.
+#[wasm_bindgen]
+pub fn set_attitude_mask(mask_deg: f64) {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.body_mask_deg = (mask_deg >= 0.0).then(|| mask_deg.min(90.0));
+        st.instances_dirty = true;
+    });
+}
+
+/// Move `delta` epochs forward (negative = backward) through the loaded log.
+/// Returns the new sim epoch, or NaN when no log is loaded.
+#[wasm_bindgen]
+pub fn replay_step(delta: i32) -> f64 {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let Some(n) = st.replay.as_ref().map(|r| r.epochs.len()) else {
+            return f64::NAN;
+        };
+        let idx = (st.replay_idx as i64 + delta as i64).clamp(0, n as i64 - 1) as usize;
+        apply_replay_epoch(&mut st, idx);
+        st.sim_epoch
+    })
+}
+
+/// Jump to epoch `idx` of the loaded log (clamped). Returns the sim epoch.
+#[wasm_bindgen]
+pub fn replay_seek(idx: u32) -> f64 {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let Some(n) = st.replay.as_ref().map(|r| r.epochs.len()) else {
+            return f64::NAN;
+        };
+        apply_replay_epoch(&mut st, (idx as usize).min(n - 1));
+        st.sim_epoch
+    })
+}
+
+/// Returns `[current_index, epoch_count]`, or `[0, 0]` with no log loaded.
+#[wasm_bindgen]
+pub fn get_replay_position() -> Vec<u32> {
+    STATE.with(|s| {
+        let st = s.borrow();
+        match &st.replay {
+            Some(r) => vec![st.replay_idx as u32, r.epochs.len() as u32],
+            None => vec![0, 0],
+        }
+    })
+}
+
+/// Observed-vs-predicted overlay for the current replay epoch: one entry
+/// per GSV satellite `{ constellation, sv_id, used, obs_az_deg, obs_el_deg,
+/// obs_snr_dbhz, pred_az_deg, pred_el_deg, pred_c_n0, d_az_deg, d_el_deg,
+/// d_c_n0 }`. Predictions come from the same values as `get_sky_data`.
+#[wasm_bindgen]
+pub fn get_replay_overlay() -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let Some(ep) = st.replay.as_ref().and_then(|r| r.epochs.get(st.replay_idx)) else {
+            return JsValue::NULL;
+        };
+        let predicted = matchable_nmea_sats(&sky_sats(&st));
+        let rows = replay::residuals(ep.unix_s, &ep.sats, &predicted);
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(&rows, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
+
+/// Residuals for every epoch of the loaded log as CSV, re-propagating the
+/// TLEs at each epoch time and recorded position. Empty without a log.
+#[wasm_bindgen]
+pub fn export_replay_residuals() -> String {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let Some(log) = &st.replay else {
+            return String::new();
+        };
+        let mut rows = Vec::new();
+        for ep in &log.epochs {
+            let observer = ep.pos.map(|(lat, lon)| Observer::new(lat, lon)).unwrap_or(st.observer);
+            let ecef = propagate_ecef(&st.tle_store, ep.unix_s);
+            let predicted = matchable_nmea_sats(&sky_sats_for(&st, Site::Primary(&observer), &ecef, ep.unix_s));
+            rows.extend(replay::residuals(ep.unix_s, &ep.sats, &predicted));
+        }
+        replay::residuals_csv(&rows)
+    })
+}
+
+/// Load a RINEX 3 observation file. Jumps `sim_epoch` to the first epoch
+/// and moves the observer to the header's approximate position, if any.
+/// Returns the number of observation epochs; 0 (previous file kept) when
+/// the text is not a RINEX 3 observation file.
+#[wasm_bindgen]
+pub fn load_rinex_obs(text: &str) -> u32 {
+    let Ok(obs) = rinex::RinexObs::parse(text) else {
+        return 0;
+    };
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        let n = obs.epochs.len() as u32;
+        if let Some(first) = obs.epochs.first() {
+            st.sim_epoch = first.unix_s;
+        }
+        if let Some((lat, lon)) = obs.approx_lat_lon() {
+            st.observer = Observer::new(lat, lon);
+            // The receiver's site replaces any route or log the observer followed
+            st.trajectory = None;
+            st.pose = None;
+            st.replay = None;
+            st.cone_needs_rebuild = true;
+        }
+        st.sat_ecef_km = propagate_ecef(&st.tle_store, st.sim_epoch);
+        st.instances_dirty = true;
+        // The clock jumped to the log
+        st.event_detector.reset();
+        st.rinex = Some(obs);
+        st.rinex_availability = None;
+        n
+    })
+}
+
+#[wasm_bindgen]
+pub fn clear_rinex_obs() {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.rinex = None;
+        st.rinex_availability = None;
+    });
+}
+
+/// Tracked-vs-predicted overlay for the RINEX epoch at or before `sim_epoch`:
+/// `{ constellation, sv_id, status, az_deg, el_deg, signals: [{ code, s_dbhz }] }`
+/// with `status` one of "both", "predicted_only", "tracked_only".
+#[wasm_bindgen]
+pub fn get_rinex_overlay() -> JsValue {
+    STATE.with(|s| {
+        let st = s.borrow();
+        let Some(ep) = st.rinex.as_ref().and_then(|r| r.epochs.get(r.index_at(st.sim_epoch)?)) else {
+            return JsValue::NULL;
+        };
+        let rows = rinex::compare(ep, &matchable_nmea_sats(&sky_sats(&st)));
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(&rows, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
+
+/// Per-satellite tracking availability over the whole RINEX file:
+/// `{ constellation, sv_id, epochs_predicted, epochs_tracked, epochs_both,
+/// availability, mean_s_dbhz }`. Predictions are re-propagated at every
+/// epoch once per loaded file; later calls return the cached result.
+#[wasm_bindgen]
+pub fn get_rinex_availability() -> JsValue {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        if st.rinex_availability.is_none() {
+            st.rinex_availability = rinex_availability(&st);
+        }
+        let Some(stats) = &st.rinex_availability else {
+            return JsValue::NULL;
+        };
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        serde::Serialize::serialize(stats, &serializer).unwrap_or(JsValue::NULL)
+    })
+}
+
+/// Compare every epoch of the loaded RINEX file against predictions from
+/// the header position (or the observer). `None` without a file.
+fn rinex_availability(st: &GnssState) -> Option<Vec<rinex::SatAvailability>> {
+    let obs = st.rinex.as_ref()?;
+    let observer = obs.approx_lat_lon().map(|(lat, lon)| Observer::new(lat, lon)).unwrap_or(st.observer);
+    let per_epoch: Vec<Vec<rinex::TrackRow>> = obs
+        .epochs
+        .iter()
+        .map(|ep| {
+            let ecef = propagate_ecef(&st.tle_store, ep.unix_s);
+            rinex::compare(ep, &matchable_nmea_sats(&sky_sats_for(st, Site::Primary(&observer), &ecef, ep.unix_s)))
+        })
+        .collect();
+    Some(rinex::availability(per_epoch.iter().map(Vec::as_slice)))
+}
+
+// ── Events ────────────────────────────────────────────────────────────────────
+
+/// Undelivered events beyond this are dropped, oldest first, so a native
+/// front end that never calls `take_events` cannot grow the queue forever.
+const MAX_QUEUED_EVENTS: usize = 4096;
+
+fn push_event(st: &mut GnssState, ev: events::ViewerEvent) {
+    if st.event_queue.len() >= MAX_QUEUED_EVENTS {
+        st.event_queue.pop_front();
+    }
+    st.event_queue.push_back(ev);
+}
+
+/// Compare the sky at the sim epoch with the last tick's and queue what
+/// changed. Called by the render loop once per propagation tick. Detection
+/// ignores the constellation toggles: hiding a system is not a set.
+fn detect_events(st: &mut GnssState) {
+    if !st.event_detection {
+        return;
+    }
+    let sats = all_sky_sats_for(st, Site::Primary(&st.observer), &st.sat_ecef_km, st.sim_epoch);
+    let dop = used_dop(&sats);
+    let mut found = Vec::new();
+    st.event_detector.check(st.sim_epoch, &sats, dop.as_ref(), &mut found);
+    for ev in found {
+        push_event(st, ev);
+    }
+}
+
+/// PDOP above which a `dop` event reports `above: true` (default 6).
+#[wasm_bindgen]
+pub fn set_dop_threshold(pdop: f64) {
+    if pdop.is_finite() && pdop > 0.0 {
+        STATE.with(|s| s.borrow_mut().event_detector.pdop_threshold = pdop);
+    }
+}
+
+/// Turn event detection on or off for native front ends, which collect
+/// events with `take_events`. In the browser `on_event` does this.
+pub fn set_event_detection(on: bool) {
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        st.event_detection = on;
+        st.event_detector.reset();
+        if !on {
+            st.event_queue.clear();
+        }
+    });
+}
+
+/// Drain the events detected since the last call, oldest first.
+pub fn take_events() -> Vec<events::ViewerEvent> {
+    STATE.with(|s| std::mem::take(&mut s.borrow_mut().event_queue).into())
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    /// JS callbacks registered with `on_event`: (id, kind or "*", callback).
+    static LISTENERS: RefCell<Vec<(u32, String, js_sys::Function)>> = const { RefCell::new(Vec::new()) };
+    static NEXT_LISTENER_ID: std::cell::Cell<u32> = const { std::cell::Cell::new(1) };
+}
+
+/// Call `callback(event)` for each event of `kind` — "rise", "set", "dop",
+/// "count", "day", "tles_loaded" or "*" for all. Events are delivered after
+/// the frame that detected them, so callbacks may call any export. Returns
+/// an id for `off_event`, or 0 for an unknown kind.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn on_event(kind: &str, callback: js_sys::Function) -> u32 {
+    if kind != "*" && !events::EVENT_KINDS.contains(&kind) {
+        return 0;
+    }
+    let id = NEXT_LISTENER_ID.with(|n| n.replace(n.get() + 1));
+    LISTENERS.with(|l| l.borrow_mut().push((id, kind.to_string(), callback)));
+    STATE.with(|s| {
+        let mut st = s.borrow_mut();
+        if !st.event_detection {
+            st.event_detection = true;
+            st.event_detector.reset();
+        }
+    });
+    id
+}
+
+/// Remove a listener added by `on_event`; detection stops with the last one.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn off_event(id: u32) {
+    let empty = LISTENERS.with(|l| {
+        let mut l = l.borrow_mut();
+        l.retain(|(lid, _, _)| *lid != id);
+        l.is_empty()
+    });
+    if empty {
+        set_event_detection(false);
+    }
+}
+
+/// Hand queued events to their listeners. Neither `STATE` nor the listener
+/// list is borrowed during the calls.
+#[cfg(target_arch = "wasm32")]
+fn dispatch_events() {
+    let queued = take_events();
+    if queued.is_empty() {
+        return;
+    }
+    let listeners = LISTENERS.with(|l| l.borrow().clone());
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    for ev in &queued {
+        let Ok(value) = serde::Serialize::serialize(ev, &serializer) else { continue };
+        for (_, kind, callback) in &listeners {
+            if kind == "*" || kind == ev.kind() {
+                // A throwing callback must not stop the others or the loop
+                let _ = callback.call1(&JsValue::NULL, &value);
             }
+        }
+    }
+}
+
+/// Record where a data set was loaded from — `kind` is "elements",
+/// "borders", "horizon", "buildings", "catalog", "nmea" or "rinex" — for
+/// `export_scenario`. An empty `location` forgets the entry.
+#[wasm_bindgen]
+pub fn set_data_source(kind: &str, location: &str) {
+    STATE.with(|s| {
+        let sources = &mut s.borrow_mut().data_sources;
+        if location.is_empty() {
+            sources.remove(kind);
         } else {
-            // Keplerian fallback
-            let t = sim_epoch as f32;
-            let obs_km_kepler = {
-                let u = STATE.with(|s| s.borrow().observer.ecef_unit());
-                [u[0] * 6371.0, u[1] * 6371.0, u[2] * 6371.0]
-            };
-            for (idx, s) in states.iter().enumerate() {
-                let base = CONST_COLORS[idx];
-                sat_gms[idx].material.color = if !cv[idx] {
-                    Srgba::new(0, 0, 0, 255)
-                } else if highlighted != -1 && highlighted != idx as i32 {
-                    Srgba::new(base[0] / 4, base[1] / 4, base[2] / 4, 255)
-                } else {
-                    Srgba::new(base[0], base[1], base[2], 255)
-                };
-                let mut xf: Vec<Mat4> = if !cv[idx] {
-                    vec![Mat4::from_scale(0.0)]
-                } else {
-                    (0..s.planes).flat_map(|p| {
-                        let raan = s.roff + p as f32 * s.rsp;
-                        (0..s.sats_per_plane).filter_map(move |i| {
-                            let ma = i as f32 * 2.0 * PI / s.sats_per_plane as f32 + s.mm * t;
-                            let p = kpos(s.r, s.inc, raan, ma);
-                            if visible_only {
-                                let sat_km = [p.x as f64 * 6371.0, p.y as f64 * 6371.0, p.z as f64 * 6371.0];
-                                let (_, el) = coords::az_el(obs_km_kepler, sat_km);
-                                if el < elev_mask { return None; }
-                            }
-                            Some(Mat4::from_translation(p) * sat_scale)
-                        })
-                    }).collect()
-                };
-                if xf.is_empty() { xf.push(Mat4::from_scale(0.0)); }
-                sat_gms[idx].geometry.set_instances(&Instances { transformations: xf, ..Default::default() });
-            }
-            // Populate sat_ecef_km from Keplerian positions so get_sky_data() works in fallback mode.
-            // Keplerian positions are in normalized scene units (Earth radius = 1); multiply by 6371 for km.
-            // NOTE: these positions are in the scene/ECEF-like frame, not true ECI. Elevation/azimuth
-            // values will be approximately correct for visualization purposes.
-            let kepler_ecef: Vec<(u8, [f64; 3])> = states.iter().enumerate().flat_map(|(const_idx, s)| {
-                let t_f = sim_epoch as f32;
-                (0..s.planes).flat_map(move |p| {
-                    let raan = s.roff + p as f32 * s.rsp;
-                    (0..s.sats_per_plane).map(move |i| {
-                        let ma = i as f32 * 2.0 * PI / s.sats_per_plane as f32 + s.mm * t_f;
-                        let pos = kpos(s.r, s.inc, raan, ma);
-                        (const_idx as u8, [pos.x as f64 * 6371.0, pos.y as f64 * 6371.0, pos.z as f64 * 6371.0])
-                    })
-                })
-            }).collect();
-            STATE.with(|s| s.borrow_mut().sat_ecef_km = kepler_ecef);
-
-            for sg in &mut tle_sat_gms {
-                sg.geometry.set_instances(&Instances {
-                    transformations: vec![Mat4::from_scale(0.0)],
-                    ..Default::default()
-                });
+            sources.insert(kind.to_string(), location.to_string());
+        }
+    });
+}
+
+fn current_pose() -> Option<scene::CameraPose> {
+    SCENE.with(|s| s.borrow().as_ref().map(|scene| scene.pose))
+}
+
+/// Apply a parsed scenario, including its camera pose when the scene exists.
+fn apply_scenario(scenario: Result<scenario::Scenario, String>) -> bool {
+    let Ok(scenario) = scenario.and_then(|sc| sc.apply().map(|()| sc)) else {
+        return false;
+    };
+    if let Some(pose) = scenario.camera {
+        SCENE.with(|s| {
+            if let Some(scene) = s.borrow_mut().as_mut() {
+                scene.pose = pose.clamped();
             }
+        });
+    }
+    true
+}
+
+/// The full viewer state — observer, clock, masks, toggles, overlays,
+/// Walker definitions, camera and data sources — as scenario JSON.
+#[wasm_bindgen]
+pub fn export_scenario() -> String {
+    scenario::Scenario::capture(current_pose()).to_json()
+}
+
+/// Restore a scenario from `export_scenario`. Missing fields take the
+/// defaults; returns `false` and changes nothing if it is invalid. Data
+/// sources are recorded but not loaded — that is the page's job.
+#[wasm_bindgen]
+pub fn import_scenario(json: &str) -> bool {
+    apply_scenario(scenario::Scenario::from_json(json))
+}
+
+/// The current scenario as a URL fragment, `scenario=<base64url>`.
+#[wasm_bindgen]
+pub fn export_scenario_fragment() -> String {
+    scenario::Scenario::capture(current_pose()).to_fragment()
+}
+
+/// Restore a scenario from `location.hash`; see `import_scenario`.
+#[wasm_bindgen]
+pub fn import_scenario_fragment(fragment: &str) -> bool {
+    apply_scenario(scenario::Scenario::from_fragment(fragment))
+}
+
+/// Set `sim_epoch`, observer and satellite positions to replay epoch `idx`.
+/// Propagates immediately since the render loop skips SGP4 while paused.
+fn apply_replay_epoch(st: &mut GnssState, idx: usize) {
+    let Some(ep) = st.replay.as_ref().and_then(|r| r.epochs.get(idx)) else {
+        return;
+    };
+    let (unix_s, pos) = (ep.unix_s, ep.pos);
+    st.replay_idx = idx;
+    st.sim_epoch = unix_s;
+    if let Some((lat, lon)) = pos {
+        st.observer = Observer::new(lat, lon);
+        st.cone_needs_rebuild = true;
+        st.instances_dirty = true;
+    }
+    st.sat_ecef_km = propagate_ecef(&st.tle_store, unix_s);
+    st.instances_dirty = true;
+}
+
+/// While the clock runs, move the observer to the latest log epoch at or
+/// before `sim_epoch`.
+fn sync_replay_observer(st: &mut GnssState) {
+    let Some(idx) = st.replay.as_ref().and_then(|r| r.index_at(st.sim_epoch)) else {
+        return;
+    };
+    if idx == st.replay_idx {
+        return;
+    }
+    st.replay_idx = idx;
+    if let Some((lat, lon)) = st.replay.as_ref().and_then(|r| r.epochs[idx].pos) {
+        st.observer = Observer::new(lat, lon);
+        st.cone_needs_rebuild = true;
+        st.instances_dirty = true;
+    }
+}
+
+/// Move the observer to the trajectory pose at `sim_epoch`. Runs every
+/// frame, paused or not, so seeking the clock also moves the vehicle.
+fn sync_trajectory_observer(st: &mut GnssState) {
+    let Some(pose) = st.trajectory.as_ref().and_then(|t| t.sample(st.sim_epoch)) else {
+        return;
+    };
+    if st.pose == Some(pose) {
+        return;
+    }
+    st.pose = Some(pose);
+    if (pose.lat_deg, pose.lon_deg) != (st.observer.lat_deg, st.observer.lon_deg) {
+        st.observer = Observer::new(pose.lat_deg, pose.lon_deg);
+        st.cone_needs_rebuild = true;
+    }
+    st.instances_dirty = true;
+}
+
+/// SGP4-propagate every TLE record to `unix_s` and rotate TEME → ECEF (km).
+fn propagate_ecef(store: &TleStore, unix_s: f64) -> SatPositions {
+    let mut out = SatPositions::with_capacity(store.records.len());
+    propagate_ecef_into(store, unix_s, &mut out);
+    out
+}
+
+/// `propagate_ecef` refilling `out` in record order, reusing its allocations.
+fn propagate_ecef_into(store: &TleStore, unix_s: f64, out: &mut SatPositions) {
+    let gmst = coords::gmst_rad(unix_s);
+    out.clear();
+    for rec in &store.records {
+        let ecef = coords::teme_to_ecef(TleStore::propagate(rec, unix_s), gmst);
+        out.push(rec.constellation, tle_group(rec.constellation, rec.orbit_class) as u16, ecef);
+    }
+}
+
+/// Mesh group of a satellite: one per (constellation, orbit type), indexed
+/// `constellation * ORBIT_CLASS_COUNT + OrbitClass::index`.
+fn tle_group(constellation: u8, class: OrbitClass) -> usize {
+    constellation as usize * ORBIT_CLASS_COUNT + class.index()
+}
+
+/// Keep `samples` bracketing `unix_s` with one sample `step_s` ahead,
+/// re-seeding at `unix_s` when the clock jumped outside them. Runs SGP4 over
+/// the whole store at most twice per call.
+fn refill_samples(store: &TleStore, samples: &mut interp::SampleRing, unix_s: f64, step_s: f64) {
+    if !samples.covers(unix_s) {
+        samples.reset(store.records.len());
+        push_propagated_sample(store, samples, unix_s);
+    }
+    if let Some((_, newest)) = samples.span() {
+        if newest < unix_s + step_s {
+            push_propagated_sample(store, samples, newest + step_s);
         }
+    }
+}
 
-        // ── 6. Ground marker (observer tower) ─────────────────────────────
-        let obs_scene = STATE.with(|s| s.borrow().observer.scene_pos());
-        let obs_n_cur = vec3(obs_scene[0], obs_scene[1], obs_scene[2]).normalize();
-        if (obs_n_cur - prev_obs_n).magnitude() > 1e-5 {
-            let tower_mesh_new = build_observer_tower(obs_n_cur);
-            ground_marker = Gm::new(
-                Mesh::new(&context, &tower_mesh_new),
-                ColorMaterial { color: Srgba::new(255, 240, 60, 255), ..Default::default() },
-            );
-            prev_obs_n = obs_n_cur;
-        }
-
-        // ── 6b. ECI axes — rotate with GMST ──────────────────────────────────────
-        let gmst = coords::gmst_rad(sim_epoch) as f32;
-        // ECI X direction in ECEF coords: (cos(GMST), sin(GMST), 0)
-        // ECI Y direction in ECEF coords: (-sin(GMST), cos(GMST), 0)
-        let eci_x_dir = vec3(gmst.cos(), gmst.sin(), 0.0f32);
-        let eci_y_dir = vec3(-gmst.sin(), gmst.cos(), 0.0f32);
-        if show_eci_axes {
-            let eci_xf = |dir: Vec3| -> Vec<Mat4> {
-                (1..=30i32).map(|i| {
-                    Mat4::from_translation(dir * (i as f32 * 2.5 / 30.0)) * Mat4::from_scale(0.010)
-                }).collect()
+/// SGP4-propagate every record to `unix_s` and store ECEF position and
+/// velocity as one interpolation sample.
+fn push_propagated_sample(store: &TleStore, samples: &mut interp::SampleRing, unix_s: f64) {
+    let gmst = coords::gmst_rad(unix_s);
+    samples.push_with(unix_s, |pos, vel| {
+        for rec in &store.records {
+            let (p, v) = TleStore::propagate_pv(rec, unix_s);
+            pos.push(coords::teme_to_ecef(p, gmst));
+            vel.push(coords::teme_to_ecef_vel(p, v, gmst));
+        }
+    });
+}
+
+/// Convert sky satellites to NMEA satellites with stable IDs; satellites
+/// without an ID are left out.
+fn to_nmea_sats(sats: &[ground::SkySat]) -> Vec<nmea::NmeaSat> {
+    sats.iter().filter_map(to_nmea_sat).collect()
+}
+
+/// C/N0 is blank for building-blocked satellites; `used` follows `visible`.
+fn to_nmea_sat(s: &ground::SkySat) -> Option<nmea::NmeaSat> {
+    Some(nmea::NmeaSat {
+        constellation: s.constellation,
+        sv_id: s.sv_id?,
+        az_deg: s.az_deg as f64,
+        el_deg: s.el_deg as f64,
+        snr_dbhz: (s.los_state != urban::LosState::Blocked).then_some(s.c_n0 as f64),
+        used: in_fix(s),
+    })
+}
+
+/// Predictions to match against a receiver log. GLONASS `sv_id`s that were
+/// gap-filled rather than taken from the catalog slot would pair the
+/// observation with an unrelated satellite, so those are left out.
+fn matchable_nmea_sats(sats: &[ground::SkySat]) -> Vec<nmea::NmeaSat> {
+    sats.iter()
+        .filter(|s| s.constellation != tles::CONSTELLATION_GLONASS || s.prn.is_some())
+        .filter_map(to_nmea_sat)
+        .collect()
+}
+
+/// A satellite contributes to the fix when it is `visible` (mask, horizon,
+/// buildings) and not flagged unhealthy in the catalog.
+fn in_fix(s: &ground::SkySat) -> bool {
+    s.visible && s.healthy != Some(false)
+}
+
+/// DOP over the satellites that contribute to the fix.
+fn used_dop(sats: &[ground::SkySat]) -> Option<dop::Dop> {
+    let az_el: Vec<(f64, f64)> = sats
+        .iter()
+        .filter(|s| in_fix(s))
+        .map(|s| (s.az_deg as f64, s.el_deg as f64))
+        .collect();
+    dop::compute(&az_el)
+}
+
+/// Satellite ID from the catalog PRN (when it matches the record's
+/// constellation), else parsed from the Celestrak object name.
+fn known_sv_id(st: &GnssState, rec: &tles::SatRecord) -> Option<u16> {
+    st.catalog
+        .get(rec.norad_id)
+        .and_then(|i| i.sat_key())
+        .filter(|(c, _)| *c == rec.constellation)
+        .map(|(_, id)| id)
+        .or_else(|| nmea::sv_id_from_name(rec.constellation, &rec.name))
+}
+
+/// NMEA satellite ID for every TLE record, in record order, followed by
+/// every Walker slot (enabled or not) in constellation order. Assigned over
+/// the whole set so gap-filled IDs stay stable as satellites rise and set,
+/// and Walker slots only take IDs the real satellites leave free.
+fn sat_sv_ids(st: &GnssState) -> Vec<Option<u16>> {
+    let records = st.tle_store.records.iter().map(|r| (r.constellation, known_sv_id(st, r)));
+    let slots = st.walkers.iter().flat_map(|w| w.slots.iter().map(|_| (w.def.constellation, None)));
+    let known: Vec<(u8, Option<u16>)> = records.chain(slots).collect();
+    nmea::fill_sv_ids(&known)
+}
+
+/// Constellation and orbit-type toggles both on.
+fn class_shown(st: &GnssState, constellation: u8, class: OrbitClass) -> bool {
+    let ci = constellation as usize;
+    st.constellation_visible.get(ci).copied().unwrap_or(false) && st.orbit_class_visible[ci][class.index()]
+}
+
+/// Per-satellite sky state for every enabled satellite above the horizon.
+///
+/// Single source of truth for the sky plot, NMEA output and DOP; callers
+/// apply the `visible_only` filter themselves.
+fn sky_sats(st: &GnssState) -> Vec<ground::SkySat> {
+    sky_sats_for(st, Site::Primary(&st.observer), &st.sat_ecef_km, st.sim_epoch)
+}
+
+/// Where a sky is evaluated from. The primary observer is masked by the
+/// global elevation mask, the horizon profile and the buildings around it;
+/// a station only by its own elevation mask.
+#[derive(Clone, Copy)]
+enum Site<'a> {
+    Primary(&'a Observer),
+    Station(&'a station::Station),
+}
+
+impl Site<'_> {
+    fn observer(&self) -> Observer {
+        match self {
+            Site::Primary(o) => **o,
+            Site::Station(s) => s.observer(),
+        }
+    }
+
+    /// Minimum elevation at `az_deg`.
+    fn mask_deg(&self, st: &GnssState, az_deg: f64) -> f64 {
+        match self {
+            Site::Primary(_) => horizon::effective_mask_deg(st.horizon.as_ref(), st.elev_mask_deg, az_deg),
+            Site::Station(s) => s.elev_mask_deg,
+        }
+    }
+
+    /// Antenna position (km, ECEF) at `unix_s`: along the route for a
+    /// primary observer following a trajectory, else the fixed site.
+    fn ecef_km_at(&self, st: &GnssState, unix_s: f64) -> [f64; 3] {
+        let pose = match self {
+            Site::Primary(_) => st.trajectory.as_ref().and_then(|t| t.sample(unix_s)),
+            Site::Station(_) => None,
+        };
+        let (u, r_km) = match pose {
+            Some(p) => (coords::geodetic_to_ecef_unit(p.lat_deg, p.lon_deg), ground::EARTH_R_KM + p.alt_m / 1000.0),
+            None => (self.observer().ecef_unit(), ground::EARTH_R_KM),
+        };
+        [u[0] * r_km, u[1] * r_km, u[2] * r_km]
+    }
+
+    /// Vehicle state when the primary observer is following a trajectory.
+    fn pose<'s>(&self, st: &'s GnssState) -> Option<&'s trajectory::Pose> {
+        match self {
+            Site::Primary(_) => st.pose.as_ref(),
+            Site::Station(_) => None,
+        }
+    }
+}
+
+/// A site's antenna — position, altitude, buildings and masks — resolved
+/// once, so the sky list and the scene's visible-only filter judge every
+/// satellite the same way.
+struct Sight<'a> {
+    st: &'a GnssState,
+    site: Site<'a>,
+    observer: Observer,
+    pose: Option<&'a trajectory::Pose>,
+    /// Antenna height above the sphere, metres (trajectory altitude).
+    height_m: f64,
+    obs_km: [f64; 3],
+    /// Building walls projected around the primary observer.
+    urban_local: Option<urban::LocalScene>,
+}
+
+/// One satellite as seen through a `Sight`.
+struct Look {
+    az: f64,
+    el: f64,
+    los: urban::LosResult,
+    /// Above the mask, not blocked by a building, inside the body mask.
+    visible: bool,
+}
+
+impl<'a> Sight<'a> {
+    fn new(st: &'a GnssState, site: Site<'a>) -> Self {
+        let observer = site.observer();
+        let pose = site.pose(st);
+        let height_m = pose.map_or(0.0, |p| p.alt_m);
+        // Observer ECEF position in km (unit vector × (Earth radius + altitude))
+        let u = observer.ecef_unit();
+        let r_km = ground::EARTH_R_KM + height_m / 1000.0;
+        let urban_local = match site {
+            Site::Primary(_) => st.urban.as_ref().map(|u| u.localise(observer.lat_deg, observer.lon_deg)),
+            Site::Station(_) => None,
+        };
+        Sight {
+            st,
+            site,
+            observer,
+            pose,
+            height_m,
+            obs_km: [u[0] * r_km, u[1] * r_km, u[2] * r_km],
+            urban_local,
+        }
+    }
+
+    /// `None` below the horizon.
+    fn look(&self, pos_km: &[f64; 3]) -> Option<Look> {
+        let st = self.st;
+        let (az, el) = coords::az_el(self.obs_km, *pos_km);
+        if el < 0.0 {
+            return None;
+        }
+        let los = self
+            .urban_local
+            .as_ref()
+            .map(|l| l.classify(st.antenna_height_m, az, el))
+            .unwrap_or(urban::LosResult::CLEAR);
+        // A tilted vehicle's roof antenna sees less on the low side
+        let body_ok = match (self.pose, st.body_mask_deg) {
+            (Some(p), Some(mask)) => trajectory::body_elevation_deg(p, az, el) >= mask,
+            _ => true,
+        };
+        let visible = el >= self.site.mask_deg(st, az) && los.state != urban::LosState::Blocked && body_ok;
+        Some(Look { az, el, los, visible })
+    }
+
+    fn sees(&self, pos_km: &[f64; 3]) -> bool {
+        self.look(pos_km).is_some_and(|l| l.visible)
+    }
+}
+
+/// `sky_sats` for an arbitrary site, satellite positions and epoch — used
+/// for stations and for times other than the current sim epoch.
+fn sky_sats_for(
+    st: &GnssState,
+    site: Site,
+    sat_ecef_km: &SatPositions,
+    unix_s: f64,
+) -> Vec<ground::SkySat> {
+    let mut sats = all_sky_sats_for(st, site, sat_ecef_km, unix_s);
+    sats.retain(|s| class_shown(st, s.constellation, s.orbit_class));
+    sats
+}
+
+/// Every satellite above the horizon, whatever the constellation and
+/// orbit-type toggles say. Enabled Walker constellations are evaluated at
+/// `unix_s` and appended.
+fn all_sky_sats_for(
+    st: &GnssState,
+    site: Site,
+    sat_ecef_km: &SatPositions,
+    unix_s: f64,
+) -> Vec<ground::SkySat> {
+    let sight = Sight::new(st, site);
+    let (observer, obs_km, height_m) = (&sight.observer, sight.obs_km, sight.height_m);
+
+    // Geometry, visibility, link budget and delays for one satellite;
+    // identity fields are left blank for the caller to fill in.
+    let sky_sat = |c_idx: u8, orbit_class: OrbitClass, pos_km: &[f64; 3]| -> Option<ground::SkySat> {
+        let Look { az, el, los, visible } = sight.look(pos_km)?;
+        let [r, g, b] = ground::orbit_class_color(ground::constellation_color(c_idx), c_idx, orbit_class);
+        let range_km = ((pos_km[0] - obs_km[0]).powi(2)
+            + (pos_km[1] - obs_km[1]).powi(2)
+            + (pos_km[2] - obs_km[2]).powi(2))
+        .sqrt();
+        let mut signals = linkbudget::signal_c_n0(&st.receiver, c_idx, range_km, el);
+        for sig in signals.iter_mut() {
+            sig.c_n0 -= los.c_n0_penalty_db as f32;
+        }
+        let c_n0 = signals.first().map(|s| s.c_n0).unwrap_or(0.0);
+        // Static observers sit on the spherical surface (height 0 m)
+        let delays = atmosphere::slant_delays(
+            &st.atmo,
+            observer.lat_deg,
+            observer.lon_deg,
+            height_m,
+            az,
+            el,
+            unix_s,
+        );
+        Some(ground::SkySat {
+            name: String::new(),
+            object_name: String::new(),
+            norad_id: 0,
+            sv_id: None,
+            prn: None,
+            svn: None,
+            block: None,
+            freq_channel: None,
+            healthy: None,
+            sbas_provider: None,
+            walker: None,
+            constellation: c_idx,
+            orbit_class,
+            orbit_label: orbit_class.label(c_idx),
+            az_deg: az as f32,
+            el_deg: el as f32,
+            visible,
+            los_state: los.state,
+            c_n0_penalty_db: los.c_n0_penalty_db as f32,
+            nlos_excess_m: los.excess_path_m as f32,
+            r,
+            g,
+            b,
+            c_n0,
+            signals,
+            iono_klobuchar_m: delays.iono_klobuchar_m as f32,
+            iono_nequick_m: delays.iono_nequick_m as f32,
+            tropo_m: delays.tropo_m as f32,
+        })
+    };
+
+    let mut out: Vec<ground::SkySat> = sat_ecef_km
+        .iter()
+        .enumerate()
+        .filter_map(|(sat_idx, (c_idx, pos_km))| {
+            // sat_ecef_km is populated in tle_store.records order,
+            // so sat_idx directly indexes the matching TLE record.
+            let rec = st.tle_store.records.get(sat_idx);
+            let mut sat = sky_sat(c_idx, rec.map(|r| r.orbit_class).unwrap_or_default(), pos_km)?;
+            let object_name = rec.map(|r| r.name.clone()).unwrap_or_default();
+            let ident = rec.and_then(|r| st.catalog.get(r.norad_id));
+            // PRN label only when the ID is known, not gap-filled
+            sat.prn = rec
+                .and_then(|r| known_sv_id(st, r))
+                .and_then(|id| catalog::prn_label(c_idx, id));
+            sat.name = match (&sat.prn, st.prn_labels) {
+                (Some(p), true) => p.clone(),
+                _ => object_name.clone(),
             };
-            eci_gm_x.geometry.set_instances(&Instances { transformations: eci_xf(eci_x_dir), ..Default::default() });
-            eci_gm_y.geometry.set_instances(&Instances { transformations: eci_xf(eci_y_dir), ..Default::default() });
-        } else {
-            let hidden = vec![Mat4::from_scale(0.0)];
-            eci_gm_x.geometry.set_instances(&Instances { transformations: hidden.clone(), ..Default::default() });
-            eci_gm_y.geometry.set_instances(&Instances { transformations: hidden, ..Default::default() });
-        }
-
-        // ── 6c. Elevation cone — solid filled surface ─────────────────────────────
-        if show_elev_cone && (cone_dirty || elev_cone_gm.is_none()) {
-            let obs_pos = STATE.with(|s| s.borrow().observer.scene_pos());
-            let obs_n_cone = vec3(obs_pos[0], obs_pos[1], obs_pos[2]).normalize();
-            let cpu = build_elev_cone(obs_n_cone, elev_mask);
-            elev_cone_gm = Some(Gm::new(
-                Mesh::new(&context, &cpu),
-                ColorMaterial {
-                    color: Srgba::new(200, 200, 200, 18),
-                    is_transparent: true,
-                    render_states: RenderStates {
-                        write_mask: WriteMask::COLOR, // no depth write — satellites behind cone stay visible
-                        blend: Blend::TRANSPARENCY,   // actual alpha blending (web-compatible)
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-            ));
-            STATE.with(|s| s.borrow_mut().cone_needs_rebuild = false);
-        } else if !show_elev_cone {
-            elev_cone_gm = None;
-        }
-
-        // ── 6d. Country borders — lazy rebuild when inject_borders() called ───────
-        if borders_dirty {
-            let json_opt = STATE.with(|s| s.borrow().borders_json.clone());
-            if let Some(ref json) = json_opt {
-                borders_gm = borders::build_border_lines(&context, json);
-            }
-            STATE.with(|s| s.borrow_mut().borders_dirty = false);
-        }
-
-        // ── 7. Render ─────────────────────────────────────────────────────
-        let mut objs: Vec<&dyn Object> = vec![&earth, &eq_ring, &graticule, &ground_marker];
-        if show_inc_rings {
-            for g in &orbit_gms { objs.push(g); }
-        }
-        for g in &sat_gms    { objs.push(g); }
-        for g in &tle_sat_gms { objs.push(g); }
-        if show_ecef_axes {
-            objs.push(&ecef_gm_x);
-            objs.push(&ecef_gm_y);
-            objs.push(&ecef_gm_z);
-        }
-        objs.push(&eci_gm_x);
-        objs.push(&eci_gm_y);
-        objs.push(&eci_gm_z);
-        if show_elev_cone {
-            if let Some(ref cone) = elev_cone_gm { objs.push(cone); }
-        }
-        if let Some(ref brd) = borders_gm {
-            if show_borders { objs.push(brd); }
-        }
-
-        frame_input
-            .screen()
-            .clear(ClearState {
-                red:   Some(0.01),
-                green: Some(0.01),
-                blue:  Some(0.01),
-                alpha: Some(1.0),
-                depth: Some(1.0),
-            })
-            .render(&camera, objs, &[]);
+            sat.object_name = object_name;
+            sat.norad_id = rec.map(|r| r.norad_id).unwrap_or(0);
+            sat.sv_id = st.sv_ids.get(sat_idx).copied().flatten();
+            sat.svn = ident.and_then(|i| i.svn.clone());
+            sat.block = ident.and_then(|i| i.block.clone());
+            sat.freq_channel = ident.and_then(|i| i.freq_channel);
+            sat.healthy = ident.and_then(|i| i.healthy);
+            sat.sbas_provider = rec.and_then(|r| r.sbas_provider);
+            Some(sat)
+        })
+        .collect();
+
+    // Walker slot IDs follow the TLE records in `sv_ids`
+    let mut slot_base = st.tle_store.records.len();
+    for w in &st.walkers {
+        let base = slot_base;
+        slot_base += w.slots.len();
+        if !w.enabled {
+            continue;
+        }
+        let class = w.orbit_class();
+        for (i, pos_km) in w.positions_ecef(unix_s).iter().enumerate() {
+            let Some(mut sat) = sky_sat(w.def.constellation, class, pos_km) else { continue };
+            sat.name = w.slot_label(i);
+            sat.object_name = sat.name.clone();
+            sat.sv_id = st.sv_ids.get(base + i).copied().flatten();
+            sat.walker = Some(w.def.name.clone());
+            [sat.r, sat.g, sat.b] = w.def.color;
+            out.push(sat);
+        }
+    }
+    out
+}
 
+// ── Entry point ───────────────────────────────────────────────────────────────
+
+/// Browser entry point: render the scene into `#gnss-canvas`. Native front
+/// ends create their own window and drive `scene::Scene` the same way.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() {
+    use three_d::{FrameOutput, Window, WindowSettings};
+    use wasm_bindgen::JsCast;
+
+    // Seed sim epoch to current wall-clock time
+    STATE.with(|s| s.borrow_mut().sim_epoch = js_sys::Date::now() / 1000.0);
+
+    // The propagation worker loads this module too; it only uses `Propagator`.
+    let Some(web_window) = web_sys::window() else { return };
+
+    let canvas = web_window
+        .document()
+        .expect("no document")
+        .get_element_by_id("gnss-canvas")
+        .expect("no #gnss-canvas")
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .expect("not a canvas");
+
+    let window = Window::new(WindowSettings {
+        title: "gnss constellation".to_string(),
+        canvas: Some(canvas),
+        ..Default::default()
+    })
+    .expect("window");
+
+    let scene = scene::Scene::new(&window.gl(), window.viewport());
+    SCENE.with(|s| *s.borrow_mut() = Some(scene));
+    window.render_loop(move |mut frame_input| {
+        SCENE.with(|s| {
+            if let Some(scene) = s.borrow_mut().as_mut() {
+                scene.handle_events(&mut frame_input.events);
+                scene.update(frame_input.elapsed_time, frame_input.viewport);
+                scene.render(&frame_input.screen());
+            }
+        });
+        dispatch_events();
         FrameOutput::default()
     });
 }
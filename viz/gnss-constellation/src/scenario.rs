@@ -0,0 +1,434 @@
+// scenario.rs — Save and restore the viewer state
+//
+// Responsibilities:
+//   - Snapshot `STATE` (observer, clock, masks, constellation and orbit-type
+//     toggles, highlight, overlays, Walker definitions, stations, moving-
+//     observer route and attitude mask, data sources) plus
+//     the camera pose into a serde `Scenario`, and apply one back
+//   - Encode a scenario for a URL fragment: `#scenario=<base64url JSON>`
+//
+// Bulk data — element sets, borders, horizon profile, buildings, catalog,
+// NMEA / RINEX logs — is not embedded. `data_sources` records where each
+// was loaded from (`set_data_source`) so the front end can load it again.
+// Every field is optional on import; missing ones take the viewer defaults.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use gnss_core::{classify::ORBIT_CLASS_COUNT, ground::Observer, station::Station, tles::CONSTELLATION_COUNT, walker};
+use gnss_core::trajectory::{Trajectory, Waypoint};
+
+use crate::scene::CameraPose;
+use crate::{propagate_ecef_into, sat_sv_ids, sync_trajectory_observer, GnssState, STATE};
+
+/// Format version written by `export_scenario`; newer files are rejected.
+pub const SCENARIO_VERSION: u32 = 1;
+
+/// URL fragment key: `https://…/gnss.html#scenario=…`.
+pub const FRAGMENT_KEY: &str = "scenario=";
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Scenario {
+    pub version: u32,
+    pub observer: ObserverSpec,
+    /// Sim epoch, Unix seconds.
+    pub epoch_unix: f64,
+    pub paused: bool,
+    pub time_warp: f64,
+    pub elev_mask_deg: f64,
+    pub visible_only: bool,
+    /// Per constellation index (GPS … Other, SBAS).
+    pub constellations: [bool; CONSTELLATION_COUNT],
+    /// Per constellation, per orbit class (MEO, IGSO, GEO).
+    pub orbit_classes: [[bool; ORBIT_CLASS_COUNT]; CONSTELLATION_COUNT],
+    /// Highlighted constellation index, -1 for none.
+    pub highlighted: i32,
+    pub overlays: Overlays,
+    /// `None` leaves the camera where it is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera: Option<CameraPose>,
+    pub walkers: Vec<WalkerSpec>,
+    pub stations: Vec<Station>,
+    /// Route the observer follows; `None` for a fixed observer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trajectory: Option<Vec<Waypoint>>,
+    /// Body-frame antenna mask, degrees above the vehicle's roof plane;
+    /// `None` ignores the attitude.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_mask_deg: Option<f64>,
+    /// Kind ("elements", "borders", "horizon", …) → URL or file path.
+    pub data_sources: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct ObserverSpec {
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub antenna_height_m: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Overlays {
+    pub inc_rings: bool,
+    pub ecef_axes: bool,
+    pub eci_axes: bool,
+    pub borders: bool,
+    pub elev_cone: bool,
+    pub ground_tracks: bool,
+    pub point_sprites: bool,
+    pub prn_labels: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WalkerSpec {
+    #[serde(flatten)]
+    pub def: walker::WalkerDef,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+impl Default for ObserverSpec {
+    fn default() -> Self {
+        Scenario::from_state(&GnssState::default(), None).observer
+    }
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        Scenario::from_state(&GnssState::default(), None).overlays
+    }
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario::from_state(&GnssState::default(), None)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Capture / apply
+// ---------------------------------------------------------------------------
+
+impl Scenario {
+    fn from_state(st: &GnssState, camera: Option<CameraPose>) -> Self {
+        Scenario {
+            version: SCENARIO_VERSION,
+            observer: ObserverSpec {
+                lat_deg: st.observer.lat_deg,
+                lon_deg: st.observer.lon_deg,
+                antenna_height_m: st.antenna_height_m,
+            },
+            epoch_unix: st.sim_epoch,
+            paused: st.paused,
+            time_warp: st.time_warp,
+            elev_mask_deg: st.elev_mask_deg,
+            visible_only: st.visible_only,
+            constellations: st.constellation_visible,
+            orbit_classes: st.orbit_class_visible,
+            highlighted: st.highlighted,
+            overlays: Overlays {
+                inc_rings: st.show_inc_rings,
+                ecef_axes: st.show_ecef_axes,
+                eci_axes: st.show_eci_axes,
+                borders: st.show_borders,
+                elev_cone: st.show_elev_cone,
+                ground_tracks: st.show_ground_tracks,
+                point_sprites: st.point_sprites,
+                prn_labels: st.prn_labels,
+            },
+            camera,
+            walkers: st
+                .walkers
+                .iter()
+                .map(|w| WalkerSpec { def: w.resolved_def(), enabled: w.enabled })
+                .collect(),
+            stations: st.stations.clone(),
+            trajectory: st.trajectory.as_ref().map(|t| t.waypoints.clone()),
+            body_mask_deg: st.body_mask_deg,
+            data_sources: st.data_sources.clone(),
+        }
+    }
+
+    /// Snapshot the current `STATE` with the given camera pose.
+    pub fn capture(camera: Option<CameraPose>) -> Self {
+        STATE.with(|s| Scenario::from_state(&s.borrow(), camera))
+    }
+
+    /// Validate and apply to `STATE`. Nothing changes unless the whole
+    /// scenario is valid. The camera pose is the caller's to apply.
+    pub fn apply(&self) -> Result<(), String> {
+        if self.version > SCENARIO_VERSION {
+            return Err(format!("scenario version {} is newer than {SCENARIO_VERSION}", self.version));
+        }
+        let o = &self.observer;
+        if !(-90.0..=90.0).contains(&o.lat_deg) || !o.lon_deg.is_finite() {
+            return Err(format!("observer {}, {} is not a valid position", o.lat_deg, o.lon_deg));
+        }
+        if !self.epoch_unix.is_finite() || !self.time_warp.is_finite() {
+            return Err("epoch and time warp must be finite".to_string());
+        }
+        let walkers = self
+            .walkers
+            .iter()
+            .map(|w| {
+                walker::Walker::new(w.def.clone(), self.epoch_unix).map(|mut built| {
+                    built.enabled = w.enabled;
+                    built
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        for stn in &self.stations {
+            stn.validate()?;
+        }
+        let trajectory = self.trajectory.clone().map(Trajectory::new).transpose()?;
+
+        STATE.with(|s| {
+            let mut st = s.borrow_mut();
+            let st = &mut *st;
+            st.observer = Observer::new(o.lat_deg, o.lon_deg);
+            st.antenna_height_m = if o.antenna_height_m.is_finite() { o.antenna_height_m.max(0.0) } else { 0.0 };
+            st.sim_epoch = self.epoch_unix;
+            st.paused = self.paused;
+            st.time_warp = self.time_warp.max(0.0);
+            st.elev_mask_deg = if self.elev_mask_deg.is_finite() { self.elev_mask_deg.clamp(0.0, 89.0) } else { 5.0 };
+            st.visible_only = self.visible_only;
+            st.constellation_visible = self.constellations;
+            st.orbit_class_visible = self.orbit_classes;
+            st.highlighted = if (0..CONSTELLATION_COUNT as i32).contains(&self.highlighted) { self.highlighted } else { -1 };
+            let ov = &self.overlays;
+            st.show_inc_rings = ov.inc_rings;
+            st.show_ecef_axes = ov.ecef_axes;
+            st.show_eci_axes = ov.eci_axes;
+            st.show_borders = ov.borders;
+            st.show_elev_cone = ov.elev_cone;
+            st.show_ground_tracks = ov.ground_tracks;
+            st.point_sprites = ov.point_sprites;
+            st.prn_labels = ov.prn_labels;
+            st.walkers = walkers;
+            st.sv_ids = sat_sv_ids(st);
+            st.stations = self.stations.clone();
+            st.data_sources = self.data_sources.clone();
+            // A route moves the observer off `observer` to the epoch's pose
+            if trajectory.is_some() {
+                st.replay = None;
+            }
+            st.trajectory = trajectory;
+            st.pose = None;
+            st.body_mask_deg = self.body_mask_deg.filter(|m| *m >= 0.0).map(|m| m.min(90.0));
+            sync_trajectory_observer(st);
+
+            // The epoch jumped: drop interpolation samples and propagate now,
+            // so a paused scenario shows the right positions immediately.
+            st.prop_samples.reset(st.tle_store.records.len());
+            propagate_ecef_into(&st.tle_store, st.sim_epoch, &mut st.sat_ecef_km);
+            st.event_detector.reset();
+            st.walkers_dirty = true;
+            st.stations_dirty = true;
+            st.instances_dirty = true;
+            st.cone_needs_rebuild = true;
+        });
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("scenario JSON parse error: {e}"))
+    }
+
+    /// `scenario=<base64url compact JSON>`, without the leading '#'.
+    pub fn to_fragment(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        format!("{FRAGMENT_KEY}{}", base64url_encode(json.as_bytes()))
+    }
+
+    /// Parse a fragment from `to_fragment`, with or without the leading '#'.
+    pub fn from_fragment(fragment: &str) -> Result<Self, String> {
+        let body = fragment.trim().trim_start_matches('#');
+        let body = body
+            .split('&')
+            .find_map(|part| part.strip_prefix(FRAGMENT_KEY))
+            .ok_or_else(|| format!("no '{FRAGMENT_KEY}' in URL fragment"))?;
+        let bytes = base64url_decode(body).ok_or("scenario fragment is not base64url")?;
+        let json = String::from_utf8(bytes).map_err(|_| "scenario fragment is not UTF-8".to_string())?;
+        Scenario::from_json(&json)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// base64url (RFC 4648 §5, no padding)
+// ---------------------------------------------------------------------------
+
+const B64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(B64URL[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Accepts padded input and the standard '+' '/' alphabet too, since
+/// links get pasted through all sorts of tools.
+fn base64url_decode(s: &str) -> Option<Vec<u8>> {
+    let sextet = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'-' | b'+' => Some(62),
+        b'_' | b'/' => Some(63),
+        _ => None,
+    };
+    let s = s.trim_end_matches('=').as_bytes();
+    if s.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            n |= (sextet(c)? as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64url_round_trip() {
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 97 + 251) as u8).collect();
+            let enc = base64url_encode(&bytes);
+            assert!(!enc.contains(['+', '/', '=']));
+            assert_eq!(base64url_decode(&enc), Some(bytes));
+        }
+        assert_eq!(base64url_encode(b"gnss?"), "Z25zcz8");
+        assert_eq!(base64url_decode("Z25zcz8="), Some(b"gnss?".to_vec()));
+        assert_eq!(base64url_decode("Z25zc"), None);
+        assert_eq!(base64url_decode("Z2*z"), None);
+    }
+
+    /// Export → change everything → import restores the exported state,
+    /// through both JSON and the URL fragment.
+    #[test]
+    fn test_capture_apply_round_trip() {
+        crate::set_ground_location(41.85, -87.65);
+        crate::set_sim_epoch(1_705_320_000.0);
+        crate::set_time_warp(600.0);
+        crate::set_elev_mask(12.0);
+        crate::toggle_constellation(1, false);
+        crate::toggle_orbit_class(3, 2, false);
+        crate::set_highlighted_constellation(2);
+        crate::set_show_elev_cone(true);
+        crate::set_data_source("elements", "/api/tle/gnss");
+        assert_eq!(crate::add_walker(r#"{"name": "LEO", "notation": "87.9:24/6/1", "alt_km": 1100}"#), 0);
+        assert_eq!(crate::add_station(r#"{"name": "BASE-1", "lat_deg": 42.0, "lon_deg": -88.0}"#), 0);
+        let route = r#"[{"time": "2024-01-15T11:50:00Z", "lat_deg": 41.9, "lon_deg": -87.9, "alt_m": 3000},
+                        {"time": "2024-01-15T12:10:00Z", "lat_deg": 41.9, "lon_deg": -87.5, "alt_m": 3000}]"#;
+        assert_eq!(crate::load_trajectory(route), 2);
+        crate::set_attitude_mask(15.0);
+        let pose = CameraPose { az: 1.0, el: -0.2, dist: 5.0 };
+        let saved = Scenario::capture(Some(pose));
+        assert_eq!(saved.trajectory.as_ref().map(Vec::len), Some(2));
+        assert_eq!(saved.body_mask_deg, Some(15.0));
+
+        crate::set_ground_location(-33.9, 151.2);
+        crate::set_sim_epoch(0.0);
+        crate::toggle_constellation(1, true);
+        crate::set_show_elev_cone(false);
+        crate::clear_walkers();
+        crate::clear_stations();
+        crate::clear_trajectory();
+        crate::set_attitude_mask(-1.0);
+        crate::set_data_source("elements", "");
+
+        let from_json = Scenario::from_json(&saved.to_json()).unwrap();
+        assert_eq!(from_json, saved);
+        let fragment = saved.to_fragment();
+        assert!(fragment.starts_with(FRAGMENT_KEY));
+        let from_link = Scenario::from_fragment(&format!("#{fragment}")).unwrap();
+        assert_eq!(from_link, saved);
+
+        from_link.apply().unwrap();
+        assert_eq!(Scenario::capture(Some(pose)), saved);
+        // The rebuilt Walker keeps its original reference epoch
+        let epoch = STATE.with(|s| s.borrow().walkers[0].resolved_def().epoch_unix);
+        assert_eq!(epoch, Some(1_705_320_000.0));
+        // The observer rides the restored route
+        let lon = STATE.with(|s| s.borrow().observer.lon_deg);
+        assert!((lon - -87.7).abs() < 1e-6, "{lon}");
+        crate::clear_walkers();
+        crate::clear_stations();
+        crate::clear_trajectory();
+        crate::set_attitude_mask(-1.0);
+    }
+
+    /// Walker slots take NMEA IDs the TLE records leave free, and keep them
+    /// when a scenario rebuilds the constellations.
+    #[test]
+    fn test_walker_sv_ids_after_tles() {
+        let tle = "GPS BIIF-2  (PRN 01)
+1 37753U 11036A   24015.50000000  .00000000  00000-0  00000-0 0  9990
+2 37753  55.0000 100.0000 0001000   0.0000   0.0000  2.00560000 12345
+";
+        assert_eq!(crate::inject_tle_text(tle), 1);
+        let walker = r#"{"name": "W", "notation": "55:4/2/1", "alt_km": 20200, "constellation": 0}"#;
+        assert_eq!(crate::add_walker(walker), 0);
+        let ids = || STATE.with(|s| s.borrow().sv_ids.clone());
+        assert_eq!(ids(), [1, 2, 3, 4, 5].map(Some));
+
+        let saved = Scenario::capture(None);
+        crate::clear_walkers();
+        assert_eq!(ids(), [Some(1)]);
+        saved.apply().unwrap();
+        assert_eq!(ids(), [1, 2, 3, 4, 5].map(Some));
+        crate::clear_walkers();
+    }
+
+    /// A hand-written scenario only needs the fields it changes; invalid
+    /// ones are rejected without touching the state.
+    #[test]
+    fn test_partial_and_invalid_scenarios() {
+        let s = Scenario::from_json(r#"{"observer": {"lat_deg": 51.5}, "overlays": {"elev_cone": true}}"#).unwrap();
+        assert_eq!(s.observer.lat_deg, 51.5);
+        assert_eq!(s.observer.antenna_height_m, Scenario::default().observer.antenna_height_m);
+        assert!(s.overlays.elev_cone && s.overlays.borders);
+        assert!(s.constellations.iter().all(|&on| on));
+        assert_eq!(s.camera, None);
+
+        crate::set_ground_location(10.0, 20.0);
+        let bad_walker = r#"{"observer": {"lat_deg": 51.5}, "walkers": [{"name": "X", "notation": "55:7/3/1"}]}"#;
+        assert!(Scenario::from_json(bad_walker).unwrap().apply().is_err());
+        assert!(Scenario::from_json(r#"{"observer": {"lat_deg": 91}}"#).unwrap().apply().is_err());
+        assert!(Scenario::from_json(r#"{"version": 99}"#).unwrap().apply().is_err());
+        assert_eq!(Scenario::capture(None).observer.lat_deg, 10.0);
+        assert!(Scenario::from_fragment("#view=abc").is_err());
+    }
+}
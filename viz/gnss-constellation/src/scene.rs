@@ -0,0 +1,1088 @@
+// scene.rs — The three-d globe scene shared by every front end
+//
+// Responsibilities:
+//   - Build the GPU objects: Earth, graticule, observer tower, Phase-1
+//     orbit rings, TLE and Walker satellites, ground tracks, axes, the
+//     elevation cone and country borders
+//   - Advance the sim clock and rebuild instance buffers from `STATE`
+//     each frame (dirty flags, sampled propagation)
+//   - Orbit the camera from mouse input and render into any `RenderTarget`
+//
+// Platform-independent: the wasm `start()` drives a `Scene` from the
+// canvas render loop, the native desktop app from a winit window. Neither
+// touches the GPU objects directly — all scene state goes through `STATE`
+// and the exported setters.
+
+use std::f32::consts::PI;
+use three_d::*;
+use gnss_core::{classify, coords, ground, horizon, interp, satbuf, tles};
+use classify::{OrbitClass, ORBIT_CLASS_COUNT};
+use tles::TleStore;
+
+use crate::{borders, class_shown, propagate_ecef_into, refill_samples, tle_group, Sight, Site, STATE};
+
+// ── Phase-1 constellation definitions (Keplerian fallback sim) ────────────────
+
+struct ConstellationDef {
+    rgb: [u8; 3],
+    alt_km: f32,
+    inc_deg: f32,
+    planes: u32,
+    sats_per_plane: u32,
+    raan_spacing_deg: f32,
+    raan_offset_deg: f32,
+}
+
+const SATS: &[ConstellationDef] = &[
+    ConstellationDef { rgb: [57,  255, 20],  alt_km: 20200.0, inc_deg: 55.0, planes: 6, sats_per_plane: 4,  raan_spacing_deg: 60.0,  raan_offset_deg: 0.0  }, // GPS
+    ConstellationDef { rgb: [255, 68,  68],  alt_km: 19130.0, inc_deg: 64.8, planes: 3, sats_per_plane: 8,  raan_spacing_deg: 120.0, raan_offset_deg: 15.0 }, // GLONASS
+    ConstellationDef { rgb: [0,   255, 204], alt_km: 23222.0, inc_deg: 56.0, planes: 3, sats_per_plane: 10, raan_spacing_deg: 120.0, raan_offset_deg: 40.0 }, // Galileo
+    ConstellationDef { rgb: [255, 170, 0],   alt_km: 21528.0, inc_deg: 55.0, planes: 3, sats_per_plane: 8,  raan_spacing_deg: 120.0, raan_offset_deg: 80.0 }, // BeiDou
+];
+
+const EARTH_R: f32 = 6371.0;
+const MU: f32 = 398_600.44;
+const RING_PTS: u32 = 120; // 120 pts/ring — visually solid, half the Mat4 cost of 240
+
+/// J2 secular nodal precession rates (rad/s) per constellation, indexed 0=GPS..3=BeiDou.
+/// dΩ/dt = -3/2 * n * J2 * (R_E/a)² * cos(i)
+/// GPS:     a=26571 km, i=55°  → -7.80e-9 rad/s
+/// GLONASS: a=25501 km, i=64.8°→ -6.69e-9 rad/s
+/// Galileo: a=29593 km, i=56°  → -5.21e-9 rad/s
+/// BeiDou:  a=27899 km, i=55°  → -6.58e-9 rad/s
+const J2_RATES: [f64; 4] = [-7.80e-9, -6.69e-9, -5.21e-9, -6.58e-9];
+
+fn alt_norm(alt_km: f32) -> f32 {
+    (EARTH_R + alt_km) / EARTH_R
+}
+
+fn period_s(alt_km: f32) -> f32 {
+    let a = EARTH_R + alt_km;
+    2.0 * PI * (a * a * a / MU).sqrt()
+}
+
+/// Keplerian position in normalised scene units (Earth radius = 1.0).
+/// Convention: Z = north pole, equatorial plane = XY.
+/// inc tilts the orbital plane from equatorial (rotation around X / line-of-nodes).
+/// raan rotates the ascending node around Z (correct J2 precession axis).
+fn kpos(r: f32, inc: f32, raan: f32, m: f32) -> Vec3 {
+    let xo = r * m.cos(); // radial in orbital plane
+    let yo = r * m.sin(); // along-track in orbital plane
+    // Apply inclination (rotation around X axis)
+    let (x1, y1, z1) = (xo, yo * inc.cos(), yo * inc.sin());
+    // Apply RAAN (rotation around Z axis — the north pole)
+    vec3(
+        x1 * raan.cos() - y1 * raan.sin(),
+        x1 * raan.sin() + y1 * raan.cos(),
+        z1,
+    )
+}
+
+struct SatState {
+    r: f32, inc: f32, rsp: f32, roff: f32, mm: f32,
+    planes: u32, sats_per_plane: u32,
+}
+
+// ── Constellation colours (per-constellation material colour in TLE mode) ─────
+
+const CONST_COLORS: [[u8; 3]; tles::CONSTELLATION_COUNT] = [
+    [57, 255, 20],    // GPS      (0) — neon green
+    [255, 68, 68],    // GLONASS  (1) — red
+    [0, 255, 204],    // Galileo  (2) — cyan
+    [255, 170, 0],    // BeiDou   (3) — orange
+    [160, 80, 255],   // QZSS     (4) — violet
+    [255, 80, 160],   // NavIC    (5) — magenta
+    [128, 128, 128],  // Other    (6) — grey
+    [255, 255, 120],  // SBAS     (7) — pale yellow
+];
+
+/// Above this many satellites the point-sprite path is used regardless of
+/// `set_point_sprites` — instanced spheres cost ~100× the vertices.
+const POINT_SPRITE_AUTO: usize = 5000;
+
+/// TLE-mode satellite mesh groups: one per (constellation, orbit type),
+/// indexed `constellation * ORBIT_CLASS_COUNT + OrbitClass::index`.
+const TLE_GROUPS: usize = tles::CONSTELLATION_COUNT * ORBIT_CLASS_COUNT;
+
+fn tle_group_color(group: usize) -> [u8; 3] {
+    let ci = group / ORBIT_CLASS_COUNT;
+    ground::orbit_class_color(CONST_COLORS[ci], ci as u8, OrbitClass::ALL[group % ORBIT_CLASS_COUNT])
+}
+
+// ── IGSO ground tracks ────────────────────────────────────────────────────────
+
+/// An IGSO repeats its ground track every sidereal day, so one day of
+/// samples traces the whole figure-eight.
+const SIDEREAL_DAY_S: f64 = 86164.0905;
+const TRACK_PTS: u32 = 288; // 5-min spacing
+/// Rebuild tracks after this much sim time — elements drift slowly.
+const TRACK_REFRESH_S: f64 = 3600.0;
+
+/// Sub-satellite points of every IGSO / QZO / GSO record over one sidereal
+/// day from `unix_s`, as ECEF unit vectors, bucketed by constellation.
+fn igso_ground_tracks(store: &TleStore, unix_s: f64) -> Vec<Vec<[f64; 3]>> {
+    let mut tracks = vec![Vec::new(); tles::CONSTELLATION_COUNT];
+    for rec in store.records.iter().filter(|r| r.orbit_class == OrbitClass::Igso) {
+        let Some(track) = tracks.get_mut(rec.constellation as usize) else { continue };
+        for i in 0..TRACK_PTS {
+            let t = unix_s + i as f64 * SIDEREAL_DAY_S / TRACK_PTS as f64;
+            let e = coords::teme_to_ecef(TleStore::propagate(rec, t), coords::gmst_rad(t));
+            let r = (e[0] * e[0] + e[1] * e[1] + e[2] * e[2]).sqrt();
+            track.push([e[0] / r, e[1] / r, e[2] / r]);
+        }
+    }
+    tracks
+}
+
+/// Builds a small cone tower pointing outward from the Earth surface at obs_n.
+/// Apex at 1.10 × Earth radius, base ring at 1.00 (surface), radius 0.025.
+fn build_observer_tower(obs_n: Vec3) -> CpuMesh {
+    let n = 8i32;
+    let tip_r = 1.10f32;
+    let base_r = 0.025f32;
+
+    let up_ref = if obs_n.z.abs() < 0.9 { vec3(0.0f32, 0.0, 1.0) } else { vec3(1.0f32, 0.0, 0.0) };
+    let e1 = obs_n.cross(up_ref).normalize();
+    let e2 = obs_n.cross(e1).normalize();
+
+    let tip = obs_n * tip_r;
+    let base_center = obs_n;
+
+    let mut verts: Vec<Vec3> = vec![tip]; // index 0 = tip
+    for i in 0..n {
+        let phi = i as f32 * 2.0 * PI / n as f32;
+        verts.push(obs_n + base_r * (phi.cos() * e1 + phi.sin() * e2));
+    }
+    let center_idx = verts.len() as u32;
+    verts.push(base_center);
+
+    let mut idxs: Vec<u32> = Vec::new();
+    // Side triangles: tip → next base → current base (CCW when viewed from outside)
+    for i in 1..=(n as u32) {
+        let next = if i == n as u32 { 1 } else { i + 1 };
+        idxs.push(0);     // tip
+        idxs.push(next);
+        idxs.push(i);
+    }
+    // Base cap: base_center → current → next (CCW from below, i.e., inward normal)
+    for i in 1..=(n as u32) {
+        let next = if i == n as u32 { 1 } else { i + 1 };
+        idxs.push(center_idx);
+        idxs.push(i);
+        idxs.push(next);
+    }
+
+    CpuMesh {
+        positions: Positions::F32(verts),
+        indices: Indices::U32(idxs),
+        ..Default::default()
+    }
+}
+
+/// Builds a solid cone from the observer surface point to GPS altitude (4.17 scene units).
+/// The cone boundary follows `mask_at(az_deg)` — the effective elevation mask
+/// per compass azimuth — so a site horizon profile deforms the rim.
+/// Returns a filled triangle-fan CpuMesh.
+fn build_elev_cone(obs_n: Vec3, mask_at: impl Fn(f64) -> f64) -> CpuMesh {
+    const N: i32 = 180; // 2° azimuth steps — resolves per-bin horizon profiles
+    const GPS_ALT_SCENE: f32 = 4.17; // (6371 + 20200) / 6371
+
+    // Local East/North basis so rim vertices line up with coords::az_el azimuths
+    let up_ref = if obs_n.z.abs() < 0.9 { vec3(0.0f32, 0.0, 1.0) } else { vec3(1.0f32, 0.0, 0.0) };
+    let east = up_ref.cross(obs_n).normalize();
+    let north = obs_n.cross(east).normalize();
+
+    // Apex at Earth surface
+    let mut verts: Vec<Vec3> = vec![obs_n];
+
+    // Rim: ray from apex in direction `dir`, intersect sphere of radius GPS_ALT_SCENE
+    // Ray: P(t) = obs_n + t*dir, |P|² = GPS_ALT_SCENE²
+    // t² + 2t*(obs_n·dir) + 1 - GPS_ALT_SCENE² = 0
+    // obs_n·dir = sin_el (since dir = sin_el*obs_n + cos_el*(sin_az*east+cos_az*north))
+    // t = -sin_el + sqrt(sin_el² - 1 + GPS_ALT_SCENE²)
+    for i in 0..N {
+        let az_deg = i as f64 * 360.0 / N as f64;
+        let el_rad = (mask_at(az_deg) as f32).to_radians();
+        let (sin_el, cos_el) = (el_rad.sin(), el_rad.cos());
+        let az = (az_deg as f32).to_radians();
+
+        let discriminant = sin_el * sin_el - 1.0 + GPS_ALT_SCENE * GPS_ALT_SCENE;
+        let t_rim = if discriminant >= 0.0 { -sin_el + discriminant.sqrt() } else { GPS_ALT_SCENE };
+
+        let dir = (sin_el * obs_n + cos_el * (az.sin() * east + az.cos() * north)).normalize();
+        verts.push(obs_n + t_rim * dir);
+    }
+
+    // Triangle fan: apex(0) → rim[next] → rim[i]. Azimuth runs clockwise
+    // seen from above, so `next` before `i` keeps the fan facing outward.
+    let mut idxs: Vec<u32> = Vec::new();
+    for i in 1..=(N as u32) {
+        let next = if i == N as u32 { 1 } else { i + 1 };
+        idxs.push(0);
+        idxs.push(next);
+        idxs.push(i);
+    }
+
+    CpuMesh {
+        positions: Positions::F32(verts),
+        indices: Indices::U32(idxs),
+        ..Default::default()
+    }
+}
+
+// ── Scene ─────────────────────────────────────────────────────────────────────
+
+/// Spherical camera pose: azimuth (longitude) and elevation (latitude) in
+/// radians, distance in scene units (Earth radius = 1.0).
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CameraPose {
+    pub az: f64,
+    pub el: f64,
+    pub dist: f64,
+}
+
+impl Default for CameraPose {
+    fn default() -> Self {
+        CameraPose {
+            az: 0.3,    // radians, initial horizontal angle
+            el: 0.42,   // radians, initial elevation (~24°)
+            dist: 19.0, // scene units — pulled back to show outer QZSS/NavIC/BeiDou rings
+        }
+    }
+}
+
+impl CameraPose {
+    /// Keep the pose within the range the mouse controls allow: short of
+    /// the poles, between just above the surface and the outer rings.
+    pub fn clamped(self) -> Self {
+        CameraPose { az: self.az, el: self.el.clamp(-1.55, 1.55), dist: self.dist.clamp(1.5, 30.0) }
+    }
+
+    /// Camera position and spherical "north": dPos/d(el), always
+    /// perpendicular to the view direction.
+    fn position_up(&self) -> (Vec3, Vec3) {
+        let (az, el) = (self.az, self.el);
+        let pos = vec3((el.cos() * az.cos()) as f32, (el.cos() * az.sin()) as f32, el.sin() as f32) * self.dist as f32;
+        let up = vec3((-el.sin() * az.cos()) as f32, (-el.sin() * az.sin()) as f32, el.cos() as f32);
+        (pos, up)
+    }
+}
+
+/// Every GPU object of the globe view plus the per-frame bookkeeping that
+/// used to live in the `start()` render loop.
+pub struct Scene {
+    context: Context,
+    pub pose: CameraPose,
+    camera: Camera,
+
+    earth: Gm<Mesh, ColorMaterial>,
+    eq_ring: Gm<Mesh, ColorMaterial>,
+    graticule: Gm<InstancedMesh, ColorMaterial>,
+    ground_marker: Gm<Mesh, ColorMaterial>,
+    prev_obs_n: Vec3,
+    /// One tower per named station, in its colour.
+    station_gms: Vec<Gm<Mesh, ColorMaterial>>,
+
+    sat_dot: CpuMesh,
+    orbit_gms: Vec<Gm<InstancedMesh, ColorMaterial>>,
+    sat_gms: Vec<Gm<InstancedMesh, ColorMaterial>>,
+    states: Vec<SatState>,
+    tle_sat_gms: Vec<Gm<InstancedMesh, ColorMaterial>>,
+    tle_sprite_gms: Vec<Gm<Sprites, ColorMaterial>>,
+    /// Per-group instance buckets, cleared and refilled in one pass per tick.
+    mat_buckets: Vec<Vec<Mat4>>,
+    sprite_buckets: Vec<Vec<Vec3>>,
+    use_sprites: bool,
+    walker_gms: Vec<(Gm<InstancedMesh, ColorMaterial>, Gm<Sprites, ColorMaterial>)>,
+    track_gms: Vec<Gm<InstancedMesh, ColorMaterial>>,
+    /// (record count, sim epoch) of the last track rebuild.
+    track_built: Option<(usize, f64)>,
+
+    ecef_gms: [Gm<InstancedMesh, ColorMaterial>; 3],
+    eci_gms: [Gm<InstancedMesh, ColorMaterial>; 3],
+    elev_cone_gm: Option<Gm<Mesh, ColorMaterial>>,
+    borders_gm: Option<Gm<Mesh, ColorMaterial>>,
+
+    /// Sim epoch at scene creation — the J2 precession reference.
+    epoch_zero: f64,
+    /// Ring dirty-check: skip Mat4 rebuild when sim is paused (epoch constant).
+    last_ring_epoch: f64,
+    /// Propagation throttle: wall-clock time since the last tick; the tick
+    /// interval grows with satellite count (interp::wall_interval_s).
+    prop_timer_ms: f64,
+    /// Sim epoch of the last propagation in frame-stepping mode.
+    stepped_epoch: f64,
+}
+
+const RING_SCALE: f32 = 0.013; // slightly larger for solid-ring appearance
+const SAT_SCALE: f32 = 0.06; // 2× size for visibility
+const SPRITE_SCALE: f32 = 0.035;
+const TRACK_SCALE: f32 = 0.005;
+const AXIS_DOTS: i32 = 30;
+const AXIS_MAX_R: f32 = 2.5;
+const AXIS_DOT_SCALE: f32 = 0.010;
+
+/// Dots along `dir` from the origin out to `AXIS_MAX_R`.
+fn axis_xforms(dir: Vec3) -> Vec<Mat4> {
+    (1..=AXIS_DOTS)
+        .map(|i| Mat4::from_translation(dir * (i as f32 * AXIS_MAX_R / AXIS_DOTS as f32)) * Mat4::from_scale(AXIS_DOT_SCALE))
+        .collect()
+}
+
+/// Instanced mesh with a single zero-scale instance — the hidden state.
+fn hidden_instanced(context: &Context, mesh: &CpuMesh, color: Srgba) -> Gm<InstancedMesh, ColorMaterial> {
+    Gm::new(
+        InstancedMesh::new(context, &Instances { transformations: vec![Mat4::from_scale(0.0)], ..Default::default() }, mesh),
+        ColorMaterial { color, ..Default::default() },
+    )
+}
+
+impl Scene {
+    pub fn new(context: &Context, viewport: Viewport) -> Self {
+        let pose = CameraPose::default();
+        let (cam_pos, cam_up) = pose.position_up();
+        let camera = Camera::new_perspective(viewport, cam_pos, vec3(0.0f32, 0.0, 0.0), cam_up, degrees(42.0), 0.1, 200.0);
+
+        // Earth sphere (radius = 1.0 scene units)
+        let earth = Gm::new(
+            Mesh::new(context, &CpuMesh::sphere(32)),
+            ColorMaterial { color: Srgba::new(8, 20, 8, 255), ..Default::default() },
+        );
+
+        // Equatorial ring
+        let eq_ring = Gm::new(
+            Mesh::new(context, &CpuMesh::circle(128)),
+            ColorMaterial { color: Srgba::new(20, 60, 20, 255), ..Default::default() },
+        );
+
+        // Lat/lon graticule — 15° grid dots on Earth surface
+        let grid_dot_mesh = CpuMesh::sphere(2);
+        let grid_dot_scale = Mat4::from_scale(0.007f32);
+        let mut grid_xforms: Vec<Mat4> = Vec::new();
+        for lat_i in -5i32..=5 {          // −75° to +75° in 15° steps
+            let lat = (lat_i as f32 * 15.0).to_radians();
+            for lon_i in 0..24i32 {        // 0° to 345° in 15° steps
+                let lon = (lon_i as f32 * 15.0).to_radians();
+                let x = lat.cos() * lon.cos();
+                let y = lat.cos() * lon.sin();
+                let z = lat.sin();
+                grid_xforms.push(Mat4::from_translation(vec3(x, y, z)) * grid_dot_scale);
+            }
+        }
+        // Polar caps
+        grid_xforms.push(Mat4::from_translation(vec3(0.0f32, 0.0, 1.0)) * grid_dot_scale);
+        grid_xforms.push(Mat4::from_translation(vec3(0.0f32, 0.0, -1.0)) * grid_dot_scale);
+        let graticule = Gm::new(
+            InstancedMesh::new(
+                context,
+                &Instances { transformations: grid_xforms, ..Default::default() },
+                &grid_dot_mesh,
+            ),
+            ColorMaterial { color: Srgba::new(100, 100, 100, 255), ..Default::default() },
+        );
+
+        // Ground observer marker — cone tower pointing outward from Earth surface
+        // Rebuilt when the observer changes. Start with a placeholder at (0,1,0).
+        let obs_init = vec3(0.0f32, 1.0, 0.0);
+        let ground_marker = Gm::new(
+            Mesh::new(context, &build_observer_tower(obs_init)),
+            ColorMaterial { color: Srgba::new(255, 240, 60, 255), ..Default::default() },
+        );
+
+        // ── Keplerian Phase-1 orbit rings + satellite meshes ──────────────────
+        let ring_dot = CpuMesh::sphere(3);
+        let sat_dot = CpuMesh::sphere(4);
+        let ring_scale = Mat4::from_scale(RING_SCALE);
+
+        let mut orbit_gms = Vec::new();
+        let mut sat_gms = Vec::new();
+        let mut states = Vec::new();
+
+        for def in SATS {
+            let r    = alt_norm(def.alt_km);
+            let inc  = def.inc_deg.to_radians();
+            let rsp  = def.raan_spacing_deg.to_radians();
+            let roff = def.raan_offset_deg.to_radians();
+            let mm   = 2.0 * PI / period_s(def.alt_km);
+
+            let ring_xforms: Vec<Mat4> = (0..def.planes)
+                .flat_map(|p| {
+                    let raan = roff + p as f32 * rsp;
+                    (0..RING_PTS).map(move |i| {
+                        let a = i as f32 * 2.0 * PI / RING_PTS as f32;
+                        Mat4::from_translation(kpos(r, inc, raan, a)) * ring_scale
+                    })
+                })
+                .collect();
+
+            let ring_col = Srgba::new(def.rgb[0] / 3, def.rgb[1] / 3, def.rgb[2] / 3, 255);
+            orbit_gms.push(Gm::new(
+                InstancedMesh::new(context, &Instances { transformations: ring_xforms, ..Default::default() }, &ring_dot),
+                ColorMaterial { color: ring_col, ..Default::default() },
+            ));
+
+            let n = def.planes * def.sats_per_plane;
+            let sat_col = Srgba::new(def.rgb[0], def.rgb[1], def.rgb[2], 255);
+            sat_gms.push(Gm::new(
+                InstancedMesh::new(context, &Instances { transformations: vec![Mat4::identity(); n as usize], ..Default::default() }, &sat_dot),
+                ColorMaterial { color: sat_col, ..Default::default() },
+            ));
+            states.push(SatState { r, inc, rsp, roff, mm, planes: def.planes, sats_per_plane: def.sats_per_plane });
+        }
+
+        // ── TLE-mode satellite meshes — one Gm per (constellation, orbit type) ─
+        let tle_sat_gms = (0..TLE_GROUPS)
+            .map(|g| {
+                let rgb = tle_group_color(g);
+                hidden_instanced(context, &sat_dot, Srgba::new(rgb[0], rgb[1], rgb[2], 255))
+            })
+            .collect();
+
+        // ── Point-sprite path — one camera-facing quad per satellite, GPU buffer
+        // refilled in place (see POINT_SPRITE_AUTO) ──────────────────────────
+        let tle_sprite_gms = (0..TLE_GROUPS)
+            .map(|g| {
+                let rgb = tle_group_color(g);
+                let mut sprites = Sprites::new(context, &[], None);
+                sprites.set_transformation(Mat4::from_scale(SPRITE_SCALE));
+                Gm::new(sprites, ColorMaterial { color: Srgba::new(rgb[0], rgb[1], rgb[2], 255), ..Default::default() })
+            })
+            .collect();
+
+        // ── IGSO ground tracks — one Gm per constellation, rebuilt when stale ─
+        let track_dot = CpuMesh::sphere(2);
+        let track_gms = (0..tles::CONSTELLATION_COUNT)
+            .map(|ci| {
+                let rgb = tle_group_color(ci * ORBIT_CLASS_COUNT + OrbitClass::Igso.index());
+                hidden_instanced(context, &track_dot, Srgba::new(rgb[0] / 2, rgb[1] / 2, rgb[2] / 2, 255))
+            })
+            .collect();
+
+        // ── ECEF reference frame axes — static dot lines; ECI X/Y follow GMST ─
+        let axis_dot_mesh = CpuMesh::sphere(2);
+        let axis = |dir: Vec3, color: Srgba| {
+            Gm::new(
+                InstancedMesh::new(context, &Instances { transformations: axis_xforms(dir), ..Default::default() }, &axis_dot_mesh),
+                ColorMaterial { color, ..Default::default() },
+            )
+        };
+        let ecef_gms = [
+            axis(vec3(1.0, 0.0, 0.0), Srgba::new(255, 80, 80, 255)),
+            axis(vec3(0.0, 1.0, 0.0), Srgba::new(80, 255, 80, 255)),
+            axis(vec3(0.0, 0.0, 1.0), Srgba::new(80, 80, 255, 255)),
+        ];
+        let eci_gms = [
+            hidden_instanced(context, &axis_dot_mesh, Srgba::new(255, 160, 80, 255)), // orange
+            hidden_instanced(context, &axis_dot_mesh, Srgba::new(160, 80, 255, 255)), // purple
+            axis(vec3(0.0, 0.0, 1.0), Srgba::new(80, 160, 255, 200)),                 // ECI Z = ECEF Z, light blue
+        ];
+
+        // A fresh scene holds no derived meshes yet — rebuild them all from STATE
+        STATE.with(|s| {
+            let mut st = s.borrow_mut();
+            st.instances_dirty = true;
+            st.walkers_dirty = true;
+            st.stations_dirty = true;
+            st.cone_needs_rebuild = true;
+            st.borders_dirty = st.borders_json.is_some();
+        });
+
+        Scene {
+            context: context.clone(),
+            pose,
+            camera,
+            earth,
+            eq_ring,
+            graticule,
+            ground_marker,
+            prev_obs_n: obs_init,
+            station_gms: Vec::new(),
+            sat_dot,
+            orbit_gms,
+            sat_gms,
+            states,
+            tle_sat_gms,
+            tle_sprite_gms,
+            mat_buckets: vec![Vec::new(); TLE_GROUPS],
+            sprite_buckets: vec![Vec::new(); TLE_GROUPS],
+            use_sprites: false,
+            walker_gms: Vec::new(),
+            track_gms,
+            track_built: None,
+            ecef_gms,
+            eci_gms,
+            elev_cone_gm: None,
+            borders_gm: None,
+            epoch_zero: STATE.with(|s| s.borrow().sim_epoch),
+            last_ring_epoch: f64::NEG_INFINITY,
+            prop_timer_ms: f64::INFINITY, // first frame propagates
+            stepped_epoch: f64::NAN,
+        }
+    }
+
+    /// Spherical camera — any-button drag → azimuth/elevation, scroll → distance.
+    pub fn handle_events(&mut self, events: &mut [Event]) {
+        for event in events.iter_mut() {
+            match event {
+                Event::MouseMotion { delta, button, handled, .. } => {
+                    if *handled { continue; }
+                    if button.is_some() {
+                        // Any button drag: horizontal → azimuth, vertical → elevation
+                        self.pose.az -= delta.0 as f64 * 0.004;
+                        self.pose.el += delta.1 as f64 * 0.004;
+                        self.pose = self.pose.clamped();
+                        *handled = true;
+                    }
+                }
+                Event::MouseWheel { delta, handled, .. } => {
+                    if *handled { continue; }
+                    self.pose.dist *= 1.0 - delta.1 as f64 * 0.08;
+                    self.pose = self.pose.clamped();
+                    *handled = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Advance the sim clock by `elapsed_ms` of wall time (× warp unless
+    /// paused) and bring every GPU object up to date with `STATE`.
+    pub fn update(&mut self, elapsed_ms: f64, viewport: Viewport) {
+        // ── 1. Advance sim clock ──────────────────────────────────────────
+        // Frame stepping stops the wall clock; `capture_frame` moves the epoch
+        let (paused, stepping) = STATE.with(|s| {
+            let st = s.borrow();
+            (st.paused, st.frame_step_s > 0.0)
+        });
+        let running = !paused && !stepping;
+        if running {
+            let warp = STATE.with(|s| s.borrow().time_warp);
+            STATE.with(|s| {
+                s.borrow_mut().sim_epoch += elapsed_ms / 1000.0 * warp;
+            });
+            // NMEA replay: follow the recorded track as the clock runs
+            STATE.with(|s| crate::sync_replay_observer(&mut s.borrow_mut()));
+        }
+        // Trajectory: the vehicle is wherever the route puts it at sim_epoch
+        STATE.with(|s| crate::sync_trajectory_observer(&mut s.borrow_mut()));
+        let sim_epoch = STATE.with(|s| s.borrow().sim_epoch);
+
+        self.prop_timer_ms += elapsed_ms;
+
+        // ── 2. Camera from the spherical pose ─────────────────────────────
+        let (cam_pos, cam_up) = self.pose.position_up();
+        self.camera.set_view(cam_pos, vec3(0.0f32, 0.0, 0.0), cam_up);
+        self.camera.set_viewport(viewport);
+
+        // Store camera VP matrix for JS axis label projection
+        {
+            let vp = self.camera.projection() * self.camera.view();
+            let arr: [f32; 16] = [
+                vp.x.x, vp.x.y, vp.x.z, vp.x.w,
+                vp.y.x, vp.y.y, vp.y.z, vp.y.w,
+                vp.z.x, vp.z.y, vp.z.z, vp.z.w,
+                vp.w.x, vp.w.y, vp.w.z, vp.w.w,
+            ];
+            STATE.with(|s| s.borrow_mut().camera_vp = arr);
+        }
+
+        // ── 3. Read display state snapshot ───────────────────────────────
+        let (has_tles, cv, highlighted, visible_only,
+             show_eci_axes, show_elev_cone, borders_dirty) = STATE.with(|s| {
+            let st = s.borrow();
+            (
+                !st.tle_store.is_empty(),
+                st.constellation_visible,
+                st.highlighted,
+                st.visible_only,
+                st.show_eci_axes,
+                st.show_elev_cone,
+                st.borders_dirty,
+            )
+        });
+        let cone_dirty = STATE.with(|s| s.borrow().cone_needs_rebuild);
+        let (ocv, show_ground_tracks) = STATE.with(|s| {
+            let st = s.borrow();
+            (st.orbit_class_visible, st.show_ground_tracks)
+        });
+
+        // The visible-only filter judges satellites through the same `Sight`
+        // as the sky data (masks, buildings, trajectory altitude and attitude).
+        let sat_scale = Mat4::from_scale(SAT_SCALE);
+
+        // ── 4. Update Keplerian orbit ring colours + J2 precession ───────
+        let ring_scale = Mat4::from_scale(RING_SCALE);
+        for (ci, og) in self.orbit_gms.iter_mut().enumerate() {
+            let base = SATS[ci].rgb;
+            og.material.color = if !cv[ci] {
+                Srgba::new(0, 0, 0, 255)
+            } else if highlighted != -1 && highlighted != ci as i32 {
+                Srgba::new(base[0] / 10, base[1] / 10, base[2] / 10, 255)
+            } else {
+                Srgba::new(base[0] / 3, base[1] / 3, base[2] / 3, 255)
+            };
+
+            // Ring transforms are pure functions of sim_epoch. Skip rebuild when
+            // paused to avoid thousands of wasted Mat4 computations per frame.
+            if (sim_epoch - self.last_ring_epoch).abs() > 1e-9 {
+                let def = &SATS[ci];
+                let r   = alt_norm(def.alt_km);
+                let inc = def.inc_deg.to_radians();
+                let rsp = def.raan_spacing_deg.to_radians();
+                let raan_base = def.raan_offset_deg.to_radians();
+                let raan_drift = (J2_RATES[ci] * (sim_epoch - self.epoch_zero)) as f32;
+
+                let ring_xforms: Vec<Mat4> = (0..def.planes)
+                    .flat_map(|p| {
+                        let raan = raan_base + p as f32 * rsp + raan_drift;
+                        (0..RING_PTS).map(move |j| {
+                            let a = j as f32 * 2.0 * std::f32::consts::PI / RING_PTS as f32;
+                            Mat4::from_translation(kpos(r, inc, raan, a)) * ring_scale
+                        })
+                    })
+                    .collect();
+                og.geometry.set_instances(&Instances { transformations: ring_xforms, ..Default::default() });
+            }
+        }
+        // Update dirty-check sentinel after processing all rings for this epoch.
+        if (sim_epoch - self.last_ring_epoch).abs() > 1e-9 {
+            self.last_ring_epoch = sim_epoch;
+        }
+
+        // ── 5. Propagate satellites — sampled, interpolated per frame ────
+        // Instance buffers are only re-bucketed on a propagation tick, an
+        // interpolated frame, or when the display filters changed; other
+        // frames reuse them as-is.
+        let interval_ms = STATE.with(|s| interp::wall_interval_s(s.borrow().tle_store.records.len())) * 1000.0;
+        // Stepped frames propagate exactly at each new epoch, so a capture
+        // never depends on wall-clock timing.
+        let tick = if stepping {
+            sim_epoch != self.stepped_epoch
+        } else {
+            running && self.prop_timer_ms >= interval_ms
+        };
+        if tick {
+            self.prop_timer_ms = 0.0;
+            self.stepped_epoch = sim_epoch;
+        }
+        let instances_dirty = STATE.with(|s| std::mem::take(&mut s.borrow_mut().instances_dirty));
+        let use_sprites = STATE.with(|s| {
+            let st = s.borrow();
+            let n = st.sat_ecef_km.len() + st.walkers.iter().map(|w| w.slots.len()).sum::<usize>();
+            st.point_sprites || n > POINT_SPRITE_AUTO
+        });
+        self.use_sprites = use_sprites;
+        if has_tles {
+            // Samples bracketing the sim epoch — from the worker, or
+            // propagated here one step ahead — are Hermite-interpolated
+            // every frame. At warps too fast to interpolate accurately,
+            // positions are propagated directly on each tick instead.
+            let interpolated = running && STATE.with(|s| {
+                let mut st = s.borrow_mut();
+                let st = &mut *st;
+                let n = st.tle_store.records.len();
+                let Some(step) = interp::sample_step_s(st.time_warp, n) else {
+                    st.prop_samples.reset(n);
+                    return false;
+                };
+                if !st.worker_propagation {
+                    refill_samples(&st.tle_store, &mut st.prop_samples, sim_epoch, step);
+                }
+                st.prop_samples.interpolate_into(sim_epoch, &mut st.sat_ecef_km.ecef_km)
+            });
+            if tick && !interpolated {
+                STATE.with(|s| {
+                    let mut st = s.borrow_mut();
+                    let st = &mut *st;
+                    propagate_ecef_into(&st.tle_store, sim_epoch, &mut st.sat_ecef_km);
+                });
+            }
+
+            for (gi, gm) in self.tle_sat_gms.iter_mut().enumerate() {
+                let ci = gi / ORBIT_CLASS_COUNT;
+                let base = tle_group_color(gi);
+                let color = if highlighted != -1 && highlighted != ci as i32 {
+                    Srgba::new(base[0] / 4, base[1] / 4, base[2] / 4, 255)
+                } else {
+                    Srgba::new(base[0], base[1], base[2], 255)
+                };
+                gm.material.color = color;
+                self.tle_sprite_gms[gi].material.color = color;
+            }
+
+            if tick || instances_dirty || interpolated {
+                // Single pass over the SoA positions, bucketed by render group
+                STATE.with(|s| {
+                    let st = s.borrow();
+                    let pos = &st.sat_ecef_km;
+                    let sight = visible_only.then(|| Sight::new(&st, Site::Primary(&st.observer)));
+                    let scene_pos = |i: usize| -> Option<Vec3> {
+                        let g = pos.group[i] as usize;
+                        let ci = g / ORBIT_CLASS_COUNT;
+                        if !(cv[ci] && ocv[ci][g % ORBIT_CLASS_COUNT]) {
+                            return None;
+                        }
+                        let pos_km = &pos.ecef_km[i];
+                        // Health check: skip satellites at implausible altitude (decayed or bad TLE)
+                        let alt_km = (pos_km[0].powi(2) + pos_km[1].powi(2) + pos_km[2].powi(2)).sqrt() - 6371.0;
+                        if !(100.0..=50_000.0).contains(&alt_km) {
+                            return None;
+                        }
+                        if sight.as_ref().is_some_and(|s| !s.sees(pos_km)) {
+                            return None;
+                        }
+                        let s = coords::km_to_scene(*pos_km);
+                        Some(vec3(s[0], s[1], s[2]))
+                    };
+                    if use_sprites {
+                        satbuf::bucket_into(pos, &mut self.sprite_buckets, scene_pos);
+                    } else {
+                        satbuf::bucket_into(pos, &mut self.mat_buckets, |i| {
+                            scene_pos(i).map(|p| Mat4::from_translation(p) * sat_scale)
+                        });
+                    }
+                });
+                if use_sprites {
+                    for (gm, centers) in self.tle_sprite_gms.iter_mut().zip(&self.sprite_buckets) {
+                        gm.geometry.set_centers(centers);
+                    }
+                } else {
+                    for (gm, xf) in self.tle_sat_gms.iter_mut().zip(self.mat_buckets.iter_mut()) {
+                        if xf.is_empty() { xf.push(Mat4::from_scale(0.0)); }
+                        // Hand the bucket to three-d and take it back to keep its allocation
+                        let inst = Instances { transformations: std::mem::take(xf), ..Default::default() };
+                        gm.geometry.set_instances(&inst);
+                        *xf = inst.transformations;
+                    }
+                }
+                // Hide Keplerian dots (rings stay as background decoration)
+                for sg in &mut self.sat_gms {
+                    sg.geometry.set_instances(&Instances {
+                        transformations: vec![Mat4::from_scale(0.0)],
+                        ..Default::default()
+                    });
+                }
+            }
+        } else {
+            // Keplerian fallback
+            let t = sim_epoch as f32;
+            STATE.with(|st| {
+                let st = st.borrow();
+                let sight = visible_only.then(|| Sight::new(&st, Site::Primary(&st.observer)));
+                let sight = sight.as_ref();
+                for (idx, s) in self.states.iter().enumerate() {
+                    let base = CONST_COLORS[idx];
+                    // Phase-1 constellations are all MEO
+                    let on = cv[idx] && ocv[idx][OrbitClass::Meo.index()];
+                    self.sat_gms[idx].material.color = if !on {
+                        Srgba::new(0, 0, 0, 255)
+                    } else if highlighted != -1 && highlighted != idx as i32 {
+                        Srgba::new(base[0] / 4, base[1] / 4, base[2] / 4, 255)
+                    } else {
+                        Srgba::new(base[0], base[1], base[2], 255)
+                    };
+                    let mut xf: Vec<Mat4> = if !on {
+                        vec![Mat4::from_scale(0.0)]
+                    } else {
+                        (0..s.planes).flat_map(|p| {
+                            let raan = s.roff + p as f32 * s.rsp;
+                            (0..s.sats_per_plane).filter_map(move |i| {
+                                let ma = i as f32 * 2.0 * PI / s.sats_per_plane as f32 + s.mm * t;
+                                let p = kpos(s.r, s.inc, raan, ma);
+                                let sat_km = [p.x as f64 * 6371.0, p.y as f64 * 6371.0, p.z as f64 * 6371.0];
+                                if sight.is_some_and(|s| !s.sees(&sat_km)) {
+                                    return None;
+                                }
+                                Some(Mat4::from_translation(p) * sat_scale)
+                            })
+                        }).collect()
+                    };
+                    if xf.is_empty() { xf.push(Mat4::from_scale(0.0)); }
+                    self.sat_gms[idx].geometry.set_instances(&Instances { transformations: xf, ..Default::default() });
+                }
+            });
+            // Populate sat_ecef_km from Keplerian positions so get_sky_data() works in fallback mode.
+            // Keplerian positions are in normalized scene units (Earth radius = 1); multiply by 6371 for km.
+            // NOTE: these positions are in the scene/ECEF-like frame, not true ECI. Elevation/azimuth
+            // values will be approximately correct for visualization purposes.
+            STATE.with(|st| {
+                let kepler_ecef = &mut st.borrow_mut().sat_ecef_km;
+                kepler_ecef.clear();
+                for (const_idx, s) in self.states.iter().enumerate() {
+                    for p in 0..s.planes {
+                        let raan = s.roff + p as f32 * s.rsp;
+                        for i in 0..s.sats_per_plane {
+                            let ma = i as f32 * 2.0 * PI / s.sats_per_plane as f32 + s.mm * t;
+                            let pos = kpos(s.r, s.inc, raan, ma);
+                            let group = tle_group(const_idx as u8, OrbitClass::Meo) as u16;
+                            kepler_ecef.push(const_idx as u8, group, [pos.x as f64 * 6371.0, pos.y as f64 * 6371.0, pos.z as f64 * 6371.0]);
+                        }
+                    }
+                }
+            });
+
+            if instances_dirty {
+                for sg in &mut self.tle_sat_gms {
+                    sg.geometry.set_instances(&Instances {
+                        transformations: vec![Mat4::from_scale(0.0)],
+                        ..Default::default()
+                    });
+                }
+                for sg in &mut self.tle_sprite_gms {
+                    sg.geometry.set_centers(&[]);
+                }
+            }
+        }
+
+        // ── 5b. Walker constellations — analytic, refreshed with the tick ────
+        STATE.with(|s| {
+            let mut st = s.borrow_mut();
+            let rebuilt = st.walkers_dirty;
+            if rebuilt {
+                self.walker_gms = st
+                    .walkers
+                    .iter()
+                    .map(|w| {
+                        let [r, g, b] = w.def.color;
+                        let material = ColorMaterial { color: Srgba::new(r, g, b, 255), ..Default::default() };
+                        let mut sprites = Sprites::new(&self.context, &[], None);
+                        sprites.set_transformation(Mat4::from_scale(SPRITE_SCALE));
+                        (
+                            hidden_instanced(&self.context, &self.sat_dot, material.color),
+                            Gm::new(sprites, material),
+                        )
+                    })
+                    .collect();
+                st.walkers_dirty = false;
+            }
+            let refresh = tick || instances_dirty || rebuilt || (!has_tles && running);
+            let sight = visible_only.then(|| Sight::new(&st, Site::Primary(&st.observer)));
+            for (w, (gm, sg)) in st.walkers.iter().zip(self.walker_gms.iter_mut()) {
+                let ci = w.def.constellation;
+                let [r, g, b] = w.def.color;
+                let color = if highlighted != -1 && highlighted != ci as i32 {
+                    Srgba::new(r / 4, g / 4, b / 4, 255)
+                } else {
+                    Srgba::new(r, g, b, 255)
+                };
+                gm.material.color = color;
+                sg.material.color = color;
+                if !refresh {
+                    continue;
+                }
+                let centers: Vec<Vec3> = if !w.enabled || !class_shown(&st, ci, w.orbit_class()) {
+                    Vec::new()
+                } else {
+                    w.positions_ecef(sim_epoch)
+                        .iter()
+                        .filter(|pos_km| sight.as_ref().is_none_or(|s| s.sees(pos_km)))
+                        .map(|pos_km| {
+                            let p = coords::km_to_scene(*pos_km);
+                            vec3(p[0], p[1], p[2])
+                        })
+                        .collect()
+                };
+                if use_sprites {
+                    sg.geometry.set_centers(&centers);
+                } else {
+                    let mut xf: Vec<Mat4> = centers.iter().map(|p| Mat4::from_translation(*p) * sat_scale).collect();
+                    if xf.is_empty() { xf.push(Mat4::from_scale(0.0)); }
+                    gm.geometry.set_instances(&Instances { transformations: xf, ..Default::default() });
+                }
+            }
+        });
+
+        // ── 5c. Events — rise/set, DOP, counts, midnight; once per tick ────
+        if tick {
+            STATE.with(|s| crate::detect_events(&mut s.borrow_mut()));
+        }
+
+        // ── 6. Ground markers (observer and station towers) ───────────────
+        let obs_scene = STATE.with(|s| s.borrow().observer.scene_pos());
+        let obs_n_cur = vec3(obs_scene[0], obs_scene[1], obs_scene[2]).normalize();
+        if (obs_n_cur - self.prev_obs_n).magnitude() > 1e-5 {
+            self.ground_marker = Gm::new(
+                Mesh::new(&self.context, &build_observer_tower(obs_n_cur)),
+                ColorMaterial { color: Srgba::new(255, 240, 60, 255), ..Default::default() },
+            );
+            self.prev_obs_n = obs_n_cur;
+        }
+        // Named stations — rebuilt when the set changes
+        let stations = STATE.with(|s| {
+            let mut st = s.borrow_mut();
+            std::mem::take(&mut st.stations_dirty).then(|| st.stations.clone())
+        });
+        if let Some(stations) = stations {
+            self.station_gms = stations
+                .iter()
+                .map(|stn| {
+                    let p = stn.observer().scene_pos();
+                    let [r, g, b] = stn.color;
+                    Gm::new(
+                        Mesh::new(&self.context, &build_observer_tower(vec3(p[0], p[1], p[2]).normalize())),
+                        ColorMaterial { color: Srgba::new(r, g, b, 255), ..Default::default() },
+                    )
+                })
+                .collect();
+        }
+
+        // ── 6b. ECI axes — rotate with GMST ──────────────────────────────────────
+        let gmst = coords::gmst_rad(sim_epoch) as f32;
+        // ECI X direction in ECEF coords: (cos(GMST), sin(GMST), 0)
+        // ECI Y direction in ECEF coords: (-sin(GMST), cos(GMST), 0)
+        let eci_x_dir = vec3(gmst.cos(), gmst.sin(), 0.0f32);
+        let eci_y_dir = vec3(-gmst.sin(), gmst.cos(), 0.0f32);
+        let [eci_gm_x, eci_gm_y, _] = &mut self.eci_gms;
+        if show_eci_axes {
+            eci_gm_x.geometry.set_instances(&Instances { transformations: axis_xforms(eci_x_dir), ..Default::default() });
+            eci_gm_y.geometry.set_instances(&Instances { transformations: axis_xforms(eci_y_dir), ..Default::default() });
+        } else {
+            let hidden = vec![Mat4::from_scale(0.0)];
+            eci_gm_x.geometry.set_instances(&Instances { transformations: hidden.clone(), ..Default::default() });
+            eci_gm_y.geometry.set_instances(&Instances { transformations: hidden, ..Default::default() });
+        }
+
+        // ── 6c. Elevation cone — solid filled surface ─────────────────────────────
+        if show_elev_cone && (cone_dirty || self.elev_cone_gm.is_none()) {
+            let cpu = STATE.with(|s| {
+                let st = s.borrow();
+                build_elev_cone(obs_n_cur, |az| {
+                    horizon::effective_mask_deg(st.horizon.as_ref(), st.elev_mask_deg, az)
+                })
+            });
+            self.elev_cone_gm = Some(Gm::new(
+                Mesh::new(&self.context, &cpu),
+                ColorMaterial {
+                    color: Srgba::new(200, 200, 200, 18),
+                    is_transparent: true,
+                    render_states: RenderStates {
+                        write_mask: WriteMask::COLOR, // no depth write — satellites behind cone stay visible
+                        blend: Blend::TRANSPARENCY,   // actual alpha blending (web-compatible)
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ));
+            STATE.with(|s| s.borrow_mut().cone_needs_rebuild = false);
+        } else if !show_elev_cone {
+            self.elev_cone_gm = None;
+        }
+
+        // ── 6d. Country borders — lazy rebuild when inject_borders() called ───────
+        if borders_dirty {
+            let json_opt = STATE.with(|s| s.borrow().borders_json.clone());
+            if let Some(ref json) = json_opt {
+                self.borders_gm = borders::build_border_lines(&self.context, json);
+            }
+            STATE.with(|s| s.borrow_mut().borders_dirty = false);
+        }
+
+        // ── 6e. IGSO ground tracks — rebuilt on TLE reload or hourly sim time ───
+        if show_ground_tracks && has_tles {
+            let n_recs = STATE.with(|s| s.borrow().tle_store.records.len());
+            let stale = self
+                .track_built
+                .is_none_or(|(n, t)| n != n_recs || (sim_epoch - t).abs() > TRACK_REFRESH_S);
+            if stale {
+                let tracks = STATE.with(|s| igso_ground_tracks(&s.borrow().tle_store, sim_epoch));
+                let track_scale = Mat4::from_scale(TRACK_SCALE);
+                for (tg, pts) in self.track_gms.iter_mut().zip(&tracks) {
+                    let mut xf: Vec<Mat4> = pts
+                        .iter()
+                        .map(|u| {
+                            let p = vec3(u[0] as f32, u[1] as f32, u[2] as f32) * 1.004;
+                            Mat4::from_translation(p) * track_scale
+                        })
+                        .collect();
+                    if xf.is_empty() { xf.push(Mat4::from_scale(0.0)); }
+                    tg.geometry.set_instances(&Instances { transformations: xf, ..Default::default() });
+                }
+                self.track_built = Some((n_recs, sim_epoch));
+            }
+        }
+    }
+
+    /// Largest offscreen capture edge this GL context supports, in pixels.
+    pub fn max_capture_size(&self) -> u32 {
+        // SAFETY: a plain integer query on the current context.
+        let max = unsafe { self.context.get_parameter_i32(three_d::context::MAX_TEXTURE_SIZE) };
+        max.max(1) as u32
+    }
+
+    /// Render into an offscreen `width` × `height` texture — independent of
+    /// the window or canvas size — and return top-down RGBA8 rows. Call
+    /// `update` first; the next `update` restores the on-screen viewport.
+    pub fn render_rgba(&mut self, width: u32, height: u32) -> Vec<u8> {
+        self.camera.set_viewport(Viewport::new_at_origo(width, height));
+        let mut color = Texture2D::new_empty::<[u8; 4]>(
+            &self.context,
+            width,
+            height,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let mut depth = DepthTexture2D::new::<f32>(&self.context, width, height, Wrapping::ClampToEdge, Wrapping::ClampToEdge);
+        let target = RenderTarget::new(color.as_color_target(None), depth.as_depth_target());
+        self.render(&target);
+        target.read_color::<[u8; 4]>().into_iter().flatten().collect()
+    }
+
+    /// Clear `target` and draw every enabled object from the current camera.
+    pub fn render(&self, target: &RenderTarget) {
+        let (has_tles, cv, ocv, show_inc_rings, show_ground_tracks, show_ecef_axes, show_borders) = STATE.with(|s| {
+            let st = s.borrow();
+            (
+                !st.tle_store.is_empty(),
+                st.constellation_visible,
+                st.orbit_class_visible,
+                st.show_inc_rings,
+                st.show_ground_tracks,
+                st.show_ecef_axes,
+                st.show_borders,
+            )
+        });
+
+        let mut objs: Vec<&dyn Object> = vec![&self.earth, &self.eq_ring, &self.graticule, &self.ground_marker];
+        for g in &self.station_gms { objs.push(g); }
+        if show_inc_rings {
+            for g in &self.orbit_gms { objs.push(g); }
+        }
+        for g in &self.sat_gms { objs.push(g); }
+        if self.use_sprites {
+            for (g, c) in self.tle_sprite_gms.iter().zip(&self.sprite_buckets) {
+                if !c.is_empty() { objs.push(g); }
+            }
+            for (_, g) in &self.walker_gms { objs.push(g); }
+        } else {
+            for g in &self.tle_sat_gms { objs.push(g); }
+            for (g, _) in &self.walker_gms { objs.push(g); }
+        }
+        if show_ground_tracks && has_tles {
+            for (ci, g) in self.track_gms.iter().enumerate() {
+                if cv[ci] && ocv[ci][OrbitClass::Igso.index()] { objs.push(g); }
+            }
+        }
+        if show_ecef_axes {
+            for g in &self.ecef_gms { objs.push(g); }
+        }
+        for g in &self.eci_gms { objs.push(g); }
+        // `update` drops the cone while it is hidden
+        if let Some(ref cone) = self.elev_cone_gm { objs.push(cone); }
+        if let Some(ref brd) = self.borders_gm {
+            if show_borders { objs.push(brd); }
+        }
+
+        target
+            .clear(ClearState {
+                red:   Some(0.01),
+                green: Some(0.01),
+                blue:  Some(0.01),
+                alpha: Some(1.0),
+                depth: Some(1.0),
+            })
+            .render(&self.camera, objs, &[]);
+    }
+}
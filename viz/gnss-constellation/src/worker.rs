@@ -0,0 +1,117 @@
+// worker.rs — SGP4 propagation entry points for a dedicated Web Worker
+//
+// The worker loads the same wasm module as the page but never touches
+// `STATE`, the canvas or three-d: `start()` returns early when there is no
+// window, and the worker drives a `Propagator` instead. It propagates the
+// same text the page's store was loaded from (`get_tle_source()`), so its
+// output is in `TleStore::records` order, and writes flat `[x0, y0, z0, x1,
+// …]` ECEF km arrays that the page hands to `push_position_sample` with the
+// store generation (see assets/js/gnss-prop-worker.js).
+
+use wasm_bindgen::prelude::*;
+
+use gnss_core::coords;
+use gnss_core::tles::TleStore;
+
+/// Owns a `TleStore` and propagates all of it to a requested epoch.
+#[wasm_bindgen]
+pub struct Propagator {
+    store: TleStore,
+}
+
+impl Default for Propagator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Propagator {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Propagator {
+        Propagator { store: TleStore::new() }
+    }
+
+    /// Replace the records with the given Celestrak OMM JSON. Returns the
+    /// record count (0 on parse failure), matching `get_tle_count()` on the
+    /// page when both were given the same JSON.
+    pub fn load_omm(&mut self, json: &str) -> u32 {
+        self.store = TleStore::new();
+        self.store.load_from_json(json).unwrap_or(0) as u32
+    }
+
+    /// Replace the records with OMM JSON or two/three-line element sets,
+    /// whichever `text` is, the way `inject_tles` / `inject_tle_text` read
+    /// it. Returns the record count (0 on parse failure).
+    pub fn load_text(&mut self, text: &str) -> u32 {
+        if text.trim_start().starts_with('[') {
+            return self.load_omm(text);
+        }
+        self.store = TleStore::new();
+        self.store.load_from_tle(text).unwrap_or(0) as u32
+    }
+
+    /// Number of loaded records; callers size the output array as 3× this.
+    pub fn len(&self) -> u32 {
+        self.store.records.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.records.is_empty()
+    }
+
+    /// Propagate every record to `unix_s` and write ECEF km into `pos_out`
+    /// and ECEF km/s into `vel_out` as consecutive xyz triples. Writes as
+    /// many satellites as fit in `pos_out` (velocities likewise; pass an
+    /// empty array to skip them) and returns how many positions were
+    /// written. Either may be a view onto a SharedArrayBuffer.
+    pub fn propagate_into(&self, unix_s: f64, pos_out: &mut [f64], vel_out: &mut [f64]) -> u32 {
+        let gmst = coords::gmst_rad(unix_s);
+        let mut vel = vel_out.chunks_exact_mut(3);
+        let mut n = 0;
+        for (rec, xyz) in self.store.records.iter().zip(pos_out.chunks_exact_mut(3)) {
+            let (p, v) = TleStore::propagate_pv(rec, unix_s);
+            xyz.copy_from_slice(&coords::teme_to_ecef(p, gmst));
+            if let Some(dxyz) = vel.next() {
+                dxyz.copy_from_slice(&coords::teme_to_ecef_vel(p, v, gmst));
+            }
+            n += 1;
+        }
+        n
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISS: &str = "ISS (ZARYA)
+1 25544U 98067A   24015.50000000  .00016717  00000-0  10270-3 0  9002
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50000000 12345
+";
+
+    /// A worker loaded from `get_tle_source()` matches the page's store;
+    /// samples tagged with an older store generation are rejected.
+    #[test]
+    fn test_samples_follow_store_generation() {
+        assert_eq!(crate::inject_tle_text(ISS), 1);
+        let gen = crate::get_tle_generation();
+        let mut prop = Propagator::new();
+        assert_eq!(prop.load_text(&crate::get_tle_source()), 1);
+        assert_eq!(prop.load_text("[]"), 0);
+        assert_eq!(prop.load_text(&crate::get_tle_source()), 1);
+
+        let (mut pos, mut vel) = ([0.0; 3], [0.0; 3]);
+        assert_eq!(prop.propagate_into(1_705_320_000.0, &mut pos, &mut vel), 1);
+        assert!(crate::push_position_sample(gen, 1_705_320_000.0, &pos, &vel));
+
+        crate::inject_tle_text(ISS);
+        assert_ne!(crate::get_tle_generation(), gen);
+        assert!(!crate::push_position_sample(gen, 1_705_320_060.0, &pos, &vel));
+        assert!(crate::push_position_sample(crate::get_tle_generation(), 1_705_320_060.0, &pos, &vel));
+    }
+}
@@ -0,0 +1,450 @@
+// atmosphere.rs — Ionospheric and tropospheric signal delay models
+//
+// Responsibilities:
+//   - Klobuchar (GPS broadcast) ionospheric delay with injectable α/β coefficients
+//   - NeQuick-lite: simplified Galileo broadcast model driven by ai0/ai1/ai2
+//   - Saastamoinen zenith tropospheric delay from a standard atmosphere
+//   - Niell hydrostatic / wet mapping functions to project zenith delay to slant
+//
+// Pure math — no wasm_bindgen exports, no three-d. All delays are returned as
+// slant range errors in metres on the L1/E1 carrier (1575.42 MHz). Geometry
+// inputs are the observer's geodetic lat/lon and the satellite az/el exactly
+// as produced by `coords::az_el`.
+
+use std::f64::consts::PI;
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Speed of light in vacuum, m/s.
+const C_M_S: f64 = 299_792_458.0;
+
+/// L1 / E1 carrier frequency, Hz.
+const F_L1_HZ: f64 = 1_575.42e6;
+
+/// Mean Earth radius in km — used by the single-layer ionosphere mapping.
+const EARTH_R_KM: f64 = 6371.0;
+
+/// Height of the thin-shell ionosphere used by NeQuick-lite, km.
+const IONO_SHELL_KM: f64 = 350.0;
+
+/// Geomagnetic north pole (IGRF dipole) used for MODIP, degrees.
+const GEOMAG_POLE_LAT_DEG: f64 = 80.65;
+const GEOMAG_POLE_LON_DEG: f64 = -72.68;
+
+// ---------------------------------------------------------------------------
+// Broadcast coefficient sets (injected from JS)
+// ---------------------------------------------------------------------------
+
+/// GPS broadcast Klobuchar coefficients from the LNAV ionospheric page.
+///
+/// `alpha` are the amplitude polynomial terms (s, s/sc, s/sc², s/sc³) and
+/// `beta` the period terms (s, s/sc, s/sc², s/sc³), where sc = semicircles.
+#[derive(Clone, Copy, Debug)]
+pub struct KlobucharCoeffs {
+    pub alpha: [f64; 4],
+    pub beta: [f64; 4],
+}
+
+impl Default for KlobucharCoeffs {
+    /// Representative moderate-solar-activity values (IS-GPS-200 example set).
+    fn default() -> Self {
+        Self {
+            alpha: [1.1176e-8, 7.4506e-9, -5.9605e-8, -5.9605e-8],
+            beta: [90112.0, 0.0, -196_608.0, -65536.0],
+        }
+    }
+}
+
+/// Galileo broadcast ionospheric coefficients (effective ionisation level).
+///
+/// Az = ai0 + ai1·MODIP + ai2·MODIP², in solar flux units (sfu).
+#[derive(Clone, Copy, Debug)]
+pub struct NequickCoeffs {
+    pub ai0: f64,
+    pub ai1: f64,
+    pub ai2: f64,
+}
+
+impl Default for NequickCoeffs {
+    /// Galileo OS SIS ICD validation example set.
+    fn default() -> Self {
+        Self {
+            ai0: 121.129893,
+            ai1: 0.351254133,
+            ai2: 0.0134635348,
+        }
+    }
+}
+
+/// Broadcast model parameters held in `GnssState`, one set per model.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AtmoConfig {
+    pub klobuchar: KlobucharCoeffs,
+    pub nequick: NequickCoeffs,
+}
+
+/// Per-satellite delay breakdown exposed through `SkySat`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AtmoDelays {
+    /// Klobuchar L1 slant ionospheric delay, metres.
+    pub iono_klobuchar_m: f64,
+    /// NeQuick-lite E1 slant ionospheric delay, metres.
+    pub iono_nequick_m: f64,
+    /// Saastamoinen + Niell slant tropospheric delay, metres.
+    pub tropo_m: f64,
+}
+
+/// Evaluate all three models for one observer→satellite line of sight.
+///
+/// `height_m` is the observer's height above the ellipsoid; `unix_s` drives
+/// local time (ionosphere) and day-of-year (Niell seasonal term).
+pub fn slant_delays(
+    cfg: &AtmoConfig,
+    lat_deg: f64,
+    lon_deg: f64,
+    height_m: f64,
+    az_deg: f64,
+    el_deg: f64,
+    unix_s: f64,
+) -> AtmoDelays {
+    AtmoDelays {
+        iono_klobuchar_m: klobuchar_delay_m(&cfg.klobuchar, lat_deg, lon_deg, az_deg, el_deg, unix_s),
+        iono_nequick_m: nequick_lite_delay_m(&cfg.nequick, lat_deg, lon_deg, el_deg, unix_s),
+        tropo_m: tropo_delay_m(lat_deg, height_m, el_deg, day_of_year(unix_s)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Klobuchar
+// ---------------------------------------------------------------------------
+
+/// Klobuchar single-frequency ionospheric delay on L1, metres.
+///
+/// Implements the IS-GPS-200 §20.3.3.5.2.5 algorithm verbatim, working in
+/// semicircles. GPS time-of-day is approximated by UTC seconds-of-day; the
+/// 18 s leap-second offset is far below the model's 50 % RMS accuracy.
+pub fn klobuchar_delay_m(
+    coeffs: &KlobucharCoeffs,
+    lat_deg: f64,
+    lon_deg: f64,
+    az_deg: f64,
+    el_deg: f64,
+    unix_s: f64,
+) -> f64 {
+    let el_sc = el_deg.max(0.0) / 180.0;
+    let az = az_deg.to_radians();
+    let phi_u = lat_deg / 180.0;
+    let lam_u = lon_deg / 180.0;
+
+    // Earth-centred angle between observer and ionospheric pierce point
+    let psi = 0.0137 / (el_sc + 0.11) - 0.022;
+
+    // Pierce point geodetic latitude / longitude (semicircles)
+    let phi_i = (phi_u + psi * az.cos()).clamp(-0.416, 0.416);
+    let lam_i = lam_u + psi * az.sin() / (phi_i * PI).cos();
+
+    // Geomagnetic latitude of the pierce point
+    let phi_m = phi_i + 0.064 * ((lam_i - 1.617) * PI).cos();
+
+    // Local time at the pierce point
+    let t = (4.32e4 * lam_i + unix_s.rem_euclid(86400.0)).rem_euclid(86400.0);
+
+    // Obliquity factor
+    let f = 1.0 + 16.0 * (0.53 - el_sc).powi(3);
+
+    let poly = |c: &[f64; 4]| c[0] + phi_m * (c[1] + phi_m * (c[2] + phi_m * c[3]));
+    let amp = poly(&coeffs.alpha).max(0.0);
+    let per = poly(&coeffs.beta).max(72000.0);
+
+    let x = 2.0 * PI * (t - 50400.0) / per;
+    let delay_s = if x.abs() < 1.57 {
+        f * (5.0e-9 + amp * (1.0 - x * x / 2.0 + x.powi(4) / 24.0))
+    } else {
+        f * 5.0e-9
+    };
+
+    delay_s * C_M_S
+}
+
+// ---------------------------------------------------------------------------
+// NeQuick-lite
+// ---------------------------------------------------------------------------
+
+/// Simplified Galileo NeQuick ionospheric delay on E1, metres.
+///
+/// The full NeQuick G integrates a 3-D electron density profile along the
+/// ray. This "lite" variant keeps the broadcast interface — effective
+/// ionisation level Az from MODIP — but replaces the profile integration
+/// with a vertical TEC climatology and a 350 km thin-shell slant mapping.
+/// Good for relative Klobuchar-vs-Galileo comparisons in teaching material,
+/// not for positioning.
+pub fn nequick_lite_delay_m(
+    coeffs: &NequickCoeffs,
+    lat_deg: f64,
+    lon_deg: f64,
+    el_deg: f64,
+    unix_s: f64,
+) -> f64 {
+    let modip = modip_deg(lat_deg, lon_deg);
+    let az = effective_ionisation(coeffs, modip);
+
+    // Local solar time at the observer, hours
+    let lt = (unix_s.rem_euclid(86400.0) / 3600.0 + lon_deg / 15.0).rem_euclid(24.0);
+    let diurnal = ((lt - 14.0) * PI / 12.0).cos().max(0.0);
+    const NIGHT_FLOOR: f64 = 0.15;
+
+    // Equatorial ionisation is strongest; taper toward the poles.
+    let lat_factor = 0.4 + 0.6 * modip.to_radians().cos();
+
+    let vtec_tecu = 0.2 * az * lat_factor * (NIGHT_FLOOR + (1.0 - NIGHT_FLOOR) * diurnal);
+
+    tecu_to_m(vtec_tecu) * iono_mapping(el_deg, IONO_SHELL_KM)
+}
+
+/// Effective ionisation level Az (sfu) per the Galileo SIS ICD §5.1.6,
+/// including the 63.7 sfu fallback for non-positive values and the 400 cap.
+pub fn effective_ionisation(coeffs: &NequickCoeffs, modip_deg: f64) -> f64 {
+    let az = coeffs.ai0 + coeffs.ai1 * modip_deg + coeffs.ai2 * modip_deg * modip_deg;
+    if az <= 0.0 {
+        63.7
+    } else {
+        az.min(400.0)
+    }
+}
+
+/// Modified dip latitude (MODIP) in degrees from a centred-dipole field.
+///
+/// tan(I) = 2·tan(φm) gives the dip I; MODIP = atan(I / √cos φ).
+pub fn modip_deg(lat_deg: f64, lon_deg: f64) -> f64 {
+    let lat = lat_deg.to_radians();
+    let lon = lon_deg.to_radians();
+    let (plat, plon) = (
+        GEOMAG_POLE_LAT_DEG.to_radians(),
+        GEOMAG_POLE_LON_DEG.to_radians(),
+    );
+
+    let sin_phi_m = lat.sin() * plat.sin() + lat.cos() * plat.cos() * (lon - plon).cos();
+    let phi_m = sin_phi_m.clamp(-1.0, 1.0).asin();
+    let dip = (2.0 * phi_m.tan()).atan();
+
+    let cos_lat = lat.cos().max(1e-6);
+    (dip / cos_lat.sqrt()).atan().to_degrees()
+}
+
+/// L1 group delay per TEC unit (1 TECU = 1e16 e⁻/m²), metres.
+#[inline]
+fn tecu_to_m(tecu: f64) -> f64 {
+    40.3e16 * tecu / (F_L1_HZ * F_L1_HZ)
+}
+
+/// Thin-shell ionospheric obliquity factor at elevation `el_deg`.
+fn iono_mapping(el_deg: f64, shell_km: f64) -> f64 {
+    let s = EARTH_R_KM / (EARTH_R_KM + shell_km) * el_deg.max(0.0).to_radians().cos();
+    1.0 / (1.0 - s * s).sqrt()
+}
+
+// ---------------------------------------------------------------------------
+// Troposphere: Saastamoinen + Niell
+// ---------------------------------------------------------------------------
+
+/// Niell latitude grid (degrees) shared by every coefficient table below.
+const NIELL_LATS: [f64; 5] = [15.0, 30.0, 45.0, 60.0, 75.0];
+
+/// Niell hydrostatic (a, b, c) averages per latitude band.
+const NIELL_HYD_AVG: [[f64; 3]; 5] = [
+    [1.276_993_4e-3, 2.915_369_5e-3, 62.610_505e-3],
+    [1.268_323_0e-3, 2.915_229_9e-3, 62.837_393e-3],
+    [1.246_539_7e-3, 2.928_844_5e-3, 63.721_774e-3],
+    [1.219_604_9e-3, 2.902_256_5e-3, 63.824_265e-3],
+    [1.204_599_6e-3, 2.902_491_2e-3, 64.258_455e-3],
+];
+
+/// Niell hydrostatic (a, b, c) seasonal amplitudes per latitude band.
+const NIELL_HYD_AMP: [[f64; 3]; 5] = [
+    [0.0, 0.0, 0.0],
+    [1.270_962_6e-5, 2.141_497_9e-5, 9.012_840_0e-5],
+    [2.652_366_2e-5, 3.016_077_9e-5, 4.349_703_7e-5],
+    [3.400_045_2e-5, 7.256_272_2e-5, 84.795_348e-5],
+    [4.120_219_1e-5, 11.723_375e-5, 170.372_06e-5],
+];
+
+/// Niell hydrostatic height-correction coefficients (a, b, c).
+const NIELL_HYD_HT: [f64; 3] = [2.53e-5, 5.49e-3, 1.14e-3];
+
+/// Niell wet (a, b, c) per latitude band (no seasonal term).
+const NIELL_WET: [[f64; 3]; 5] = [
+    [5.802_189_7e-4, 1.427_526_8e-3, 4.347_296_1e-2],
+    [5.679_484_7e-4, 1.513_862_5e-3, 4.672_951_0e-2],
+    [5.811_801_9e-4, 1.457_275_2e-3, 4.390_893_1e-2],
+    [5.972_754_2e-4, 1.500_742_8e-3, 4.462_698_2e-2],
+    [6.164_169_3e-4, 1.759_908_2e-3, 5.473_603_8e-2],
+];
+
+/// Slant tropospheric delay in metres: Saastamoinen zenith delays mapped
+/// with the Niell hydrostatic and wet functions.
+pub fn tropo_delay_m(lat_deg: f64, height_m: f64, el_deg: f64, doy: u32) -> f64 {
+    let (zhd, zwd) = saastamoinen_zenith_m(lat_deg, height_m);
+    let (mh, mw) = niell_mapping(lat_deg, height_m, el_deg, doy);
+    zhd * mh + zwd * mw
+}
+
+/// Saastamoinen zenith hydrostatic and wet delays (metres) using a
+/// standard atmosphere at `height_m` with 50 % relative humidity.
+pub fn saastamoinen_zenith_m(lat_deg: f64, height_m: f64) -> (f64, f64) {
+    let h = height_m.clamp(-500.0, 10_000.0);
+    let pressure_hpa = 1013.25 * (1.0 - 2.2557e-5 * h).powf(5.2568);
+    let temp_k = 15.0 - 6.5e-3 * h + 273.15;
+    let rel_humidity = 0.5;
+    let e_hpa = 6.108 * rel_humidity * ((17.15 * temp_k - 4684.0) / (temp_k - 38.45)).exp();
+
+    let lat = lat_deg.to_radians();
+    let zhd = 0.002_276_8 * pressure_hpa / (1.0 - 0.002_66 * (2.0 * lat).cos() - 0.000_28 * h / 1000.0);
+    let zwd = 0.002_277 * (1255.0 / temp_k + 0.05) * e_hpa;
+    (zhd, zwd)
+}
+
+/// Niell (1996) hydrostatic and wet mapping functions `(m_h, m_w)`.
+///
+/// Coefficients are linearly interpolated in |latitude|; the seasonal term is
+/// phased to day 28 and shifted by half a year in the southern hemisphere.
+pub fn niell_mapping(lat_deg: f64, height_m: f64, el_deg: f64, doy: u32) -> (f64, f64) {
+    let el = el_deg.max(1.0).to_radians();
+    let abs_lat = lat_deg.abs();
+
+    let mut t = doy as f64 - 28.0;
+    if lat_deg < 0.0 {
+        t += 365.25 / 2.0;
+    }
+    let season = (2.0 * PI * t / 365.25).cos();
+
+    let avg = interp_lat(&NIELL_HYD_AVG, abs_lat);
+    let amp = interp_lat(&NIELL_HYD_AMP, abs_lat);
+    let hyd = [
+        avg[0] - amp[0] * season,
+        avg[1] - amp[1] * season,
+        avg[2] - amp[2] * season,
+    ];
+
+    let sin_el = el.sin();
+    let height_corr =
+        (1.0 / sin_el - marini(el, &NIELL_HYD_HT)) * height_m / 1000.0;
+
+    let mh = marini(el, &hyd) + height_corr;
+    let mw = marini(el, &interp_lat(&NIELL_WET, abs_lat));
+    (mh, mw)
+}
+
+/// Marini continued fraction normalised to unity at zenith.
+fn marini(el_rad: f64, abc: &[f64; 3]) -> f64 {
+    let [a, b, c] = *abc;
+    let s = el_rad.sin();
+    (1.0 + a / (1.0 + b / (1.0 + c))) / (s + a / (s + b / (s + c)))
+}
+
+/// Linear interpolation of a Niell table row-set at |latitude| `abs_lat`.
+fn interp_lat(table: &[[f64; 3]; 5], abs_lat: f64) -> [f64; 3] {
+    if abs_lat <= NIELL_LATS[0] {
+        return table[0];
+    }
+    if abs_lat >= NIELL_LATS[4] {
+        return table[4];
+    }
+    let i = NIELL_LATS.iter().rposition(|&l| l <= abs_lat).unwrap_or(0);
+    let w = (abs_lat - NIELL_LATS[i]) / (NIELL_LATS[i + 1] - NIELL_LATS[i]);
+    [
+        table[i][0] + w * (table[i + 1][0] - table[i][0]),
+        table[i][1] + w * (table[i + 1][1] - table[i][1]),
+        table[i][2] + w * (table[i + 1][2] - table[i][2]),
+    ]
+}
+
+/// 1-indexed UTC day-of-year for a Unix timestamp (1 on parse failure).
+fn day_of_year(unix_s: f64) -> u32 {
+    use chrono::Datelike;
+    chrono::DateTime::from_timestamp(unix_s.floor() as i64, 0)
+        .map(|dt| dt.ordinal())
+        .unwrap_or(1)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Noon-ish local time at the equator must sit well above the 5 ns
+    /// night-time floor; midnight must collapse to it (×F at zenith ≈ 1.5 m).
+    #[test]
+    fn test_klobuchar_day_night() {
+        let k = KlobucharCoeffs::default();
+        let day = klobuchar_delay_m(&k, 0.0, 0.0, 0.0, 90.0, 14.0 * 3600.0);
+        let night = klobuchar_delay_m(&k, 0.0, 0.0, 0.0, 90.0, 2.0 * 3600.0);
+        assert!(day > night, "day={day} night={night}");
+        let floor_m = 5.0e-9 * C_M_S;
+        assert!((night - floor_m).abs() < 0.05 * floor_m, "night={night}");
+    }
+
+    /// Low-elevation delays must exceed zenith delays (obliquity factor).
+    #[test]
+    fn test_klobuchar_obliquity() {
+        let k = KlobucharCoeffs::default();
+        let zen = klobuchar_delay_m(&k, 40.0, -90.0, 180.0, 90.0, 50400.0);
+        let low = klobuchar_delay_m(&k, 40.0, -90.0, 180.0, 10.0, 50400.0);
+        assert!(low > 2.0 * zen, "zen={zen} low={low}");
+    }
+
+    #[test]
+    fn test_effective_ionisation_bounds() {
+        let neg = NequickCoeffs { ai0: -5.0, ai1: 0.0, ai2: 0.0 };
+        assert_eq!(effective_ionisation(&neg, 10.0), 63.7);
+        let huge = NequickCoeffs { ai0: 1e4, ai1: 0.0, ai2: 0.0 };
+        assert_eq!(effective_ionisation(&huge, 10.0), 400.0);
+    }
+
+    /// MODIP is zero on the dipole equator (9.35°S on the pole meridian) and
+    /// approaches ±90° at high geomagnetic latitude.
+    #[test]
+    fn test_modip_range() {
+        assert!(modip_deg(-9.35, -72.68).abs() < 1e-6);
+        assert!(modip_deg(80.0, -72.68) > 70.0);
+        assert!(modip_deg(-70.0, 110.0) < -60.0);
+    }
+
+    #[test]
+    fn test_nequick_lite_positive_and_mapped() {
+        let n = NequickCoeffs::default();
+        let zen = nequick_lite_delay_m(&n, 0.0, 0.0, 90.0, 14.0 * 3600.0);
+        let low = nequick_lite_delay_m(&n, 0.0, 0.0, 10.0, 14.0 * 3600.0);
+        assert!(zen > 0.5 && zen < 20.0, "zen={zen}");
+        assert!(low > 2.0 * zen, "low={low}");
+    }
+
+    /// Sea-level ZHD ≈ 2.3 m and ZWD of order 0.1–0.2 m.
+    #[test]
+    fn test_saastamoinen_sea_level() {
+        let (zhd, zwd) = saastamoinen_zenith_m(45.0, 0.0);
+        assert!((zhd - 2.31).abs() < 0.05, "zhd={zhd}");
+        assert!(zwd > 0.05 && zwd < 0.3, "zwd={zwd}");
+    }
+
+    /// Niell mapping is ≈1 at zenith and ≈10 near 5° elevation.
+    #[test]
+    fn test_niell_mapping_values() {
+        let (mh, mw) = niell_mapping(45.0, 0.0, 90.0, 180);
+        assert!((mh - 1.0).abs() < 1e-3, "mh={mh}");
+        assert!((mw - 1.0).abs() < 1e-3, "mw={mw}");
+        let (mh5, _) = niell_mapping(45.0, 0.0, 5.0, 180);
+        assert!(mh5 > 9.5 && mh5 < 11.0, "mh5={mh5}");
+    }
+
+    #[test]
+    fn test_day_of_year() {
+        assert_eq!(day_of_year(0.0), 1);
+        // 2024-03-01T00:00:00Z → day 61 (leap year)
+        assert_eq!(day_of_year(1_709_251_200.0), 61);
+    }
+}
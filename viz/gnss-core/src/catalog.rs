@@ -0,0 +1,202 @@
+// catalog.rs — Satellite identity catalog (NORAD → PRN / SVN / block / channel)
+//
+// Responsibilities:
+//   - Hold per-satellite identity keyed by NORAD catalog number: PRN / slot,
+//     SVN, block or generation, GLONASS frequency channel, health
+//   - Parse the catalog from injected JSON
+//   - Convert between RINEX-style PRN labels ("G05", "R07", "J02") and the
+//     `(constellation, sv_id)` keys shared with nmea.rs / rinex.rs
+//
+// Pure data — no wasm_bindgen exports, no three-d. Celestrak object names
+// stay on `SatRecord`; the viewer looks identities up by `SatRecord::norad_id`.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{nmea, rinex};
+use crate::tles::{
+    CONSTELLATION_BEIDOU, CONSTELLATION_GALILEO, CONSTELLATION_GLONASS, CONSTELLATION_GPS,
+    CONSTELLATION_NAVIC, CONSTELLATION_QZSS, CONSTELLATION_SBAS,
+};
+
+/// GLONASS FDMA frequency channels span k = −7 … +6.
+const GLONASS_CHANNELS: std::ops::RangeInclusive<i8> = -7..=6;
+
+// ---------------------------------------------------------------------------
+// Identity records
+// ---------------------------------------------------------------------------
+
+/// Identity of one satellite. Every field except `norad_id` is optional.
+///
+/// JSON shape (one array element):
+/// `{ "norad_id": 32711, "prn": "G05", "svn": "G050", "block": "IIR-M",
+///    "freq_channel": null, "healthy": true }`
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SatIdentity {
+    #[serde(alias = "NORAD_CAT_ID", alias = "norad")]
+    pub norad_id: u64,
+    /// RINEX-style label: system letter + PRN (GLONASS: orbital slot).
+    pub prn: Option<String>,
+    /// Space vehicle number, as published by the operator.
+    #[serde(deserialize_with = "str_or_num")]
+    pub svn: Option<String>,
+    /// Block / generation: "IIR", "IIF", "III", "FOC", "BDS-3 MEO", …
+    pub block: Option<String>,
+    /// GLONASS FDMA frequency channel (−7 … +6).
+    #[serde(alias = "channel", alias = "glonass_channel")]
+    pub freq_channel: Option<i8>,
+    /// Broadcast health; `None` when unknown.
+    pub healthy: Option<bool>,
+}
+
+impl SatIdentity {
+    /// `(constellation, sv_id)` for the `prn` label, if it parses.
+    pub fn sat_key(&self) -> Option<(u8, u16)> {
+        parse_prn(self.prn.as_deref()?)
+    }
+}
+
+/// Accept `"svn": "G050"` as well as `"svn": 50`.
+fn str_or_num<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrOrNum {
+        S(String),
+        N(u64),
+    }
+    Ok(Option::<StrOrNum>::deserialize(d)?.map(|v| match v {
+        StrOrNum::S(s) => s,
+        StrOrNum::N(n) => n.to_string(),
+    }))
+}
+
+/// NORAD-keyed identity catalog.
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    entries: HashMap<u64, SatIdentity>,
+}
+
+impl Catalog {
+    /// Parse a JSON array of `SatIdentity` objects. Entries with an
+    /// unparseable or out-of-range `prn` or GLONASS channel lose just that
+    /// field; entries without a NORAD ID are dropped.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let list: Vec<SatIdentity> =
+            serde_json::from_str(json).map_err(|e| format!("catalog JSON parse error: {e}"))?;
+        let mut cat = Catalog::default();
+        for mut id in list {
+            if id.norad_id == 0 {
+                continue;
+            }
+            if id.prn.is_some() && id.sat_key().is_none() {
+                id.prn = None;
+            }
+            id.freq_channel = id.freq_channel.filter(|k| GLONASS_CHANNELS.contains(k));
+            cat.entries.insert(id.norad_id, id);
+        }
+        Ok(cat)
+    }
+
+    /// Add or replace entries from `other`.
+    pub fn merge(&mut self, other: Catalog) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn get(&self, norad_id: u64) -> Option<&SatIdentity> {
+        self.entries.get(&norad_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// PRN labels
+// ---------------------------------------------------------------------------
+
+/// `"G05"` → `(GPS, 5)`, `"R07"` → `(GLONASS, 71)`, `"J02"` → `(QZSS, 2)`.
+/// `None` for numbers outside the constellation's NMEA range (`"G999"`).
+pub fn parse_prn(label: &str) -> Option<(u8, u16)> {
+    let label = label.trim();
+    let sys = label.chars().next()?.to_ascii_uppercase();
+    let num: u16 = label.get(1..)?.trim().parse().ok()?;
+    let (constellation, sv_id) = rinex::sat_key(sys, num)?;
+    let (lo, hi) = nmea::sv_id_range(constellation);
+    (lo..=hi).contains(&sv_id).then_some((constellation, sv_id))
+}
+
+/// Inverse of `parse_prn`: RINEX-style label for an NMEA-numbered satellite.
+pub fn prn_label(constellation: u8, sv_id: u16) -> Option<String> {
+    let (sys, num) = match constellation {
+        CONSTELLATION_GPS => ('G', sv_id),
+        CONSTELLATION_GLONASS => ('R', sv_id.checked_sub(64)?),
+        CONSTELLATION_GALILEO => ('E', sv_id),
+        CONSTELLATION_BEIDOU => ('C', sv_id),
+        CONSTELLATION_QZSS => ('J', sv_id),
+        CONSTELLATION_NAVIC => ('I', sv_id),
+        CONSTELLATION_SBAS => ('S', sv_id),
+        _ => return None,
+    };
+    Some(format!("{sys}{num:02}"))
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"[
+        {"norad_id": 32711, "prn": "G05", "svn": 50, "block": "IIR-M", "healthy": true},
+        {"NORAD_CAT_ID": 32276, "prn": "R07", "svn": "R745", "block": "GLONASS-M", "channel": 5},
+        {"norad_id": 40128, "prn": "X99", "block": "FOC", "healthy": false},
+        {"norad_id": 41434, "prn": "R09", "freq_channel": 12},
+        {"prn": "G01"}
+    ]"#;
+
+    #[test]
+    fn test_parse_catalog() {
+        let c = Catalog::from_json(SAMPLE).unwrap();
+        assert_eq!(c.len(), 4); // entry without NORAD ID dropped
+        let g05 = c.get(32711).unwrap();
+        assert_eq!(g05.svn.as_deref(), Some("50"));
+        assert_eq!(g05.sat_key(), Some((CONSTELLATION_GPS, 5)));
+        let r07 = c.get(32276).unwrap();
+        assert_eq!((r07.sat_key(), r07.freq_channel), (Some((CONSTELLATION_GLONASS, 71)), Some(5)));
+        // Bad PRN and out-of-range channel are cleared, the rest kept
+        let bad = c.get(40128).unwrap();
+        assert_eq!((bad.prn.as_deref(), bad.block.as_deref(), bad.healthy), (None, Some("FOC"), Some(false)));
+        assert_eq!(c.get(41434).unwrap().freq_channel, None);
+        assert!(Catalog::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_prn_label_round_trip() {
+        for label in ["G05", "R07", "E11", "C23", "J02", "I05", "S20"] {
+            let (c, id) = parse_prn(label).unwrap();
+            assert_eq!(prn_label(c, id).as_deref(), Some(label));
+        }
+        assert_eq!(parse_prn("L01"), None); // LEO not modelled
+        assert_eq!(parse_prn("G999"), None);
+        assert_eq!(parse_prn("R65500"), None);
+        assert_eq!(parse_prn("R33"), None);
+        assert_eq!(prn_label(CONSTELLATION_GLONASS, 10), None);
+    }
+
+    #[test]
+    fn test_merge_overrides() {
+        let mut a = Catalog::from_json(r#"[{"norad_id": 1, "prn": "G01"}, {"norad_id": 2, "prn": "G02"}]"#).unwrap();
+        a.merge(Catalog::from_json(r#"[{"norad_id": 2, "prn": "G12", "healthy": false}]"#).unwrap());
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.get(2).unwrap().prn.as_deref(), Some("G12"));
+    }
+}
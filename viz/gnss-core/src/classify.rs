@@ -0,0 +1,455 @@
+// classify.rs — Table-driven constellation classification
+//
+// Responsibilities:
+//   - Classify OMM records by NORAD catalog number, COSPAR international
+//     designator (OMM `OBJECT_ID`) and object name, in table order
+//   - Disambiguate generic names with orbit bands (e.g. COSMOS → GLONASS
+//     only in the 11/8-sidereal-day, 64.8° GLONASS orbit)
+//   - Put SBAS GEOs in their own category with a per-provider subclass
+//   - Split orbit types (MEO / IGSO / GEO) from mean motion and inclination,
+//     with the per-system names (QZSS QZO, NavIC GSO)
+//
+// Pure data — no wasm_bindgen exports, no three-d. tles.rs calls `classify`
+// once per record while loading; first matching rule wins.
+
+use serde::Serialize;
+
+use crate::tles::{
+    CONSTELLATION_BEIDOU, CONSTELLATION_GALILEO, CONSTELLATION_GLONASS, CONSTELLATION_GPS,
+    CONSTELLATION_NAVIC, CONSTELLATION_OTHER, CONSTELLATION_QZSS, CONSTELLATION_SBAS,
+};
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// SBAS service provider.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SbasProvider {
+    /// USA — Wide Area Augmentation System
+    Waas,
+    /// Europe — European Geostationary Navigation Overlay Service
+    Egnos,
+    /// India — GPS-aided GEO augmented navigation
+    Gagan,
+    /// Japan — MTSAT Satellite Augmentation System
+    Msas,
+    /// Russia — System for Differential Corrections and Monitoring
+    Sdcm,
+    /// Korea — Korea Augmentation Satellite System
+    Kass,
+    /// Australia / New Zealand — Southern Positioning Augmentation Network
+    SouthPan,
+}
+
+/// Orbit type. Index order (`Meo` = 0, `Igso` = 1, `Geo` = 2) matches the
+/// per-class toggle arrays in the viewer.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OrbitClass {
+    /// Medium Earth orbit — everything outside the geosynchronous band.
+    #[default]
+    Meo,
+    /// Inclined geosynchronous (BeiDou IGSO, QZSS QZO, NavIC GSO).
+    Igso,
+    /// Geostationary.
+    Geo,
+}
+
+/// Number of `OrbitClass` variants (size of per-class arrays).
+pub const ORBIT_CLASS_COUNT: usize = 3;
+
+/// Everything a rule can look at for one OMM record.
+#[derive(Clone, Copy, Debug)]
+pub struct ObjectInfo<'a> {
+    pub name: &'a str,
+    pub norad_id: u64,
+    /// OMM `OBJECT_ID`, e.g. "2018-109A"; empty when absent.
+    pub object_id: &'a str,
+    /// Revolutions per day.
+    pub mean_motion: f64,
+    pub inclination_deg: f64,
+}
+
+/// Classification result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Class {
+    pub constellation: u8,
+    /// Set only for `CONSTELLATION_SBAS`.
+    pub sbas: Option<SbasProvider>,
+}
+
+/// Identity test for one rule.
+#[derive(Clone, Copy, Debug)]
+pub enum Matcher {
+    /// Inclusive NORAD catalog number range.
+    Norad(u64, u64),
+    /// COSPAR designator prefix ("2005-051A" or a whole launch "2005-051").
+    Cospar(&'static str),
+    /// Upper-case object name prefix.
+    NamePrefix(&'static str),
+    /// Upper-case object name substring.
+    NameContains(&'static str),
+}
+
+/// Mean-motion (rev/day) and inclination (deg) window, inclusive.
+#[derive(Clone, Copy, Debug)]
+pub struct OrbitBand {
+    pub mean_motion: (f64, f64),
+    pub inclination_deg: (f64, f64),
+}
+
+/// One table row: if `matcher` and (optionally) `orbit` match, the object
+/// belongs to `constellation` / `sbas`.
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    pub matcher: Matcher,
+    pub orbit: Option<OrbitBand>,
+    pub constellation: u8,
+    pub sbas: Option<SbasProvider>,
+}
+
+// ---------------------------------------------------------------------------
+// Built-in table
+// ---------------------------------------------------------------------------
+
+/// Geosynchronous: one revolution per sidereal day ≈ 1.0027 rev/day.
+/// Drifting or end-of-life GEOs stray a few percent either side.
+const GEOSYNC_MEAN_MOTION: (f64, f64) = (0.95, 1.05);
+/// GEOs are station-kept to within ~1°; BeiDou GEOs at end of life drift
+/// to ~4°. The shallowest IGSO/GSO plane (NavIC) is ~27°.
+const GEO_MAX_INCLINATION_DEG: f64 = 5.0;
+
+/// GLONASS: 8 orbits in 17 sidereal days ≈ 2.13 rev/day at 64.8°.
+const GLONASS_ORBIT: OrbitBand = OrbitBand { mean_motion: (2.10, 2.16), inclination_deg: (62.0, 68.0) };
+
+/// Galileo: 17 orbits in 10 days ≈ 1.70 rev/day at 56°.
+const GALILEO_ORBIT: OrbitBand = OrbitBand { mean_motion: (1.65, 1.75), inclination_deg: (50.0, 62.0) };
+
+const fn rule(matcher: Matcher, constellation: u8) -> Rule {
+    Rule { matcher, orbit: None, constellation, sbas: None }
+}
+
+const fn in_orbit(matcher: Matcher, orbit: OrbitBand, constellation: u8) -> Rule {
+    Rule { matcher, orbit: Some(orbit), constellation, sbas: None }
+}
+
+const fn sbas(matcher: Matcher, provider: SbasProvider) -> Rule {
+    Rule { matcher, orbit: None, constellation: CONSTELLATION_SBAS, sbas: Some(provider) }
+}
+
+/// Classification table, checked top to bottom.
+///
+/// SBAS GEOs are commercial or government communication satellites with
+/// generic names, so they are listed by NORAD number before any name rule.
+/// Navigation satellites are listed by NORAD number and COSPAR payload
+/// designator as well, so two-line sets without a name line still
+/// classify; the name rules then catch anything launched since. NORAD
+/// ranges cover consecutive payloads of one launch, never the upper stage.
+/// Update this table when providers add or retire satellites.
+pub const RULES: &[Rule] = &[
+    // ── SBAS by NORAD catalog number ─────────────────────────────────────
+    sbas(Matcher::Norad(28868, 28868), SbasProvider::Waas),     // Anik F1R
+    sbas(Matcher::Norad(28884, 28884), SbasProvider::Waas),     // Galaxy 15
+    sbas(Matcher::Norad(41589, 41589), SbasProvider::Waas),     // Eutelsat 117 West B
+    sbas(Matcher::Norad(42709, 42709), SbasProvider::Waas),     // SES-15
+    sbas(Matcher::Norad(46114, 46114), SbasProvider::Waas),     // Galaxy 30
+    sbas(Matcher::Norad(28899, 28899), SbasProvider::Egnos),    // Inmarsat 4-F2
+    sbas(Matcher::Norad(38652, 38652), SbasProvider::Egnos),    // SES-5
+    sbas(Matcher::Norad(39617, 39617), SbasProvider::Egnos),    // Astra 5B
+    sbas(Matcher::Norad(44624, 44624), SbasProvider::Egnos),    // Eutelsat 5 West B
+    sbas(Matcher::Norad(37605, 37605), SbasProvider::Gagan),    // GSAT-8
+    sbas(Matcher::Norad(38779, 38779), SbasProvider::Gagan),    // GSAT-10
+    sbas(Matcher::Norad(41028, 41028), SbasProvider::Gagan),    // GSAT-15
+    sbas(Matcher::Norad(28622, 28622), SbasProvider::Msas),     // MTSAT-1R
+    sbas(Matcher::Norad(28937, 28937), SbasProvider::Msas),     // MTSAT-2
+    sbas(Matcher::Norad(37951, 37951), SbasProvider::Sdcm),     // Luch-5A
+    sbas(Matcher::Norad(38977, 38977), SbasProvider::Sdcm),     // Luch-5B
+    sbas(Matcher::Norad(39727, 39727), SbasProvider::Sdcm),     // Luch-5V
+    sbas(Matcher::Norad(28628, 28628), SbasProvider::SouthPan), // Inmarsat 4-F1
+    // ── SBAS by name (newer GEOs, renamed entries) ───────────────────────
+    sbas(Matcher::NamePrefix("KOREASAT 6A"), SbasProvider::Kass),
+    sbas(Matcher::NamePrefix("LUCH-5"), SbasProvider::Sdcm),
+    sbas(Matcher::NamePrefix("LUCH 5"), SbasProvider::Sdcm),
+    sbas(Matcher::NamePrefix("MTSAT"), SbasProvider::Msas),
+    // ── Galileo test satellites by COSPAR designator ─────────────────────
+    rule(Matcher::Cospar("2005-051A"), CONSTELLATION_GALILEO), // GIOVE-A
+    rule(Matcher::Cospar("2008-020A"), CONSTELLATION_GALILEO), // GIOVE-B
+    // ── BeiDou-3 MEO by NORAD catalog number (dual launches) ─────────────
+    rule(Matcher::Norad(43001, 43002), CONSTELLATION_BEIDOU), // M1, M2
+    rule(Matcher::Norad(43107, 43108), CONSTELLATION_BEIDOU), // M7, M8
+    rule(Matcher::Norad(43207, 43208), CONSTELLATION_BEIDOU), // M3, M4
+    rule(Matcher::Norad(43581, 43582), CONSTELLATION_BEIDOU), // M9, M10
+    rule(Matcher::Norad(43602, 43603), CONSTELLATION_BEIDOU), // M5, M6
+    rule(Matcher::Norad(43622, 43623), CONSTELLATION_BEIDOU), // M11, M12
+    rule(Matcher::Norad(43647, 43648), CONSTELLATION_BEIDOU), // M13, M14
+    rule(Matcher::Norad(43706, 43707), CONSTELLATION_BEIDOU), // M15, M16
+    rule(Matcher::Norad(44542, 44543), CONSTELLATION_BEIDOU), // M23, M24
+    rule(Matcher::Norad(44793, 44794), CONSTELLATION_BEIDOU), // M21, M22
+    // ── BeiDou-3 IGSO / GEO by COSPAR designator ─────────────────────────
+    rule(Matcher::Cospar("2018-085A"), CONSTELLATION_BEIDOU), // G1
+    rule(Matcher::Cospar("2019-023A"), CONSTELLATION_BEIDOU), // IGSO-1
+    rule(Matcher::Cospar("2019-035A"), CONSTELLATION_BEIDOU), // IGSO-2
+    rule(Matcher::Cospar("2019-073A"), CONSTELLATION_BEIDOU), // IGSO-3
+    rule(Matcher::Cospar("2020-017A"), CONSTELLATION_BEIDOU), // G2
+    rule(Matcher::Cospar("2020-040A"), CONSTELLATION_BEIDOU), // G3
+    // ── GPS by NORAD catalog number ──────────────────────────────────────
+    rule(Matcher::Norad(36585, 36585), CONSTELLATION_GPS), // BIIF-1
+    rule(Matcher::Norad(37753, 37753), CONSTELLATION_GPS), // BIIF-2
+    rule(Matcher::Norad(38833, 38833), CONSTELLATION_GPS), // BIIF-3
+    rule(Matcher::Norad(39166, 39166), CONSTELLATION_GPS), // BIIF-4
+    rule(Matcher::Norad(39533, 39533), CONSTELLATION_GPS), // BIIF-5
+    rule(Matcher::Norad(39741, 39741), CONSTELLATION_GPS), // BIIF-6
+    rule(Matcher::Norad(40105, 40105), CONSTELLATION_GPS), // BIIF-7
+    rule(Matcher::Norad(40294, 40294), CONSTELLATION_GPS), // BIIF-8
+    rule(Matcher::Norad(40534, 40534), CONSTELLATION_GPS), // BIIF-9
+    rule(Matcher::Norad(40730, 40730), CONSTELLATION_GPS), // BIIF-10
+    rule(Matcher::Norad(41019, 41019), CONSTELLATION_GPS), // BIIF-11
+    rule(Matcher::Norad(41328, 41328), CONSTELLATION_GPS), // BIIF-12
+    // ── GPS III by COSPAR designator ─────────────────────────────────────
+    rule(Matcher::Cospar("2018-109A"), CONSTELLATION_GPS), // GPS III-1
+    rule(Matcher::Cospar("2019-056A"), CONSTELLATION_GPS), // GPS III-2
+    rule(Matcher::Cospar("2020-041A"), CONSTELLATION_GPS), // GPS III-3
+    rule(Matcher::Cospar("2020-078A"), CONSTELLATION_GPS), // GPS III-4
+    rule(Matcher::Cospar("2021-054A"), CONSTELLATION_GPS), // GPS III-5
+    rule(Matcher::Cospar("2023-009A"), CONSTELLATION_GPS), // GPS III-6
+    // ── GLONASS by NORAD catalog number (three-satellite Proton launches) ─
+    rule(Matcher::Norad(36111, 36113), CONSTELLATION_GLONASS), // Cosmos 2456–2458
+    rule(Matcher::Norad(36400, 36402), CONSTELLATION_GLONASS), // Cosmos 2459–2461
+    rule(Matcher::Norad(37139, 37141), CONSTELLATION_GLONASS), // Cosmos 2464–2466
+    rule(Matcher::Norad(37867, 37869), CONSTELLATION_GLONASS), // Cosmos 2475–2477
+    // ── GLONASS-M / -K by COSPAR designator ──────────────────────────────
+    rule(Matcher::Cospar("2013-019A"), CONSTELLATION_GLONASS), // Cosmos 2485
+    rule(Matcher::Cospar("2014-012A"), CONSTELLATION_GLONASS), // Cosmos 2492
+    rule(Matcher::Cospar("2014-032A"), CONSTELLATION_GLONASS), // Cosmos 2500
+    rule(Matcher::Cospar("2016-008A"), CONSTELLATION_GLONASS), // Cosmos 2514
+    rule(Matcher::Cospar("2016-032A"), CONSTELLATION_GLONASS), // Cosmos 2516
+    rule(Matcher::Cospar("2017-055A"), CONSTELLATION_GLONASS), // Cosmos 2522
+    rule(Matcher::Cospar("2018-053A"), CONSTELLATION_GLONASS), // Cosmos 2527
+    rule(Matcher::Cospar("2018-086A"), CONSTELLATION_GLONASS), // Cosmos 2529
+    rule(Matcher::Cospar("2019-030A"), CONSTELLATION_GLONASS), // Cosmos 2534
+    rule(Matcher::Cospar("2019-088A"), CONSTELLATION_GLONASS), // Cosmos 2544
+    rule(Matcher::Cospar("2020-018A"), CONSTELLATION_GLONASS), // Cosmos 2545
+    rule(Matcher::Cospar("2020-075A"), CONSTELLATION_GLONASS), // Cosmos 2547, GLONASS-K1
+    // ── Galileo by NORAD catalog number ──────────────────────────────────
+    rule(Matcher::Norad(37846, 37847), CONSTELLATION_GALILEO), // IOV PFM, FM2
+    rule(Matcher::Norad(38857, 38858), CONSTELLATION_GALILEO), // IOV FM3, FM4
+    rule(Matcher::Norad(40128, 40129), CONSTELLATION_GALILEO), // FOC FM1, FM2
+    rule(Matcher::Norad(40544, 40545), CONSTELLATION_GALILEO), // FOC FM3, FM4
+    rule(Matcher::Norad(40889, 40890), CONSTELLATION_GALILEO), // FOC FM5, FM6
+    rule(Matcher::Norad(41174, 41175), CONSTELLATION_GALILEO), // FOC FM8, FM9
+    rule(Matcher::Norad(41549, 41550), CONSTELLATION_GALILEO), // FOC FM10, FM11
+    rule(Matcher::Norad(41859, 41862), CONSTELLATION_GALILEO), // FOC FM7, FM12–FM14
+    rule(Matcher::Norad(43055, 43058), CONSTELLATION_GALILEO), // FOC FM15–FM18
+    rule(Matcher::Norad(43564, 43567), CONSTELLATION_GALILEO), // FOC FM19–FM22
+    rule(Matcher::Norad(49809, 49810), CONSTELLATION_GALILEO), // FOC FM23, FM24
+    // ── Navigation constellations by name ────────────────────────────────
+    rule(Matcher::NamePrefix("GPS"), CONSTELLATION_GPS),
+    rule(Matcher::NamePrefix("NAVSTAR"), CONSTELLATION_GPS),
+    rule(Matcher::NamePrefix("GLONASS"), CONSTELLATION_GLONASS),
+    // Most Cosmos satellites are not GLONASS — require the GLONASS orbit
+    in_orbit(Matcher::NamePrefix("COSMOS"), GLONASS_ORBIT, CONSTELLATION_GLONASS),
+    rule(Matcher::NamePrefix("GALILEO"), CONSTELLATION_GALILEO),
+    // Galileo "GSAT0101"… vs ISRO "GSAT-8" communication satellites
+    in_orbit(Matcher::NamePrefix("GSAT0"), GALILEO_ORBIT, CONSTELLATION_GALILEO),
+    rule(Matcher::NameContains("BEIDOU"), CONSTELLATION_BEIDOU),
+    rule(Matcher::NamePrefix("BDSM"), CONSTELLATION_BEIDOU),
+    rule(Matcher::NamePrefix("MICHIBIKI"), CONSTELLATION_QZSS),
+    rule(Matcher::NamePrefix("QZS-"), CONSTELLATION_QZSS),
+    rule(Matcher::NamePrefix("IRNSS-"), CONSTELLATION_NAVIC),
+    rule(Matcher::NamePrefix("NAVIC"), CONSTELLATION_NAVIC),
+];
+
+// ---------------------------------------------------------------------------
+// Classification
+// ---------------------------------------------------------------------------
+
+/// Classify with the built-in `RULES` table.
+pub fn classify(obj: &ObjectInfo) -> Class {
+    classify_with(RULES, obj)
+}
+
+/// Classify with an explicit rule table; unmatched objects are `OTHER`.
+pub fn classify_with(rules: &[Rule], obj: &ObjectInfo) -> Class {
+    let name = obj.name.trim().to_ascii_uppercase();
+    let object_id = obj.object_id.trim().to_ascii_uppercase();
+
+    rules
+        .iter()
+        .find(|r| {
+            let id_ok = match r.matcher {
+                Matcher::Norad(lo, hi) => (lo..=hi).contains(&obj.norad_id),
+                Matcher::Cospar(prefix) => !object_id.is_empty() && object_id.starts_with(prefix),
+                Matcher::NamePrefix(p) => name.starts_with(p),
+                Matcher::NameContains(p) => name.contains(p),
+            };
+            id_ok && r.orbit.is_none_or(|b| in_band(&b, obj))
+        })
+        .map(|r| Class { constellation: r.constellation, sbas: r.sbas })
+        .unwrap_or(Class { constellation: CONSTELLATION_OTHER, sbas: None })
+}
+
+fn in_band(b: &OrbitBand, obj: &ObjectInfo) -> bool {
+    (b.mean_motion.0..=b.mean_motion.1).contains(&obj.mean_motion)
+        && (b.inclination_deg.0..=b.inclination_deg.1).contains(&obj.inclination_deg)
+}
+
+// ---------------------------------------------------------------------------
+// Orbit type
+// ---------------------------------------------------------------------------
+
+impl OrbitClass {
+    pub const ALL: [OrbitClass; ORBIT_CLASS_COUNT] = [OrbitClass::Meo, OrbitClass::Igso, OrbitClass::Geo];
+
+    /// Orbit type from mean motion (rev/day) and inclination (deg).
+    pub fn from_elements(mean_motion: f64, inclination_deg: f64) -> Self {
+        let (lo, hi) = GEOSYNC_MEAN_MOTION;
+        if !(lo..=hi).contains(&mean_motion) {
+            OrbitClass::Meo
+        } else if inclination_deg.abs() <= GEO_MAX_INCLINATION_DEG {
+            OrbitClass::Geo
+        } else {
+            OrbitClass::Igso
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(idx: usize) -> Option<Self> {
+        Self::ALL.get(idx).copied()
+    }
+
+    /// Display name in the system's own terms: QZSS calls its IGSOs
+    /// "QZO" (quasi-zenith orbit), NavIC calls them "GSO".
+    pub fn label(self, constellation: u8) -> &'static str {
+        match (self, constellation) {
+            (OrbitClass::Igso, CONSTELLATION_QZSS) => "QZO",
+            (OrbitClass::Igso, CONSTELLATION_NAVIC) => "GSO",
+            (OrbitClass::Igso, _) => "IGSO",
+            (OrbitClass::Geo, _) => "GEO",
+            (OrbitClass::Meo, _) => "MEO",
+        }
+    }
+}
+
+/// Constellations that fly a mix of orbit types and are therefore coloured
+/// and toggled per class.
+pub fn has_orbit_split(constellation: u8) -> bool {
+    matches!(constellation, CONSTELLATION_BEIDOU | CONSTELLATION_QZSS | CONSTELLATION_NAVIC)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn obj(name: &str, norad_id: u64, mean_motion: f64, inclination_deg: f64) -> ObjectInfo<'_> {
+        ObjectInfo { name, norad_id, object_id: "", mean_motion, inclination_deg }
+    }
+
+    fn constellation(name: &str, norad_id: u64, mm: f64, inc: f64) -> u8 {
+        classify(&obj(name, norad_id, mm, inc)).constellation
+    }
+
+    #[test]
+    fn test_gnss_names() {
+        assert_eq!(constellation("GPS BIIR-2  (PRN 13)", 24876, 2.005, 55.0), CONSTELLATION_GPS);
+        assert_eq!(constellation("GLONASS-K1 (COSMOS 2547)", 46805, 2.13, 64.8), CONSTELLATION_GLONASS);
+        assert_eq!(constellation("GSAT0210 (GALILEO 12)", 41550, 1.70, 56.0), CONSTELLATION_GALILEO);
+        assert_eq!(constellation("BEIDOU-3 M1 (C19)", 43001, 1.86, 55.0), CONSTELLATION_BEIDOU);
+        assert_eq!(constellation("QZS-2 (QZSS/PRN 194)", 42738, 1.0, 41.0), CONSTELLATION_QZSS);
+        assert_eq!(constellation("IRNSS-1I", 43286, 1.0, 29.0), CONSTELLATION_NAVIC);
+    }
+
+    /// A Cosmos name alone is not enough — the orbit must be GLONASS's.
+    #[test]
+    fn test_cosmos_needs_glonass_orbit() {
+        assert_eq!(constellation("COSMOS 2488", 39155, 2.131, 64.9), CONSTELLATION_GLONASS);
+        assert_eq!(constellation("COSMOS 2558", 53328, 15.2, 97.3), CONSTELLATION_OTHER);
+        assert_eq!(constellation("COSMOS 2520", 42798, 1.003, 0.1), CONSTELLATION_OTHER);
+    }
+
+    /// ISRO GSAT communication satellites must not become Galileo; the
+    /// GAGAN ones become SBAS.
+    #[test]
+    fn test_isro_gsat_vs_galileo() {
+        assert_eq!(constellation("GSAT-30", 45026, 1.0027, 0.05), CONSTELLATION_OTHER);
+        let g = classify(&obj("GSAT-15", 41028, 1.0027, 0.05));
+        assert_eq!(g, Class { constellation: CONSTELLATION_SBAS, sbas: Some(SbasProvider::Gagan) });
+    }
+
+    #[test]
+    fn test_sbas_providers() {
+        let p = |name, id| classify(&obj(name, id, 1.0027, 0.05)).sbas;
+        assert_eq!(p("SES-15", 42709), Some(SbasProvider::Waas));
+        assert_eq!(p("ASTRA 5B", 39617), Some(SbasProvider::Egnos));
+        assert_eq!(p("LUCH-5A", 37951), Some(SbasProvider::Sdcm));
+        assert_eq!(p("LUCH 5X", 99999), Some(SbasProvider::Sdcm));
+        assert_eq!(p("MTSAT-2", 28937), Some(SbasProvider::Msas));
+        assert_eq!(p("INTELSAT 901", 26824), None);
+    }
+
+    #[test]
+    fn test_cospar_and_custom_rules() {
+        let giove = ObjectInfo { object_id: "2005-051A", ..obj("GIOVE-A", 28922, 1.67, 56.0) };
+        assert_eq!(classify(&giove).constellation, CONSTELLATION_GALILEO);
+
+        let rules = [rule(Matcher::Cospar("2018-109"), CONSTELLATION_GPS), rule(Matcher::Norad(100, 200), CONSTELLATION_QZSS)];
+        let launch = ObjectInfo { object_id: "2018-109A", ..obj("X", 1, 2.0, 55.0) };
+        assert_eq!(classify_with(&rules, &launch).constellation, CONSTELLATION_GPS);
+        assert_eq!(classify_with(&rules, &obj("Y", 150, 1.0, 40.0)).constellation, CONSTELLATION_QZSS);
+        assert_eq!(classify_with(&rules, &obj("Z", 201, 1.0, 40.0)).constellation, CONSTELLATION_OTHER);
+    }
+
+    /// Two-line sets without a name line classify from the NORAD number or
+    /// the COSPAR payload designator; the launch's upper stage does not.
+    #[test]
+    fn test_unnamed_records() {
+        let unnamed = |norad_id, object_id| ObjectInfo { object_id, ..obj("", norad_id, 2.0, 55.0) };
+        let c = |o: ObjectInfo| classify(&o).constellation;
+        assert_eq!(c(unnamed(40294, "")), CONSTELLATION_GPS);
+        assert_eq!(c(unnamed(99001, "2020-078A")), CONSTELLATION_GPS);
+        assert_eq!(c(unnamed(37868, "")), CONSTELLATION_GLONASS);
+        assert_eq!(c(unnamed(99002, "2019-088A")), CONSTELLATION_GLONASS);
+        assert_eq!(c(unnamed(43057, "2017-079C")), CONSTELLATION_GALILEO);
+        assert_eq!(c(unnamed(43622, "")), CONSTELLATION_BEIDOU);
+        assert_eq!(c(unnamed(99003, "2020-040A")), CONSTELLATION_BEIDOU);
+        // Ariane 5 upper stage after the four Galileo FOC payloads
+        assert_eq!(c(unnamed(41863, "2016-069E")), CONSTELLATION_OTHER);
+        assert_eq!(c(unnamed(99004, "2020-078B")), CONSTELLATION_OTHER);
+    }
+
+    #[test]
+    fn test_orbit_class_from_elements() {
+        // BeiDou: MEO (C19), IGSO (C38), GEO (C59)
+        assert_eq!(OrbitClass::from_elements(1.8617, 55.1), OrbitClass::Meo);
+        assert_eq!(OrbitClass::from_elements(1.0027, 55.0), OrbitClass::Igso);
+        assert_eq!(OrbitClass::from_elements(1.0027, 1.2), OrbitClass::Geo);
+        // QZSS QZO (e ≈ 0.075, 41°) vs QZS-3 GEO; NavIC GSO at 29°
+        assert_eq!(OrbitClass::from_elements(1.0026, 41.0), OrbitClass::Igso);
+        assert_eq!(OrbitClass::from_elements(1.0027, 0.1), OrbitClass::Geo);
+        assert_eq!(OrbitClass::from_elements(1.0027, 29.0), OrbitClass::Igso);
+        // GPS and GLONASS are never geosynchronous
+        assert_eq!(OrbitClass::from_elements(2.0056, 55.0), OrbitClass::Meo);
+        assert_eq!(OrbitClass::from_elements(2.1309, 64.8), OrbitClass::Meo);
+    }
+
+    #[test]
+    fn test_orbit_class_labels() {
+        assert_eq!(OrbitClass::Igso.label(CONSTELLATION_BEIDOU), "IGSO");
+        assert_eq!(OrbitClass::Igso.label(CONSTELLATION_QZSS), "QZO");
+        assert_eq!(OrbitClass::Igso.label(CONSTELLATION_NAVIC), "GSO");
+        assert_eq!(OrbitClass::Geo.label(CONSTELLATION_NAVIC), "GEO");
+        for c in OrbitClass::ALL {
+            assert_eq!(OrbitClass::from_index(c.index()), Some(c));
+        }
+        assert_eq!(OrbitClass::from_index(ORBIT_CLASS_COUNT), None);
+        assert!(has_orbit_split(CONSTELLATION_QZSS) && !has_orbit_split(CONSTELLATION_GPS));
+    }
+}
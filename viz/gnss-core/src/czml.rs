@@ -0,0 +1,356 @@
+// czml.rs — CZML export of time-dynamic satellite scenes
+//
+// Responsibilities:
+//   - Write a CZML document (Cesium's JSON packet stream): a clock over the
+//     export window, one packet per satellite with sampled positions in the
+//     Earth-fixed or inertial frame, constellation-coloured point, label and
+//     path, and one packet per ground site (the primary observer under its
+//     own id, apart from the stations; time-tagged along its route when it
+//     moves)
+//   - Observer-to-satellite access: a line between site and satellite that
+//     is shown exactly during the visibility intervals
+//
+// Pure data like gis.rs — the caller samples positions (ECEF, or GCRF from
+// coords::teme_to_gcrf) and finds the access intervals with
+// passes::find_passes.
+
+use serde_json::{json, Value};
+
+use crate::gis::{iso8601, GisSite};
+use crate::ground;
+use crate::tles::CONSTELLATION_NAMES;
+
+/// Reference frame of the satellite position samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frame {
+    /// ECEF — CZML `FIXED`.
+    Fixed,
+    /// GCRF — CZML `INERTIAL` (ICRF axes). SGP4's TEME must be rotated
+    /// first: precession and nutation since J2000 put it ~0.3° off.
+    Inertial,
+}
+
+impl Frame {
+    fn czml_name(self) -> &'static str {
+        match self {
+            Frame::Fixed => "FIXED",
+            Frame::Inertial => "INERTIAL",
+        }
+    }
+}
+
+/// One satellite's samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CzmlSat {
+    /// Unique key, e.g. the NORAD id or Walker slot label.
+    pub id: String,
+    pub name: String,
+    pub constellation: u8,
+    /// `(unix_s, position km)` in the document's frame, time-ordered.
+    pub samples: Vec<(f64, [f64; 3])>,
+}
+
+/// Intervals in which satellite `sat` is visible from site `site`
+/// (indices into the document's lists).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Access {
+    pub site: usize,
+    pub sat: usize,
+    pub intervals: Vec<(f64, f64)>,
+}
+
+/// A window's worth of scene, ready to write.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CzmlDocument {
+    pub name: String,
+    pub start_unix: f64,
+    pub end_unix: f64,
+    /// Playback speed of the clock, sim seconds per wall second.
+    pub multiplier: f64,
+    pub frame: Frame,
+    pub sites: Vec<GisSite>,
+    /// Index into `sites` of the primary observer, written as `observer`
+    /// so a station of the same name cannot clash with it.
+    pub primary_site: Option<usize>,
+    pub sats: Vec<CzmlSat>,
+    pub access: Vec<Access>,
+}
+
+// ---------------------------------------------------------------------------
+// Writer
+// ---------------------------------------------------------------------------
+
+impl CzmlDocument {
+    /// The CZML packet array. Positions are metres, sampled relative to the
+    /// window start and Lagrange-interpolated; the primary observer's packet
+    /// is `observer`, stations `site/…`, satellites `sat/…` and access lines
+    /// `access/<site id>/…`, grouped under parent packets.
+    pub fn to_json(&self) -> String {
+        // Whole-second epoch so sample offsets and interval strings agree
+        let epoch = self.start_unix.round();
+        let window = interval(self.start_unix, self.end_unix);
+
+        let mut packets = vec![
+            json!({
+                "id": "document",
+                "name": self.name,
+                "version": "1.0",
+                "clock": {
+                    "interval": window,
+                    "currentTime": iso8601(self.start_unix),
+                    "multiplier": self.multiplier,
+                    "range": "LOOP_STOP",
+                    "step": "SYSTEM_CLOCK_MULTIPLIER",
+                },
+            }),
+            json!({ "id": "sites", "name": "Observers" }),
+            json!({ "id": "satellites", "name": "Satellites" }),
+            json!({ "id": "access", "name": "Access" }),
+        ];
+
+        for (i, site) in self.sites.iter().enumerate() {
+            let position = if site.track.is_empty() {
+                json!({ "cartographicDegrees": [site.lon_deg, site.lat_deg, 0.0] })
+            } else {
+                let samples: Vec<f64> = site
+                    .track
+                    .iter()
+                    .flat_map(|p| [p.unix_s - epoch, p.lon_deg, p.lat_deg, p.alt_km * 1000.0])
+                    .collect();
+                json!({ "epoch": iso8601(epoch), "interpolationAlgorithm": "LINEAR", "cartographicDegrees": samples })
+            };
+            packets.push(json!({
+                "id": self.site_id(i),
+                "name": site.name,
+                "parent": "sites",
+                "position": position,
+                "point": { "color": rgba(site.color, 255), "pixelSize": 8, "outlineWidth": 1 },
+                "label": label(&site.name, site.color),
+            }));
+        }
+
+        for sat in &self.sats {
+            let color = ground::constellation_color(sat.constellation);
+            let cartesian: Vec<f64> = sat
+                .samples
+                .iter()
+                .flat_map(|(t, p)| [t - epoch, p[0] * 1000.0, p[1] * 1000.0, p[2] * 1000.0])
+                .collect();
+            packets.push(json!({
+                "id": sat_id(sat),
+                "name": sat.name,
+                "parent": "satellites",
+                "description": constellation_name(sat.constellation),
+                "availability": window,
+                "position": {
+                    "epoch": iso8601(epoch),
+                    "referenceFrame": self.frame.czml_name(),
+                    "interpolationAlgorithm": "LAGRANGE",
+                    "interpolationDegree": 5,
+                    "cartesian": cartesian,
+                },
+                "point": { "color": rgba(color, 255), "pixelSize": 6 },
+                "label": label(&sat.name, color),
+                "path": {
+                    "material": { "solidColor": { "color": rgba(color, 128) } },
+                    "width": 1,
+                },
+            }));
+        }
+
+        for a in &self.access {
+            let (Some(site), Some(sat)) = (self.sites.get(a.site), self.sats.get(a.sat)) else {
+                continue;
+            };
+            if a.intervals.is_empty() {
+                continue;
+            }
+            packets.push(json!({
+                "id": format!("access/{}/{}", self.site_id(a.site), sat.id),
+                "name": format!("{} – {}", site.name, sat.name),
+                "parent": "access",
+                "availability": window,
+                "polyline": {
+                    "show": show_intervals(self.start_unix, self.end_unix, &a.intervals),
+                    "positions": {
+                        "references": [
+                            format!("{}#position", self.site_id(a.site)),
+                            format!("{}#position", sat_id(sat)),
+                        ],
+                    },
+                    "arcType": "NONE",
+                    "width": 1,
+                    "material": { "solidColor": { "color": rgba(site.color, 160) } },
+                },
+            }));
+        }
+        Value::Array(packets).to_string()
+    }
+
+    fn site_id(&self, idx: usize) -> String {
+        if self.primary_site == Some(idx) {
+            "observer".to_string()
+        } else {
+            format!("site/{}", self.sites[idx].name)
+        }
+    }
+}
+
+fn sat_id(sat: &CzmlSat) -> String {
+    format!("sat/{}", sat.id)
+}
+
+fn constellation_name(idx: u8) -> &'static str {
+    CONSTELLATION_NAMES.get(idx as usize).copied().unwrap_or("Other")
+}
+
+fn interval(start_unix: f64, end_unix: f64) -> String {
+    format!("{}/{}", iso8601(start_unix), iso8601(end_unix))
+}
+
+fn rgba([r, g, b]: [u8; 3], a: u8) -> Value {
+    json!({ "rgba": [r, g, b, a] })
+}
+
+fn label(text: &str, color: [u8; 3]) -> Value {
+    json!({
+        "text": text,
+        "font": "11pt sans-serif",
+        "fillColor": rgba(color, 255),
+        "horizontalOrigin": "LEFT",
+        "pixelOffset": { "cartesian2": [8, 0] },
+    })
+}
+
+/// Alternating hidden / shown intervals covering the whole window, so the
+/// line is visible exactly during `visible` (sorted, non-overlapping).
+fn show_intervals(start_unix: f64, end_unix: f64, visible: &[(f64, f64)]) -> Vec<Value> {
+    let mut out = Vec::new();
+    let mut t = start_unix;
+    for &(a, b) in visible {
+        if a > t {
+            out.push(json!({ "interval": interval(t, a), "boolean": false }));
+        }
+        out.push(json!({ "interval": interval(a.max(t), b), "boolean": true }));
+        t = b;
+    }
+    if t < end_unix {
+        out.push(json!({ "interval": interval(t, end_unix), "boolean": false }));
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gis::TrackPoint;
+
+    const T0: f64 = 1_705_320_000.0; // 2024-01-15T12:00:00Z
+
+    fn doc(frame: Frame) -> CzmlDocument {
+        CzmlDocument {
+            name: "test".to_string(),
+            start_unix: T0,
+            end_unix: T0 + 3600.0,
+            multiplier: 60.0,
+            frame,
+            sites: vec![GisSite {
+                name: "BASE-1".to_string(),
+                lat_deg: 41.85,
+                lon_deg: -87.65,
+                color: [255, 240, 60],
+                track: Vec::new(),
+            }],
+            primary_site: None,
+            sats: vec![CzmlSat {
+                id: "36585".to_string(),
+                name: "GPS BIIF-1".to_string(),
+                constellation: 0,
+                samples: vec![(T0, [26_571.0, 0.0, 0.0]), (T0 + 1800.0, [0.0, 26_571.0, 0.0])],
+            }],
+            access: vec![Access { site: 0, sat: 0, intervals: vec![(T0 + 600.0, T0 + 1200.0)] }],
+        }
+    }
+
+    /// Document, parent, site, satellite and access packets with the
+    /// expected ids, frame, metre samples and colours.
+    #[test]
+    fn test_packets() {
+        let packets: Vec<Value> = serde_json::from_str(&doc(Frame::Fixed).to_json()).unwrap();
+        let ids: Vec<&str> = packets.iter().map(|p| p["id"].as_str().unwrap()).collect();
+        assert_eq!(ids[..4], ["document", "sites", "satellites", "access"]);
+        assert_eq!(ids[4..], ["site/BASE-1", "sat/36585", "access/site/BASE-1/36585"]);
+        assert_eq!(packets[0]["clock"]["interval"], "2024-01-15T12:00:00Z/2024-01-15T13:00:00Z");
+
+        let sat = &packets[5];
+        assert_eq!(sat["position"]["referenceFrame"], "FIXED");
+        assert_eq!(sat["position"]["epoch"], "2024-01-15T12:00:00Z");
+        let cart: Vec<f64> = serde_json::from_value(sat["position"]["cartesian"].clone()).unwrap();
+        assert_eq!(cart, [0.0, 26_571_000.0, 0.0, 0.0, 1800.0, 0.0, 26_571_000.0, 0.0]);
+        assert_eq!(sat["point"]["color"]["rgba"], json!([57, 255, 20, 255]));
+        assert_eq!(sat["label"]["text"], "GPS BIIF-1");
+
+        let access = &packets[6]["polyline"];
+        assert_eq!(access["positions"]["references"], json!(["site/BASE-1#position", "sat/36585#position"]));
+
+        let inertial: Vec<Value> = serde_json::from_str(&doc(Frame::Inertial).to_json()).unwrap();
+        assert_eq!(inertial[5]["position"]["referenceFrame"], "INERTIAL");
+    }
+
+    /// The primary observer has its own id, so a station named like it
+    /// keeps a separate packet and access line.
+    #[test]
+    fn test_primary_site_id() {
+        let mut d = doc(Frame::Fixed);
+        d.sites[0].name = "Observer".to_string();
+        d.sites.push(GisSite { name: "Observer".to_string(), color: [0, 200, 255], ..d.sites[0].clone() });
+        d.primary_site = Some(0);
+        d.access.push(Access { site: 1, sat: 0, intervals: vec![(T0, T0 + 600.0)] });
+        let packets: Vec<Value> = serde_json::from_str(&d.to_json()).unwrap();
+        let ids: Vec<&str> = packets.iter().map(|p| p["id"].as_str().unwrap()).collect();
+        assert_eq!(ids[4..7], ["observer", "site/Observer", "sat/36585"]);
+        assert_eq!(ids[7..], ["access/observer/36585", "access/site/Observer/36585"]);
+        assert_eq!(packets[8]["polyline"]["positions"]["references"][0], "site/Observer#position");
+    }
+
+    /// An observer on a route gets time-tagged positions in metres.
+    #[test]
+    fn test_moving_site() {
+        let mut d = doc(Frame::Fixed);
+        d.sites[0].track = vec![
+            TrackPoint { unix_s: T0, lat_deg: 41.9, lon_deg: -87.9, alt_km: 3.0 },
+            TrackPoint { unix_s: T0 + 600.0, lat_deg: 41.9, lon_deg: -87.5, alt_km: 3.0 },
+        ];
+        let packets: Vec<Value> = serde_json::from_str(&d.to_json()).unwrap();
+        let pos = &packets[4]["position"];
+        assert_eq!(pos["epoch"], "2024-01-15T12:00:00Z");
+        let samples: Vec<f64> = serde_json::from_value(pos["cartographicDegrees"].clone()).unwrap();
+        assert_eq!(samples, [0.0, -87.9, 41.9, 3000.0, 600.0, -87.5, 41.9, 3000.0]);
+    }
+
+    /// Access lines are hidden outside the intervals, including clipped
+    /// intervals at the window edges; empty access produces no packet.
+    #[test]
+    fn test_show_intervals() {
+        let shown = |v: &[(f64, f64)]| -> Vec<(String, bool)> {
+            show_intervals(T0, T0 + 3600.0, v)
+                .iter()
+                .map(|i| (i["interval"].as_str().unwrap().to_string(), i["boolean"].as_bool().unwrap()))
+                .collect()
+        };
+        let s = shown(&[(T0 + 600.0, T0 + 1200.0)]);
+        assert_eq!(s.iter().map(|(_, b)| *b).collect::<Vec<_>>(), [false, true, false]);
+        assert_eq!(s[1].0, "2024-01-15T12:10:00Z/2024-01-15T12:20:00Z");
+        let s = shown(&[(T0, T0 + 60.0), (T0 + 3000.0, T0 + 3600.0)]);
+        assert_eq!(s.iter().map(|(_, b)| *b).collect::<Vec<_>>(), [true, false, true]);
+        assert_eq!(shown(&[]).len(), 1);
+
+        let mut d = doc(Frame::Fixed);
+        d.access[0].intervals.clear();
+        assert!(!d.to_json().contains("access/site/BASE-1"));
+    }
+}
@@ -0,0 +1,141 @@
+// dop.rs — Dilution of precision from satellite sky geometry
+//
+// Responsibilities:
+//   - Build the single-clock geometry matrix G from visible-satellite az/el
+//   - Invert GᵀG and report GDOP / PDOP / HDOP / VDOP / TDOP
+//
+// Pure math — no wasm_bindgen exports, no three-d. Callers decide which
+// satellites count as "used" (elevation mask, horizon profile, buildings)
+// and pass only those az/el pairs in.
+
+use serde::Serialize;
+
+/// Dilution-of-precision figures for one epoch and observer.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Dop {
+    pub gdop: f64,
+    pub pdop: f64,
+    pub hdop: f64,
+    pub vdop: f64,
+    pub tdop: f64,
+    /// Number of satellites in the solution.
+    pub n_sats: usize,
+}
+
+/// Compute DOP from `(az_deg, el_deg)` pairs of the satellites in view.
+///
+/// Uses a local East/North/Up frame and a single receiver clock term shared
+/// by every constellation (inter-system biases ignored). Returns `None` with
+/// fewer than four satellites or a singular geometry.
+pub fn compute(az_el_deg: &[(f64, f64)]) -> Option<Dop> {
+    if az_el_deg.len() < 4 {
+        return None;
+    }
+
+    // Accumulate the normal matrix GᵀG directly; each row of G is the unit
+    // line-of-sight vector (negated) plus 1 for the clock.
+    let mut n = [[0.0f64; 4]; 4];
+    for &(az, el) in az_el_deg {
+        let (az, el) = (az.to_radians(), el.to_radians());
+        let row = [
+            -el.cos() * az.sin(),
+            -el.cos() * az.cos(),
+            -el.sin(),
+            1.0,
+        ];
+        for i in 0..4 {
+            for j in 0..4 {
+                n[i][j] += row[i] * row[j];
+            }
+        }
+    }
+
+    let q = invert4(n)?;
+    let (qe, qn, qu, qt) = (q[0][0], q[1][1], q[2][2], q[3][3]);
+    if qe < 0.0 || qn < 0.0 || qu < 0.0 || qt < 0.0 {
+        return None;
+    }
+    Some(Dop {
+        gdop: (qe + qn + qu + qt).sqrt(),
+        pdop: (qe + qn + qu).sqrt(),
+        hdop: (qe + qn).sqrt(),
+        vdop: qu.sqrt(),
+        tdop: qt.sqrt(),
+        n_sats: az_el_deg.len(),
+    })
+}
+
+/// Gauss-Jordan inverse of a 4×4 matrix with partial pivoting.
+fn invert4(m: [[f64; 4]; 4]) -> Option<[[f64; 4]; 4]> {
+    let mut a = m;
+    let mut inv = [[0.0f64; 4]; 4];
+    for (i, row) in inv.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&r1, &r2| a[r1][col].abs().total_cmp(&a[r2][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        inv.swap(col, pivot);
+
+        let p = a[col][col];
+        for j in 0..4 {
+            a[col][j] /= p;
+            inv[col][j] /= p;
+        }
+        for r in 0..4 {
+            if r != col {
+                let f = a[r][col];
+                for j in 0..4 {
+                    a[r][j] -= f * a[col][j];
+                    inv[r][j] -= f * inv[col][j];
+                }
+            }
+        }
+    }
+    Some(inv)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_too_few_sats() {
+        assert!(compute(&[(0.0, 45.0), (90.0, 45.0), (180.0, 45.0)]).is_none());
+    }
+
+    /// One zenith satellite plus three on the horizon, 120° apart: the
+    /// horizontal block of GᵀG is diag(1.5, 1.5), so HDOP = √(4/3).
+    #[test]
+    fn test_good_geometry() {
+        let d = compute(&[(0.0, 90.0), (0.0, 0.0), (120.0, 0.0), (240.0, 0.0)]).unwrap();
+        assert!((d.hdop - (4.0f64 / 3.0).sqrt()).abs() < 1e-9, "hdop={}", d.hdop);
+        assert!(d.pdop >= d.hdop && d.gdop >= d.pdop);
+        assert_eq!(d.n_sats, 4);
+    }
+
+    /// Four satellites all at the same elevation form a cone and make the
+    /// vertical and clock terms inseparable → singular.
+    #[test]
+    fn test_singular_cone() {
+        assert!(compute(&[(0.0, 30.0), (90.0, 30.0), (180.0, 30.0), (270.0, 30.0)]).is_none());
+    }
+
+    /// Clustering satellites in one quadrant must inflate DOP.
+    #[test]
+    fn test_clustered_geometry_is_worse() {
+        let spread = [(0.0, 20.0), (90.0, 40.0), (180.0, 20.0), (270.0, 40.0), (45.0, 85.0)];
+        let clustered = [(10.0, 20.0), (20.0, 40.0), (30.0, 25.0), (40.0, 45.0), (25.0, 60.0)];
+        let a = compute(&spread).unwrap();
+        let b = compute(&clustered).unwrap();
+        assert!(b.pdop > a.pdop, "spread={} clustered={}", a.pdop, b.pdop);
+    }
+}
@@ -0,0 +1,610 @@
+// gis.rs — KML and GPX export of ground tracks, footprints, sites and passes
+//
+// Responsibilities:
+//   - Sample a satellite's sub-satellite ground track over a window
+//   - Coverage footprint: the ground ring from which a satellite is above
+//     a given elevation mask, split at the antimeridian and closed along
+//     the pole it covers
+//   - Ground-track arcs of the passes over an observer
+//   - Moving observers: a site following a route is written as a
+//     time-stamped track instead of a single point
+//   - Write the lot as a KML document (time-stamped gx:Track placemarks,
+//     footprint polygons, pass lines, constellation-coloured styles) or as
+//     GPX waypoints and tracks
+//
+// The caller supplies each satellite as an ECEF position function of time
+// (SGP4 records or Walker slots alike) and finds the passes with
+// passes::find_passes, as for pass prediction and common view.
+
+use chrono::{Datelike, Timelike};
+
+use crate::coords;
+use crate::ground::{self, EARTH_R_KM};
+use crate::passes::Pass;
+use crate::tles::CONSTELLATION_NAMES;
+
+/// Vertices in a footprint ring.
+pub const FOOTPRINT_POINTS: usize = 72;
+
+// ---------------------------------------------------------------------------
+// Geometry
+// ---------------------------------------------------------------------------
+
+/// One sub-satellite point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrackPoint {
+    pub unix_s: f64,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    /// Satellite height above the spherical Earth, km.
+    pub alt_km: f64,
+}
+
+/// Sub-satellite point of an ECEF position (km).
+pub fn track_point(unix_s: f64, ecef_km: [f64; 3]) -> TrackPoint {
+    let (lat_deg, lon_deg) = coords::ecef_to_geodetic_deg(ecef_km);
+    let r = (ecef_km[0].powi(2) + ecef_km[1].powi(2) + ecef_km[2].powi(2)).sqrt();
+    TrackPoint { unix_s, lat_deg, lon_deg, alt_km: r - EARTH_R_KM }
+}
+
+/// Ground track every `step_s` over `[start_unix, end_unix]`, both ends
+/// included. `ecef_km(t)` is the satellite position at `t`.
+pub fn ground_track(
+    ecef_km: impl Fn(f64) -> [f64; 3],
+    start_unix: f64,
+    end_unix: f64,
+    step_s: f64,
+) -> Vec<TrackPoint> {
+    sample_times(start_unix, end_unix, step_s).into_iter().map(|t| track_point(t, ecef_km(t))).collect()
+}
+
+/// `start_unix`, then every `step_s` up to and including `end_unix`.
+pub fn sample_times(start_unix: f64, end_unix: f64, step_s: f64) -> Vec<f64> {
+    let mut out = Vec::new();
+    if !(step_s > 0.0 && end_unix >= start_unix) {
+        return out;
+    }
+    let mut t = start_unix;
+    loop {
+        out.push(t);
+        if t >= end_unix {
+            break;
+        }
+        t = (t + step_s).min(end_unix);
+    }
+    out
+}
+
+/// Ground-track arc of each pass, from rise to set.
+pub fn pass_arcs(ecef_km: impl Fn(f64) -> [f64; 3], passes: &[Pass], step_s: f64) -> Vec<PassArc> {
+    passes
+        .iter()
+        .map(|p| PassArc { pass: *p, track: ground_track(&ecef_km, p.rise_unix, p.set_unix, step_s) })
+        .collect()
+}
+
+/// Coverage footprint of a satellite at `ecef_km`: the ground region from
+/// which it stands `mask_deg` above the horizon, as closed rings (first
+/// vertex repeated) of `(lat°, lon°)` vertices. A region crossing the
+/// antimeridian comes back as one ring per side; one covering a pole is
+/// closed along that pole. Empty if the satellite is inside the Earth.
+pub fn footprint(ecef_km: [f64; 3], mask_deg: f64, points: usize) -> Vec<Vec<(f64, f64)>> {
+    let r = (ecef_km[0].powi(2) + ecef_km[1].powi(2) + ecef_km[2].powi(2)).sqrt();
+    if r <= EARTH_R_KM || points < 3 {
+        return Vec::new();
+    }
+    // Earth central angle from the sub-satellite point to the mask ring
+    let el = mask_deg.clamp(0.0, 90.0).to_radians();
+    let lambda = ((EARTH_R_KM / r) * el.cos()).acos() - el;
+    let (lat0, lon0) = coords::ecef_to_geodetic_deg(ecef_km);
+    let (p0, l0) = (lat0.to_radians(), lon0.to_radians());
+
+    // Walk the ring with continuous longitudes: they return to the start,
+    // or end a full turn away when the ring goes round a pole
+    let mut ring: Vec<(f64, f64)> = Vec::with_capacity(points + 3);
+    for i in 0..points {
+        let bearing = i as f64 / points as f64 * std::f64::consts::TAU;
+        let p = (p0.sin() * lambda.cos() + p0.cos() * lambda.sin() * bearing.cos()).asin();
+        let dl = (bearing.sin() * lambda.sin() * p0.cos()).atan2(lambda.cos() - p0.sin() * p.sin());
+        let lon = match ring.last() {
+            Some(&(_, prev)) => prev + wrap_deg((l0 + dl).to_degrees() - prev),
+            None => wrap_deg((l0 + dl).to_degrees()),
+        };
+        ring.push((p.to_degrees(), lon));
+    }
+    let (first_lat, first_lon) = ring[0];
+    let last_lon = ring[points - 1].1;
+    let turn = ((last_lon + wrap_deg(first_lon - last_lon) - first_lon) / 360.0).round() * 360.0;
+    if turn != 0.0 {
+        let pole = 90.0_f64.copysign(lat0);
+        ring.extend([(first_lat, first_lon + turn), (pole, first_lon + turn), (pole, first_lon)]);
+    }
+
+    // Cut into 360° longitude bands and shift each piece home
+    let lo = ring.iter().map(|v| v.1).fold(f64::INFINITY, f64::min);
+    let hi = ring.iter().map(|v| v.1).fold(f64::NEG_INFINITY, f64::max);
+    let bands = ((lo + 180.0) / 360.0).floor() as i32..=((hi + 180.0) / 360.0).floor() as i32;
+    bands
+        .filter_map(|band| {
+            let shift = band as f64 * 360.0;
+            let piece = clip_lon(&clip_lon(&ring, shift - 180.0, 1.0), shift + 180.0, -1.0);
+            let span = piece.iter().map(|v| v.1).fold(f64::NEG_INFINITY, f64::max)
+                - piece.iter().map(|v| v.1).fold(f64::INFINITY, f64::min);
+            if piece.len() < 3 || span < 1e-9 {
+                return None;
+            }
+            let mut piece: Vec<(f64, f64)> = piece.into_iter().map(|(lat, lon)| (lat, lon - shift)).collect();
+            piece.push(piece[0]);
+            Some(piece)
+        })
+        .collect()
+}
+
+/// Angle folded into [-180°, 180°).
+fn wrap_deg(deg: f64) -> f64 {
+    (deg + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Part of the closed polygon `ring` (last vertex joins the first) on the
+/// `side` (+1 east, -1 west) of meridian `lon`, Sutherland–Hodgman style.
+fn clip_lon(ring: &[(f64, f64)], lon: f64, side: f64) -> Vec<(f64, f64)> {
+    let inside = |v: (f64, f64)| (v.1 - lon) * side >= 0.0;
+    let mut out = Vec::with_capacity(ring.len() + 2);
+    for (i, &cur) in ring.iter().enumerate() {
+        let prev = ring[(i + ring.len() - 1) % ring.len()];
+        if inside(cur) != inside(prev) {
+            let f = (lon - prev.1) / (cur.1 - prev.1);
+            out.push((prev.0 + f * (cur.0 - prev.0), lon));
+        }
+        if inside(cur) {
+            out.push(cur);
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Export model
+// ---------------------------------------------------------------------------
+
+/// A named ground site (observer or station).
+#[derive(Clone, Debug, PartialEq)]
+pub struct GisSite {
+    pub name: String,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub color: [u8; 3],
+    /// Sampled route of an observer on the move (`alt_km` its height);
+    /// empty for a fixed site at `lat_deg`, `lon_deg`.
+    pub track: Vec<TrackPoint>,
+}
+
+/// One pass and its ground-track arc.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassArc {
+    pub pass: Pass,
+    pub track: Vec<TrackPoint>,
+}
+
+/// Everything exported for one satellite.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SatGeometry {
+    pub name: String,
+    pub norad_id: u64,
+    pub constellation: u8,
+    pub track: Vec<TrackPoint>,
+    /// Footprint rings at the window start; empty to leave it out.
+    pub footprint: Vec<Vec<(f64, f64)>>,
+    pub passes: Vec<PassArc>,
+}
+
+/// A window's worth of geometry, ready to write.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GisExport {
+    pub title: String,
+    pub start_unix: f64,
+    pub end_unix: f64,
+    pub sites: Vec<GisSite>,
+    pub sats: Vec<SatGeometry>,
+}
+
+// ---------------------------------------------------------------------------
+// KML
+// ---------------------------------------------------------------------------
+
+impl GisExport {
+    /// KML 2.2 with Google's gx extension: folders of observers, ground
+    /// tracks (gx:Track, one `<when>` per sample so time sliders animate
+    /// them), footprints stamped at the window start and pass arcs spanning
+    /// rise to set. Styles `c0`, `c1`, … carry the constellation colours.
+    pub fn to_kml(&self) -> String {
+        let mut k = String::new();
+        k.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        k.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\" xmlns:gx=\"http://www.google.com/kml/ext/2.2\">\n");
+        k.push_str(&format!("<Document>\n<name>{}</name>\n", xml_escape(&self.title)));
+        let mut used: Vec<u8> = self.sats.iter().map(|s| s.constellation).collect();
+        used.sort_unstable();
+        used.dedup();
+        for c in used {
+            let rgb = ground::constellation_color(c);
+            k.push_str(&format!(
+                "<Style id=\"c{c}\"><IconStyle><color>{}</color></IconStyle>\
+                 <LineStyle><color>{}</color><width>2</width></LineStyle>\
+                 <PolyStyle><color>{}</color></PolyStyle></Style>\n",
+                kml_color(rgb, 0xff),
+                kml_color(rgb, 0xff),
+                kml_color(rgb, 0x40),
+            ));
+        }
+
+        k.push_str("<Folder><name>Observers</name>\n");
+        for site in &self.sites {
+            if !site.track.is_empty() {
+                k.push_str(&format!(
+                    "<Placemark><name>{}</name><Style><IconStyle><color>{}</color></IconStyle>\
+                     <LineStyle><color>{}</color><width>2</width></LineStyle></Style>",
+                    xml_escape(&site.name),
+                    kml_color(site.color, 0xff),
+                    kml_color(site.color, 0xff),
+                ));
+                k.push_str(&gx_track(&site.track, true));
+                k.push_str("</Placemark>\n");
+                continue;
+            }
+            k.push_str(&format!(
+                "<Placemark><name>{}</name><Style><IconStyle><color>{}</color></IconStyle></Style>\
+                 <Point><coordinates>{:.6},{:.6},0</coordinates></Point></Placemark>\n",
+                xml_escape(&site.name),
+                kml_color(site.color, 0xff),
+                site.lon_deg,
+                site.lat_deg,
+            ));
+        }
+        k.push_str("</Folder>\n");
+
+        k.push_str("<Folder><name>Ground tracks</name>\n");
+        for sat in self.sats.iter().filter(|s| !s.track.is_empty()) {
+            k.push_str(&format!("<Placemark><name>{}</name>{}", xml_escape(&sat.name), style_url(sat)));
+            k.push_str(&gx_track(&sat.track, false));
+            k.push_str("</Placemark>\n");
+        }
+        k.push_str("</Folder>\n");
+
+        k.push_str("<Folder><name>Footprints</name>\n");
+        for sat in self.sats.iter().filter(|s| !s.footprint.is_empty()) {
+            k.push_str(&format!(
+                "<Placemark><name>{}</name>{}<TimeStamp><when>{}</when></TimeStamp>\
+                 <MultiGeometry>",
+                xml_escape(&sat.name),
+                style_url(sat),
+                iso8601(self.start_unix),
+            ));
+            for ring in &sat.footprint {
+                k.push_str("<Polygon><tessellate>1</tessellate><outerBoundaryIs><LinearRing><coordinates>");
+                k.push_str(&coordinates(ring.iter().copied()));
+                k.push_str("</coordinates></LinearRing></outerBoundaryIs></Polygon>");
+            }
+            k.push_str("</MultiGeometry></Placemark>\n");
+        }
+        k.push_str("</Folder>\n");
+
+        k.push_str("<Folder><name>Passes</name>\n");
+        for sat in &self.sats {
+            for arc in sat.passes.iter().filter(|a| !a.track.is_empty()) {
+                let p = &arc.pass;
+                k.push_str(&format!(
+                    "<Placemark><name>{} {:.0}°</name>{}\
+                     <description>rise {} az {:.0}°, max {:.1}° at {}, set {} az {:.0}°</description>\
+                     <TimeSpan><begin>{}</begin><end>{}</end></TimeSpan>\
+                     <LineString><tessellate>1</tessellate><coordinates>",
+                    xml_escape(&sat.name),
+                    p.max_el_deg,
+                    style_url(sat),
+                    iso8601(p.rise_unix),
+                    p.rise_az_deg,
+                    p.max_el_deg,
+                    iso8601(p.max_el_unix),
+                    iso8601(p.set_unix),
+                    p.set_az_deg,
+                    iso8601(p.rise_unix),
+                    iso8601(p.set_unix),
+                ));
+                k.push_str(&coordinates(arc.track.iter().map(|t| (t.lat_deg, t.lon_deg))));
+                k.push_str("</coordinates></LineString></Placemark>\n");
+            }
+        }
+        k.push_str("</Folder>\n</Document>\n</kml>\n");
+        k
+    }
+
+    // -----------------------------------------------------------------------
+    // GPX
+    // -----------------------------------------------------------------------
+
+    /// GPX 1.1: a waypoint per fixed site and a track per moving one, a
+    /// track per ground track and one per pass arc, each `<type>` naming
+    /// the constellation. GPX has no areas,
+    /// so footprints are left out.
+    pub fn to_gpx(&self) -> String {
+        let mut g = String::new();
+        g.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        g.push_str("<gpx version=\"1.1\" creator=\"gnss-constellation\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+        g.push_str(&format!(
+            "<metadata><name>{}</name><time>{}</time></metadata>\n",
+            xml_escape(&self.title),
+            iso8601(self.start_unix)
+        ));
+        for site in self.sites.iter().filter(|s| s.track.is_empty()) {
+            g.push_str(&format!(
+                "<wpt lat=\"{:.6}\" lon=\"{:.6}\"><name>{}</name><type>observer</type></wpt>\n",
+                site.lat_deg,
+                site.lon_deg,
+                xml_escape(&site.name)
+            ));
+        }
+        let mut track = |name: &str, kind: String, points: &[TrackPoint]| {
+            g.push_str(&format!("<trk><name>{}</name><type>{}</type><trkseg>\n", xml_escape(name), kind));
+            for p in points {
+                g.push_str(&format!(
+                    "<trkpt lat=\"{:.6}\" lon=\"{:.6}\"><time>{}</time></trkpt>\n",
+                    p.lat_deg,
+                    p.lon_deg,
+                    iso8601(p.unix_s)
+                ));
+            }
+            g.push_str("</trkseg></trk>\n");
+        };
+        for site in self.sites.iter().filter(|s| !s.track.is_empty()) {
+            track(&site.name, "observer route".to_string(), &site.track);
+        }
+        for sat in &self.sats {
+            let c = constellation_name(sat.constellation);
+            if !sat.track.is_empty() {
+                track(&sat.name, format!("{c} ground track"), &sat.track);
+            }
+            for arc in sat.passes.iter().filter(|a| !a.track.is_empty()) {
+                let name = format!("{} pass {}", sat.name, iso8601(arc.pass.rise_unix));
+                track(&name, format!("{c} pass"), &arc.track);
+            }
+        }
+        g.push_str("</gpx>\n");
+        g
+    }
+}
+
+/// `gx:Track` with one `<when>` per point; `with_altitude` places the points
+/// at their height instead of on the ground.
+fn gx_track(points: &[TrackPoint], with_altitude: bool) -> String {
+    let mode = if with_altitude { "absolute" } else { "clampToGround" };
+    let mut k = format!("<gx:Track><altitudeMode>{mode}</altitudeMode>\n");
+    for p in points {
+        k.push_str(&format!("<when>{}</when>", iso8601(p.unix_s)));
+    }
+    k.push('\n');
+    for p in points {
+        let alt_m = if with_altitude { p.alt_km * 1000.0 } else { 0.0 };
+        k.push_str(&format!("<gx:coord>{:.6} {:.6} {alt_m:.0}</gx:coord>", p.lon_deg, p.lat_deg));
+    }
+    k.push_str("\n</gx:Track>");
+    k
+}
+
+fn style_url(sat: &SatGeometry) -> String {
+    format!("<styleUrl>#c{}</styleUrl>", sat.constellation)
+}
+
+fn constellation_name(idx: u8) -> &'static str {
+    CONSTELLATION_NAMES.get(idx as usize).copied().unwrap_or("Other")
+}
+
+/// KML `aabbggrr` hex.
+fn kml_color([r, g, b]: [u8; 3], alpha: u8) -> String {
+    format!("{alpha:02x}{b:02x}{g:02x}{r:02x}")
+}
+
+/// `lon,lat,0 lon,lat,0 …` from (lat, lon) pairs.
+fn coordinates(points: impl Iterator<Item = (f64, f64)>) -> String {
+    points.map(|(lat, lon)| format!("{lon:.6},{lat:.6},0")).collect::<Vec<_>>().join(" ")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// ISO 8601 UTC, whole seconds: `2024-01-15T12:00:00Z`.
+pub fn iso8601(unix_s: f64) -> String {
+    let t = chrono::DateTime::from_timestamp(unix_s.round() as i64, 0)
+        .unwrap_or(chrono::DateTime::UNIX_EPOCH)
+        .naive_utc();
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        t.year(),
+        t.month(),
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second()
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trajectory::Trajectory;
+
+    const T0: f64 = 1_705_320_000.0; // 2024-01-15T12:00:00Z
+
+    /// Equatorial circular orbit at GPS height, one revolution a day
+    /// eastwards relative to the ground.
+    fn equatorial(t: f64) -> [f64; 3] {
+        let r = EARTH_R_KM + 20_200.0;
+        let a = (t - T0) / 86_400.0 * std::f64::consts::TAU;
+        [r * a.cos(), r * a.sin(), 0.0]
+    }
+
+    #[test]
+    fn test_ground_track_and_footprint() {
+        let track = ground_track(equatorial, T0, T0 + 21_600.0, 3600.0);
+        assert_eq!(track.len(), 7);
+        assert_eq!(track[6].unix_s, T0 + 21_600.0);
+        assert!((track[6].lon_deg - 90.0).abs() < 1e-9 && track[6].lat_deg.abs() < 1e-9);
+        assert!((track[0].alt_km - 20_200.0).abs() < 1e-6);
+        assert_eq!(ground_track(equatorial, T0, T0 + 10.0, 60.0).len(), 2, "the end is always sampled");
+
+        // Horizon footprint of a GPS satellite reaches ~76° from the
+        // sub-satellite point; a 10° mask pulls it in.
+        let rings = footprint(equatorial(T0), 0.0, 4);
+        assert_eq!(rings.len(), 1);
+        let ring = &rings[0];
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring[0], ring[4]);
+        let reach = (EARTH_R_KM / (EARTH_R_KM + 20_200.0)).acos().to_degrees();
+        assert!((ring[0].0 - reach).abs() < 1e-9, "north vertex {:?}", ring[0]);
+        assert!((ring[1].1 - reach).abs() < 1e-9, "east vertex {:?}", ring[1]);
+        assert!(footprint(equatorial(T0), 10.0, 4)[0][0].0 < reach - 5.0);
+        assert!(footprint([1000.0, 0.0, 0.0], 0.0, 4).is_empty());
+    }
+
+    /// A footprint over the antimeridian splits into a ring per side, and
+    /// one covering a pole is closed along it; no edge jumps across the map
+    /// except along the pole itself.
+    #[test]
+    fn test_footprint_antimeridian_and_pole() {
+        let at = |lat: f64, lon: f64, alt_km: f64| {
+            let (p, l) = (lat.to_radians(), lon.to_radians());
+            let r = EARTH_R_KM + alt_km;
+            [r * p.cos() * l.cos(), r * p.cos() * l.sin(), r * p.sin()]
+        };
+        let check = |rings: &[Vec<(f64, f64)>]| {
+            for ring in rings {
+                assert_eq!(ring.first(), ring.last());
+                assert!(ring.iter().all(|&(lat, lon)| lat.abs() <= 90.0 && lon.abs() <= 180.0), "{ring:?}");
+                let along_pole = |w: &[(f64, f64)]| w[0].0.abs() == 90.0 && w[1].0.abs() == 90.0;
+                assert!(ring.windows(2).all(|w| along_pole(w) || (w[1].1 - w[0].1).abs() < 180.0), "{ring:?}");
+            }
+        };
+
+        let rings = footprint(at(0.0, 170.0, 20_200.0), 5.0, FOOTPRINT_POINTS);
+        check(&rings);
+        assert_eq!(rings.len(), 2);
+        let east = |r: &Vec<(f64, f64)>| r.iter().all(|v| v.1 > 0.0);
+        assert_eq!(rings.iter().filter(|r| east(r)).count(), 1);
+        assert!(rings.iter().all(|r| r.iter().any(|v| v.1.abs() == 180.0)), "both sides meet the antimeridian");
+        assert!(rings.iter().all(|r| r.iter().all(|v| v.0.abs() < 80.0)));
+
+        let rings = footprint(at(60.0, 0.0, 20_200.0), 5.0, FOOTPRINT_POINTS);
+        check(&rings);
+        assert!(rings.iter().any(|r| r.iter().filter(|v| v.0 == 90.0).count() >= 2), "closed along the pole");
+        assert!(rings.iter().all(|r| r.iter().all(|v| v.0 > -20.0)), "the south pole stays out");
+        let lons = rings.iter().flatten().map(|v| v.1);
+        let (lo, hi) = lons.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), l| (lo.min(l), hi.max(l)));
+        assert_eq!((lo, hi), (-180.0, 180.0), "all longitudes covered");
+        // Off the prime meridian the polar cap also splits at the antimeridian
+        let rings = footprint(at(60.0, 100.0, 20_200.0), 5.0, FOOTPRINT_POINTS);
+        check(&rings);
+        assert_eq!(rings.len(), 2);
+        assert!(rings.iter().all(|r| r.iter().any(|v| v.0 == 90.0)));
+
+        // Not over the pole or the antimeridian: one ring, as before
+        assert_eq!(footprint(at(60.0, 0.0, 550.0), 5.0, FOOTPRINT_POINTS).len(), 1);
+    }
+
+    /// An observer on a route is written as a track at its height, not a
+    /// fixed point or waypoint.
+    #[test]
+    fn test_moving_site() {
+        let route: Vec<TrackPoint> = [(T0, 41.9, -87.9), (T0 + 600.0, 41.9, -87.5)]
+            .iter()
+            .map(|&(unix_s, lat_deg, lon_deg)| TrackPoint { unix_s, lat_deg, lon_deg, alt_km: 3.0 })
+            .collect();
+        let export = GisExport {
+            title: "Route".to_string(),
+            start_unix: T0,
+            end_unix: T0 + 600.0,
+            sites: vec![GisSite {
+                name: "Observer".to_string(),
+                lat_deg: 41.9,
+                lon_deg: -87.7,
+                color: [255, 240, 60],
+                track: route,
+            }],
+            sats: Vec::new(),
+        };
+        let kml = export.to_kml();
+        assert!(!kml.contains("<Point>"));
+        assert!(kml.contains("<altitudeMode>absolute</altitudeMode>"));
+        assert!(kml.contains("<gx:coord>-87.500000 41.900000 3000</gx:coord>"));
+        let back = Trajectory::from_kml(&kml).unwrap();
+        assert_eq!((back.start_unix(), back.end_unix()), (T0, T0 + 600.0));
+
+        let gpx = export.to_gpx();
+        assert!(!gpx.contains("<wpt"));
+        assert!(gpx.contains("<type>observer route</type>"));
+        assert_eq!(Trajectory::from_gpx(&gpx).unwrap().waypoints.len(), 2);
+    }
+
+    /// The written files are well formed enough to read back as tracks,
+    /// with the original timestamps and coordinates.
+    #[test]
+    fn test_kml_and_gpx() {
+        let track = ground_track(equatorial, T0, T0 + 3600.0, 600.0);
+        let pass = Pass {
+            rise_unix: T0,
+            set_unix: T0 + 1200.0,
+            rise_az_deg: 250.0,
+            set_az_deg: 110.0,
+            max_el_unix: T0 + 600.0,
+            max_el_deg: 71.6,
+            max_el_az_deg: 180.0,
+            rise_clipped: true,
+            set_clipped: false,
+        };
+        let export = GisExport {
+            title: "Test & export".to_string(),
+            start_unix: T0,
+            end_unix: T0 + 3600.0,
+            sites: vec![GisSite {
+                name: "BASE <1>".to_string(),
+                lat_deg: 1.5,
+                lon_deg: 2.5,
+                color: [255, 0, 16],
+                track: Vec::new(),
+            }],
+            sats: vec![SatGeometry {
+                name: "GPS BIIF-1 (PRN 25)".to_string(),
+                norad_id: 36585,
+                constellation: 0,
+                footprint: footprint(equatorial(T0), 5.0, FOOTPRINT_POINTS),
+                passes: pass_arcs(equatorial, &[pass], 300.0),
+                track: track.clone(),
+            }],
+        };
+
+        let kml = export.to_kml();
+        assert!(kml.contains("<name>Test &amp; export</name>"));
+        assert!(kml.contains("<name>BASE &lt;1&gt;</name>"));
+        assert!(kml.contains("<Style id=\"c0\"><IconStyle><color>ff14ff39</color>"));
+        assert!(kml.contains("<color>ff1000ff</color>"), "site colour as aabbggrr");
+        assert!(kml.contains("<TimeSpan><begin>2024-01-15T12:00:00Z</begin><end>2024-01-15T12:20:00Z</end>"));
+        assert_eq!(kml.matches("<LinearRing>").count(), 1);
+        assert_eq!(kml.matches("<MultiGeometry>").count(), 1);
+        let back = Trajectory::from_kml(&kml).unwrap();
+        assert_eq!(back.waypoints.len(), track.len());
+        assert_eq!(back.end_unix(), T0 + 3600.0);
+        assert!((back.waypoints[1].lon_deg - track[1].lon_deg).abs() < 1e-6);
+
+        let gpx = export.to_gpx();
+        assert!(gpx.contains("<wpt lat=\"1.500000\" lon=\"2.500000\">"));
+        assert!(gpx.contains("<type>GPS pass</type>"));
+        assert_eq!(gpx.matches("<trk>").count(), 2);
+        // Ground track every 10 min plus the pass arc every 5 min: the
+        // reader merges both tracks, dropping the shared timestamps
+        let back = Trajectory::from_gpx(&gpx).unwrap();
+        assert_eq!(back.waypoints.len(), 9);
+        assert_eq!(iso8601(T0 + 0.4), "2024-01-15T12:00:00Z");
+    }
+}
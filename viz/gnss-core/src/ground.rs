@@ -1,13 +1,16 @@
 /// Ground observer location, satellite visibility, and sky-plot data export.
 ///
-/// Pure math + JS-bridge — no three-d imports, no sgp4 imports.
-/// lib.rs is responsible for TEME→ECEF conversion and for calling into
+/// Pure math — no wasm_bindgen, no three-d imports, no sgp4 imports.
+/// The caller is responsible for TEME→ECEF conversion and for calling into
 /// this module with pre-computed az/el values and ECEF positions.
 ///
 /// All heavy trig uses f64; only WebGL/JS-facing outputs downcast to f32.
-use wasm_bindgen::prelude::*;
 use serde::Serialize;
 
+use crate::classify::{self, OrbitClass, SbasProvider};
+use crate::linkbudget::SignalCn0;
+use crate::urban::LosState;
+
 // ---------------------------------------------------------------------------
 // Earth radius constant
 // ---------------------------------------------------------------------------
@@ -48,7 +51,7 @@ impl Observer {
     /// ECEF unit vector for this observer (spherical Earth, radius = 1).
     ///
     /// Implements the standard geodetic-to-ECEF conversion inline so that
-    /// this module does not need to import `coords.rs`; lib.rs coordinates
+    /// this module does not need to import `coords.rs`; the caller coordinates
     /// the two modules when both are needed together.
     ///
     /// ```text
@@ -82,23 +85,66 @@ impl Observer {
 
 /// One visible satellite entry for the sky-plot overlay.
 ///
-/// Serialises to a plain JS object via `serde_wasm_bindgen` so the host
-/// page can render it with Canvas2D or SVG without any extra parsing.
+/// Serialises to a plain object (the viewer converts it via
+/// `serde_wasm_bindgen`) so the host page can render it with Canvas2D or
+/// SVG without any extra parsing.
 #[allow(dead_code)]
 #[derive(Serialize)]
 pub struct SkySat {
+    /// Display label: the PRN ("G05") when known and PRN labels are on,
+    /// otherwise the Celestrak object name.
     pub name: String,
-    /// Constellation index (0 = GPS, 1 = GLONASS, 2 = Galileo, 3 = BeiDou MEO).
+    /// Celestrak object name, e.g. "GPS BIIR-13 (PRN 05)".
+    pub object_name: String,
+    pub norad_id: u64,
+    /// NMEA satellite ID (GLONASS 65–96); gap-filled when the PRN is
+    /// unknown, `None` when the constellation's ID range is used up.
+    pub sv_id: Option<u16>,
+    /// RINEX-style PRN from the catalog or object name; `None` if unknown.
+    pub prn: Option<String>,
+    /// Catalog identity fields — `None` when no catalog entry exists.
+    pub svn: Option<String>,
+    pub block: Option<String>,
+    /// GLONASS FDMA frequency channel (−7 … +6).
+    pub freq_channel: Option<i8>,
+    pub healthy: Option<bool>,
+    /// SBAS provider ("WAAS", "EGNOS", …) for constellation 7, else `None`.
+    pub sbas_provider: Option<SbasProvider>,
+    /// Name of the generating Walker constellation for synthetic satellites.
+    pub walker: Option<String>,
+    /// Constellation index (0 = GPS, 1 = GLONASS, 2 = Galileo, 3 = BeiDou,
+    /// 4 = QZSS, 5 = NavIC, 6 = other, 7 = SBAS).
     pub constellation: u8,
+    /// Orbit type: "meo", "igso" or "geo".
+    pub orbit_class: OrbitClass,
+    /// Orbit type in the system's own terms ("IGSO", "QZO", "GSO", …).
+    pub orbit_label: &'static str,
     /// Azimuth in degrees [0, 360).  0 = North, 90 = East.
     pub az_deg: f32,
     /// Elevation in degrees [min_el, 90].
     pub el_deg: f32,
+    /// `true` if the satellite clears the global elevation mask and the site
+    /// horizon profile at its azimuth, and is not blocked by a building.
+    pub visible: bool,
+    /// Urban-canyon line of sight: "los", "blocked" or "nlos_reflected".
+    pub los_state: LosState,
+    /// Building loss already subtracted from `c_n0` and `signals`, dB.
+    pub c_n0_penalty_db: f32,
+    /// Reflected-path excess range for NLOS signals, metres (0 otherwise).
+    pub nlos_excess_m: f32,
     pub r: u8,
     pub g: u8,
     pub b: u8,
-    /// Simulated carrier-to-noise density ratio in dB-Hz. Range ~20–55.
+    /// Link-budget C/N0 of the constellation's primary signal in dB-Hz.
     pub c_n0: f32,
+    /// Per-signal link-budget C/N0 (L1 C/A, L5, E1, E5a, B1I …).
+    pub signals: Vec<SignalCn0>,
+    /// Klobuchar L1 slant ionospheric delay in metres.
+    pub iono_klobuchar_m: f32,
+    /// NeQuick-lite E1 slant ionospheric delay in metres.
+    pub iono_nequick_m: f32,
+    /// Saastamoinen + Niell slant tropospheric delay in metres.
+    pub tropo_m: f32,
 }
 
 // ---------------------------------------------------------------------------
@@ -107,7 +153,7 @@ pub struct SkySat {
 
 /// RGB colour for a constellation index.
 ///
-/// Matches the `SATS` colour definitions in `lib.rs` so that sky-plot dots
+/// Matches the `SATS` colour definitions in the viewer's `lib.rs` so that sky-plot dots
 /// and 3-D scene dots share the same palette.
 ///
 /// | idx | Constellation | Colour            |
@@ -127,6 +173,20 @@ pub fn constellation_color(idx: u8) -> [u8; 3] {
     }
 }
 
+/// Per-orbit-type variant of a constellation colour: IGSOs are lightened
+/// towards white, GEOs darkened. Constellations flying a single orbit type
+/// keep their base colour.
+pub fn orbit_class_color(rgb: [u8; 3], constellation: u8, class: OrbitClass) -> [u8; 3] {
+    if !classify::has_orbit_split(constellation) {
+        return rgb;
+    }
+    match class {
+        OrbitClass::Meo => rgb,
+        OrbitClass::Igso => rgb.map(|c| c + (255 - c) / 2),
+        OrbitClass::Geo => rgb.map(|c| (c as u16 * 3 / 5) as u8),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Visibility test
 // ---------------------------------------------------------------------------
@@ -141,67 +201,6 @@ pub fn is_visible(el_deg: f64, min_el_deg: f64) -> bool {
     el_deg >= min_el_deg
 }
 
-// ---------------------------------------------------------------------------
-// Sky-plot JS export
-// ---------------------------------------------------------------------------
-
-/// Serialise a slice of visible `SkySat` entries to a JS Array of objects.
-///
-/// The host page receives an Array where each element has the shape:
-/// ```json
-/// { "name": "G01", "constellation": 0,
-///   "az_deg": 135.4, "el_deg": 42.1,
-///   "r": 57, "g": 255, "b": 20 }
-/// ```
-///
-/// Returns `JsValue::NULL` only if serialisation fails (should never happen
-/// for well-formed `SkySat` values).
-pub fn sky_plot_jsvalue(sats: &[SkySat]) -> JsValue {
-    use serde::Serialize as _;
-    let ser = serde_wasm_bindgen::Serializer::json_compatible();
-    sats.serialize(&ser).unwrap_or(JsValue::NULL)
-}
-
-// ---------------------------------------------------------------------------
-// C/N0 simulation
-// ---------------------------------------------------------------------------
-
-/// Simulate carrier-to-noise density ratio (dB-Hz) as a function of elevation
-/// angle and a simple ionospheric model.
-///
-/// Model:
-///   base = 47.0 (peak C/N0 at zenith in ideal conditions)
-///   elevation factor: +8.0 * sin(el_rad)  (better at high elevation)
-///   ionospheric loss: proportional to geomagnetic latitude and local solar time
-///     - iono_loss peaks at ~14:00 local solar time, at low geomagnetic latitudes
-///     - max iono_loss ~4 dB-Hz on L1 (simplified)
-///   noise jitter: deterministic per-satellite variation using (constellation*7 + sat_idx*13) % 10 → ±2 dB-Hz
-///
-/// Returns a value clamped to [20.0, 55.0].
-pub fn simulate_c_n0(
-    el_deg: f32,
-    obs_lat_deg: f64,
-    obs_lon_deg: f64,
-    unix_s: f64,
-    constellation: u8,
-    sat_idx: usize,
-) -> f32 {
-    let el_rad = el_deg.to_radians();
-    let base_cn0 = 47.0_f32 + 8.0 * el_rad.sin();
-
-    let lst_hours = (unix_s / 3600.0 % 24.0 + obs_lon_deg / 15.0).rem_euclid(24.0);
-    let iono_peak = ((lst_hours - 14.0) * std::f64::consts::PI / 12.0)
-        .cos()
-        .max(0.0);
-    let geo_lat_factor = 1.0 - (obs_lat_deg.abs() / 90.0);
-    let iono_loss = (iono_peak * geo_lat_factor * 4.0) as f32;
-
-    let jitter =
-        ((constellation as usize * 7 + sat_idx * 13) % 10) as f32 * 0.4 - 2.0;
-
-    (base_cn0 - iono_loss + jitter).clamp(20.0, 55.0)
-}
-
 // ---------------------------------------------------------------------------
 // Line-segment geometry for observer→satellite overlay
 // ---------------------------------------------------------------------------
@@ -320,6 +319,16 @@ mod tests {
         assert_eq!(constellation_color(255), [128, 128, 128]);
     }
 
+    #[test]
+    fn test_orbit_class_color() {
+        let bds = constellation_color(3);
+        assert_eq!(orbit_class_color(bds, 3, OrbitClass::Meo), bds);
+        assert_eq!(orbit_class_color(bds, 3, OrbitClass::Igso), [255, 212, 127]);
+        assert_eq!(orbit_class_color(bds, 3, OrbitClass::Geo), [153, 102, 0]);
+        // GPS has no orbit split — colour unchanged whatever the class
+        assert_eq!(orbit_class_color(constellation_color(0), 0, OrbitClass::Geo), constellation_color(0));
+    }
+
     // --- build_line_segments ---
 
     #[test]
@@ -358,18 +367,6 @@ mod tests {
         assert_eq!(buf[8], 1.0);
     }
 
-    // --- simulate_c_n0 ---
-
-    #[test]
-    fn test_simulate_c_n0_range() {
-        // zenith satellite should have high C/N0
-        let cn0 = simulate_c_n0(90.0, 0.0, 0.0, 0.0, 0, 0);
-        assert!(cn0 >= 40.0 && cn0 <= 55.0, "zenith cn0={}", cn0);
-        // near-horizon satellite should have low C/N0
-        let cn0_low = simulate_c_n0(5.0, 0.0, 0.0, 50400.0, 0, 0); // 14:00 LST at equator
-        assert!(cn0_low >= 20.0 && cn0_low <= 45.0, "horizon cn0={}", cn0_low);
-    }
-
     /// Buffer capacity must be 6 × number of visible satellites.
     #[test]
     fn test_build_line_segments_n_sats() {
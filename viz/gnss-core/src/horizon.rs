@@ -0,0 +1,229 @@
+// horizon.rs — Azimuth-dependent horizon / terrain elevation mask
+//
+// Responsibilities:
+//   - Hold a per-azimuth elevation profile for the observer site
+//   - Parse the profile from a JSON array of bin elevations or a simple CSV
+//   - Combine the profile with the global elevation mask into one predicate
+//
+// Pure data + math — no wasm_bindgen exports, no three-d. Every visibility
+// decision in the viewer (sky data, visible-only filter, cone mesh) goes through
+// `effective_mask_deg` so the site profile is applied consistently.
+
+use serde::Deserialize;
+
+/// Resolution used when resampling irregular `az,el` CSV points, degrees.
+const RESAMPLE_STEP_DEG: f64 = 1.0;
+
+/// Elevation mask per azimuth bin.
+///
+/// `bins[i]` is the obstruction elevation (degrees) for azimuths in
+/// `[i·w, (i+1)·w)` where `w = 360 / bins.len()`, measured clockwise from
+/// North like `coords::az_el`.
+#[derive(Clone, Debug)]
+pub struct HorizonMask {
+    pub bins: Vec<f64>,
+}
+
+impl HorizonMask {
+    /// Build a mask from equal-width azimuth bins starting at North.
+    /// Returns `None` for an empty profile or non-finite elevations.
+    pub fn from_bins(bins: Vec<f64>) -> Option<Self> {
+        if bins.is_empty() || bins.iter().any(|e| !e.is_finite()) {
+            return None;
+        }
+        let bins = bins.into_iter().map(|e| e.clamp(-90.0, 90.0)).collect();
+        Some(Self { bins })
+    }
+
+    /// Build a mask from irregular `(az_deg, el_deg)` samples.
+    ///
+    /// Samples are linearly interpolated (wrapping through 360°) onto
+    /// 1° bins, so sparse surveys like "every 10°" become a smooth profile.
+    pub fn from_points(mut pts: Vec<(f64, f64)>) -> Option<Self> {
+        if pts.is_empty() || pts.iter().any(|(a, e)| !a.is_finite() || !e.is_finite()) {
+            return None;
+        }
+        for p in pts.iter_mut() {
+            p.0 = p.0.rem_euclid(360.0);
+        }
+        pts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let n = (360.0 / RESAMPLE_STEP_DEG) as usize;
+        let bins = (0..n)
+            .map(|i| interp_wrapped(&pts, (i as f64 + 0.5) * RESAMPLE_STEP_DEG))
+            .collect();
+        Self::from_bins(bins)
+    }
+
+    /// Parse a JSON profile.
+    ///
+    /// Accepted shapes:
+    ///   `[5.0, 7.5, 12.0, ...]`               — elevation per equal bin
+    ///   `[{"az": 0, "el": 5}, ...]`          — irregular samples
+    pub fn from_json(json: &str) -> Option<Self> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Profile {
+            Bins(Vec<f64>),
+            Points(Vec<AzEl>),
+        }
+        #[derive(Deserialize)]
+        struct AzEl {
+            az: f64,
+            el: f64,
+        }
+
+        match serde_json::from_str::<Profile>(json).ok()? {
+            Profile::Bins(b) => Self::from_bins(b),
+            Profile::Points(p) => Self::from_points(p.into_iter().map(|s| (s.az, s.el)).collect()),
+        }
+    }
+
+    /// Parse a CSV profile.
+    ///
+    /// Either one elevation per line (equal bins) or `az,el` per line.
+    /// Blank lines, `#` comments and a non-numeric header row are skipped.
+    pub fn from_csv(csv: &str) -> Option<Self> {
+        let mut singles: Vec<f64> = Vec::new();
+        let mut pairs: Vec<(f64, f64)> = Vec::new();
+
+        for line in csv.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split([',', ';', '\t'])
+                .map(str::trim)
+                .filter(|f| !f.is_empty())
+                .collect();
+            let nums: Option<Vec<f64>> = fields.iter().map(|f| f.parse().ok()).collect();
+            match nums.as_deref() {
+                Some([el]) => singles.push(*el),
+                Some([az, el, ..]) => pairs.push((*az, *el)),
+                // Header row or junk — skip
+                _ => continue,
+            }
+        }
+
+        match (singles.is_empty(), pairs.is_empty()) {
+            (false, true) => Self::from_bins(singles),
+            (true, false) => Self::from_points(pairs),
+            _ => None,
+        }
+    }
+
+    /// Auto-detect JSON (leading `[`) vs CSV and parse accordingly.
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.trim_start().starts_with('[') {
+            Self::from_json(text)
+        } else {
+            Self::from_csv(text)
+        }
+    }
+
+    /// Obstruction elevation (degrees) at azimuth `az_deg`.
+    pub fn elevation_at(&self, az_deg: f64) -> f64 {
+        let n = self.bins.len();
+        let w = 360.0 / n as f64;
+        let i = ((az_deg.rem_euclid(360.0) / w) as usize).min(n - 1);
+        self.bins[i]
+    }
+}
+
+/// Effective elevation mask at `az_deg`: the larger of the global mask and
+/// the site horizon profile (if one is loaded).
+#[inline]
+pub fn effective_mask_deg(mask: Option<&HorizonMask>, global_deg: f64, az_deg: f64) -> f64 {
+    match mask {
+        Some(m) => m.elevation_at(az_deg).max(global_deg),
+        None => global_deg,
+    }
+}
+
+/// Return `true` if a satellite at `(az_deg, el_deg)` clears the effective mask.
+#[inline]
+pub fn is_visible(mask: Option<&HorizonMask>, global_deg: f64, az_deg: f64, el_deg: f64) -> bool {
+    el_deg >= effective_mask_deg(mask, global_deg, az_deg)
+}
+
+/// Linear interpolation over azimuth-sorted samples, wrapping at 360°.
+fn interp_wrapped(pts: &[(f64, f64)], az: f64) -> f64 {
+    if pts.len() == 1 {
+        return pts[0].1;
+    }
+    // Find the first sample at or after `az`; wrap to index 0 past the end.
+    let hi = pts.iter().position(|p| p.0 >= az).unwrap_or(pts.len());
+    let (a0, e0) = if hi == 0 {
+        let (a, e) = pts[pts.len() - 1];
+        (a - 360.0, e)
+    } else {
+        pts[hi - 1]
+    };
+    let (a1, e1) = if hi == pts.len() {
+        let (a, e) = pts[0];
+        (a + 360.0, e)
+    } else {
+        pts[hi]
+    };
+    if (a1 - a0).abs() < 1e-12 {
+        return e0;
+    }
+    e0 + (az - a0) / (a1 - a0) * (e1 - e0)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bins_lookup() {
+        let m = HorizonMask::from_bins(vec![10.0, 20.0, 30.0, 40.0]).unwrap();
+        assert_eq!(m.elevation_at(0.0), 10.0);
+        assert_eq!(m.elevation_at(89.9), 10.0);
+        assert_eq!(m.elevation_at(90.0), 20.0);
+        assert_eq!(m.elevation_at(359.9), 40.0);
+        assert_eq!(m.elevation_at(-45.0), 40.0); // wraps
+    }
+
+    #[test]
+    fn test_json_bins_and_points() {
+        let m = HorizonMask::from_json("[5, 15]").unwrap();
+        assert_eq!(m.bins, vec![5.0, 15.0]);
+        let p = HorizonMask::from_json(r#"[{"az":0,"el":0},{"az":180,"el":18}]"#).unwrap();
+        assert_eq!(p.bins.len(), 360);
+        assert!((p.elevation_at(90.0) - 9.05).abs() < 0.01);
+        assert!((p.elevation_at(270.0) - 8.95).abs() < 0.01);
+        assert!(HorizonMask::from_json("[]").is_none());
+    }
+
+    #[test]
+    fn test_csv_single_column_and_pairs() {
+        let m = HorizonMask::from_csv("# site A\n3\n6\n9\n12\n").unwrap();
+        assert_eq!(m.bins, vec![3.0, 6.0, 9.0, 12.0]);
+        let p = HorizonMask::from_csv("az,el\n0,10\n90,10\n180,10\n270,10\n").unwrap();
+        assert!(p.bins.iter().all(|&e| (e - 10.0).abs() < 1e-9));
+        assert!(HorizonMask::from_csv("az,el\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_autodetect() {
+        assert!(HorizonMask::parse("  [1, 2, 3]").is_some());
+        assert!(HorizonMask::parse("0,5\n180,25").is_some());
+    }
+
+    /// The global mask acts as a floor beneath the site profile.
+    #[test]
+    fn test_effective_mask_and_visibility() {
+        let m = HorizonMask::from_bins(vec![2.0, 30.0]).unwrap();
+        assert_eq!(effective_mask_deg(Some(&m), 5.0, 90.0), 5.0);
+        assert_eq!(effective_mask_deg(Some(&m), 5.0, 200.0), 30.0);
+        assert_eq!(effective_mask_deg(None, 5.0, 200.0), 5.0);
+        assert!(is_visible(Some(&m), 5.0, 90.0, 10.0));
+        assert!(!is_visible(Some(&m), 5.0, 200.0, 10.0));
+    }
+}
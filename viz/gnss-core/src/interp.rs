@@ -0,0 +1,289 @@
+// interp.rs — Propagation samples and per-frame interpolation
+//
+// Responsibilities:
+//   - Hold the last few propagated snapshots (ECEF position and velocity in
+//     `TleStore::records` order), pushed by the render loop's own SGP4 or
+//     posted back by the propagation worker
+//   - Interpolate between the two snapshots bracketing the render time —
+//     cubic Hermite when both carry velocities, linear otherwise — so
+//     satellites move every frame instead of stepping at the sample rate
+//   - Choose the sample cadence from time warp and satellite count
+//
+// Pure data — no wasm_bindgen exports, no three-d. The viewer writes the
+// interpolated positions into `SatPositions::ecef_km`.
+
+use std::collections::VecDeque;
+
+/// Snapshots kept: enough to bracket the render time while the next one is
+/// in flight.
+pub const MAX_SAMPLES: usize = 4;
+
+/// Wall-clock time between propagations: a floor plus a per-satellite cost
+/// term, so large catalogues are sampled less often.
+const BASE_INTERVAL_S: f64 = 0.05;
+const INTERVAL_PER_SAT_S: f64 = 2.0e-5;
+const MAX_INTERVAL_S: f64 = 0.25;
+
+/// Longest sim-time gap Hermite interpolation bridges: 20° of a LEO orbit,
+/// where the cubic error is still ~0.3 km. Beyond this, motion cannot be
+/// smooth anyway and the render loop propagates directly.
+pub const MAX_STEP_S: f64 = 300.0;
+
+// ---------------------------------------------------------------------------
+// Cadence
+// ---------------------------------------------------------------------------
+
+/// Wall-clock seconds between propagations for `n_sats` satellites.
+pub fn wall_interval_s(n_sats: usize) -> f64 {
+    (BASE_INTERVAL_S + n_sats as f64 * INTERVAL_PER_SAT_S).min(MAX_INTERVAL_S)
+}
+
+/// Sim-time spacing between samples at this warp and satellite count, or
+/// `None` when one wall interval covers more than `MAX_STEP_S` of sim time
+/// and interpolation would no longer be accurate.
+pub fn sample_step_s(time_warp: f64, n_sats: usize) -> Option<f64> {
+    let step = time_warp.max(0.0) * wall_interval_s(n_sats);
+    (step <= MAX_STEP_S).then_some(step.max(1.0e-3))
+}
+
+// ---------------------------------------------------------------------------
+// Samples
+// ---------------------------------------------------------------------------
+
+/// One snapshot: every satellite's ECEF position at `unix_s`, and its
+/// velocity (empty when the producer did not supply one).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub unix_s: f64,
+    pub ecef_km: Vec<[f64; 3]>,
+    pub vel_km_s: Vec<[f64; 3]>,
+}
+
+/// Time-ordered worker snapshots for a fixed satellite count.
+#[derive(Clone, Debug, Default)]
+pub struct SampleRing {
+    samples: VecDeque<Sample>,
+    n_sats: usize,
+}
+
+impl SampleRing {
+    /// Drop every sample and expect `n_sats` satellites from now on (called
+    /// when the TLE set is replaced).
+    pub fn reset(&mut self, n_sats: usize) {
+        self.samples.clear();
+        self.n_sats = n_sats;
+    }
+
+    /// Store a flat xyz snapshot, with flat xyz velocities or an empty
+    /// slice. Rejected (false) when a length does not match the expected
+    /// satellite count or the time is not finite.
+    pub fn push(&mut self, unix_s: f64, flat_km: &[f64], flat_vel_km_s: &[f64]) -> bool {
+        let n = self.n_sats;
+        if flat_km.len() != n * 3 || !(flat_vel_km_s.is_empty() || flat_vel_km_s.len() == n * 3) {
+            return false;
+        }
+        let xyz = |c: &[f64]| [c[0], c[1], c[2]];
+        self.push_with(unix_s, |pos, vel| {
+            pos.extend(flat_km.chunks_exact(3).map(xyz));
+            vel.extend(flat_vel_km_s.chunks_exact(3).map(xyz));
+        })
+    }
+
+    /// Store a snapshot by letting `fill` push positions (and optionally
+    /// velocities) into recycled buffers. A sample at or before the newest
+    /// one means the clock was moved back, so the now-stale later samples
+    /// are dropped first. Rejected (false) as for `push`.
+    pub fn push_with(
+        &mut self,
+        unix_s: f64,
+        fill: impl FnOnce(&mut Vec<[f64; 3]>, &mut Vec<[f64; 3]>),
+    ) -> bool {
+        if self.n_sats == 0 || !unix_s.is_finite() {
+            return false;
+        }
+        if self.samples.back().is_some_and(|s| unix_s <= s.unix_s) {
+            self.samples.clear();
+        }
+        // Recycle the oldest snapshot's allocations once the ring is full
+        let recycled = if self.samples.len() >= MAX_SAMPLES { self.samples.pop_front() } else { None };
+        let mut sample = recycled.unwrap_or_else(|| Sample {
+            unix_s,
+            ecef_km: Vec::with_capacity(self.n_sats),
+            vel_km_s: Vec::with_capacity(self.n_sats),
+        });
+        sample.unix_s = unix_s;
+        sample.ecef_km.clear();
+        sample.vel_km_s.clear();
+        fill(&mut sample.ecef_km, &mut sample.vel_km_s);
+        let n = self.n_sats;
+        if sample.ecef_km.len() != n || !(sample.vel_km_s.is_empty() || sample.vel_km_s.len() == n) {
+            return false;
+        }
+        self.samples.push_back(sample);
+        true
+    }
+
+    /// True when `unix_s` lies within the stored samples' time range.
+    pub fn covers(&self, unix_s: f64) -> bool {
+        self.span().is_some_and(|(t0, t1)| (t0..=t1).contains(&unix_s))
+    }
+
+    /// Time range covered by the stored samples.
+    pub fn span(&self) -> Option<(f64, f64)> {
+        Some((self.samples.front()?.unix_s, self.samples.back()?.unix_s))
+    }
+
+    /// Write positions at `unix_s` into `out`, interpolated between the
+    /// bracketing samples: cubic Hermite when both have velocities, linear
+    /// otherwise. Returns false — leaving `out` untouched — when `unix_s` is
+    /// outside the sampled span or `out` has the wrong length; the caller
+    /// then propagates directly.
+    pub fn interpolate_into(&self, unix_s: f64, out: &mut [[f64; 3]]) -> bool {
+        if out.len() != self.n_sats || !self.covers(unix_s) {
+            return false;
+        }
+        let i = self.samples.iter().rposition(|s| s.unix_s <= unix_s).unwrap_or(0);
+        let a = &self.samples[i];
+        let Some(b) = self.samples.get(i + 1) else {
+            out.copy_from_slice(&a.ecef_km);
+            return true;
+        };
+        let h = b.unix_s - a.unix_s;
+        let f = (unix_s - a.unix_s) / h;
+        if a.vel_km_s.is_empty() || b.vel_km_s.is_empty() {
+            for ((o, pa), pb) in out.iter_mut().zip(&a.ecef_km).zip(&b.ecef_km) {
+                for k in 0..3 {
+                    o[k] = pa[k] + (pb[k] - pa[k]) * f;
+                }
+            }
+            return true;
+        }
+        // Hermite basis; velocity terms scaled by the interval length
+        let (f2, f3) = (f * f, f * f * f);
+        let h00 = 2.0 * f3 - 3.0 * f2 + 1.0;
+        let h10 = (f3 - 2.0 * f2 + f) * h;
+        let h01 = -2.0 * f3 + 3.0 * f2;
+        let h11 = (f3 - f2) * h;
+        let pv = a.ecef_km.iter().zip(&a.vel_km_s).zip(b.ecef_km.iter().zip(&b.vel_km_s));
+        for (o, ((pa, va), (pb, vb))) in out.iter_mut().zip(pv) {
+            for k in 0..3 {
+                o[k] = h00 * pa[k] + h10 * va[k] + h01 * pb[k] + h11 * vb[k];
+            }
+        }
+        true
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring() -> SampleRing {
+        let mut r = SampleRing::default();
+        r.reset(2);
+        assert!(r.push(100.0, &[0.0, 0.0, 0.0, 10.0, 0.0, 0.0], &[]));
+        assert!(r.push(110.0, &[10.0, 0.0, 0.0, 10.0, 20.0, 0.0], &[]));
+        r
+    }
+
+    /// Without velocities, midway between two samples gives the midpoint
+    /// of every satellite.
+    #[test]
+    fn test_interpolates_between_samples() {
+        let r = ring();
+        let mut out = [[0.0; 3]; 2];
+        assert!(r.interpolate_into(105.0, &mut out));
+        assert_eq!(out, [[5.0, 0.0, 0.0], [10.0, 10.0, 0.0]]);
+        assert!(r.interpolate_into(110.0, &mut out));
+        assert_eq!(out[1], [10.0, 20.0, 0.0]);
+    }
+
+    /// Outside the sampled span, or with a mismatched buffer, the caller
+    /// must fall back to direct propagation.
+    #[test]
+    fn test_out_of_span_falls_back() {
+        let r = ring();
+        let mut out = [[7.0; 3]; 2];
+        assert!(!r.interpolate_into(99.0, &mut out));
+        assert!(!r.interpolate_into(111.0, &mut out));
+        assert_eq!(out, [[7.0; 3]; 2]);
+        assert!(!r.interpolate_into(105.0, &mut [[0.0; 3]; 3]));
+    }
+
+    /// Wrong-length snapshots are rejected; a sample earlier than the
+    /// newest one drops the stale future samples.
+    #[test]
+    fn test_push_validation_and_clock_rewind() {
+        let mut r = ring();
+        assert!(!r.push(120.0, &[1.0, 2.0, 3.0], &[]));
+        assert!(!r.push(120.0, &[1.0; 6], &[1.0; 3]));
+        assert_eq!(r.span(), Some((100.0, 110.0)));
+        assert!(r.push(50.0, &[1.0; 6], &[]));
+        assert_eq!(r.span(), Some((50.0, 50.0)));
+        let mut out = [[0.0; 3]; 2];
+        assert!(r.interpolate_into(50.0, &mut out));
+        assert_eq!(out, [[1.0; 3]; 2]);
+    }
+
+    /// The ring holds at most MAX_SAMPLES snapshots, oldest dropped first.
+    #[test]
+    fn test_ring_capacity() {
+        let mut r = SampleRing::default();
+        r.reset(1);
+        for i in 0..10 {
+            assert!(r.push(i as f64, &[i as f64, 0.0, 0.0], &[]));
+        }
+        assert_eq!(r.span(), Some(((10 - MAX_SAMPLES) as f64, 9.0)));
+    }
+
+    /// Hermite with orbital velocities tracks a circular MEO orbit to metres
+    /// across a 300 s gap, where the linear chord is kilometres off.
+    #[test]
+    fn test_hermite_circular_orbit() {
+        let (r, w) = (26_560.0_f64, std::f64::consts::TAU / 43_080.0);
+        let pos = |t: f64| [r * (w * t).cos(), r * (w * t).sin(), 0.0];
+        let vel = |t: f64| [-r * w * (w * t).sin(), r * w * (w * t).cos(), 0.0];
+        let mut ring = SampleRing::default();
+        ring.reset(1);
+        for t in [0.0, MAX_STEP_S] {
+            assert!(ring.push_with(t, |p, v| {
+                p.push(pos(t));
+                v.push(vel(t));
+            }));
+        }
+        let mid = MAX_STEP_S / 2.0;
+        let err = |o: [f64; 3]| {
+            let e = pos(mid);
+            ((o[0] - e[0]).powi(2) + (o[1] - e[1]).powi(2)).sqrt()
+        };
+        let mut out = [[0.0; 3]];
+        assert!(ring.interpolate_into(mid, &mut out));
+        assert!(err(out[0]) < 0.01, "hermite err {} km", err(out[0]));
+
+        let mut linear = SampleRing::default();
+        linear.reset(1);
+        for t in [0.0, MAX_STEP_S] {
+            let p = pos(t);
+            assert!(linear.push(t, &p, &[]));
+        }
+        assert!(linear.interpolate_into(mid, &mut out));
+        assert!(err(out[0]) > 1.0, "linear err {} km", err(out[0]));
+    }
+
+    /// The sample step grows with warp and satellite count until
+    /// interpolation stops being accurate.
+    #[test]
+    fn test_sample_step() {
+        assert!(wall_interval_s(10_000) > wall_interval_s(100));
+        assert!(wall_interval_s(1_000_000) <= MAX_INTERVAL_S);
+        let slow = sample_step_s(1.0, 100).unwrap();
+        let fast = sample_step_s(120.0, 100).unwrap();
+        assert!(fast > slow);
+        assert!(sample_step_s(120.0, 10_000).unwrap() > fast);
+        assert_eq!(sample_step_s(1.0e6, 100), None);
+    }
+}
@@ -0,0 +1,31 @@
+//! gnss-core — platform-independent GNSS constellation math
+//!
+//! SGP4 propagation, reference frames, the ground observer and visibility,
+//! pass prediction and common view between stations, moving-observer
+//! trajectories, atmosphere and link budget, DOP, the NMEA / RINEX / OMM /
+//! TLE / catalog / Walker / GPX / KML parsers and KML / GPX / CZML export.
+//! No wasm-bindgen, no three-d, no clock: callers pass the epoch in, so the
+//! same code runs in the wasm viewer, native tools and backend services,
+//! and `cargo test -p gnss-core` runs natively.
+
+pub mod coords;
+pub mod tles;
+pub mod ground;
+pub mod atmosphere;
+pub mod linkbudget;
+pub mod horizon;
+pub mod urban;
+pub mod dop;
+pub mod nmea;
+pub mod replay;
+pub mod rinex;
+pub mod catalog;
+pub mod classify;
+pub mod walker;
+pub mod satbuf;
+pub mod interp;
+pub mod passes;
+pub mod station;
+pub mod trajectory;
+pub mod gis;
+pub mod czml;
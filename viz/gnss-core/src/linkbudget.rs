@@ -0,0 +1,320 @@
+// linkbudget.rs — Physically based per-signal C/N0 link budget
+//
+// Responsibilities:
+//   - Per-constellation / per-signal transmit EIRP and carrier frequency table
+//   - Free-space path loss from the actual observer→satellite range
+//   - Elevation-dependent atmospheric (gaseous) absorption
+//   - Receive antenna gain vs elevation: isotropic, patch and choke-ring
+//     presets plus a JS-injectable custom table
+//   - Receiver system noise temperature → noise density N0
+//
+// Pure math — no wasm_bindgen exports, no three-d. The caller supplies the
+// range and elevation already computed by `coords::az_el`.
+//
+// C/N0 [dB-Hz] = EIRP − FSPL − L_atm + G_rx(el) − L_impl − 10·log10(k·T_sys)
+
+use serde::Serialize;
+
+use crate::tles::{
+    CONSTELLATION_BEIDOU, CONSTELLATION_GALILEO, CONSTELLATION_GLONASS, CONSTELLATION_GPS,
+    CONSTELLATION_NAVIC, CONSTELLATION_QZSS,
+};
+
+// ---------------------------------------------------------------------------
+// Constants
+// ---------------------------------------------------------------------------
+
+/// Speed of light in vacuum, m/s.
+const C_M_S: f64 = 299_792_458.0;
+
+/// Boltzmann's constant in dBW/(K·Hz).
+const BOLTZMANN_DBW: f64 = -228.6;
+
+/// Zenith gaseous absorption at L-band (ITU-R P.676), dB.
+const ZENITH_ATMO_LOSS_DB: f64 = 0.035;
+
+/// Lowest elevation used in the cosecant absorption law, degrees.
+const MIN_ATMO_EL_DEG: f64 = 3.0;
+
+// ---------------------------------------------------------------------------
+// Signal table
+// ---------------------------------------------------------------------------
+
+/// One broadcast navigation signal.
+pub struct SignalDef {
+    /// Short RINEX-style label shown in the HUD (e.g. "L1 C/A").
+    pub name: &'static str,
+    pub freq_hz: f64,
+    /// Representative transmit EIRP toward the Earth's limb, dBW. Chosen so
+    /// the received power at 5–10° elevation matches each ICD's minimum.
+    pub eirp_dbw: f64,
+}
+
+const GPS_SIGNALS: &[SignalDef] = &[
+    SignalDef { name: "L1 C/A", freq_hz: 1_575.42e6, eirp_dbw: 26.8 },
+    SignalDef { name: "L2C",    freq_hz: 1_227.60e6, eirp_dbw: 23.5 },
+    SignalDef { name: "L5",     freq_hz: 1_176.45e6, eirp_dbw: 25.0 },
+];
+
+const GLONASS_SIGNALS: &[SignalDef] = &[
+    SignalDef { name: "G1", freq_hz: 1_602.00e6, eirp_dbw: 24.0 },
+    SignalDef { name: "G2", freq_hz: 1_246.00e6, eirp_dbw: 21.0 },
+];
+
+const GALILEO_SIGNALS: &[SignalDef] = &[
+    SignalDef { name: "E1",  freq_hz: 1_575.42e6, eirp_dbw: 28.9 },
+    SignalDef { name: "E5a", freq_hz: 1_176.45e6, eirp_dbw: 28.4 },
+    SignalDef { name: "E5b", freq_hz: 1_207.14e6, eirp_dbw: 28.4 },
+];
+
+const BEIDOU_SIGNALS: &[SignalDef] = &[
+    SignalDef { name: "B1I", freq_hz: 1_561.098e6, eirp_dbw: 22.0 },
+    SignalDef { name: "B1C", freq_hz: 1_575.42e6,  eirp_dbw: 26.0 },
+    SignalDef { name: "B2a", freq_hz: 1_176.45e6,  eirp_dbw: 26.0 },
+];
+
+const QZSS_SIGNALS: &[SignalDef] = &[
+    SignalDef { name: "L1 C/A", freq_hz: 1_575.42e6, eirp_dbw: 30.3 },
+    SignalDef { name: "L5",     freq_hz: 1_176.45e6, eirp_dbw: 29.0 },
+];
+
+const NAVIC_SIGNALS: &[SignalDef] = &[
+    SignalDef { name: "L5", freq_hz: 1_176.45e6, eirp_dbw: 27.5 },
+    SignalDef { name: "S",  freq_hz: 2_492.028e6, eirp_dbw: 31.0 },
+];
+
+/// SBAS and unclassified objects: assume a single L1 signal.
+const OTHER_SIGNALS: &[SignalDef] = &[
+    SignalDef { name: "L1", freq_hz: 1_575.42e6, eirp_dbw: 30.0 },
+];
+
+/// Signals broadcast by a constellation, primary (legacy civil) signal first.
+pub fn signals_for(constellation: u8) -> &'static [SignalDef] {
+    match constellation {
+        CONSTELLATION_GPS => GPS_SIGNALS,
+        CONSTELLATION_GLONASS => GLONASS_SIGNALS,
+        CONSTELLATION_GALILEO => GALILEO_SIGNALS,
+        CONSTELLATION_BEIDOU => BEIDOU_SIGNALS,
+        CONSTELLATION_QZSS => QZSS_SIGNALS,
+        CONSTELLATION_NAVIC => NAVIC_SIGNALS,
+        _ => OTHER_SIGNALS,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Receive antenna
+// ---------------------------------------------------------------------------
+
+/// Receive antenna gain pattern as a function of elevation.
+///
+/// Presets are tabulated `(elevation_deg, gain_dBic)` pairs, linearly
+/// interpolated; azimuthal symmetry is assumed.
+#[derive(Clone, Debug)]
+pub enum AntennaPattern {
+    /// 0 dBic in every direction — useful as a reference.
+    Isotropic,
+    /// Typical survey/consumer microstrip patch.
+    Patch,
+    /// Geodetic choke-ring: higher zenith gain, steep low-elevation roll-off.
+    ChokeRing,
+    /// User-supplied table, sorted by ascending elevation.
+    Custom(Vec<(f64, f64)>),
+}
+
+const PATCH_GAIN: &[(f64, f64)] = &[
+    (0.0, -7.0), (10.0, -4.5), (20.0, -2.0), (30.0, 0.0),
+    (45.0, 1.5), (60.0, 2.5), (90.0, 3.5),
+];
+
+const CHOKE_RING_GAIN: &[(f64, f64)] = &[
+    (0.0, -12.0), (10.0, -6.0), (20.0, -3.0), (30.0, -1.0),
+    (45.0, 1.0), (60.0, 3.0), (90.0, 5.0),
+];
+
+impl AntennaPattern {
+    /// Look up a preset by name ("isotropic", "patch", "choke_ring").
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "isotropic" => Some(Self::Isotropic),
+            "patch" => Some(Self::Patch),
+            "choke_ring" | "chokering" => Some(Self::ChokeRing),
+            _ => None,
+        }
+    }
+
+    /// Build a custom pattern from parallel elevation/gain arrays.
+    /// Returns `None` if the arrays are empty or of different lengths.
+    pub fn custom(el_deg: &[f64], gain_dbi: &[f64]) -> Option<Self> {
+        if el_deg.is_empty() || el_deg.len() != gain_dbi.len() {
+            return None;
+        }
+        let mut table: Vec<(f64, f64)> =
+            el_deg.iter().copied().zip(gain_dbi.iter().copied()).collect();
+        table.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(Self::Custom(table))
+    }
+
+    /// Antenna gain in dBic at elevation `el_deg`.
+    pub fn gain_dbi(&self, el_deg: f64) -> f64 {
+        match self {
+            Self::Isotropic => 0.0,
+            Self::Patch => interp_table(PATCH_GAIN, el_deg),
+            Self::ChokeRing => interp_table(CHOKE_RING_GAIN, el_deg),
+            Self::Custom(table) => interp_table(table, el_deg),
+        }
+    }
+}
+
+/// Piecewise-linear lookup, clamped to the first/last entries.
+fn interp_table(table: &[(f64, f64)], x: f64) -> f64 {
+    let (first, last) = (table[0], table[table.len() - 1]);
+    if x <= first.0 {
+        return first.1;
+    }
+    if x >= last.0 {
+        return last.1;
+    }
+    for w in table.windows(2) {
+        let (x0, y0) = w[0];
+        let (x1, y1) = w[1];
+        if x <= x1 {
+            let t = if x1 > x0 { (x - x0) / (x1 - x0) } else { 0.0 };
+            return y0 + t * (y1 - y0);
+        }
+    }
+    last.1
+}
+
+// ---------------------------------------------------------------------------
+// Receiver
+// ---------------------------------------------------------------------------
+
+/// Receiver front-end configuration held in `GnssState`.
+#[derive(Clone, Debug)]
+pub struct ReceiverConfig {
+    pub antenna: AntennaPattern,
+    /// System noise temperature (antenna + LNA + cable), kelvin.
+    pub noise_temp_k: f64,
+    /// Correlation / quantisation / filtering losses, dB.
+    pub implementation_loss_db: f64,
+}
+
+impl Default for ReceiverConfig {
+    fn default() -> Self {
+        Self {
+            antenna: AntennaPattern::Patch,
+            noise_temp_k: 290.0,
+            implementation_loss_db: 1.5,
+        }
+    }
+}
+
+impl ReceiverConfig {
+    /// Noise power spectral density N0 in dBW/Hz.
+    pub fn noise_density_dbw_hz(&self) -> f64 {
+        BOLTZMANN_DBW + 10.0 * self.noise_temp_k.max(1.0).log10()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Budget
+// ---------------------------------------------------------------------------
+
+/// C/N0 of one signal, serialised into `SkySat.signals`.
+#[derive(Serialize, Clone, Debug)]
+pub struct SignalCn0 {
+    pub signal: &'static str,
+    /// Carrier-to-noise density ratio, dB-Hz.
+    pub c_n0: f32,
+}
+
+/// Free-space path loss in dB for `range_km` at `freq_hz`.
+pub fn fspl_db(range_km: f64, freq_hz: f64) -> f64 {
+    let d_m = range_km.max(1e-3) * 1000.0;
+    20.0 * (4.0 * std::f64::consts::PI * d_m * freq_hz / C_M_S).log10()
+}
+
+/// Gaseous atmospheric absorption in dB at elevation `el_deg`
+/// (cosecant law, floored at 3° to stay finite at the horizon).
+pub fn atmo_loss_db(el_deg: f64) -> f64 {
+    ZENITH_ATMO_LOSS_DB / el_deg.max(MIN_ATMO_EL_DEG).to_radians().sin()
+}
+
+/// C/N0 (dB-Hz) of every signal the constellation broadcasts, in table order.
+pub fn signal_c_n0(
+    rx: &ReceiverConfig,
+    constellation: u8,
+    range_km: f64,
+    el_deg: f64,
+) -> Vec<SignalCn0> {
+    let common = atmo_loss_db(el_deg) - rx.antenna.gain_dbi(el_deg)
+        + rx.implementation_loss_db
+        + rx.noise_density_dbw_hz();
+    signals_for(constellation)
+        .iter()
+        .map(|sig| SignalCn0 {
+            signal: sig.name,
+            c_n0: (sig.eirp_dbw - fspl_db(range_km, sig.freq_hz) - common) as f32,
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// FSPL for GPS L1 at 20 200 km is ≈ 182.5 dB.
+    #[test]
+    fn test_fspl_gps_zenith() {
+        let l = fspl_db(20_200.0, 1_575.42e6);
+        assert!((l - 182.5).abs() < 0.1, "fspl={l}");
+    }
+
+    #[test]
+    fn test_noise_density_290k() {
+        let rx = ReceiverConfig::default();
+        assert!((rx.noise_density_dbw_hz() - -204.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_antenna_presets() {
+        let patch = AntennaPattern::preset("patch").unwrap();
+        let choke = AntennaPattern::preset("choke_ring").unwrap();
+        assert!((patch.gain_dbi(90.0) - 3.5).abs() < 1e-9);
+        assert!((patch.gain_dbi(15.0) - -3.25).abs() < 1e-9);
+        assert!(choke.gain_dbi(5.0) < patch.gain_dbi(5.0));
+        assert_eq!(AntennaPattern::Isotropic.gain_dbi(-10.0), 0.0);
+        assert!(AntennaPattern::preset("dish").is_none());
+    }
+
+    #[test]
+    fn test_custom_antenna_sorted_and_validated() {
+        let a = AntennaPattern::custom(&[90.0, 0.0], &[4.0, -6.0]).unwrap();
+        assert!((a.gain_dbi(45.0) - -1.0).abs() < 1e-9);
+        assert!(AntennaPattern::custom(&[0.0], &[]).is_none());
+    }
+
+    /// GPS L1 C/A with a patch antenna: ~50 dB-Hz at zenith, ~40 at 5°.
+    #[test]
+    fn test_gps_l1_cn0_range() {
+        let rx = ReceiverConfig::default();
+        let zen = signal_c_n0(&rx, CONSTELLATION_GPS, 20_200.0, 90.0);
+        let low = signal_c_n0(&rx, CONSTELLATION_GPS, 25_400.0, 5.0);
+        assert_eq!(zen[0].signal, "L1 C/A");
+        assert!(zen[0].c_n0 > 47.0 && zen[0].c_n0 < 53.0, "zen={}", zen[0].c_n0);
+        assert!(low[0].c_n0 > 36.0 && low[0].c_n0 < 43.0, "low={}", low[0].c_n0);
+    }
+
+    /// Every constellation index maps to at least one signal.
+    #[test]
+    fn test_signals_for_all_constellations() {
+        for c in 0..=6u8 {
+            assert!(!signals_for(c).is_empty(), "constellation {c}");
+        }
+        assert_eq!(signals_for(CONSTELLATION_GALILEO).len(), 3);
+    }
+}
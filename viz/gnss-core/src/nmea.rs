@@ -0,0 +1,724 @@
+// nmea.rs — NMEA 0183 sentence generator and parser
+//
+// Responsibilities:
+//   - Format GGA / RMC / ZDA from the observer position and sim epoch
+//   - Format GSA (per-system, NMEA 4.11 system ID) with DOP
+//   - Format GSV per constellation with the proper talker ID
+//     (GP / GL / GA / GB / GQ / GI)
+//   - Compute the XOR checksum and CRLF-terminate every sentence
+//   - Derive NMEA satellite IDs from Celestrak object names
+//   - Parse recorded GGA / RMC / ZDA / GSA / GSV back into the same types
+//     (checksum-validated) for log replay
+//
+// Pure formatting — no wasm_bindgen exports, no three-d. The viewer feeds it
+// exactly the `SkySat` values that `get_sky_data()` returns.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike, NaiveDate, Timelike};
+
+use crate::dop::Dop;
+use crate::tles::{
+    CONSTELLATION_BEIDOU, CONSTELLATION_GALILEO, CONSTELLATION_GLONASS, CONSTELLATION_GPS,
+    CONSTELLATION_NAVIC, CONSTELLATION_QZSS,
+};
+
+/// Maximum satellite IDs in one GSA sentence.
+const GSA_MAX_IDS: usize = 12;
+
+/// Satellites per GSV sentence.
+const GSV_PER_MSG: usize = 4;
+
+// ---------------------------------------------------------------------------
+// Inputs
+// ---------------------------------------------------------------------------
+
+/// Receiver position and time for GGA / RMC / ZDA.
+#[derive(Clone, Copy, Debug)]
+pub struct NmeaFix {
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    /// Antenna altitude above mean sea level, metres.
+    pub alt_m: f64,
+    pub unix_s: f64,
+}
+
+/// One satellite in view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NmeaSat {
+    pub constellation: u8,
+    /// NMEA satellite ID in the constellation's numbering (see `sv_id_range`).
+    pub sv_id: u16,
+    pub az_deg: f64,
+    pub el_deg: f64,
+    /// C/N0 in dB-Hz; `None` when the signal is not tracked.
+    pub snr_dbhz: Option<f64>,
+    /// Whether the satellite contributes to the fix (listed in GSA).
+    pub used: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Constellation mapping
+// ---------------------------------------------------------------------------
+
+/// GSV talker ID for a constellation, or `None` for non-GNSS objects.
+pub fn talker_id(constellation: u8) -> Option<&'static str> {
+    match constellation {
+        CONSTELLATION_GPS => Some("GP"),
+        CONSTELLATION_GLONASS => Some("GL"),
+        CONSTELLATION_GALILEO => Some("GA"),
+        CONSTELLATION_BEIDOU => Some("GB"),
+        CONSTELLATION_QZSS => Some("GQ"),
+        CONSTELLATION_NAVIC => Some("GI"),
+        _ => None,
+    }
+}
+
+/// NMEA 4.11 GNSS system ID used in the last GSA field.
+fn system_id(constellation: u8) -> u8 {
+    match constellation {
+        CONSTELLATION_GLONASS => 2,
+        CONSTELLATION_GALILEO => 3,
+        CONSTELLATION_BEIDOU => 4,
+        CONSTELLATION_QZSS => 5,
+        CONSTELLATION_NAVIC => 6,
+        _ => 1,
+    }
+}
+
+/// NMEA 4.11 GSV signal ID of the primary signal (L1 C/A, G1, E1, B1I…).
+fn signal_id(constellation: u8) -> u8 {
+    match constellation {
+        CONSTELLATION_GALILEO => 7, // E1 B/C
+        _ => 1,
+    }
+}
+
+/// Valid NMEA satellite-ID range for a constellation (GLONASS is 65–96).
+pub fn sv_id_range(constellation: u8) -> (u16, u16) {
+    match constellation {
+        CONSTELLATION_GPS => (1, 32),
+        CONSTELLATION_GLONASS => (65, 96),
+        CONSTELLATION_GALILEO => (1, 36),
+        CONSTELLATION_BEIDOU => (1, 63),
+        CONSTELLATION_QZSS => (1, 10),
+        CONSTELLATION_NAVIC => (1, 14),
+        _ => (1, 99),
+    }
+}
+
+/// Extract a satellite ID from a Celestrak object name.
+///
+/// Recognised forms: `GPS BIIR-2  (PRN 13)`, `GSAT0210 (PRN E01)`,
+/// `BEIDOU-3 M1 (C19)`, `QZS-2 (QZSS/PRN 194)`. QZSS PRNs 193–202 map to
+/// NMEA IDs 1–10. GLONASS names carry no slot number, so they return `None`.
+pub fn sv_id_from_name(constellation: u8, name: &str) -> Option<u16> {
+    let open = name.rfind('(')?;
+    let inner = name[open + 1..].trim_end_matches(')').to_ascii_uppercase();
+    let tail = inner.rsplit(['/', ' ']).next()?;
+    let digits = tail.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    let mut id: u16 = digits.parse().ok()?;
+
+    let explicit_prn = inner.contains("PRN") || tail.len() != digits.len();
+    if !explicit_prn {
+        return None;
+    }
+    if constellation == CONSTELLATION_QZSS && id > 192 {
+        id -= 192;
+    }
+    let (lo, hi) = sv_id_range(constellation);
+    (lo..=hi).contains(&id).then_some(id)
+}
+
+/// Complete `(constellation, known_id)` entries: known IDs (from the catalog
+/// or `sv_id_from_name`) are kept and the rest get the lowest ID unused in
+/// their constellation's range, so GSV never shows duplicates. Once a
+/// range is used up the remaining satellites get `None` and are left out
+/// of the NMEA output.
+pub fn fill_sv_ids(sats: &[(u8, Option<u16>)]) -> Vec<Option<u16>> {
+    let mut used: HashMap<u8, HashSet<u16>> = HashMap::new();
+    for &(c, id) in sats {
+        if let Some(id) = id {
+            used.entry(c).or_default().insert(id);
+        }
+    }
+    // Per-constellation cursor: every ID below it is taken
+    let mut next: HashMap<u8, u16> = HashMap::new();
+    sats.iter()
+        .map(|&(c, id)| {
+            id.or_else(|| {
+                let (lo, hi) = sv_id_range(c);
+                let cand = next.entry(c).or_insert(lo);
+                while *cand <= hi && used.get(&c).is_some_and(|u| u.contains(cand)) {
+                    *cand += 1;
+                }
+                (*cand <= hi).then(|| {
+                    *cand += 1;
+                    *cand - 1
+                })
+            })
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Sentence formatting
+// ---------------------------------------------------------------------------
+
+/// Wrap a sentence body (without `$` and `*`) with checksum and CRLF.
+pub fn sentence(body: &str) -> String {
+    format!("${body}*{:02X}\r\n", checksum(body))
+}
+
+/// XOR of every byte between `$` and `*`.
+pub fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0u8, |acc, b| acc ^ b)
+}
+
+/// Full epoch block: GGA, RMC, GSA (one per system), GSV (per talker), ZDA.
+pub fn generate(fix: &NmeaFix, sats: &[NmeaSat], dop: Option<&Dop>) -> String {
+    let mut out = String::new();
+    let n_used = sats.iter().filter(|s| s.used).count();
+    let has_fix = n_used >= 4 && dop.is_some();
+
+    out.push_str(&gga(fix, n_used, dop.map(|d| d.hdop), has_fix));
+    out.push_str(&rmc(fix, has_fix));
+    for s in gsa(sats, dop, has_fix) {
+        out.push_str(&s);
+    }
+    for s in gsv(sats) {
+        out.push_str(&s);
+    }
+    out.push_str(&zda(fix));
+    out
+}
+
+/// `$GNGGA` — time, position, fix quality, satellites used, HDOP, altitude.
+pub fn gga(fix: &NmeaFix, n_used: usize, hdop: Option<f64>, has_fix: bool) -> String {
+    let (lat, ns) = fmt_lat(fix.lat_deg);
+    let (lon, ew) = fmt_lon(fix.lon_deg);
+    sentence(&format!(
+        "GNGGA,{},{lat},{ns},{lon},{ew},{},{:02},{},{:.1},M,0.0,M,,",
+        fmt_time(fix.unix_s),
+        if has_fix { 1 } else { 0 },
+        n_used.min(99),
+        hdop.map(|h| format!("{:.1}", h.min(99.9))).unwrap_or_default(),
+        fix.alt_m,
+    ))
+}
+
+/// `$GNRMC` — recommended minimum: time, status, position, SOG/COG, date.
+pub fn rmc(fix: &NmeaFix, has_fix: bool) -> String {
+    let (lat, ns) = fmt_lat(fix.lat_deg);
+    let (lon, ew) = fmt_lon(fix.lon_deg);
+    let (status, mode) = if has_fix { ('A', 'A') } else { ('V', 'N') };
+    sentence(&format!(
+        "GNRMC,{},{status},{lat},{ns},{lon},{ew},0.00,,{},,,{mode}",
+        fmt_time(fix.unix_s),
+        fmt_date_ddmmyy(fix.unix_s),
+    ))
+}
+
+/// `$GNZDA` — UTC time and date.
+pub fn zda(fix: &NmeaFix) -> String {
+    let dt = datetime(fix.unix_s);
+    sentence(&format!(
+        "GNZDA,{},{:02},{:02},{:04},00,00",
+        fmt_time(fix.unix_s),
+        dt.day(),
+        dt.month(),
+        dt.year(),
+    ))
+}
+
+/// `$GNGSA` — one sentence per system (12 IDs max each) with PDOP/HDOP/VDOP.
+pub fn gsa(sats: &[NmeaSat], dop: Option<&Dop>, has_fix: bool) -> Vec<String> {
+    let mode = if has_fix { 3 } else { 1 };
+    let dops = dop
+        .map(|d| format!("{:.1},{:.1},{:.1}", d.pdop.min(99.9), d.hdop.min(99.9), d.vdop.min(99.9)))
+        .unwrap_or_else(|| ",,".to_string());
+
+    let mut out = Vec::new();
+    for c in systems_in(sats) {
+        let mut ids: Vec<u16> = sats
+            .iter()
+            .filter(|s| s.used && s.constellation == c)
+            .map(|s| s.sv_id)
+            .collect();
+        ids.sort_unstable();
+        for chunk in ids.chunks(GSA_MAX_IDS) {
+            let mut fields: Vec<String> = chunk.iter().map(|id| format!("{id:02}")).collect();
+            fields.resize(GSA_MAX_IDS, String::new());
+            out.push(sentence(&format!(
+                "GNGSA,A,{mode},{},{dops},{:X}",
+                fields.join(","),
+                system_id(c),
+            )));
+        }
+    }
+    if out.is_empty() {
+        out.push(sentence(&format!("GNGSA,A,1,{},{dops},1", ",".repeat(GSA_MAX_IDS - 1))));
+    }
+    out
+}
+
+/// `$xxGSV` — satellites in view, four per sentence, one group per talker.
+pub fn gsv(sats: &[NmeaSat]) -> Vec<String> {
+    let mut out = Vec::new();
+    for c in systems_in(sats) {
+        let Some(talker) = talker_id(c) else { continue };
+        let mut group: Vec<&NmeaSat> = sats.iter().filter(|s| s.constellation == c).collect();
+        group.sort_by_key(|s| s.sv_id);
+
+        let total = group.len().div_ceil(GSV_PER_MSG);
+        for (i, chunk) in group.chunks(GSV_PER_MSG).enumerate() {
+            let mut body = format!("{talker}GSV,{total},{},{:02}", i + 1, group.len());
+            for s in chunk {
+                body.push_str(&format!(
+                    ",{:02},{:02},{:03},{}",
+                    s.sv_id,
+                    s.el_deg.round().clamp(0.0, 90.0) as i32,
+                    s.az_deg.round().rem_euclid(360.0) as i32,
+                    s.snr_dbhz
+                        .map(|v| format!("{:02}", v.round().clamp(0.0, 99.0) as i32))
+                        .unwrap_or_default(),
+                ));
+            }
+            body.push_str(&format!(",{:X}", signal_id(c)));
+            out.push(sentence(&body));
+        }
+    }
+    out
+}
+
+/// GNSS constellations present in `sats`, ascending, non-GNSS excluded.
+fn systems_in(sats: &[NmeaSat]) -> Vec<u8> {
+    let mut cs: Vec<u8> = sats
+        .iter()
+        .map(|s| s.constellation)
+        .filter(|c| talker_id(*c).is_some())
+        .collect();
+    cs.sort_unstable();
+    cs.dedup();
+    cs
+}
+
+// ---------------------------------------------------------------------------
+// Field formatting
+// ---------------------------------------------------------------------------
+
+fn datetime(unix_s: f64) -> chrono::NaiveDateTime {
+    let ms = (unix_s * 1000.0).round() as i64;
+    chrono::DateTime::from_timestamp_millis(ms)
+        .unwrap_or(chrono::DateTime::UNIX_EPOCH)
+        .naive_utc()
+}
+
+/// `hhmmss.ss` UTC.
+fn fmt_time(unix_s: f64) -> String {
+    let dt = datetime(unix_s);
+    let centis = dt.nanosecond() / 10_000_000;
+    format!("{:02}{:02}{:02}.{:02}", dt.hour(), dt.minute(), dt.second(), centis.min(99))
+}
+
+/// `ddmmyy` UTC.
+fn fmt_date_ddmmyy(unix_s: f64) -> String {
+    let dt = datetime(unix_s);
+    format!("{:02}{:02}{:02}", dt.day(), dt.month(), dt.year().rem_euclid(100))
+}
+
+/// `ddmm.mmmmm` plus hemisphere.
+fn fmt_lat(lat_deg: f64) -> (String, char) {
+    let hemi = if lat_deg < 0.0 { 'S' } else { 'N' };
+    (fmt_deg_min(lat_deg.abs(), 2), hemi)
+}
+
+/// `dddmm.mmmmm` plus hemisphere.
+fn fmt_lon(lon_deg: f64) -> (String, char) {
+    let lon = (lon_deg + 180.0).rem_euclid(360.0) - 180.0;
+    let hemi = if lon < 0.0 { 'W' } else { 'E' };
+    (fmt_deg_min(lon.abs(), 3), hemi)
+}
+
+fn fmt_deg_min(abs_deg: f64, deg_width: usize) -> String {
+    // Round at the last printed digit first so 59.999995′ carries into degrees
+    let total_min = (abs_deg * 60.0 * 1e5).round() / 1e5;
+    let deg = (total_min / 60.0).floor();
+    let min = total_min - deg * 60.0;
+    format!("{:0deg_width$}{:08.5}", deg as u32, min)
+}
+
+// ---------------------------------------------------------------------------
+// Sentence parsing
+// ---------------------------------------------------------------------------
+
+/// One decoded sentence from a recorded log.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sentence {
+    /// `GGA`: UTC time of day (s), position if the receiver had a fix.
+    Gga { tod_s: f64, pos: Option<(f64, f64)>, alt_m: f64 },
+    /// `RMC`: UTC time of day, date and position if status is `A`.
+    Rmc { tod_s: f64, date: Option<NaiveDate>, pos: Option<(f64, f64)> },
+    /// `ZDA`: UTC time of day and full date.
+    Zda { tod_s: f64, date: Option<NaiveDate> },
+    /// `GSA`: satellites used in the fix, as `(constellation, sv_id)`.
+    Gsa { used: Vec<(u8, u16)> },
+    /// `GSV`: satellites in view that report both azimuth and elevation.
+    Gsv { sats: Vec<NmeaSat> },
+}
+
+/// Parse one line. Returns `None` for unsupported sentence types, malformed
+/// fields and checksum mismatches; a missing checksum is accepted.
+pub fn parse_sentence(line: &str) -> Option<Sentence> {
+    let line = line.trim();
+    let body = line.strip_prefix('$')?;
+    let body = match body.rsplit_once('*') {
+        Some((b, cs)) => {
+            let cs = u8::from_str_radix(cs.get(..2)?, 16).ok()?;
+            if cs != checksum(b) {
+                return None;
+            }
+            b
+        }
+        None => body,
+    };
+    let fields: Vec<&str> = body.split(',').collect();
+    let head = fields[0];
+    if head.len() != 5 || !head.is_ascii() {
+        return None;
+    }
+    let (talker, kind) = head.split_at(2);
+    let f = |i: usize| fields.get(i).copied().unwrap_or("");
+
+    match kind {
+        "GGA" => {
+            let quality: u8 = f(6).parse().unwrap_or(0);
+            Some(Sentence::Gga {
+                tod_s: parse_tod(f(1))?,
+                pos: if quality > 0 { parse_pos(f(2), f(3), f(4), f(5)) } else { None },
+                alt_m: f(9).parse().unwrap_or(0.0),
+            })
+        }
+        "RMC" => Some(Sentence::Rmc {
+            tod_s: parse_tod(f(1))?,
+            date: parse_date_ddmmyy(f(9)),
+            pos: if f(2) == "A" { parse_pos(f(3), f(4), f(5), f(6)) } else { None },
+        }),
+        "ZDA" => {
+            let (d, m, y) = (f(2).parse().ok(), f(3).parse().ok(), f(4).parse().ok());
+            Some(Sentence::Zda {
+                tod_s: parse_tod(f(1))?,
+                date: match (y, m, d) {
+                    (Some(y), Some(m), Some(d)) => NaiveDate::from_ymd_opt(y, m, d),
+                    _ => None,
+                },
+            })
+        }
+        "GSA" => {
+            // NMEA 4.11 appends a system ID after VDOP (field 18); older
+            // receivers rely on the talker or the ID range instead.
+            let system = f(18).parse::<u8>().ok().and_then(constellation_from_system_id);
+            let talker_c = system.or_else(|| constellation_from_talker(talker));
+            let used = (3..15)
+                .filter_map(|i| f(i).parse::<u16>().ok())
+                .map(|id| normalise_sv_id(talker_c, id))
+                .collect();
+            Some(Sentence::Gsa { used })
+        }
+        "GSV" => {
+            let talker_c = constellation_from_talker(talker);
+            let mut sats = Vec::new();
+            let mut i = 4;
+            // Groups of four fields; a trailing single field is the signal ID.
+            while i + 3 < fields.len() {
+                let (id, el, az, snr) = (f(i), f(i + 1), f(i + 2), f(i + 3));
+                i += 4;
+                let (Ok(id), Ok(el), Ok(az)) = (id.parse::<u16>(), el.parse::<f64>(), az.parse::<f64>()) else {
+                    continue;
+                };
+                let (constellation, sv_id) = normalise_sv_id(talker_c, id);
+                sats.push(NmeaSat {
+                    constellation,
+                    sv_id,
+                    az_deg: az,
+                    el_deg: el,
+                    snr_dbhz: snr.parse().ok(),
+                    used: false,
+                });
+            }
+            Some(Sentence::Gsv { sats })
+        }
+        _ => None,
+    }
+}
+
+/// Inverse of `talker_id`; also accepts the legacy `BD` and `QZ` talkers.
+/// `GN` (combined) and unknown talkers return `None`.
+pub fn constellation_from_talker(talker: &str) -> Option<u8> {
+    match talker {
+        "GP" => Some(CONSTELLATION_GPS),
+        "GL" => Some(CONSTELLATION_GLONASS),
+        "GA" => Some(CONSTELLATION_GALILEO),
+        "GB" | "BD" => Some(CONSTELLATION_BEIDOU),
+        "GQ" | "QZ" => Some(CONSTELLATION_QZSS),
+        "GI" => Some(CONSTELLATION_NAVIC),
+        _ => None,
+    }
+}
+
+/// Inverse of `system_id`.
+fn constellation_from_system_id(id: u8) -> Option<u8> {
+    match id {
+        1 => Some(CONSTELLATION_GPS),
+        2 => Some(CONSTELLATION_GLONASS),
+        3 => Some(CONSTELLATION_GALILEO),
+        4 => Some(CONSTELLATION_BEIDOU),
+        5 => Some(CONSTELLATION_QZSS),
+        6 => Some(CONSTELLATION_NAVIC),
+        _ => None,
+    }
+}
+
+/// Resolve a raw NMEA satellite ID to `(constellation, sv_id)`.
+///
+/// Legacy GP/GN logs mix systems by range: 65–96 are GLONASS slots and
+/// 193–202 are QZSS PRNs (mapped to 1–10 like `sv_id_from_name`).
+fn normalise_sv_id(talker_c: Option<u8>, id: u16) -> (u8, u16) {
+    match (talker_c, id) {
+        (None | Some(CONSTELLATION_GPS), 65..=96) => (CONSTELLATION_GLONASS, id),
+        (None | Some(CONSTELLATION_GPS) | Some(CONSTELLATION_QZSS), 193..=202) => (CONSTELLATION_QZSS, id - 192),
+        (Some(c), _) => (c, id),
+        (None, _) => (CONSTELLATION_GPS, id),
+    }
+}
+
+/// `hhmmss[.sss]` → seconds since UTC midnight.
+fn parse_tod(s: &str) -> Option<f64> {
+    if s.len() < 6 || !s.is_ascii() {
+        return None;
+    }
+    let h: f64 = s[0..2].parse().ok()?;
+    let m: f64 = s[2..4].parse().ok()?;
+    let sec: f64 = s[4..].parse().ok()?;
+    Some(h * 3600.0 + m * 60.0 + sec)
+}
+
+/// `ddmmyy` → date (years 80–99 map to the 1900s, as in GPS receivers).
+fn parse_date_ddmmyy(s: &str) -> Option<NaiveDate> {
+    if s.len() != 6 || !s.is_ascii() {
+        return None;
+    }
+    let d = s[0..2].parse().ok()?;
+    let m = s[2..4].parse().ok()?;
+    let yy: i32 = s[4..6].parse().ok()?;
+    NaiveDate::from_ymd_opt(if yy >= 80 { 1900 + yy } else { 2000 + yy }, m, d)
+}
+
+/// `ddmm.mmmm,N,dddmm.mmmm,E` → signed decimal degrees.
+fn parse_pos(lat: &str, ns: &str, lon: &str, ew: &str) -> Option<(f64, f64)> {
+    let lat = parse_deg_min(lat, 2)? * if ns == "S" { -1.0 } else { 1.0 };
+    let lon = parse_deg_min(lon, 3)? * if ew == "W" { -1.0 } else { 1.0 };
+    Some((lat, lon))
+}
+
+fn parse_deg_min(s: &str, deg_width: usize) -> Option<f64> {
+    if s.len() <= deg_width || !s.is_ascii() {
+        return None;
+    }
+    let deg: f64 = s[..deg_width].parse().ok()?;
+    let min: f64 = s[deg_width..].parse().ok()?;
+    Some(deg + min / 60.0)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every line must be `$...*HH\r\n` with a checksum matching its body.
+    fn assert_valid_lines(block: &str) {
+        for line in block.split_terminator("\r\n") {
+            assert!(line.starts_with('$'), "{line}");
+            let star = line.rfind('*').expect("no checksum");
+            let body = &line[1..star];
+            let cs = u8::from_str_radix(&line[star + 1..], 16).unwrap();
+            assert_eq!(cs, checksum(body), "{line}");
+        }
+    }
+
+    /// Known-good reference: `$GPGLL,4916.45,N,12311.12,W,225444,A,*1D`.
+    #[test]
+    fn test_checksum_reference() {
+        assert_eq!(checksum("GPGLL,4916.45,N,12311.12,W,225444,A,"), 0x1D);
+        assert_eq!(sentence("GPGLL,4916.45,N,12311.12,W,225444,A,"), "$GPGLL,4916.45,N,12311.12,W,225444,A,*1D\r\n");
+    }
+
+    #[test]
+    fn test_lat_lon_formatting() {
+        assert_eq!(fmt_lat(41.85), ("4151.00000".to_string(), 'N'));
+        assert_eq!(fmt_lon(-87.65), ("08739.00000".to_string(), 'W'));
+        assert_eq!(fmt_lat(-0.5).1, 'S');
+        assert_eq!(fmt_deg_min(10.0 - 1e-9, 2), "1000.00000");
+    }
+
+    #[test]
+    fn test_time_and_date() {
+        // 2024-03-01T12:34:56.78Z
+        let t = 1_709_296_496.78;
+        assert_eq!(fmt_time(t), "123456.78");
+        assert_eq!(fmt_date_ddmmyy(t), "010324");
+        let zda = zda(&NmeaFix { lat_deg: 0.0, lon_deg: 0.0, alt_m: 0.0, unix_s: t });
+        assert!(zda.starts_with("$GNZDA,123456.78,01,03,2024,00,00*"), "{zda}");
+    }
+
+    #[test]
+    fn test_sv_id_from_name() {
+        assert_eq!(sv_id_from_name(CONSTELLATION_GPS, "GPS BIIR-2  (PRN 13)"), Some(13));
+        assert_eq!(sv_id_from_name(CONSTELLATION_GALILEO, "GSAT0210 (PRN E01)"), Some(1));
+        assert_eq!(sv_id_from_name(CONSTELLATION_BEIDOU, "BEIDOU-3 M1 (C19)"), Some(19));
+        assert_eq!(sv_id_from_name(CONSTELLATION_QZSS, "QZS-2 (QZSS/PRN 194)"), Some(2));
+        assert_eq!(sv_id_from_name(CONSTELLATION_GLONASS, "COSMOS 2425 (716)"), None);
+        assert_eq!(sv_id_from_name(CONSTELLATION_GPS, "NAVSTAR 68"), None);
+    }
+
+    #[test]
+    fn test_fill_sv_ids_no_duplicates() {
+        let sats = [
+            (CONSTELLATION_GPS, "GPS (PRN 01)"),
+            (CONSTELLATION_GPS, "NAVSTAR X"),
+            (CONSTELLATION_GLONASS, "COSMOS 1"),
+            (CONSTELLATION_GLONASS, "COSMOS 2"),
+        ];
+        let known: Vec<(u8, Option<u16>)> = sats.iter().map(|(c, n)| (*c, sv_id_from_name(*c, n))).collect();
+        assert_eq!(fill_sv_ids(&known), vec![Some(1), Some(2), Some(65), Some(66)]);
+    }
+
+    /// Gap fill skips known IDs; satellites beyond the range get no ID
+    /// instead of repeating the last one.
+    #[test]
+    fn test_fill_sv_ids_overflow() {
+        let mut known = vec![(CONSTELLATION_QZSS, Some(2)), (CONSTELLATION_GPS, None)];
+        known.extend((0..10).map(|_| (CONSTELLATION_QZSS, None)));
+        let ids = fill_sv_ids(&known);
+        assert_eq!(ids[..2], [Some(2), Some(1)]);
+        assert_eq!(ids[2..11], [1, 3, 4, 5, 6, 7, 8, 9, 10].map(Some));
+        assert_eq!(ids[11], None);
+    }
+
+    #[test]
+    fn test_gsv_paging_and_talkers() {
+        let sats: Vec<NmeaSat> = (1..=6)
+            .map(|i| NmeaSat {
+                constellation: if i <= 5 { CONSTELLATION_GPS } else { CONSTELLATION_GALILEO },
+                sv_id: i,
+                az_deg: 10.0 * i as f64,
+                el_deg: 30.0,
+                snr_dbhz: if i == 3 { None } else { Some(42.4) },
+                used: true,
+            })
+            .collect();
+        let lines = gsv(&sats);
+        assert_eq!(lines.len(), 3); // 2 × GPGSV + 1 × GAGSV
+        assert!(lines[0].starts_with("$GPGSV,2,1,05,01,30,010,42,02,30,020,42,03,30,030,,04,30,040,42,1*"), "{}", lines[0]);
+        assert!(lines[2].starts_with("$GAGSV,1,1,01,06,30,060,42,7*"), "{}", lines[2]);
+        assert_valid_lines(&lines.concat());
+    }
+
+    #[test]
+    fn test_generate_full_block() {
+        let fix = NmeaFix { lat_deg: 41.85, lon_deg: -87.65, alt_m: 180.0, unix_s: 1_709_296_496.0 };
+        let sats: Vec<NmeaSat> = [(0.0, 90.0), (0.0, 10.0), (120.0, 10.0), (240.0, 10.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(az, el))| NmeaSat {
+                constellation: CONSTELLATION_GPS,
+                sv_id: i as u16 + 1,
+                az_deg: az,
+                el_deg: el,
+                snr_dbhz: Some(45.0),
+                used: true,
+            })
+            .collect();
+        let azel: Vec<(f64, f64)> = sats.iter().map(|s| (s.az_deg, s.el_deg)).collect();
+        let dop = crate::dop::compute(&azel).unwrap();
+        let block = generate(&fix, &sats, Some(&dop));
+        assert_valid_lines(&block);
+        assert!(block.starts_with("$GNGGA,123456.00,4151.00000,N,08739.00000,W,1,04,"));
+        assert!(block.contains("$GNRMC,123456.00,A,"));
+        assert!(block.contains("$GNGSA,A,3,01,02,03,04,,,,,,,,,"));
+        assert!(block.contains("$GPGSV,1,1,04,"));
+        assert!(block.contains("$GNZDA,"));
+    }
+
+    #[test]
+    fn test_no_fix_block() {
+        let fix = NmeaFix { lat_deg: 0.0, lon_deg: 0.0, alt_m: 0.0, unix_s: 0.0 };
+        let block = generate(&fix, &[], None);
+        assert_valid_lines(&block);
+        assert!(block.contains("$GNGGA,000000.00,0000.00000,N,00000.00000,E,0,00,,0.0,M"));
+        assert!(block.contains("$GNRMC,000000.00,V,"));
+        assert!(block.contains("$GNGSA,A,1,,,,,,,,,,,,,,,,1*"));
+    }
+
+    /// Generated sentences must parse back to the same values.
+    #[test]
+    fn test_parse_round_trip() {
+        let fix = NmeaFix { lat_deg: -33.5, lon_deg: 151.25, alt_m: 12.0, unix_s: 1_709_296_496.5 };
+        let (lat, lon) = match parse_sentence(&rmc(&fix, true)).unwrap() {
+            Sentence::Rmc { tod_s, date, pos } => {
+                assert!((tod_s - 45_296.5).abs() < 1e-6);
+                assert_eq!(date, NaiveDate::from_ymd_opt(2024, 3, 1));
+                pos.unwrap()
+            }
+            other => panic!("{other:?}"),
+        };
+        assert!((lat + 33.5).abs() < 1e-6 && (lon - 151.25).abs() < 1e-6);
+
+        let sats = [NmeaSat {
+            constellation: CONSTELLATION_GLONASS, sv_id: 70, az_deg: 123.0, el_deg: 45.0, snr_dbhz: Some(40.0), used: true,
+        }];
+        match parse_sentence(&gsv(&sats)[0]).unwrap() {
+            Sentence::Gsv { sats: p } => {
+                assert_eq!(p.len(), 1);
+                assert_eq!((p[0].constellation, p[0].sv_id, p[0].snr_dbhz), (CONSTELLATION_GLONASS, 70, Some(40.0)));
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_checksum() {
+        assert!(parse_sentence("$GPGLL,4916.45,N,12311.12,W,225444,A,*1D").is_none()); // unsupported type
+        assert!(parse_sentence("$GPZDA,123456.00,01,03,2024,00,00*00").is_none());
+        assert!(parse_sentence("$GPZDA,123456.00,01,03,2024,00,00").is_some());
+    }
+
+    /// Multi-byte characters in a time field are rejected, not sliced.
+    #[test]
+    fn test_parse_non_ascii_time() {
+        assert_eq!(parse_tod("1é3456"), None);
+        assert_eq!(parse_tod("12é456"), None);
+        assert!(parse_sentence("$GPZDA,1é3456.00,01,03,2024,00,00").is_none());
+    }
+
+    /// Legacy GP/GN logs put GLONASS in 65–96 and QZSS in 193–202.
+    #[test]
+    fn test_parse_legacy_id_ranges() {
+        let line = sentence("GNGSA,A,3,05,67,195,,,,,,,,,,1.5,0.9,1.2");
+        match parse_sentence(&line).unwrap() {
+            Sentence::Gsa { used } => assert_eq!(
+                used,
+                vec![(CONSTELLATION_GPS, 5), (CONSTELLATION_GLONASS, 67), (CONSTELLATION_QZSS, 3)]
+            ),
+            other => panic!("{other:?}"),
+        }
+        // No-fix GGA keeps the time but drops the position
+        match parse_sentence("$GPGGA,000001.00,,,,,0,00,,,M,,M,,").unwrap() {
+            Sentence::Gga { tod_s, pos, .. } => assert_eq!((tod_s, pos), (1.0, None)),
+            other => panic!("{other:?}"),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// passes.rs — Rise / culmination / set prediction for one satellite
+//
+// Responsibilities:
+//   - Step a look-angle function across a time window and find the
+//     intervals where the satellite is above the (per-azimuth) mask
+//   - Refine rise and set times by bisection and the culmination by
+//     ternary search, to about a second
+//
+// Pure math — no wasm_bindgen exports, no three-d. The caller supplies the
+// look angles (propagation + observer) and the mask, so the same search
+// serves TLE satellites, Walker slots and horizon profiles.
+
+use serde::Serialize;
+
+/// Time resolution of refined rise, set and culmination times, seconds.
+pub const REFINE_S: f64 = 1.0;
+
+// ---------------------------------------------------------------------------
+// Passes
+// ---------------------------------------------------------------------------
+
+/// One pass above the mask. A pass already in progress at the start of the
+/// window, or still in progress at its end, is clipped to the window and
+/// flagged.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Pass {
+    pub rise_unix: f64,
+    pub set_unix: f64,
+    pub rise_az_deg: f64,
+    pub set_az_deg: f64,
+    pub max_el_unix: f64,
+    pub max_el_deg: f64,
+    pub max_el_az_deg: f64,
+    /// Already above the mask at the window start.
+    pub rise_clipped: bool,
+    /// Still above the mask at the window end.
+    pub set_clipped: bool,
+}
+
+impl Pass {
+    pub fn duration_s(&self) -> f64 {
+        self.set_unix - self.rise_unix
+    }
+}
+
+/// Find every pass in `[start_unix, end_unix]`. `look(t)` returns the
+/// satellite's `(az_deg, el_deg)` at `t`; `mask_deg(az)` the minimum
+/// elevation at that azimuth. Passes shorter than `step_s` can be missed.
+pub fn find_passes(
+    look: impl Fn(f64) -> (f64, f64),
+    mask_deg: impl Fn(f64) -> f64,
+    start_unix: f64,
+    end_unix: f64,
+    step_s: f64,
+) -> Vec<Pass> {
+    let step_s = step_s.max(REFINE_S);
+    let up = |t: f64| {
+        let (az, el) = look(t);
+        el >= mask_deg(az)
+    };
+    // Bisect a rise (false→true) or set (true→false) between a and b
+    let edge = |mut a: f64, mut b: f64| {
+        let at_a = up(a);
+        while b - a > REFINE_S {
+            let m = 0.5 * (a + b);
+            if up(m) == at_a { a = m } else { b = m }
+        }
+        if at_a { a } else { b }
+    };
+
+    let mut out = Vec::new();
+    let mut rise: Option<(f64, bool)> = up(start_unix).then_some((start_unix, true));
+    let mut t = start_unix;
+    while t < end_unix {
+        let next = (t + step_s).min(end_unix);
+        match (rise, up(next)) {
+            (None, true) => rise = Some((edge(t, next), false)),
+            (Some((r, clipped)), false) => {
+                out.push(make_pass(&look, r, edge(t, next), clipped, false));
+                rise = None;
+            }
+            _ => {}
+        }
+        t = next;
+    }
+    if let Some((r, clipped)) = rise {
+        out.push(make_pass(&look, r, end_unix, clipped, true));
+    }
+    out
+}
+
+fn make_pass(look: &impl Fn(f64) -> (f64, f64), rise: f64, set: f64, rise_clipped: bool, set_clipped: bool) -> Pass {
+    // Elevation is unimodal over a pass; ternary search for the peak
+    let (mut a, mut b) = (rise, set);
+    while b - a > REFINE_S {
+        let (m1, m2) = (a + (b - a) / 3.0, b - (b - a) / 3.0);
+        if look(m1).1 < look(m2).1 { a = m1 } else { b = m2 }
+    }
+    let max_el_unix = 0.5 * (a + b);
+    let (max_el_az_deg, max_el_deg) = look(max_el_unix);
+    Pass {
+        rise_unix: rise,
+        set_unix: set,
+        rise_az_deg: look(rise).0,
+        set_az_deg: look(set).0,
+        max_el_unix,
+        max_el_deg,
+        max_el_az_deg,
+        rise_clipped,
+        set_clipped,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Elevation as a sine wave with a 1000 s period and ±60° amplitude,
+    /// azimuth sweeping with time.
+    fn wave(t: f64) -> (f64, f64) {
+        ((t * 0.1) % 360.0, 60.0 * (t * std::f64::consts::TAU / 1000.0).sin())
+    }
+
+    /// Rise and set land on the zero crossings; the peak at the crest.
+    #[test]
+    fn test_finds_passes() {
+        let passes = find_passes(wave, |_| 0.0, 100.0, 2100.0, 60.0);
+        assert_eq!(passes.len(), 3);
+        let p = passes[1];
+        assert!((p.rise_unix - 1000.0).abs() <= REFINE_S, "rise {}", p.rise_unix);
+        assert!((p.set_unix - 1500.0).abs() <= REFINE_S, "set {}", p.set_unix);
+        assert!((p.max_el_unix - 1250.0).abs() <= REFINE_S);
+        assert!((p.max_el_deg - 60.0).abs() < 0.01);
+        assert!((p.duration_s() - 500.0).abs() <= 2.0 * REFINE_S);
+        assert!(!p.rise_clipped && !p.set_clipped);
+    }
+
+    /// Passes cut by the window edges are clipped and flagged.
+    #[test]
+    fn test_clipped_passes() {
+        let passes = find_passes(wave, |_| 0.0, 100.0, 2100.0, 60.0);
+        assert!(passes[0].rise_clipped && passes[0].rise_unix == 100.0);
+        assert!(passes[2].set_clipped && passes[2].set_unix == 2100.0);
+    }
+
+    /// A higher mask shortens the pass symmetrically; a mask above the
+    /// peak removes it.
+    #[test]
+    fn test_mask() {
+        let passes = find_passes(wave, |_| 30.0, 900.0, 1600.0, 30.0);
+        assert_eq!(passes.len(), 1);
+        // sin = 0.5 at 1/12 and 5/12 of the period
+        assert!((passes[0].rise_unix - 1083.3).abs() <= 1.5);
+        assert!((passes[0].set_unix - 1416.7).abs() <= 1.5);
+        assert!(find_passes(wave, |_| 70.0, 0.0, 2000.0, 30.0).is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+// replay.rs — Recorded NMEA log replay and observed-vs-predicted residuals
+//
+// Responsibilities:
+//   - Group parsed GGA / RMC / ZDA / GSA / GSV sentences into timed epochs
+//   - Resolve absolute UTC times (date from RMC/ZDA, midnight rollover)
+//   - Carry the recorded receiver track so the viewer can move the Observer
+//   - Match observed GSV satellites against predicted sky satellites and
+//     report az / el / C/N0 residuals, with CSV export
+//
+// Pure data — no wasm_bindgen exports, no three-d. Predictions are passed in
+// as `nmea::NmeaSat` so both sides share the same (constellation, sv_id) keys.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::nmea::{self, NmeaSat, Sentence};
+
+/// Two time tags closer than this (s) belong to the same epoch.
+const SAME_EPOCH_S: f64 = 1e-3;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+// ---------------------------------------------------------------------------
+// Log → epochs
+// ---------------------------------------------------------------------------
+
+/// One receiver output epoch.
+#[derive(Clone, Debug)]
+pub struct ReplayEpoch {
+    /// UTC Unix timestamp, seconds.
+    pub unix_s: f64,
+    /// Receiver position, carried forward from the last fix when this epoch
+    /// has none. `None` only before the first fix in the log.
+    pub pos: Option<(f64, f64)>,
+    /// Satellites in view (GSV) with `used` set from GSA.
+    pub sats: Vec<NmeaSat>,
+}
+
+/// A parsed NMEA log, epochs in time order.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayLog {
+    pub epochs: Vec<ReplayEpoch>,
+}
+
+/// Epoch under construction: time of day plus the date if one was seen.
+#[derive(Default)]
+struct Pending {
+    tod_s: Option<f64>,
+    date: Option<NaiveDate>,
+    pos: Option<(f64, f64)>,
+    sats: Vec<NmeaSat>,
+    used: Vec<(u8, u16)>,
+}
+
+impl ReplayLog {
+    /// Parse a log of NMEA sentences.
+    ///
+    /// GSV/GSA are attached to the most recent time tag (receivers emit
+    /// GGA/RMC first in each epoch). Epochs without an RMC/ZDA date use the
+    /// last known date, or `fallback_day_unix` (any instant on the day) if the
+    /// log carries no date at all; a backwards jump in time of day is taken as
+    /// a midnight rollover. Lines that fail to parse are skipped.
+    pub fn parse(text: &str, fallback_day_unix: f64) -> Self {
+        let mut pending: Vec<Pending> = Vec::new();
+        let mut cur = Pending::default();
+
+        for s in text.lines().filter_map(nmea::parse_sentence) {
+            let tod = match &s {
+                Sentence::Gga { tod_s, .. } | Sentence::Rmc { tod_s, .. } | Sentence::Zda { tod_s, .. } => Some(*tod_s),
+                _ => None,
+            };
+            if let Some(t) = tod {
+                match cur.tod_s {
+                    Some(prev) if (prev - t).abs() > SAME_EPOCH_S => {
+                        pending.push(std::mem::take(&mut cur));
+                        cur.tod_s = Some(t);
+                    }
+                    None => cur.tod_s = Some(t),
+                    _ => {}
+                }
+            }
+            match s {
+                Sentence::Gga { pos, .. } => cur.pos = pos.or(cur.pos),
+                Sentence::Rmc { date, pos, .. } => {
+                    cur.date = date.or(cur.date);
+                    cur.pos = cur.pos.or(pos);
+                }
+                Sentence::Zda { date, .. } => cur.date = date.or(cur.date),
+                Sentence::Gsa { used } => cur.used.extend(used),
+                Sentence::Gsv { sats } => {
+                    for sat in sats {
+                        // NMEA 4.11 repeats a satellite once per signal; keep
+                        // the first report, filling SNR from later ones.
+                        match cur.sats.iter_mut().find(|o| o.constellation == sat.constellation && o.sv_id == sat.sv_id) {
+                            Some(o) => o.snr_dbhz = o.snr_dbhz.or(sat.snr_dbhz),
+                            None => cur.sats.push(sat),
+                        }
+                    }
+                }
+            }
+        }
+        pending.push(cur);
+
+        Self { epochs: resolve_times(pending, fallback_day_unix) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    /// Index of the last epoch at or before `unix_s` (the first epoch if
+    /// `unix_s` precedes the log).
+    pub fn index_at(&self, unix_s: f64) -> Option<usize> {
+        if self.epochs.is_empty() {
+            return None;
+        }
+        let n = self.epochs.partition_point(|e| e.unix_s <= unix_s);
+        Some(n.saturating_sub(1))
+    }
+}
+
+/// Turn time-of-day epochs into absolute Unix times and carry position forward.
+fn resolve_times(pending: Vec<Pending>, fallback_day_unix: f64) -> Vec<ReplayEpoch> {
+    let first_date = pending.iter().find_map(|p| p.date);
+    let fallback_midnight = (fallback_day_unix / SECONDS_PER_DAY).floor() * SECONDS_PER_DAY;
+    let mut midnight = first_date.map(date_to_unix).unwrap_or(fallback_midnight);
+    let mut last_tod = f64::NEG_INFINITY;
+    let mut last_pos = None;
+
+    let mut out = Vec::new();
+    for p in pending {
+        let Some(tod) = p.tod_s else { continue };
+        match p.date {
+            Some(d) => midnight = date_to_unix(d),
+            // Half a day backwards with no date update → crossed midnight
+            None if tod < last_tod - SECONDS_PER_DAY / 2.0 => midnight += SECONDS_PER_DAY,
+            None => {}
+        }
+        last_tod = tod;
+        last_pos = p.pos.or(last_pos);
+
+        let mut sats = p.sats;
+        for s in sats.iter_mut() {
+            s.used = p.used.contains(&(s.constellation, s.sv_id));
+        }
+        out.push(ReplayEpoch { unix_s: midnight + tod, pos: last_pos, sats });
+    }
+    out.sort_by(|a, b| a.unix_s.total_cmp(&b.unix_s));
+    out
+}
+
+fn date_to_unix(d: NaiveDate) -> f64 {
+    d.and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp() as f64)
+        .unwrap_or(0.0)
+}
+
+// ---------------------------------------------------------------------------
+// Residuals
+// ---------------------------------------------------------------------------
+
+/// Observed-vs-predicted comparison for one satellite at one epoch.
+/// Residuals are observed − predicted; `None` when the satellite has no
+/// prediction (or no SNR on either side for `d_c_n0`).
+#[derive(Serialize, Clone, Debug)]
+pub struct Residual {
+    pub unix_s: f64,
+    pub constellation: u8,
+    pub sv_id: u16,
+    pub used: bool,
+    pub obs_az_deg: f64,
+    pub obs_el_deg: f64,
+    pub obs_snr_dbhz: Option<f64>,
+    pub pred_az_deg: Option<f64>,
+    pub pred_el_deg: Option<f64>,
+    pub pred_c_n0: Option<f64>,
+    pub d_az_deg: Option<f64>,
+    pub d_el_deg: Option<f64>,
+    pub d_c_n0: Option<f64>,
+}
+
+/// Match each observed satellite to the prediction with the same
+/// `(constellation, sv_id)` and compute residuals. Azimuth residuals are
+/// wrapped to [-180, 180).
+pub fn residuals(unix_s: f64, observed: &[NmeaSat], predicted: &[NmeaSat]) -> Vec<Residual> {
+    observed
+        .iter()
+        .map(|o| {
+            let p = predicted
+                .iter()
+                .find(|p| p.constellation == o.constellation && p.sv_id == o.sv_id);
+            let pred_c_n0 = p.and_then(|p| p.snr_dbhz);
+            Residual {
+                unix_s,
+                constellation: o.constellation,
+                sv_id: o.sv_id,
+                used: o.used,
+                obs_az_deg: o.az_deg,
+                obs_el_deg: o.el_deg,
+                obs_snr_dbhz: o.snr_dbhz,
+                pred_az_deg: p.map(|p| p.az_deg),
+                pred_el_deg: p.map(|p| p.el_deg),
+                pred_c_n0,
+                d_az_deg: p.map(|p| (o.az_deg - p.az_deg + 180.0).rem_euclid(360.0) - 180.0),
+                d_el_deg: p.map(|p| o.el_deg - p.el_deg),
+                d_c_n0: o.snr_dbhz.zip(pred_c_n0).map(|(a, b)| a - b),
+            }
+        })
+        .collect()
+}
+
+/// CSV with a header row; missing values are empty fields.
+pub fn residuals_csv(rows: &[Residual]) -> String {
+    fn opt(v: Option<f64>) -> String {
+        v.map(|x| format!("{x:.2}")).unwrap_or_default()
+    }
+    let mut out = String::from(
+        "unix_s,constellation,sv_id,used,obs_az_deg,obs_el_deg,obs_snr_dbhz,\
+         pred_az_deg,pred_el_deg,pred_c_n0,d_az_deg,d_el_deg,d_c_n0\n",
+    );
+    for r in rows {
+        out.push_str(&format!(
+            "{:.3},{},{},{},{:.2},{:.2},{},{},{},{},{},{},{}\n",
+            r.unix_s,
+            r.constellation,
+            r.sv_id,
+            r.used as u8,
+            r.obs_az_deg,
+            r.obs_el_deg,
+            opt(r.obs_snr_dbhz),
+            opt(r.pred_az_deg),
+            opt(r.pred_el_deg),
+            opt(r.pred_c_n0),
+            opt(r.d_az_deg),
+            opt(r.d_el_deg),
+            opt(r.d_c_n0),
+        ));
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmea::sentence;
+    use crate::tles::{CONSTELLATION_GALILEO, CONSTELLATION_GPS};
+
+    fn sat(c: u8, id: u16, az: f64, el: f64, snr: Option<f64>) -> NmeaSat {
+        NmeaSat { constellation: c, sv_id: id, az_deg: az, el_deg: el, snr_dbhz: snr, used: false }
+    }
+
+    /// Two epochs, GSV/GSA attached to their epoch, date from RMC.
+    #[test]
+    fn test_epoch_grouping() {
+        let log = [
+            sentence("GPGGA,120000.00,4151.00000,N,08739.00000,W,1,04,1.0,180.0,M,0.0,M,,"),
+            sentence("GPRMC,120000.00,A,4151.00000,N,08739.00000,W,0.0,0.0,010324,,,A"),
+            sentence("GNGSA,A,3,05,,,,,,,,,,,,1.5,1.0,1.1,1"),
+            sentence("GPGSV,1,1,02,05,45,090,40,07,10,200,,1"),
+            sentence("GAGSV,1,1,01,11,30,300,38,7"),
+            sentence("GPGGA,120001.00,4151.00060,N,08739.00000,W,1,04,1.0,180.0,M,0.0,M,,"),
+            sentence("GPGSV,1,1,01,05,45,091,41,1"),
+        ]
+        .concat();
+        let r = ReplayLog::parse(&log, 0.0);
+        assert_eq!(r.epochs.len(), 2);
+        let e0 = &r.epochs[0];
+        assert_eq!(e0.unix_s, 1_709_294_400.0); // 2024-03-01T12:00:00Z
+        assert_eq!(e0.sats.len(), 3);
+        assert!(e0.sats[0].used && !e0.sats[1].used);
+        assert_eq!(e0.sats[2].constellation, CONSTELLATION_GALILEO);
+        assert_eq!(r.epochs[1].unix_s, 1_709_294_401.0);
+        assert!(r.epochs[1].pos.unwrap().0 > e0.pos.unwrap().0);
+    }
+
+    /// Without any date the fallback day is used, and a backwards jump in
+    /// time of day rolls over to the next day. Position carries forward.
+    #[test]
+    fn test_fallback_date_and_rollover() {
+        let log = [
+            sentence("GPGGA,235959.00,0100.00000,N,00200.00000,E,1,04,1.0,0.0,M,0.0,M,,"),
+            sentence("GPGGA,000000.00,,,,,0,00,,,M,,M,,"),
+        ]
+        .concat();
+        let day = 1_709_294_400.0 - 12.0 * 3600.0; // 2024-03-01T00:00Z
+        let r = ReplayLog::parse(&log, day + 5000.0);
+        assert_eq!(r.epochs[0].unix_s, day + 86_399.0);
+        assert_eq!(r.epochs[1].unix_s, day + 86_400.0);
+        assert_eq!(r.epochs[1].pos, r.epochs[0].pos);
+    }
+
+    #[test]
+    fn test_index_at() {
+        let r = ReplayLog {
+            epochs: [10.0, 20.0, 30.0]
+                .iter()
+                .map(|&t| ReplayEpoch { unix_s: t, pos: None, sats: Vec::new() })
+                .collect(),
+        };
+        assert_eq!(r.index_at(5.0), Some(0));
+        assert_eq!(r.index_at(20.0), Some(1));
+        assert_eq!(r.index_at(29.9), Some(1));
+        assert_eq!(r.index_at(99.0), Some(2));
+        assert_eq!(ReplayLog::default().index_at(0.0), None);
+    }
+
+    #[test]
+    fn test_residuals_wrap_and_missing() {
+        let obs = [sat(CONSTELLATION_GPS, 5, 1.0, 40.0, Some(42.0)), sat(CONSTELLATION_GPS, 9, 10.0, 10.0, None)];
+        let pred = [sat(CONSTELLATION_GPS, 5, 359.0, 41.5, Some(45.0))];
+        let r = residuals(0.0, &obs, &pred);
+        assert!((r[0].d_az_deg.unwrap() - 2.0).abs() < 1e-9);
+        assert!((r[0].d_el_deg.unwrap() + 1.5).abs() < 1e-9);
+        assert!((r[0].d_c_n0.unwrap() + 3.0).abs() < 1e-9);
+        assert!(r[1].pred_az_deg.is_none() && r[1].d_c_n0.is_none());
+
+        let csv = residuals_csv(&r);
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.lines().nth(2).unwrap().ends_with(",,,,,,,"), "{csv}");
+    }
+}
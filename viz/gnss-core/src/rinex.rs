@@ -0,0 +1,445 @@
+// rinex.rs — RINEX 3 observation file reader and tracked-vs-predicted comparison
+//
+// Responsibilities:
+//   - Parse the RINEX 3.0x observation header (version, SYS / # / OBS TYPES,
+//     APPROX POSITION XYZ, time system) and epoch records
+//   - Extract per epoch and satellite which signals were tracked and their
+//     S (C/N0) observables
+//   - Compare tracked satellites against predicted-visible satellites
+//   - Accumulate per-satellite tracking availability over the whole file
+//
+// Pure parsing — no wasm_bindgen exports, no three-d. Satellite IDs use the
+// NMEA numbering from nmea.rs (GLONASS slot + 64, QZSS PRN − 192) so the
+// comparison can key on the same `(constellation, sv_id)` as replay.rs.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::nmea::NmeaSat;
+use crate::tles::{
+    CONSTELLATION_BEIDOU, CONSTELLATION_GALILEO, CONSTELLATION_GLONASS, CONSTELLATION_GPS,
+    CONSTELLATION_NAVIC, CONSTELLATION_QZSS, CONSTELLATION_SBAS,
+};
+
+/// GPS − UTC leap seconds (constant since 2017-01-01).
+const GPS_UTC_LEAP_S: f64 = 18.0;
+
+/// BDT − UTC: BeiDou time started 14 s behind GPS time.
+const BDT_UTC_LEAP_S: f64 = GPS_UTC_LEAP_S - 14.0;
+
+/// Width of one observation field: F14.3 value + LLI + signal strength.
+const OBS_FIELD_W: usize = 16;
+
+// ---------------------------------------------------------------------------
+// Parsed file
+// ---------------------------------------------------------------------------
+
+/// One tracked signal (RINEX attribute code such as `1C`, `5Q`, `2I`).
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TrackedSignal {
+    pub code: String,
+    /// `S` observable for this signal in dB-Hz, if recorded.
+    pub s_dbhz: Option<f64>,
+}
+
+/// Signals tracked for one satellite in one epoch.
+#[derive(Clone, Debug)]
+pub struct TrackedSat {
+    pub constellation: u8,
+    pub sv_id: u16,
+    pub signals: Vec<TrackedSignal>,
+}
+
+/// One observation epoch.
+#[derive(Clone, Debug)]
+pub struct RinexEpoch {
+    /// Epoch time converted to UTC Unix seconds.
+    pub unix_s: f64,
+    pub sats: Vec<TrackedSat>,
+}
+
+/// A parsed RINEX 3 observation file.
+#[derive(Clone, Debug, Default)]
+pub struct RinexObs {
+    pub version: f64,
+    /// Observation type codes per system letter, e.g. `'G' → ["C1C", "L1C", "S1C"]`.
+    pub obs_types: BTreeMap<char, Vec<String>>,
+    /// Receiver position from `APPROX POSITION XYZ`, metres ECEF.
+    pub approx_pos_m: Option<[f64; 3]>,
+    pub epochs: Vec<RinexEpoch>,
+}
+
+impl RinexObs {
+    /// Parse RINEX 3 observation text.
+    ///
+    /// Event epochs (flags 2–5) and cycle-slip records (flag 6) are skipped.
+    /// Satellites from systems the visualiser does not model (LEO) are
+    /// dropped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let mut obs = RinexObs::default();
+        let mut time_offset_s = GPS_UTC_LEAP_S;
+        let mut last_sys = None;
+
+        // ── Header ────────────────────────────────────────────────────────
+        loop {
+            let line = lines.next().ok_or("missing END OF HEADER")?;
+            let label = line.get(60..).unwrap_or("").trim();
+            match label {
+                "RINEX VERSION / TYPE" => {
+                    obs.version = col(line, 0, 9).parse().map_err(|_| "bad RINEX version")?;
+                    if obs.version < 3.0 {
+                        return Err(format!("RINEX {} not supported (need 3.x)", obs.version));
+                    }
+                    if col(line, 20, 21) != "O" {
+                        return Err("not an observation file".into());
+                    }
+                }
+                "SYS / # / OBS TYPES" => {
+                    // Continuation lines leave the system letter blank
+                    let sys = line.chars().next().filter(|c| *c != ' ').or(last_sys);
+                    let Some(sys) = sys else { continue };
+                    last_sys = Some(sys);
+                    let types = obs.obs_types.entry(sys).or_default();
+                    types.extend(col(line, 7, 60).split_whitespace().map(str::to_string));
+                }
+                "APPROX POSITION XYZ" => {
+                    let v: Vec<f64> = col(line, 0, 60).split_whitespace().filter_map(|f| f.parse().ok()).collect();
+                    if let [x, y, z] = v[..] {
+                        if x != 0.0 || y != 0.0 || z != 0.0 {
+                            obs.approx_pos_m = Some([x, y, z]);
+                        }
+                    }
+                }
+                "TIME OF FIRST OBS" => {
+                    time_offset_s = match col(line, 48, 51) {
+                        "GLO" | "UTC" => 0.0,
+                        "BDT" => BDT_UTC_LEAP_S,
+                        _ => GPS_UTC_LEAP_S, // GPS, GAL, QZS, IRN share GPS time
+                    };
+                }
+                "END OF HEADER" => break,
+                _ => {}
+            }
+        }
+        if obs.obs_types.is_empty() {
+            return Err("no SYS / # / OBS TYPES in header".into());
+        }
+
+        // ── Epoch records ─────────────────────────────────────────────────
+        while let Some(line) = lines.next() {
+            if !line.starts_with('>') {
+                continue;
+            }
+            let flag: u8 = col(line, 31, 32).parse().unwrap_or(0);
+            let n: usize = col(line, 32, 35).parse().unwrap_or(0);
+            let records: Vec<&str> = lines.by_ref().take(n).collect();
+            if flag > 1 {
+                continue;
+            }
+            let Some(t) = parse_epoch_time(line) else { continue };
+
+            let sats = records.iter().filter_map(|r| parse_sat_record(r, &obs.obs_types)).collect();
+            obs.epochs.push(RinexEpoch { unix_s: t - time_offset_s, sats });
+        }
+        Ok(obs)
+    }
+
+    /// Receiver (lat°, lon°) from the header position, if given.
+    pub fn approx_lat_lon(&self) -> Option<(f64, f64)> {
+        self.approx_pos_m.map(crate::coords::ecef_to_geodetic_deg)
+    }
+
+    /// Index of the last epoch at or before `unix_s` (the first epoch if
+    /// `unix_s` precedes the file).
+    pub fn index_at(&self, unix_s: f64) -> Option<usize> {
+        if self.epochs.is_empty() {
+            return None;
+        }
+        Some(self.epochs.partition_point(|e| e.unix_s <= unix_s).saturating_sub(1))
+    }
+}
+
+/// Fixed-column slice, trimmed; empty when the line is shorter.
+fn col(line: &str, start: usize, end: usize) -> &str {
+    let end = end.min(line.len());
+    line.get(start..end).unwrap_or("").trim()
+}
+
+/// `> yyyy mm dd hh mm ss.sssssss` → Unix seconds in the file's time system.
+fn parse_epoch_time(line: &str) -> Option<f64> {
+    let f: Vec<&str> = col(line, 1, 29).split_whitespace().collect();
+    let [y, mo, d, h, mi, s] = f[..] else { return None };
+    let date = chrono::NaiveDate::from_ymd_opt(y.parse().ok()?, mo.parse().ok()?, d.parse().ok()?)?;
+    let midnight = date.and_hms_opt(0, 0, 0)?.and_utc().timestamp() as f64;
+    let h: f64 = h.parse().ok()?;
+    let mi: f64 = mi.parse().ok()?;
+    let s: f64 = s.parse().ok()?;
+    Some(midnight + h * 3600.0 + mi * 60.0 + s)
+}
+
+/// Map a RINEX system letter and PRN to `(constellation, NMEA sv_id)`.
+pub fn sat_key(sys: char, prn: u16) -> Option<(u8, u16)> {
+    match sys {
+        'G' => Some((CONSTELLATION_GPS, prn)),
+        'R' => Some((CONSTELLATION_GLONASS, prn.checked_add(64)?)),
+        'E' => Some((CONSTELLATION_GALILEO, prn)),
+        'C' => Some((CONSTELLATION_BEIDOU, prn)),
+        // RINEX J01 is PRN 193, which NMEA numbers 1
+        'J' => Some((CONSTELLATION_QZSS, prn)),
+        'I' => Some((CONSTELLATION_NAVIC, prn)),
+        // RINEX S20 is SBAS PRN 120; keep the RINEX number
+        'S' => Some((CONSTELLATION_SBAS, prn)),
+        _ => None,
+    }
+}
+
+/// One satellite line: `Snn` followed by 16-column observation fields in the
+/// order declared for that system. A signal counts as tracked when any of its
+/// observables (C / L / D / S) is present.
+fn parse_sat_record(line: &str, types: &BTreeMap<char, Vec<String>>) -> Option<TrackedSat> {
+    let sys = line.chars().next()?;
+    let prn: u16 = col(line, 1, 3).parse().ok()?;
+    let (constellation, sv_id) = sat_key(sys, prn)?;
+    let types = types.get(&sys)?;
+
+    let mut signals: Vec<TrackedSignal> = Vec::new();
+    for (i, t) in types.iter().enumerate() {
+        let start = 3 + i * OBS_FIELD_W;
+        let Ok(value) = col(line, start, start + 14).parse::<f64>() else { continue };
+        let Some(code) = t.get(1..3) else { continue };
+        let idx = match signals.iter().position(|s| s.code == code) {
+            Some(idx) => idx,
+            None => {
+                signals.push(TrackedSignal { code: code.to_string(), s_dbhz: None });
+                signals.len() - 1
+            }
+        };
+        if t.starts_with('S') {
+            signals[idx].s_dbhz = Some(value);
+        }
+    }
+    (!signals.is_empty()).then_some(TrackedSat { constellation, sv_id, signals })
+}
+
+// ---------------------------------------------------------------------------
+// Tracked vs predicted
+// ---------------------------------------------------------------------------
+
+/// How a satellite appears in one epoch.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackStatus {
+    /// Predicted visible and tracked.
+    Both,
+    /// Predicted visible but absent from the RINEX epoch.
+    PredictedOnly,
+    /// Tracked although not predicted visible (below mask, wrong TLE, …).
+    TrackedOnly,
+}
+
+/// One sky-plot overlay row.
+#[derive(Serialize, Clone, Debug)]
+pub struct TrackRow {
+    pub constellation: u8,
+    pub sv_id: u16,
+    pub status: TrackStatus,
+    /// Predicted position, when the satellite is above the horizon.
+    pub az_deg: Option<f64>,
+    pub el_deg: Option<f64>,
+    pub signals: Vec<TrackedSignal>,
+}
+
+/// Compare one RINEX epoch with predicted satellites. `predicted` holds
+/// every satellite above the horizon; `used` marks predicted-visible ones.
+pub fn compare(epoch: &RinexEpoch, predicted: &[NmeaSat]) -> Vec<TrackRow> {
+    let find = |c: u8, id: u16| predicted.iter().find(|p| p.constellation == c && p.sv_id == id);
+    let mut rows: Vec<TrackRow> = epoch
+        .sats
+        .iter()
+        .map(|t| {
+            let p = find(t.constellation, t.sv_id);
+            TrackRow {
+                constellation: t.constellation,
+                sv_id: t.sv_id,
+                status: if p.is_some_and(|p| p.used) { TrackStatus::Both } else { TrackStatus::TrackedOnly },
+                az_deg: p.map(|p| p.az_deg),
+                el_deg: p.map(|p| p.el_deg),
+                signals: t.signals.clone(),
+            }
+        })
+        .collect();
+    for p in predicted.iter().filter(|p| p.used) {
+        let tracked = epoch.sats.iter().any(|t| t.constellation == p.constellation && t.sv_id == p.sv_id);
+        if !tracked {
+            rows.push(TrackRow {
+                constellation: p.constellation,
+                sv_id: p.sv_id,
+                status: TrackStatus::PredictedOnly,
+                az_deg: Some(p.az_deg),
+                el_deg: Some(p.el_deg),
+                signals: Vec::new(),
+            });
+        }
+    }
+    rows
+}
+
+/// Tracking statistics for one satellite over a file.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SatAvailability {
+    pub constellation: u8,
+    pub sv_id: u16,
+    pub epochs_predicted: u32,
+    pub epochs_tracked: u32,
+    pub epochs_both: u32,
+    /// `epochs_both / epochs_predicted`, or 0 if never predicted visible.
+    pub availability: f64,
+    /// Mean S observable over tracked epochs (first signal with S), dB-Hz.
+    pub mean_s_dbhz: Option<f64>,
+}
+
+/// Accumulate per-satellite availability over compared epochs.
+pub fn availability<'a>(epochs: impl IntoIterator<Item = &'a [TrackRow]>) -> Vec<SatAvailability> {
+    let mut map: BTreeMap<(u8, u16), (SatAvailability, f64, u32)> = BTreeMap::new();
+    for rows in epochs {
+        for r in rows {
+            let (a, s_sum, s_n) = map.entry((r.constellation, r.sv_id)).or_insert_with(|| {
+                (SatAvailability { constellation: r.constellation, sv_id: r.sv_id, ..Default::default() }, 0.0, 0)
+            });
+            match r.status {
+                TrackStatus::Both => {
+                    a.epochs_predicted += 1;
+                    a.epochs_tracked += 1;
+                    a.epochs_both += 1;
+                }
+                TrackStatus::PredictedOnly => a.epochs_predicted += 1,
+                TrackStatus::TrackedOnly => a.epochs_tracked += 1,
+            }
+            if let Some(s) = r.signals.iter().find_map(|s| s.s_dbhz) {
+                *s_sum += s;
+                *s_n += 1;
+            }
+        }
+    }
+    map.into_values()
+        .map(|(mut a, s_sum, s_n)| {
+            if a.epochs_predicted > 0 {
+                a.availability = a.epochs_both as f64 / a.epochs_predicted as f64;
+            }
+            a.mean_s_dbhz = (s_n > 0).then(|| s_sum / s_n as f64);
+            a
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pad header content to 60 columns and append the label.
+    fn hdr(content: &str, label: &str) -> String {
+        format!("{content:<60}{label}\n")
+    }
+
+    /// Format one satellite line from optional observation values.
+    fn sat_line(id: &str, vals: &[Option<f64>]) -> String {
+        let mut s = id.to_string();
+        for v in vals {
+            match v {
+                Some(v) => s.push_str(&format!("{v:14.3}  ")),
+                None => s.push_str(&" ".repeat(OBS_FIELD_W)),
+            }
+        }
+        s.trim_end().to_string() + "\n"
+    }
+
+    fn sample() -> String {
+        let mut t = String::new();
+        t += &hdr("     3.04           OBSERVATION DATA    M                   ", "RINEX VERSION / TYPE");
+        t += &hdr("  4431862.0000  -641395.0000  4521340.0000", "APPROX POSITION XYZ");
+        t += &hdr("G    4 C1C L1C S1C S5Q", "SYS / # / OBS TYPES");
+        t += &hdr("R    2 C1C S1C", "SYS / # / OBS TYPES");
+        t += &hdr("  2024     3     1     0     0    0.0000000     GPS", "TIME OF FIRST OBS");
+        t += &hdr("", "END OF HEADER");
+        t += "> 2024 03 01 00 00 18.0000000  0  3\n";
+        t += &sat_line("G05", &[Some(2.1e7), Some(1.1e8), Some(45.0), Some(48.5)]);
+        t += &sat_line("G07", &[Some(2.2e7), None, None, None]);
+        t += &sat_line("R03", &[Some(2.0e7), Some(40.0)]);
+        t += "> 2024 03 01 00 00 30.0000000  4  1\n";
+        t += "  EVENT COMMENT                                              COMMENT\n";
+        t += "> 2024 03 01 00 00 48.0000000  0  1\n";
+        t += &sat_line("G05", &[Some(2.1e7), Some(1.1e8), Some(44.0)]);
+        t
+    }
+
+    #[test]
+    fn test_parse_header_and_epochs() {
+        let o = RinexObs::parse(&sample()).unwrap();
+        assert_eq!(o.version, 3.04);
+        assert_eq!(o.obs_types[&'G'], vec!["C1C", "L1C", "S1C", "S5Q"]);
+        assert_eq!(o.approx_pos_m, Some([4431862.0, -641395.0, 4521340.0]));
+        // Event epoch skipped, GPS time shifted to UTC
+        assert_eq!(o.epochs.len(), 2);
+        assert_eq!(o.epochs[0].unix_s, 1_709_251_200.0);
+        assert_eq!(o.epochs[1].unix_s, 1_709_251_230.0);
+    }
+
+    #[test]
+    fn test_signals_and_s_observables() {
+        let o = RinexObs::parse(&sample()).unwrap();
+        let e = &o.epochs[0];
+        assert_eq!(e.sats.len(), 3);
+        let g05 = &e.sats[0];
+        assert_eq!(
+            g05.signals,
+            vec![
+                TrackedSignal { code: "1C".into(), s_dbhz: Some(45.0) },
+                TrackedSignal { code: "5Q".into(), s_dbhz: Some(48.5) },
+            ]
+        );
+        // Pseudorange only → tracked, no S
+        assert_eq!(e.sats[1].signals, vec![TrackedSignal { code: "1C".into(), s_dbhz: None }]);
+        // GLONASS slot 3 → NMEA 67
+        assert_eq!((e.sats[2].constellation, e.sats[2].sv_id), (CONSTELLATION_GLONASS, 67));
+    }
+
+    #[test]
+    fn test_rejects_rinex2_and_nav() {
+        let v2 = hdr("     2.11           OBSERVATION DATA    G", "RINEX VERSION / TYPE");
+        assert!(RinexObs::parse(&v2).is_err());
+        let nav = hdr("     3.04           N: GNSS NAV DATA    M", "RINEX VERSION / TYPE");
+        assert!(RinexObs::parse(&nav).is_err());
+    }
+
+    /// G05 both, G07 tracked but predicted below mask, G09 predicted only.
+    #[test]
+    fn test_compare_and_availability() {
+        let o = RinexObs::parse(&sample()).unwrap();
+        let pred = |id: u16, used: bool| NmeaSat {
+            constellation: CONSTELLATION_GPS, sv_id: id, az_deg: 90.0, el_deg: 30.0, snr_dbhz: None, used,
+        };
+        let predicted = [pred(5, true), pred(7, false), pred(9, true)];
+        let rows0 = compare(&o.epochs[0], &predicted);
+        let status = |rows: &[TrackRow], id: u16| {
+            rows.iter().find(|r| r.constellation == CONSTELLATION_GPS && r.sv_id == id).unwrap().status
+        };
+        assert_eq!(status(&rows0, 5), TrackStatus::Both);
+        assert_eq!(status(&rows0, 7), TrackStatus::TrackedOnly);
+        assert_eq!(status(&rows0, 9), TrackStatus::PredictedOnly);
+        assert!(rows0.iter().any(|r| r.sv_id == 67 && r.az_deg.is_none()));
+
+        let rows1 = compare(&o.epochs[1], &predicted);
+        let stats = availability([rows0.as_slice(), rows1.as_slice()]);
+        let g05 = stats.iter().find(|a| a.constellation == CONSTELLATION_GPS && a.sv_id == 5).unwrap();
+        assert_eq!((g05.epochs_predicted, g05.epochs_both), (2, 2));
+        assert_eq!(g05.mean_s_dbhz, Some(44.5));
+        let g09 = stats.iter().find(|a| a.constellation == CONSTELLATION_GPS && a.sv_id == 9).unwrap();
+        assert_eq!((g09.epochs_predicted, g09.epochs_tracked, g09.availability), (2, 0, 0.0));
+    }
+}
@@ -0,0 +1,138 @@
+// satbuf.rs — Contiguous satellite position storage and render bucketing
+//
+// Responsibilities:
+//   - Hold propagated satellite state as structure-of-arrays (constellation
+//     tags, render groups, ECEF km) so per-frame passes stream through
+//     contiguous memory and refills reuse the same allocations
+//   - Bucket satellites into render groups in a single pass, keeping each
+//     bucket's allocation from frame to frame
+//
+// Pure data — no wasm_bindgen exports, no three-d. The viewer turns the buckets
+// into instance buffers; sky data and NMEA read `SatPositions` directly.
+
+// ---------------------------------------------------------------------------
+// Positions
+// ---------------------------------------------------------------------------
+
+/// Per-satellite state, one entry per satellite in every array. In TLE
+/// mode the index matches `TleStore::records`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SatPositions {
+    /// CONSTELLATION_* index.
+    pub constellation: Vec<u8>,
+    /// Render group (viewer: constellation × orbit type).
+    pub group: Vec<u16>,
+    /// ECEF position, km.
+    pub ecef_km: Vec<[f64; 3]>,
+}
+
+impl SatPositions {
+    pub fn with_capacity(n: usize) -> Self {
+        SatPositions {
+            constellation: Vec::with_capacity(n),
+            group: Vec::with_capacity(n),
+            ecef_km: Vec::with_capacity(n),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ecef_km.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ecef_km.is_empty()
+    }
+
+    /// Empty every array, keeping the allocations for the next refill.
+    pub fn clear(&mut self) {
+        self.constellation.clear();
+        self.group.clear();
+        self.ecef_km.clear();
+    }
+
+    pub fn push(&mut self, constellation: u8, group: u16, ecef_km: [f64; 3]) {
+        self.constellation.push(constellation);
+        self.group.push(group);
+        self.ecef_km.push(ecef_km);
+    }
+
+    /// `(constellation, ecef_km)` per satellite, in index order.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &[f64; 3])> {
+        self.constellation.iter().copied().zip(self.ecef_km.iter())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Bucketing
+// ---------------------------------------------------------------------------
+
+/// Clear every bucket (keeping its allocation), then make one pass over
+/// `pos`, pushing `item(i)` into the bucket of satellite `i`'s group.
+/// Satellites whose `item` is `None`, or whose group has no bucket, are
+/// skipped.
+pub fn bucket_into<T>(
+    pos: &SatPositions,
+    buckets: &mut [Vec<T>],
+    mut item: impl FnMut(usize) -> Option<T>,
+) {
+    for b in buckets.iter_mut() {
+        b.clear();
+    }
+    for (i, &g) in pos.group.iter().enumerate() {
+        let Some(bucket) = buckets.get_mut(g as usize) else { continue };
+        if let Some(x) = item(i) {
+            bucket.push(x);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SatPositions {
+        let mut p = SatPositions::with_capacity(4);
+        p.push(0, 0, [1.0, 0.0, 0.0]);
+        p.push(3, 10, [2.0, 0.0, 0.0]);
+        p.push(0, 0, [3.0, 0.0, 0.0]);
+        p.push(3, 11, [4.0, 0.0, 0.0]);
+        p
+    }
+
+    /// Arrays stay in lockstep; `iter` pairs tags with positions.
+    #[test]
+    fn test_push_and_iter() {
+        let p = sample();
+        assert_eq!(p.len(), 4);
+        let v: Vec<(u8, f64)> = p.iter().map(|(c, e)| (c, e[0])).collect();
+        assert_eq!(v, vec![(0, 1.0), (3, 2.0), (0, 3.0), (3, 4.0)]);
+    }
+
+    /// Clearing keeps capacity so the next propagation does not reallocate.
+    #[test]
+    fn test_clear_keeps_capacity() {
+        let mut p = sample();
+        let cap = p.ecef_km.capacity();
+        p.clear();
+        assert!(p.ecef_km.is_empty() && p.group.is_empty());
+        assert_eq!(p.ecef_km.capacity(), cap);
+    }
+
+    /// One pass fills every group; filtered satellites and out-of-range
+    /// groups are dropped; stale bucket contents are cleared first.
+    #[test]
+    fn test_bucket_into() {
+        let p = sample();
+        let mut buckets: Vec<Vec<f64>> = vec![vec![99.0]; 11];
+        bucket_into(&p, &mut buckets, |i| (p.ecef_km[i][0] != 3.0).then_some(p.ecef_km[i][0]));
+        assert_eq!(buckets[0], vec![1.0]);
+        assert_eq!(buckets[10], vec![2.0]);
+        assert!(buckets[1].is_empty() && buckets[5].is_empty());
+        // group 11 has no bucket
+        assert_eq!(buckets.iter().map(Vec::len).sum::<usize>(), 2);
+    }
+}
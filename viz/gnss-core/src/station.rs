@@ -0,0 +1,223 @@
+// station.rs — Named observer sites and common view between them
+//
+// Responsibilities:
+//   - Parse and validate named stations (RTK reference stations, timing
+//     labs) with their own marker colour and elevation mask
+//   - Baseline length between two sites
+//   - Intersect two stations' passes of one satellite into common-view
+//     windows, for planning differential and time-transfer links
+//
+// Pure math — the caller finds the passes (passes::find_passes) with its
+// own propagation, as for single-site pass prediction.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ground::Observer;
+use crate::passes::Pass;
+
+/// Spherical Earth radius used throughout the viewer, km.
+const EARTH_R_KM: f64 = 6371.0;
+
+// ---------------------------------------------------------------------------
+// Stations
+// ---------------------------------------------------------------------------
+
+/// One named observer site.
+///
+/// JSON shape: `{ "name": "BASE-1", "lat_deg": 41.85, "lon_deg": -87.65,
+///   "color": [255, 200, 0], "elev_mask_deg": 10 }`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Station {
+    pub name: String,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub color: [u8; 3],
+    /// Elevation mask for this site, degrees.
+    pub elev_mask_deg: f64,
+}
+
+impl Default for Station {
+    fn default() -> Self {
+        Station {
+            name: String::new(),
+            lat_deg: 0.0,
+            lon_deg: 0.0,
+            color: [255, 255, 255],
+            elev_mask_deg: 10.0,
+        }
+    }
+}
+
+impl Station {
+    /// Parse and validate one station definition.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let st: Station = serde_json::from_str(json).map_err(|e| format!("station JSON parse error: {e}"))?;
+        st.validate()?;
+        Ok(st)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("station needs a name".to_string());
+        }
+        if !(-90.0..=90.0).contains(&self.lat_deg) || !self.lon_deg.is_finite() {
+            return Err(format!("station {}: {}, {} is not a valid position", self.name, self.lat_deg, self.lon_deg));
+        }
+        if !(0.0..90.0).contains(&self.elev_mask_deg) {
+            return Err(format!("station {}: mask {}° outside 0–90°", self.name, self.elev_mask_deg));
+        }
+        Ok(())
+    }
+
+    pub fn observer(&self) -> Observer {
+        Observer::new(self.lat_deg, self.lon_deg)
+    }
+}
+
+/// Straight-line (chord) distance between two sites on the spherical
+/// Earth, km — the baseline vector length of a differential link.
+pub fn baseline_km(a: &Observer, b: &Observer) -> f64 {
+    let (u, v) = (a.ecef_unit(), b.ecef_unit());
+    let d2: f64 = (0..3).map(|i| (u[i] - v[i]).powi(2)).sum();
+    d2.sqrt() * EARTH_R_KM
+}
+
+// ---------------------------------------------------------------------------
+// Common view
+// ---------------------------------------------------------------------------
+
+/// An interval in which one satellite is above the masks of both stations.
+/// Clipped ends inherit the flags of the passes that bound them.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct CommonWindow {
+    pub start_unix: f64,
+    pub end_unix: f64,
+    /// Already in common view at the search window start.
+    pub start_clipped: bool,
+    /// Still in common view at the search window end.
+    pub end_clipped: bool,
+}
+
+impl CommonWindow {
+    pub fn duration_s(&self) -> f64 {
+        self.end_unix - self.start_unix
+    }
+}
+
+/// Overlaps of two time-ordered pass lists of the same satellite.
+pub fn common_windows(a: &[Pass], b: &[Pass]) -> Vec<CommonWindow> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let (pa, pb) = (&a[i], &b[j]);
+        let start = pa.rise_unix.max(pb.rise_unix);
+        let end = pa.set_unix.min(pb.set_unix);
+        if end > start {
+            // Each end is clipped if a pass bounding it there is
+            let start_clipped =
+                (pa.rise_clipped && pa.rise_unix == start) || (pb.rise_clipped && pb.rise_unix == start);
+            let end_clipped = (pa.set_clipped && pa.set_unix == end) || (pb.set_clipped && pb.set_unix == end);
+            out.push(CommonWindow { start_unix: start, end_unix: end, start_clipped, end_clipped });
+        }
+        // Advance whichever pass ends first
+        if pa.set_unix <= pb.set_unix { i += 1 } else { j += 1 }
+    }
+    out
+}
+
+/// Common-view windows of one satellite between two sites.
+#[derive(Serialize, Clone, Debug)]
+pub struct CommonView {
+    pub name: String,
+    /// 0 for Walker slots.
+    pub norad_id: u64,
+    pub constellation: u8,
+    pub windows: Vec<CommonWindow>,
+    /// Sum of the window durations, seconds.
+    pub total_s: f64,
+}
+
+impl CommonView {
+    /// `None` when the satellite is never in common view.
+    pub fn new(name: String, norad_id: u64, constellation: u8, windows: Vec<CommonWindow>) -> Option<Self> {
+        if windows.is_empty() {
+            return None;
+        }
+        let total_s = windows.iter().map(CommonWindow::duration_s).sum();
+        Some(CommonView { name, norad_id, constellation, windows, total_s })
+    }
+}
+
+/// Common view between sites `a` and `b` over a search window.
+#[derive(Serialize, Clone, Debug)]
+pub struct CommonViewReport {
+    pub a: String,
+    pub b: String,
+    pub baseline_km: f64,
+    pub start_unix: f64,
+    pub end_unix: f64,
+    pub satellites: Vec<CommonView>,
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(rise: f64, set: f64) -> Pass {
+        Pass {
+            rise_unix: rise,
+            set_unix: set,
+            rise_az_deg: 0.0,
+            set_az_deg: 0.0,
+            max_el_unix: 0.5 * (rise + set),
+            max_el_deg: 45.0,
+            max_el_az_deg: 0.0,
+            rise_clipped: false,
+            set_clipped: false,
+        }
+    }
+
+    #[test]
+    fn test_station_json() {
+        let st = Station::from_json(r#"{"name": "BASE-1", "lat_deg": 41.85, "lon_deg": -87.65}"#).unwrap();
+        assert_eq!(st.elev_mask_deg, 10.0);
+        assert_eq!(st.color, [255, 255, 255]);
+        assert!(Station::from_json(r#"{"lat_deg": 10}"#).is_err());
+        assert!(Station::from_json(r#"{"name": "X", "lat_deg": 95}"#).is_err());
+        assert!(Station::from_json(r#"{"name": "X", "elev_mask_deg": 90}"#).is_err());
+    }
+
+    #[test]
+    fn test_baseline() {
+        let a = Observer::new(0.0, 0.0);
+        assert_eq!(baseline_km(&a, &a), 0.0);
+        // Antipodes: one Earth diameter; a quarter turn: R·√2
+        assert!((baseline_km(&a, &Observer::new(0.0, 180.0)) - 2.0 * EARTH_R_KM).abs() < 1e-6);
+        assert!((baseline_km(&a, &Observer::new(90.0, 0.0)) - EARTH_R_KM * 2f64.sqrt()).abs() < 1e-6);
+    }
+
+    /// Partial overlaps, a pass of A spanning two of B, disjoint passes,
+    /// and clip flags taken from the bounding passes.
+    #[test]
+    fn test_common_windows() {
+        let a = [pass(0.0, 100.0), pass(200.0, 500.0), pass(900.0, 950.0)];
+        let b = [pass(50.0, 150.0), pass(250.0, 300.0), pass(400.0, 600.0)];
+        let w = common_windows(&a, &b);
+        let spans: Vec<(f64, f64)> = w.iter().map(|w| (w.start_unix, w.end_unix)).collect();
+        assert_eq!(spans, [(50.0, 100.0), (250.0, 300.0), (400.0, 500.0)]);
+        assert_eq!(w[0].duration_s(), 50.0);
+
+        let a = [Pass { rise_clipped: true, ..pass(0.0, 100.0) }];
+        let b = [Pass { set_clipped: true, ..pass(0.0, 100.0) }];
+        let w = common_windows(&a, &b);
+        assert!(w[0].start_clipped && w[0].end_clipped);
+        assert!(common_windows(&a, &[]).is_empty());
+        assert!(CommonView::new("G01".into(), 1, 0, Vec::new()).is_none());
+        assert_eq!(CommonView::new("G01".into(), 1, 0, w).unwrap().total_s, 100.0);
+    }
+}
@@ -2,14 +2,15 @@
 //
 // Responsibilities:
 //   - Parse Celestrak OMM JSON into SatRecord structs
-//   - Classify satellites by constellation (GPS, GLONASS, Galileo, BeiDou, other)
+//   - Classify satellites by constellation via the classify.rs rule table
 //   - Propagate satellite positions via sgp4 crate (TEME frame, km)
 //   - Keplerian fallback when sgp4 fails (long-range sim or bad elements)
 //   - Epoch helpers: parse ISO / "YYYY-DDD.FFF" strings to Unix timestamps
 
-use js_sys;
 use serde::Deserialize;
 
+use crate::classify::{self, OrbitClass, SbasProvider};
+
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
@@ -19,7 +20,7 @@ const EARTH_R: f64 = 6371.0;
 
 /// Gravitational parameter μ = GM (km³/s²) — used for mean-motion sanity, not propagation.
 #[allow(dead_code)]
-const MU: f64 = 398_600.4418;
+const MU: f64 = 398_600.441_8;
 
 // ---------------------------------------------------------------------------
 // Constellation indices (u8 flags passed back to JS / three-d renderer)
@@ -31,7 +32,15 @@ pub const CONSTELLATION_GALILEO: u8 = 2;
 pub const CONSTELLATION_BEIDOU: u8 = 3;
 pub const CONSTELLATION_QZSS: u8 = 4;   // Japan — Michibiki quasi-zenith satellites
 pub const CONSTELLATION_NAVIC: u8 = 5;  // India — IRNSS regional navigation
-pub const CONSTELLATION_OTHER: u8 = 6;  // SBAS augmentation sats and unknowns
+pub const CONSTELLATION_OTHER: u8 = 6;  // unclassified objects
+pub const CONSTELLATION_SBAS: u8 = 7;   // WAAS / EGNOS / GAGAN / MSAS / SDCM … GEOs
+
+/// Number of constellation indices (size of per-constellation arrays).
+pub const CONSTELLATION_COUNT: usize = 8;
+
+/// Display names, indexed by CONSTELLATION_* constant.
+pub const CONSTELLATION_NAMES: [&str; CONSTELLATION_COUNT] =
+    ["GPS", "GLONASS", "Galileo", "BeiDou", "QZSS", "NavIC", "Other", "SBAS"];
 
 // ---------------------------------------------------------------------------
 // Celestrak OMM JSON schema (serde Deserialize)
@@ -48,6 +57,10 @@ struct OmmRecord {
     #[serde(rename = "NORAD_CAT_ID")]
     norad_cat_id: u64,
 
+    /// COSPAR international designator, e.g. "2018-109A"
+    #[serde(rename = "OBJECT_ID", default)]
+    object_id: String,
+
     /// Epoch as "YYYY-DDD.FFFFFFFF" or "YYYY-MM-DDTHH:MM:SS[.sss]"
     #[serde(rename = "EPOCH")]
     epoch: String,
@@ -95,8 +108,14 @@ struct OmmRecord {
 /// One satellite record with pre-computed sgp4 Constants for fast per-frame propagation.
 pub struct SatRecord {
     pub name: String,
-    /// 0=GPS 1=GLONASS 2=Galileo 3=BeiDou 4=other
+    /// NORAD catalog number — key into the identity catalog (catalog.rs).
+    pub norad_id: u64,
+    /// CONSTELLATION_* index (0=GPS … 6=Other, 7=SBAS)
     pub constellation: u8,
+    /// SBAS provider when `constellation == CONSTELLATION_SBAS`.
+    pub sbas_provider: Option<SbasProvider>,
+    /// MEO / IGSO / GEO, from mean motion and inclination.
+    pub orbit_class: OrbitClass,
     /// Pre-initialised sgp4 propagator constants (expensive to build, cache here).
     pub constants: sgp4::Constants,
     /// TLE epoch expressed as Unix timestamp (seconds since 1970-01-01T00:00:00Z).
@@ -121,6 +140,12 @@ pub struct TleStore {
 // TleStore implementation
 // ---------------------------------------------------------------------------
 
+impl Default for TleStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TleStore {
     pub fn new() -> Self {
         TleStore {
@@ -135,134 +160,178 @@ impl TleStore {
             serde_json::from_str(json).map_err(|e| format!("JSON parse error: {e}"))?;
 
         let mut count = 0usize;
-
         for omm in &omm_records {
-            // --- Extract NORAD ID (already u64 from JSON) ---
-            let norad_id: u64 = omm.norad_cat_id;
-
-            // --- Parse epoch to (year_2digit, day_of_year, unix_ts) ---
-            let (epoch_year, epoch_doy, epoch_unix) =
-                match parse_epoch(&omm.epoch) {
-                    Some(v) => v,
-                    None => {
-                        // Skip records with unparseable epochs
-                        continue;
-                    }
-                };
+            if self.push_omm(omm) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
 
-            // --- Build chrono::NaiveDateTime from the parsed epoch_unix timestamp ---
-            let datetime = chrono::DateTime::from_timestamp(
-                epoch_unix as i64,
-                (epoch_unix.fract().abs() * 1e9) as u32,
-            )
-            .map(|dt| dt.naive_utc())
-            .unwrap_or(chrono::DateTime::UNIX_EPOCH.naive_utc());
-
-            // --- Build sgp4::Elements ---
-            // object_name / international_designator require sgp4 "alloc" feature —
-            // omit them to avoid the cfg-guard; the satellite name lives in SatRecord.name.
-            let elements = sgp4::Elements {
-                norad_id,
-                classification: sgp4::Classification::Unclassified,
-                datetime,
-                ephemeris_type: 0,
-                mean_motion_dot: omm.mean_motion_dot,
-                mean_motion_ddot: omm.mean_motion_ddot,
-                drag_term: omm.bstar,
-                element_set_number: 0,
-                inclination: omm.inclination,
-                right_ascension: omm.ra_of_asc_node,
-                eccentricity: omm.eccentricity,
-                argument_of_perigee: omm.arg_of_pericenter,
-                mean_anomaly: omm.mean_anomaly,
-                mean_motion: omm.mean_motion,
-                revolution_number: 0,
+    /// Parse two- or three-line element sets (optional name line before
+    /// each line pair), append records to the store. Returns the count of
+    /// successfully parsed satellites, or an error string when the text
+    /// holds no element sets at all.
+    pub fn load_from_tle(&mut self, text: &str) -> Result<usize, String> {
+        let lines: Vec<&str> = text.lines().map(str::trim_end).filter(|l| !l.trim().is_empty()).collect();
+        let mut count = 0usize;
+        let mut sets = 0usize;
+        let mut i = 0;
+        while i + 1 < lines.len() {
+            let (name, l1, l2) = if lines[i].starts_with("1 ") && lines[i + 1].starts_with("2 ") {
+                (None, lines[i], lines[i + 1])
+            } else if i + 2 < lines.len() && lines[i + 1].starts_with("1 ") && lines[i + 2].starts_with("2 ") {
+                (Some(lines[i]), lines[i + 1], lines[i + 2])
+            } else {
+                i += 1;
+                continue;
             };
+            i += if name.is_some() { 3 } else { 2 };
+            sets += 1;
+            if parse_tle_lines(name, l1, l2).is_some_and(|omm| self.push_omm(&omm)) {
+                count += 1;
+            }
+        }
+        if sets == 0 {
+            return Err("no TLE line pairs found".to_string());
+        }
+        Ok(count)
+    }
 
-            // --- Build sgp4::Constants (expensive, do once per satellite) ---
-            let constants = match sgp4::Constants::from_elements(&elements) {
-                Ok(c) => c,
-                Err(_) => {
-                    // Bad elements — skip this satellite
-                    continue;
+    /// Build and append one record. Returns false — skipping the satellite —
+    /// for unparseable epochs or elements sgp4 rejects.
+    fn push_omm(&mut self, omm: &OmmRecord) -> bool {
+        // --- Extract NORAD ID (already u64 from JSON) ---
+        let norad_id: u64 = omm.norad_cat_id;
+
+        // --- Parse epoch to (year_2digit, day_of_year, unix_ts) ---
+        let (_, _, epoch_unix) =
+            match parse_epoch(&omm.epoch) {
+                Some(v) => v,
+                None => {
+                    // Skip records with unparseable epochs
+                    return false;
                 }
             };
 
-            // --- Keplerian fallback parameters ---
-            // Mean motion: rev/day → rad/s
-            //   rev/day × 2π / 86400 = rad/s
-            let mean_motion_rad_s = (omm.mean_motion * 2.0 * std::f64::consts::PI / 86400.0) as f32;
-
-            // Semi-major axis from mean motion (for alt_km):
-            //   n = sqrt(μ / a³)  →  a = (μ / n²)^(1/3)   where n is in rad/s
-            let n_rad_s = mean_motion_rad_s as f64;
-            let a_km = (MU / (n_rad_s * n_rad_s)).powf(1.0 / 3.0);
-            let alt_km = (a_km - EARTH_R) as f32;
-
-            let inclination_rad = omm.inclination.to_radians() as f32;
-            let raan_rad = omm.ra_of_asc_node.to_radians() as f32;
-
-            // --- Constellation classification ---
-            let constellation = classify_constellation(&omm.object_name, norad_id);
-
-            self.records.push(SatRecord {
-                name: omm.object_name.clone(),
-                constellation,
-                constants,
-                epoch_unix,
-                inclination_rad,
-                raan_rad,
-                alt_km,
-                mean_motion_rad_s,
-            });
-
-            count += 1;
-        }
+        // --- Build chrono::NaiveDateTime from the parsed epoch_unix timestamp ---
+        let datetime = chrono::DateTime::from_timestamp(
+            epoch_unix as i64,
+            (epoch_unix.fract().abs() * 1e9) as u32,
+        )
+        .map(|dt| dt.naive_utc())
+        .unwrap_or(chrono::DateTime::UNIX_EPOCH.naive_utc());
+
+        // --- Build sgp4::Elements ---
+        // object_name / international_designator require sgp4 "alloc" feature —
+        // omit them to avoid the cfg-guard; the satellite name lives in SatRecord.name.
+        let elements = sgp4::Elements {
+            norad_id,
+            classification: sgp4::Classification::Unclassified,
+            datetime,
+            ephemeris_type: 0,
+            mean_motion_dot: omm.mean_motion_dot,
+            mean_motion_ddot: omm.mean_motion_ddot,
+            drag_term: omm.bstar,
+            element_set_number: 0,
+            inclination: omm.inclination,
+            right_ascension: omm.ra_of_asc_node,
+            eccentricity: omm.eccentricity,
+            argument_of_perigee: omm.arg_of_pericenter,
+            mean_anomaly: omm.mean_anomaly,
+            mean_motion: omm.mean_motion,
+            revolution_number: 0,
+        };
+
+        // --- Build sgp4::Constants (expensive, do once per satellite) ---
+        let constants = match sgp4::Constants::from_elements(&elements) {
+            Ok(c) => c,
+            Err(_) => {
+                // Bad elements — skip this satellite
+                return false;
+            }
+        };
+
+        // --- Keplerian fallback parameters ---
+        // Mean motion: rev/day → rad/s
+        //   rev/day × 2π / 86400 = rad/s
+        let mean_motion_rad_s = (omm.mean_motion * 2.0 * std::f64::consts::PI / 86400.0) as f32;
+
+        // Semi-major axis from mean motion (for alt_km):
+        //   n = sqrt(μ / a³)  →  a = (μ / n²)^(1/3)   where n is in rad/s
+        let n_rad_s = mean_motion_rad_s as f64;
+        let a_km = (MU / (n_rad_s * n_rad_s)).powf(1.0 / 3.0);
+        let alt_km = (a_km - EARTH_R) as f32;
+
+        let inclination_rad = omm.inclination.to_radians() as f32;
+        let raan_rad = omm.ra_of_asc_node.to_radians() as f32;
+
+        // --- Constellation classification (NORAD / COSPAR / name table) ---
+        let class = classify::classify(&classify::ObjectInfo {
+            name: &omm.object_name,
+            norad_id,
+            object_id: &omm.object_id,
+            mean_motion: omm.mean_motion,
+            inclination_deg: omm.inclination,
+        });
+
+        self.records.push(SatRecord {
+            name: omm.object_name.clone(),
+            norad_id,
+            constellation: class.constellation,
+            sbas_provider: class.sbas,
+            orbit_class: OrbitClass::from_elements(omm.mean_motion, omm.inclination),
+            constants,
+            epoch_unix,
+            inclination_rad,
+            raan_rad,
+            alt_km,
+            mean_motion_rad_s,
+        });
 
-        Ok(count)
+        true
     }
 
-    /// Propagate all satellites to the given Unix timestamp (seconds).
+    /// Propagate one record to the given Unix timestamp (seconds).
     ///
-    /// Returns a Vec of `(constellation_idx, [x_km, y_km, z_km])` in TEME frame.
-    /// Note: TEME ≈ ECI at GNSS altitudes — GMST rotation (ECI→ECEF) is a Phase 2 concern.
+    /// Returns `[x_km, y_km, z_km]` in TEME frame; `coords::teme_to_ecef` rotates to ECEF.
     ///
     /// Falls back to circular Keplerian propagation if sgp4 returns an error
     /// (e.g., satellite below horizon, long time extrapolation, near-degenerate elements).
-    pub fn propagate_all(&self, unix_s: f64) -> Vec<(u8, [f64; 3])> {
-        let mut out = Vec::with_capacity(self.records.len());
-
-        for rec in &self.records {
-            // Minutes since TLE epoch — sgp4 expects this as its time argument.
-            let minutes = (unix_s - rec.epoch_unix) / 60.0;
-
-            // TODO: verify sgp4 v2.3 propagate API; MinutesSinceEpoch may be a newtype.
-            let pos: [f64; 3] = match rec.constants.propagate(sgp4::MinutesSinceEpoch(minutes)) {
-                Ok(prediction) => {
-                    // prediction.position is [f64; 3] in km, TEME frame
-                    prediction.position
-                }
-                Err(_) => {
-                    // SGP4 failed — use circular Keplerian fallback.
-                    // This happens for:
-                    //   - Very large |minutes| (element set too old)
-                    //   - Satellites with unusual eccentricity driving them below Earth
-                    //   - Numerical issues in SGP4 deep-space model
+    pub fn propagate(rec: &SatRecord, unix_s: f64) -> [f64; 3] {
+        Self::propagate_pv(rec, unix_s).0
+    }
+
+    /// `propagate` plus the TEME velocity in km/s, used for Hermite
+    /// interpolation between propagation samples. The Keplerian fallback
+    /// velocity is a ±1 s central difference.
+    pub fn propagate_pv(rec: &SatRecord, unix_s: f64) -> ([f64; 3], [f64; 3]) {
+        // Minutes since TLE epoch — sgp4 expects this as its time argument.
+        let minutes = (unix_s - rec.epoch_unix) / 60.0;
+
+        match rec.constants.propagate(sgp4::MinutesSinceEpoch(minutes)) {
+            // km and km/s, TEME frame
+            Ok(prediction) => (prediction.position, prediction.velocity),
+            Err(_) => {
+                // SGP4 failed — use circular Keplerian fallback.
+                // This happens for:
+                //   - Very large |minutes| (element set too old)
+                //   - Satellites with unusual eccentricity driving them below Earth
+                //   - Numerical issues in SGP4 deep-space model
+                let at = |t: f64| {
                     keplerian_pos(
                         rec.alt_km,
                         rec.inclination_rad,
                         rec.raan_rad,
                         rec.mean_motion_rad_s,
                         rec.epoch_unix,
-                        unix_s,
+                        t,
                     )
-                }
-            };
-
-            out.push((rec.constellation, pos));
+                };
+                let (a, b) = (at(unix_s - 1.0), at(unix_s + 1.0));
+                (at(unix_s), [(b[0] - a[0]) / 2.0, (b[1] - a[1]) / 2.0, (b[2] - a[2]) / 2.0])
+            }
         }
-
-        out
     }
 
     pub fn is_empty(&self) -> bool {
@@ -271,42 +340,64 @@ impl TleStore {
 }
 
 // ---------------------------------------------------------------------------
-// Constellation classification
+// Two-line element sets
 // ---------------------------------------------------------------------------
 
-/// Classify a satellite by name prefix and NORAD ID.
-///
-/// Classification rules (name checked first, then NORAD ranges):
-///   GPS      — name starts with "GPS" or "NAVSTAR"
-///   GLONASS  — name starts with "COSMOS" or "GLONASS"  (NORAD 11: 11-20xxx range broadly)
-///   Galileo  — name starts with "GSAT" or "GALILEO"
-///   BeiDou   — name starts with "BEIDOU", "BDSM", or contains "BEIDOU"
-///   Other    — everything else (include in render, mark separately)
-fn classify_constellation(name: &str, _norad_id: u64) -> u8 {
-    let up = name.to_ascii_uppercase();
-
-    if up.starts_with("GPS") || up.starts_with("NAVSTAR") {
-        return CONSTELLATION_GPS;
-    }
-    if up.starts_with("GLONASS") || up.starts_with("COSMOS") {
-        return CONSTELLATION_GLONASS;
-    }
-    if up.starts_with("GSAT") || up.starts_with("GALILEO") {
-        return CONSTELLATION_GALILEO;
-    }
-    if up.starts_with("BEIDOU") || up.starts_with("BDSM") || up.contains("BEIDOU") {
-        return CONSTELLATION_BEIDOU;
-    }
-    // QZSS: Japan's quasi-zenith system. Celestrak names start with "MICHIBIKI" or "QZS-".
-    if up.starts_with("MICHIBIKI") || up.starts_with("QZS-") {
-        return CONSTELLATION_QZSS;
-    }
-    // NavIC/IRNSS: India's regional navigation system. Names start with "IRNSS-".
-    if up.starts_with("IRNSS-") || up.starts_with("NAVIC") {
-        return CONSTELLATION_NAVIC;
+/// Convert one TLE line pair into the OMM fields `push_omm` consumes.
+/// Columns follow the NORAD two-line format; checksums are not verified
+/// and Alpha-5 catalog numbers are not supported.
+fn parse_tle_lines(name: Option<&str>, l1: &str, l2: &str) -> Option<OmmRecord> {
+    // 1-indexed inclusive column range, trimmed
+    fn col(l: &str, a: usize, b: usize) -> Option<&str> {
+        l.get(a - 1..b.min(l.len())).map(str::trim)
     }
+    let num = |l: &str, a, b| col(l, a, b)?.parse::<f64>().ok();
+
+    let norad_cat_id: u64 = col(l1, 3, 7)?.parse().ok()?;
+    let yy: u64 = col(l1, 19, 20)?.parse().ok()?;
+    let full_year = |yy: u64| if yy < 57 { 2000 + yy } else { 1900 + yy };
+    let intl = col(l1, 10, 17).unwrap_or("");
+    let object_id = match intl.get(..2).and_then(|y| y.parse::<u64>().ok()) {
+        Some(y) if intl.len() > 2 => format!("{}-{}", full_year(y), &intl[2..]),
+        _ => String::new(),
+    };
+    Some(OmmRecord {
+        object_name: name
+            .map(|n| n.trim().trim_start_matches("0 ").to_string())
+            .unwrap_or_else(|| norad_cat_id.to_string()),
+        norad_cat_id,
+        object_id,
+        epoch: format!("{}-{}", full_year(yy), col(l1, 21, 32)?),
+        mean_motion_dot: num(l1, 34, 43)?,
+        mean_motion_ddot: implied_decimal(col(l1, 45, 52)?)?,
+        bstar: implied_decimal(col(l1, 54, 61)?)?,
+        inclination: num(l2, 9, 16)?,
+        ra_of_asc_node: num(l2, 18, 25)?,
+        eccentricity: format!("0.{}", col(l2, 27, 33)?).parse().ok()?,
+        arg_of_pericenter: num(l2, 35, 42)?,
+        mean_anomaly: num(l2, 44, 51)?,
+        mean_motion: num(l2, 53, 63)?,
+    })
+}
 
-    CONSTELLATION_OTHER
+/// Decode an assumed-decimal TLE field: " 12345-3" → 0.12345e-3,
+/// "-11606-4" → -0.11606e-4. Blank fields are zero.
+fn implied_decimal(field: &str) -> Option<f64> {
+    let f = field.trim();
+    if f.is_empty() {
+        return Some(0.0);
+    }
+    let (sign, rest) = match f.strip_prefix('-') {
+        Some(r) => (-1.0, r),
+        None => (1.0, f.trim_start_matches('+')),
+    };
+    let (mantissa, exp) = match rest.rfind(['-', '+']) {
+        Some(i) => rest.split_at(i),
+        None => (rest, "0"),
+    };
+    let m: f64 = format!("0.{mantissa}").parse().ok()?;
+    let e: i32 = exp.parse().ok()?;
+    Some(sign * m * 10f64.powi(e))
 }
 
 // ---------------------------------------------------------------------------
@@ -316,7 +407,7 @@ fn classify_constellation(name: &str, _norad_id: u64) -> u8 {
 /// Circular Keplerian position in TEME ≈ ECI frame, returned in km.
 ///
 /// This is the same orbital geometry used in the Phase 1 Keplerian sim
-/// (see `kpos` in lib.rs), adapted to accept real orbital elements and
+/// (see `kpos` in the viewer's lib.rs), adapted to accept real orbital elements and
 /// return km rather than normalised scene units.
 ///
 /// Arguments:
@@ -332,7 +423,8 @@ fn classify_constellation(name: &str, _norad_id: u64) -> u8 {
 ///   2. Circular orbit in orbital plane: (r·cos M, r·sin M, 0)  [perifocal frame]
 ///   3. Rotate by inclination around x-axis (tilt the plane)
 ///   4. Rotate by RAAN around z-axis (orient the ascending node)
-///   Result is in ECI/TEME (z = north, y = completes right-hand frame).
+///
+/// Result is in ECI/TEME (z = north, y = completes right-hand frame).
 fn keplerian_pos(
     alt_km: f32,
     inc: f32,
@@ -348,7 +440,7 @@ fn keplerian_pos(
 
     // Mean anomaly at target time (radians) — starts at 0 at epoch
     // (For a more accurate fallback we could read mean_anomaly_at_epoch, but
-    //  for Phase 1 visual purposes starting at 0 is fine and matches lib.rs kpos.)
+    //  for Phase 1 visual purposes starting at 0 is fine and matches the viewer's kpos.)
     let ma = mm_rad_s * dt;
 
     // Position in orbital plane (perifocal frame, eccentricity = 0 ⟹ E = M):
@@ -385,7 +477,7 @@ fn keplerian_pos(
 ///   "YYYY-MM-DDTHH:MM:SS.sss" — with fractional seconds
 ///
 /// Returns `None` if the string cannot be parsed.
-fn parse_epoch(epoch_str: &str) -> Option<(u64, f64, f64)> {
+pub fn parse_epoch(epoch_str: &str) -> Option<(u64, f64, f64)> {
     let s = epoch_str.trim();
 
     // Detect format by counting '-' separators before 'T' (or before '.').
@@ -464,7 +556,7 @@ fn doy_and_year_to_unix(year: u64, doy: f64) -> Option<f64> {
 /// Compute Unix timestamp (seconds) for midnight UTC on Jan 1 of the given year.
 /// Valid for years 1970–2100 (covers all plausible TLE epochs).
 fn years_to_unix(year: u64) -> Option<i64> {
-    if year < 1970 || year > 2100 {
+    if !(1970..=2100).contains(&year) {
         return None;
     }
     let mut days: i64 = 0;
@@ -476,7 +568,7 @@ fn years_to_unix(year: u64) -> Option<i64> {
 
 /// Returns true if the given year is a Gregorian leap year.
 fn is_leap_year(year: u64) -> bool {
-    (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
 }
 
 /// Return the 1-indexed day-of-year for a given (year, month [1-12], day [1-31]).
@@ -484,12 +576,12 @@ fn day_of_year(year: u64, month: u32, day: u32) -> Option<u32> {
     // Cumulative days before each month (non-leap)
     const MONTH_DAYS: [u32; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
 
-    if month < 1 || month > 12 {
+    if !(1..=12).contains(&month) {
         return None;
     }
     let mut doy: u32 = 0;
-    for m in 0..(month - 1) as usize {
-        doy += MONTH_DAYS[m];
+    for (m, days) in MONTH_DAYS.iter().enumerate().take((month - 1) as usize) {
+        doy += days;
         // Add leap day after February (month index 1) in leap years
         if m == 1 && is_leap_year(year) {
             doy += 1;
@@ -500,7 +592,7 @@ fn day_of_year(year: u64, month: u32, day: u32) -> Option<u32> {
 }
 
 // ---------------------------------------------------------------------------
-// Public epoch helpers (called from lib.rs)
+// Public epoch helpers
 // ---------------------------------------------------------------------------
 
 /// Parse a Celestrak epoch string ("YYYY-DDD.FFF" or ISO "YYYY-MM-DDTHH:MM:SS")
@@ -514,16 +606,8 @@ pub fn epoch_str_to_unix(epoch_str: &str) -> f64 {
         .unwrap_or(0.0)
 }
 
-/// Current time as Unix timestamp (seconds), sourced from `js_sys::Date::now()`.
-///
-/// `Date.now()` returns milliseconds since Unix epoch; divide by 1000 for seconds.
-#[allow(dead_code)]
-pub fn now_unix() -> f64 {
-    js_sys::Date::now() / 1000.0
-}
-
 // ---------------------------------------------------------------------------
-// Tests (run with `cargo test --target x86_64-unknown-linux-gnu`)
+// Tests (run with `cargo test -p gnss-core`)
 // ---------------------------------------------------------------------------
 
 #[cfg(test)]
@@ -581,14 +665,17 @@ mod tests {
 
     #[test]
     fn test_classify_constellation() {
-        assert_eq!(classify_constellation("GPS BIIA-10", 11054), CONSTELLATION_GPS);
-        assert_eq!(classify_constellation("NAVSTAR 68", 40534), CONSTELLATION_GPS);
-        assert_eq!(classify_constellation("GLONASS-M 752", 32276), CONSTELLATION_GLONASS);
-        assert_eq!(classify_constellation("COSMOS 2471", 37139), CONSTELLATION_GLONASS);
-        assert_eq!(classify_constellation("GSAT0211", 41859), CONSTELLATION_GALILEO);
-        assert_eq!(classify_constellation("GALILEO 5", 37846), CONSTELLATION_GALILEO);
-        assert_eq!(classify_constellation("BEIDOU-3 M1", 43001), CONSTELLATION_BEIDOU);
-        assert_eq!(classify_constellation("UNKNOWN SAT", 99999), CONSTELLATION_OTHER);
+        let c = |name, norad_id, mean_motion, inclination_deg| {
+            classify::classify(&classify::ObjectInfo { name, norad_id, object_id: "", mean_motion, inclination_deg }).constellation
+        };
+        assert_eq!(c("GPS BIIA-10", 11054, 2.005, 55.0), CONSTELLATION_GPS);
+        assert_eq!(c("NAVSTAR 68", 40534, 2.005, 55.0), CONSTELLATION_GPS);
+        assert_eq!(c("GLONASS-M 752", 32276, 2.131, 64.8), CONSTELLATION_GLONASS);
+        assert_eq!(c("COSMOS 2471", 37139, 2.131, 64.8), CONSTELLATION_GLONASS);
+        assert_eq!(c("GSAT0211", 41859, 1.705, 56.0), CONSTELLATION_GALILEO);
+        assert_eq!(c("GALILEO 5", 37846, 1.705, 56.0), CONSTELLATION_GALILEO);
+        assert_eq!(c("BEIDOU-3 M1", 43001, 1.86, 55.0), CONSTELLATION_BEIDOU);
+        assert_eq!(c("UNKNOWN SAT", 99999, 1.0, 0.0), CONSTELLATION_OTHER);
     }
 
     #[test]
@@ -610,4 +697,38 @@ mod tests {
         assert!(pos[1].abs() < 0.01, "y={}", pos[1]);
         assert!(pos[2].abs() < 0.01, "z={}", pos[2]);
     }
+
+    const ISS_3LE: &str = "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+";
+
+    #[test]
+    fn test_implied_decimal() {
+        assert_eq!(implied_decimal(" 00000-0"), Some(0.0));
+        assert!((implied_decimal("-11606-4").unwrap() + 0.11606e-4).abs() < 1e-12);
+        assert!((implied_decimal(" 12345+1").unwrap() - 1.2345).abs() < 1e-12);
+        assert_eq!(implied_decimal(""), Some(0.0));
+    }
+
+    /// Three-line and bare two-line sets parse to the same elements; the
+    /// propagated radius is a LEO one.
+    #[test]
+    fn test_load_from_tle() {
+        let mut store = TleStore::new();
+        assert_eq!(store.load_from_tle(ISS_3LE), Ok(1));
+        let bare: String = ISS_3LE.lines().skip(1).map(|l| format!("{l}\n")).collect();
+        assert_eq!(store.load_from_tle(&bare), Ok(1));
+        let (named, unnamed) = (&store.records[0], &store.records[1]);
+        assert_eq!(named.name, "ISS (ZARYA)");
+        assert_eq!(unnamed.name, "25544");
+        assert_eq!(named.norad_id, 25544);
+        assert!((named.inclination_rad as f64 - 51.6416_f64.to_radians()).abs() < 1e-6);
+        let expected_epoch = parse_epoch("2008-264.51782528").unwrap().2;
+        assert!((named.epoch_unix - expected_epoch).abs() < 1e-3);
+        let p = TleStore::propagate(named, named.epoch_unix + 600.0);
+        let r = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+        assert!((6600.0..6800.0).contains(&r), "r={r}");
+        assert!(store.load_from_tle("not a tle").is_err());
+    }
 }
@@ -0,0 +1,584 @@
+// trajectory.rs — Moving observer: timestamped route and vehicle attitude
+//
+// Responsibilities:
+//   - Parse a route from JSON waypoints, a GPX track (trkpt / rtept / wpt
+//     with <time>) or a KML track (gx:Track, or timestamped Placemark points)
+//   - Interpolate position, altitude, heading, pitch and roll at any epoch
+//     (great-circle between waypoints; heading from the track when absent)
+//   - Elevation of a sky direction above a tilted vehicle's antenna plane,
+//     for body-frame antenna masking
+//
+// Pure data — the viewer samples the route at `sim_epoch` and moves its
+// observer, as it does for NMEA replay. Outside the route's time span the
+// vehicle waits at the first or last waypoint.
+
+use serde::{Deserialize, Serialize};
+
+/// Spherical Earth radius used throughout the viewer, km.
+const EARTH_R_KM: f64 = 6371.0;
+
+// ---------------------------------------------------------------------------
+// Waypoints and poses
+// ---------------------------------------------------------------------------
+
+/// One timestamped route point. Attitude angles follow the aerospace
+/// convention: heading clockwise from north, pitch nose-up, roll right
+/// wing down, all degrees.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Waypoint {
+    pub unix_s: f64,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    /// Height above the spherical surface, metres.
+    pub alt_m: f64,
+    /// `None` takes the heading from the direction of travel.
+    pub heading_deg: Option<f64>,
+    pub pitch_deg: f64,
+    pub roll_deg: f64,
+}
+
+/// Interpolated vehicle state at one epoch.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub unix_s: f64,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub alt_m: f64,
+    pub heading_deg: f64,
+    pub pitch_deg: f64,
+    pub roll_deg: f64,
+    /// Ground speed along the current leg, m/s; 0 outside the route.
+    pub speed_m_s: f64,
+}
+
+/// JSON waypoint: time as Unix seconds or an ISO-8601 UTC string.
+#[derive(Deserialize)]
+struct WaypointJson {
+    unix_s: Option<f64>,
+    time: Option<String>,
+    lat_deg: f64,
+    lon_deg: f64,
+    #[serde(default)]
+    alt_m: f64,
+    heading_deg: Option<f64>,
+    #[serde(default)]
+    pitch_deg: f64,
+    #[serde(default)]
+    roll_deg: f64,
+}
+
+/// A bare waypoint array or `{ "waypoints": [...] }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RouteJson {
+    Bare(Vec<WaypointJson>),
+    Wrapped { waypoints: Vec<WaypointJson> },
+}
+
+/// A route, waypoints in strictly increasing time order.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Trajectory {
+    pub waypoints: Vec<Waypoint>,
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+impl Trajectory {
+    /// Parse JSON, GPX or KML, told apart by the leading `<` and root tag.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let head = text.trim_start();
+        if !head.starts_with('<') {
+            return Self::from_json(text);
+        }
+        if head.contains("<gpx") {
+            Self::from_gpx(text)
+        } else if head.contains("<kml") {
+            Self::from_kml(text)
+        } else {
+            Err("trajectory XML is neither GPX nor KML".to_string())
+        }
+    }
+
+    /// `[{ "time": "2024-01-15T12:00:00Z", "lat_deg": 41.98, "lon_deg": -87.90,
+    /// "alt_m": 3000, "heading_deg": 270, "pitch_deg": 2, "roll_deg": 0 }, ...]`
+    /// — `unix_s` may replace `time`; altitude and attitude are optional.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let route: RouteJson = serde_json::from_str(json).map_err(|e| format!("trajectory JSON parse error: {e}"))?;
+        let (RouteJson::Bare(points) | RouteJson::Wrapped { waypoints: points }) = route;
+        let waypoints = points
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let unix_s = match (p.unix_s, &p.time) {
+                    (Some(t), _) => t,
+                    (None, Some(s)) => parse_iso8601(s).ok_or_else(|| format!("waypoint {i}: bad time {s:?}"))?,
+                    (None, None) => return Err(format!("waypoint {i}: needs time or unix_s")),
+                };
+                Ok(Waypoint {
+                    unix_s,
+                    lat_deg: p.lat_deg,
+                    lon_deg: p.lon_deg,
+                    alt_m: p.alt_m,
+                    heading_deg: p.heading_deg,
+                    pitch_deg: p.pitch_deg,
+                    roll_deg: p.roll_deg,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Self::new(waypoints)
+    }
+
+    /// Track, route or waypoint points that carry a `<time>`; `<ele>` is the
+    /// altitude. Untimed points are skipped.
+    pub fn from_gpx(text: &str) -> Result<Self, String> {
+        let mut waypoints = Vec::new();
+        for tag in ["trkpt", "rtept", "wpt"] {
+            for (attrs, inner) in elements(text, tag) {
+                let (Some(lat), Some(lon)) = (attr(attrs, "lat"), attr(attrs, "lon")) else {
+                    continue;
+                };
+                let Some(unix_s) = child_text(inner, "time").and_then(parse_iso8601) else {
+                    continue;
+                };
+                let (Ok(lat_deg), Ok(lon_deg)) = (lat.parse(), lon.parse()) else {
+                    continue;
+                };
+                let alt_m = child_text(inner, "ele").and_then(|e| e.parse().ok()).unwrap_or(0.0);
+                waypoints.push(Waypoint { unix_s, lat_deg, lon_deg, alt_m, ..Waypoint::default() });
+            }
+            if !waypoints.is_empty() {
+                break; // a track wins over the route and loose waypoints
+            }
+        }
+        Self::new(waypoints)
+    }
+
+    /// `<gx:Track>` `<when>` / `<gx:coord>` pairs, or else Placemarks with a
+    /// `<TimeStamp>` and a `<Point>`. KML coordinates are lon, lat, alt.
+    pub fn from_kml(text: &str) -> Result<Self, String> {
+        let mut waypoints = Vec::new();
+        for (_, track) in elements(text, "gx:Track") {
+            let whens = elements(track, "when").into_iter().map(|(_, w)| parse_iso8601(w.trim()));
+            let coords = elements(track, "gx:coord").into_iter().map(|(_, c)| parse_kml_coord(c, ' '));
+            for (t, c) in whens.zip(coords) {
+                if let (Some(unix_s), Some((lon_deg, lat_deg, alt_m))) = (t, c) {
+                    waypoints.push(Waypoint { unix_s, lat_deg, lon_deg, alt_m, ..Waypoint::default() });
+                }
+            }
+        }
+        if waypoints.is_empty() {
+            for (_, pm) in elements(text, "Placemark") {
+                let Some((_, stamp)) = elements(pm, "TimeStamp").into_iter().next() else {
+                    continue;
+                };
+                let Some((_, point)) = elements(pm, "Point").into_iter().next() else {
+                    continue;
+                };
+                let t = child_text(stamp, "when").and_then(parse_iso8601);
+                let c = child_text(point, "coordinates").and_then(|c| parse_kml_coord(c, ','));
+                if let (Some(unix_s), Some((lon_deg, lat_deg, alt_m))) = (t, c) {
+                    waypoints.push(Waypoint { unix_s, lat_deg, lon_deg, alt_m, ..Waypoint::default() });
+                }
+            }
+        }
+        Self::new(waypoints)
+    }
+
+    /// Validate, sort by time and drop repeated timestamps (the last wins).
+    pub fn new(mut waypoints: Vec<Waypoint>) -> Result<Self, String> {
+        if waypoints.is_empty() {
+            return Err("trajectory has no timestamped waypoints".to_string());
+        }
+        for (i, w) in waypoints.iter().enumerate() {
+            if !w.unix_s.is_finite() || !(-90.0..=90.0).contains(&w.lat_deg) || !w.lon_deg.is_finite() {
+                return Err(format!("waypoint {i}: {}, {} at {} is not valid", w.lat_deg, w.lon_deg, w.unix_s));
+            }
+        }
+        waypoints.sort_by(|a, b| a.unix_s.total_cmp(&b.unix_s));
+        waypoints.reverse();
+        waypoints.dedup_by(|later, kept| later.unix_s == kept.unix_s);
+        waypoints.reverse();
+        Ok(Trajectory { waypoints })
+    }
+
+    pub fn start_unix(&self) -> f64 {
+        self.waypoints.first().map_or(f64::NAN, |w| w.unix_s)
+    }
+
+    pub fn end_unix(&self) -> f64 {
+        self.waypoints.last().map_or(f64::NAN, |w| w.unix_s)
+    }
+
+    // -----------------------------------------------------------------------
+    // Interpolation
+    // -----------------------------------------------------------------------
+
+    /// Vehicle state at `unix_s`; `None` only for an empty trajectory.
+    pub fn sample(&self, unix_s: f64) -> Option<Pose> {
+        let wps = &self.waypoints;
+        let last = wps.len().checked_sub(1)?;
+        let i = wps.partition_point(|w| w.unix_s <= unix_s);
+        if i == 0 || i > last {
+            // Parked at an end, facing along the first or last leg
+            let (w, leg) = if i == 0 { (&wps[0], 0) } else { (&wps[last], last.saturating_sub(1)) };
+            let heading_deg = w.heading_deg.unwrap_or_else(|| leg_bearing(wps, leg));
+            return Some(Pose { unix_s, heading_deg, speed_m_s: 0.0, ..w.pose() });
+        }
+
+        let (a, b) = (&wps[i - 1], &wps[i]);
+        let dt = b.unix_s - a.unix_s;
+        let f = (unix_s - a.unix_s) / dt;
+        let (ua, ub) = (unit(a.lat_deg, a.lon_deg), unit(b.lat_deg, b.lon_deg));
+        let angle = angle_between(ua, ub);
+        let (lat_deg, lon_deg) = lat_lon(slerp(ua, ub, angle, f));
+        let heading_deg = match (a.heading_deg, b.heading_deg) {
+            (Some(ha), Some(hb)) => (ha + wrap_180(hb - ha) * f).rem_euclid(360.0),
+            _ if angle > 1e-12 => bearing_deg(lat_deg, lon_deg, b.lat_deg, b.lon_deg, a.lat_deg, a.lon_deg),
+            _ => a.heading_deg.or(b.heading_deg).unwrap_or(0.0),
+        };
+        Some(Pose {
+            unix_s,
+            lat_deg,
+            lon_deg,
+            alt_m: lerp(a.alt_m, b.alt_m, f),
+            heading_deg,
+            pitch_deg: lerp(a.pitch_deg, b.pitch_deg, f),
+            roll_deg: lerp(a.roll_deg, b.roll_deg, f),
+            speed_m_s: angle * EARTH_R_KM * 1000.0 / dt,
+        })
+    }
+}
+
+impl Default for Waypoint {
+    fn default() -> Self {
+        Waypoint {
+            unix_s: 0.0,
+            lat_deg: 0.0,
+            lon_deg: 0.0,
+            alt_m: 0.0,
+            heading_deg: None,
+            pitch_deg: 0.0,
+            roll_deg: 0.0,
+        }
+    }
+}
+
+impl Waypoint {
+    fn pose(&self) -> Pose {
+        Pose {
+            unix_s: self.unix_s,
+            lat_deg: self.lat_deg,
+            lon_deg: self.lon_deg,
+            alt_m: self.alt_m,
+            heading_deg: self.heading_deg.unwrap_or(0.0),
+            pitch_deg: self.pitch_deg,
+            roll_deg: self.roll_deg,
+            speed_m_s: 0.0,
+        }
+    }
+}
+
+/// Initial great-circle bearing of leg `i` (waypoint i → i+1); 0 for a
+/// single waypoint.
+fn leg_bearing(wps: &[Waypoint], i: usize) -> f64 {
+    match (wps.get(i), wps.get(i + 1)) {
+        (Some(a), Some(b)) => bearing_deg(a.lat_deg, a.lon_deg, b.lat_deg, b.lon_deg, a.lat_deg, a.lon_deg),
+        _ => 0.0,
+    }
+}
+
+/// Great-circle bearing from (lat, lon) towards `to`, or, at `to` itself,
+/// the bearing of arrival from `from`. Degrees clockwise from north.
+fn bearing_deg(lat: f64, lon: f64, to_lat: f64, to_lon: f64, from_lat: f64, from_lon: f64) -> f64 {
+    let bearing = |lat1: f64, lon1: f64, lat2: f64, lon2: f64| {
+        let (p1, p2) = (lat1.to_radians(), lat2.to_radians());
+        let dl = (lon2 - lon1).to_radians();
+        let y = dl.sin() * p2.cos();
+        let x = p1.cos() * p2.sin() - p1.sin() * p2.cos() * dl.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    };
+    if angle_between(unit(lat, lon), unit(to_lat, to_lon)) > 1e-9 {
+        bearing(lat, lon, to_lat, to_lon)
+    } else {
+        // Arrived: continue on the reverse of the bearing back to `from`
+        (bearing(to_lat, to_lon, from_lat, from_lon) + 180.0).rem_euclid(360.0)
+    }
+}
+
+fn unit(lat_deg: f64, lon_deg: f64) -> [f64; 3] {
+    let (lat, lon) = (lat_deg.to_radians(), lon_deg.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn lat_lon(u: [f64; 3]) -> (f64, f64) {
+    let lat = u[2].atan2((u[0] * u[0] + u[1] * u[1]).sqrt()).to_degrees();
+    (lat, u[1].atan2(u[0]).to_degrees())
+}
+
+fn angle_between(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let dot: f64 = (0..3).map(|i| a[i] * b[i]).sum();
+    dot.clamp(-1.0, 1.0).acos()
+}
+
+/// Spherical interpolation between unit vectors `angle` radians apart.
+fn slerp(a: [f64; 3], b: [f64; 3], angle: f64, f: f64) -> [f64; 3] {
+    if angle < 1e-12 {
+        return a;
+    }
+    let (wa, wb) = (((1.0 - f) * angle).sin() / angle.sin(), (f * angle).sin() / angle.sin());
+    [wa * a[0] + wb * b[0], wa * a[1] + wb * b[1], wa * a[2] + wb * b[2]]
+}
+
+fn lerp(a: f64, b: f64, f: f64) -> f64 {
+    a + (b - a) * f
+}
+
+fn wrap_180(deg: f64) -> f64 {
+    (deg + 180.0).rem_euclid(360.0) - 180.0
+}
+
+// ---------------------------------------------------------------------------
+// Body-frame masking
+// ---------------------------------------------------------------------------
+
+/// Elevation of the sky direction (`az_deg`, `el_deg`) above the antenna
+/// plane of a vehicle in `pose`, degrees — equal to `el_deg` when level.
+/// The antenna looks out of the roof, along the body's −z (up) axis.
+pub fn body_elevation_deg(pose: &Pose, az_deg: f64, el_deg: f64) -> f64 {
+    let (az, el) = (az_deg.to_radians(), el_deg.to_radians());
+    // Direction in north-east-down
+    let dir = [el.cos() * az.cos(), el.cos() * az.sin(), -el.sin()];
+    let (psi, theta, phi) = (pose.heading_deg.to_radians(), pose.pitch_deg.to_radians(), pose.roll_deg.to_radians());
+    // Body z axis in NED: third column of the body → NED rotation
+    let z_body = [
+        phi.cos() * theta.sin() * psi.cos() + phi.sin() * psi.sin(),
+        phi.cos() * theta.sin() * psi.sin() - phi.sin() * psi.cos(),
+        phi.cos() * theta.cos(),
+    ];
+    let up_dot: f64 = -(0..3).map(|i| dir[i] * z_body[i]).sum::<f64>();
+    up_dot.clamp(-1.0, 1.0).asin().to_degrees()
+}
+
+// ---------------------------------------------------------------------------
+// Minimal XML and time helpers
+// ---------------------------------------------------------------------------
+
+/// `(attributes, inner text)` of each `<tag …>…</tag>` or `<tag …/>` in
+/// document order. Enough for GPX and KML tracks: no CDATA, comments or
+/// nesting of the same tag.
+fn elements<'a>(text: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{tag}");
+    let close = format!("</{tag}>");
+    let mut out = Vec::new();
+    let mut rest = text;
+    while let Some(i) = rest.find(&open) {
+        let after = &rest[i + open.len()..];
+        // `<trk` must not match `<trkpt`
+        if !after.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            rest = after;
+            continue;
+        }
+        let Some(gt) = after.find('>') else { break };
+        let attrs = &after[..gt];
+        if let Some(attrs) = attrs.strip_suffix('/') {
+            out.push((attrs, ""));
+            rest = &after[gt + 1..];
+            continue;
+        }
+        let body = &after[gt + 1..];
+        let Some(end) = body.find(&close) else { break };
+        out.push((attrs, &body[..end]));
+        rest = &body[end + close.len()..];
+    }
+    out
+}
+
+/// Value of `name="…"` (or single-quoted) in an attribute list.
+fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    for q in ['"', '\''] {
+        let key = format!("{name}={q}");
+        let mut from = 0;
+        while let Some(i) = attrs[from..].find(&key) {
+            let start = from + i;
+            // Whole attribute names only: `lat` must not match `xlat`
+            if start == 0 || attrs[..start].ends_with(char::is_whitespace) {
+                let value = &attrs[start + key.len()..];
+                return value.find(q).map(|end| &value[..end]);
+            }
+            from = start + key.len();
+        }
+    }
+    None
+}
+
+/// Trimmed text of the first `<tag>` child.
+fn child_text<'a>(inner: &'a str, tag: &str) -> Option<&'a str> {
+    elements(inner, tag).into_iter().next().map(|(_, t)| t.trim())
+}
+
+/// `lon<sep>lat[<sep>alt]` → (lon, lat, alt).
+fn parse_kml_coord(s: &str, sep: char) -> Option<(f64, f64, f64)> {
+    let mut it = s.trim().split(sep).map(str::trim).filter(|f| !f.is_empty());
+    let lon = it.next()?.parse().ok()?;
+    let lat = it.next()?.parse().ok()?;
+    let alt = it.next().and_then(|a| a.parse().ok()).unwrap_or(0.0);
+    Some((lon, lat, alt))
+}
+
+/// `YYYY-MM-DDTHH:MM[:SS[.fff]][Z|±HH:MM|±HHMM]` → Unix seconds, through
+/// chrono. No zone means UTC.
+pub fn parse_iso8601(s: &str) -> Option<f64> {
+    use chrono::{DateTime, NaiveDateTime};
+    let s = s.trim().replacen(' ', "T", 1);
+    let zoned = DateTime::parse_from_rfc3339(&s)
+        .or_else(|_| DateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .map(|t| t.naive_utc());
+    let t = zoned
+        .or_else(|_| NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M"))
+        .ok()?
+        .and_utc();
+    Some(t.timestamp() as f64 + t.timestamp_subsec_nanos() as f64 * 1e-9)
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: f64 = 1_705_320_000.0; // 2024-01-15T12:00:00Z
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(parse_iso8601("2024-01-15T12:00:00Z"), Some(T0));
+        assert_eq!(parse_iso8601("2024-01-15T12:00:01.5Z"), Some(T0 + 1.5));
+        assert_eq!(parse_iso8601("2024-01-15T13:30:00+01:30"), Some(T0));
+        assert_eq!(parse_iso8601("2024-01-15T07:00:00-05:00"), Some(T0));
+        assert_eq!(parse_iso8601("2024-01-15T17:30:00+0530"), Some(T0));
+        assert_eq!(parse_iso8601("2024-01-15T12:00"), Some(T0));
+        assert_eq!(parse_iso8601("2024-01-15 12:00:00"), Some(T0));
+        assert_eq!(parse_iso8601("2024-13-15T12:00:00Z"), None);
+        assert_eq!(parse_iso8601("noon"), None);
+    }
+
+    /// JSON in both shapes; unsorted input is ordered and duplicate
+    /// timestamps collapse to the last one.
+    #[test]
+    fn test_json_route() {
+        let tr = Trajectory::parse(
+            r#"[{"unix_s": 1705320060, "lat_deg": 1, "lon_deg": 0, "alt_m": 500},
+                {"time": "2024-01-15T12:00:00Z", "lat_deg": 0, "lon_deg": 0, "heading_deg": 10},
+                {"unix_s": 1705320060, "lat_deg": 2, "lon_deg": 0}]"#,
+        )
+        .unwrap();
+        assert_eq!(tr.waypoints.len(), 2);
+        assert_eq!(tr.start_unix(), T0);
+        assert_eq!(tr.waypoints[0].heading_deg, Some(10.0));
+        assert_eq!(tr.waypoints[1].lat_deg, 2.0);
+
+        let wrapped = Trajectory::parse(r#"{"waypoints": [{"unix_s": 5, "lat_deg": 1, "lon_deg": 2}]}"#).unwrap();
+        assert_eq!(wrapped.waypoints[0].lon_deg, 2.0);
+        assert!(Trajectory::parse("[]").is_err());
+        assert!(Trajectory::parse(r#"[{"lat_deg": 1, "lon_deg": 2}]"#).is_err());
+        assert!(Trajectory::parse(r#"[{"unix_s": 0, "lat_deg": 91, "lon_deg": 2}]"#).is_err());
+    }
+
+    #[test]
+    fn test_gpx_and_kml() {
+        let gpx = r#"<?xml version="1.0"?>
+<gpx version="1.1" creator="test"><trk><name>Flight</name><trkseg>
+  <trkpt lat="41.98" lon="-87.90"><ele>200</ele><time>2024-01-15T12:00:00Z</time></trkpt>
+  <trkpt lat='42.10' lon='-88.20'><ele>3000.5</ele><time>2024-01-15T12:10:00Z</time></trkpt>
+  <trkpt lat="42.20" lon="-88.40"/>
+</trkseg></trk></gpx>"#;
+        let tr = Trajectory::parse(gpx).unwrap();
+        assert_eq!(tr.waypoints.len(), 2, "the untimed point is skipped");
+        assert_eq!((tr.waypoints[1].lat_deg, tr.waypoints[1].alt_m), (42.10, 3000.5));
+        assert_eq!(tr.end_unix(), T0 + 600.0);
+
+        let track = r#"<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+<Placemark><gx:Track>
+  <when>2024-01-15T12:00:00Z</when><when>2024-01-15T12:01:00Z</when>
+  <gx:coord>-87.90 41.98 200</gx:coord><gx:coord>-87.95 42.00 400</gx:coord>
+</gx:Track></Placemark></kml>"#;
+        let tr = Trajectory::parse(track).unwrap();
+        assert_eq!(tr.waypoints.len(), 2);
+        assert_eq!((tr.waypoints[1].lon_deg, tr.waypoints[1].lat_deg, tr.waypoints[1].alt_m), (-87.95, 42.0, 400.0));
+
+        let points = r#"<kml><Document>
+<Placemark><TimeStamp><when>2024-01-15T12:00:00Z</when></TimeStamp><Point><coordinates>10,50,0</coordinates></Point></Placemark>
+<Placemark><TimeStamp><when>2024-01-15T12:05:00Z</when></TimeStamp><Point><coordinates>10.5,50</coordinates></Point></Placemark>
+</Document></kml>"#;
+        let tr = Trajectory::parse(points).unwrap();
+        assert_eq!(tr.waypoints.len(), 2);
+        assert_eq!(tr.waypoints[1].lon_deg, 10.5);
+        assert!(Trajectory::parse("<kml><Placemark><LineString/></Placemark></kml>").is_err());
+        assert!(Trajectory::parse("<svg/>").is_err());
+    }
+
+    /// Eastward along the equator: 1° in 100 s, heading from the track,
+    /// attitude interpolated, parked outside the time span.
+    #[test]
+    fn test_sample() {
+        let wp = |t: f64, lon: f64, alt: f64, pitch: f64| Waypoint {
+            unix_s: t,
+            lon_deg: lon,
+            alt_m: alt,
+            pitch_deg: pitch,
+            ..Waypoint::default()
+        };
+        let tr = Trajectory::new(vec![wp(0.0, 0.0, 0.0, 0.0), wp(100.0, 1.0, 1000.0, 10.0)]).unwrap();
+        let p = tr.sample(25.0).unwrap();
+        assert!((p.lon_deg - 0.25).abs() < 1e-9 && p.lat_deg.abs() < 1e-9);
+        assert!((p.alt_m - 250.0).abs() < 1e-9 && (p.pitch_deg - 2.5).abs() < 1e-9);
+        assert!((p.heading_deg - 90.0).abs() < 1e-6);
+        let leg_m = 1f64.to_radians() * EARTH_R_KM * 1000.0;
+        assert!((p.speed_m_s - leg_m / 100.0).abs() < 1e-6);
+
+        let end = tr.sample(500.0).unwrap();
+        assert_eq!((end.lon_deg, end.speed_m_s), (1.0, 0.0));
+        assert!((end.heading_deg - 90.0).abs() < 1e-6, "parked facing along the last leg");
+        assert_eq!(tr.sample(-5.0).unwrap().lon_deg, 0.0);
+        assert!((tr.sample(100.0).unwrap().heading_deg - 90.0).abs() < 1e-6);
+
+        // Given headings interpolate the short way round through north
+        let turn = Trajectory::new(vec![
+            Waypoint { heading_deg: Some(350.0), ..wp(0.0, 0.0, 0.0, 0.0) },
+            Waypoint { heading_deg: Some(30.0), ..wp(100.0, 0.0, 0.0, 0.0) },
+        ])
+        .unwrap();
+        assert!((turn.sample(25.0).unwrap().heading_deg - 0.0).abs() < 1e-9);
+        assert!(Trajectory::default().sample(0.0).is_none());
+    }
+
+    /// Level: body elevation equals elevation. Pitching the nose up tilts
+    /// the roof antenna away from satellites ahead; rolling right tilts it
+    /// towards satellites on the right.
+    #[test]
+    fn test_body_elevation() {
+        let level = Pose {
+            unix_s: 0.0,
+            lat_deg: 0.0,
+            lon_deg: 0.0,
+            alt_m: 0.0,
+            heading_deg: 90.0,
+            pitch_deg: 0.0,
+            roll_deg: 0.0,
+            speed_m_s: 0.0,
+        };
+        for (az, el) in [(0.0, 10.0), (123.0, 45.0), (300.0, 89.0)] {
+            assert!((body_elevation_deg(&level, az, el) - el).abs() < 1e-9);
+        }
+        let nose_up = Pose { pitch_deg: 10.0, ..level };
+        assert!((body_elevation_deg(&nose_up, 90.0, 30.0) - 20.0).abs() < 1e-9);
+        assert!((body_elevation_deg(&nose_up, 270.0, 30.0) - 40.0).abs() < 1e-9);
+        let right_wing_down = Pose { roll_deg: 20.0, ..level };
+        assert!((body_elevation_deg(&right_wing_down, 180.0, 30.0) - 50.0).abs() < 1e-9);
+        assert!((body_elevation_deg(&right_wing_down, 0.0, 30.0) - 10.0).abs() < 1e-9);
+    }
+}
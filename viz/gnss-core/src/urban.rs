@@ -0,0 +1,433 @@
+// urban.rs — Urban canyon line-of-sight simulation against 3D buildings
+//
+// Responsibilities:
+//   - Parse extruded building footprints from GeoJSON (Polygon / MultiPolygon
+//     features with a height property)
+//   - Project footprints into a local East/North frame around the observer
+//   - Ray-cast each satellite line of sight against the building walls and
+//     classify it as LOS, blocked, or NLOS via a single specular reflection
+//   - Estimate the C/N0 penalty and reflected-path excess range
+//
+// Pure math — no wasm_bindgen exports, no three-d. Azimuth/elevation follow
+// the `coords::az_el` convention (compass azimuth, degrees).
+
+use serde::Serialize;
+
+/// Metres per degree of latitude on the spherical Earth (R = 6371 km).
+const M_PER_DEG: f64 = 6_371_000.0 * std::f64::consts::PI / 180.0;
+
+/// Storey height used when a feature only carries a level count, metres.
+const LEVEL_HEIGHT_M: f64 = 3.0;
+
+/// Specular reflection loss off typical façades (concrete / glass), dB.
+const REFLECTION_LOSS_DB: f64 = 6.0;
+
+/// Attenuation of a blocked signal (penetration / diffraction), dB.
+/// Large enough to push every signal below typical tracking thresholds.
+const BLOCKAGE_LOSS_DB: f64 = 25.0;
+
+// ---------------------------------------------------------------------------
+// Scene types
+// ---------------------------------------------------------------------------
+
+/// One extruded building: outer footprint ring in (lon°, lat°) and a roof height.
+#[derive(Clone, Debug)]
+pub struct Building {
+    pub ring: Vec<[f64; 2]>,
+    pub height_m: f64,
+}
+
+/// Buildings loaded from GeoJSON, in geographic coordinates.
+#[derive(Clone, Debug, Default)]
+pub struct UrbanScene {
+    pub buildings: Vec<Building>,
+}
+
+/// A vertical wall segment in the observer's local East/North frame (metres).
+#[derive(Clone, Copy, Debug)]
+struct Wall {
+    a: [f64; 2],
+    b: [f64; 2],
+    height_m: f64,
+}
+
+/// `UrbanScene` projected around one observer, ready for ray casting.
+pub struct LocalScene {
+    walls: Vec<Wall>,
+}
+
+/// Line-of-sight classification for one satellite.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LosState {
+    Los,
+    Blocked,
+    NlosReflected,
+}
+
+/// Result of ray casting one satellite.
+#[derive(Clone, Copy, Debug)]
+pub struct LosResult {
+    pub state: LosState,
+    /// Loss to subtract from every signal's C/N0, dB.
+    pub c_n0_penalty_db: f64,
+    /// Extra path length of the reflected ray vs. the direct ray, metres.
+    /// Zero unless `state == NlosReflected`.
+    pub excess_path_m: f64,
+}
+
+impl LosResult {
+    pub const CLEAR: LosResult = LosResult {
+        state: LosState::Los,
+        c_n0_penalty_db: 0.0,
+        excess_path_m: 0.0,
+    };
+}
+
+// ---------------------------------------------------------------------------
+// GeoJSON parsing
+// ---------------------------------------------------------------------------
+
+impl UrbanScene {
+    /// Parse a GeoJSON FeatureCollection of building footprints.
+    ///
+    /// Heights are read from `properties.height` (metres), falling back to
+    /// `properties.levels` / `building:levels` × 3 m. Features without a
+    /// usable height or with fewer than three vertices are skipped; only the
+    /// outer ring of each polygon is used.
+    pub fn from_geojson(json: &str) -> Result<Self, String> {
+        let root: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("JSON parse error: {e}"))?;
+        let features = root
+            .get("features")
+            .and_then(|f| f.as_array())
+            .ok_or("expected a FeatureCollection with a \"features\" array")?;
+
+        let mut buildings = Vec::new();
+        for feat in features {
+            let Some(height_m) = feature_height(feat) else { continue };
+            let Some(geom) = feat.get("geometry") else { continue };
+            let coords = geom.get("coordinates");
+            let polygons: Vec<&serde_json::Value> = match geom.get("type").and_then(|t| t.as_str()) {
+                Some("Polygon") => coords.into_iter().collect(),
+                Some("MultiPolygon") => coords
+                    .and_then(|c| c.as_array())
+                    .map(|a| a.iter().collect())
+                    .unwrap_or_default(),
+                _ => continue,
+            };
+            for poly in polygons {
+                let Some(outer) = poly.get(0).and_then(|r| r.as_array()) else { continue };
+                let ring: Vec<[f64; 2]> = outer
+                    .iter()
+                    .filter_map(|p| Some([p.get(0)?.as_f64()?, p.get(1)?.as_f64()?]))
+                    .collect();
+                if ring.len() >= 3 {
+                    buildings.push(Building { ring, height_m });
+                }
+            }
+        }
+        Ok(UrbanScene { buildings })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buildings.is_empty()
+    }
+
+    /// Project every footprint into metres East/North of `(lat0, lon0)`
+    /// (equirectangular — accurate to well under 1 m within a few km).
+    ///
+    /// Buildings whose footprint contains the observer are dropped: the
+    /// antenna is assumed to be outdoors (street level or on that roof).
+    pub fn localise(&self, lat0_deg: f64, lon0_deg: f64) -> LocalScene {
+        let k_east = M_PER_DEG * lat0_deg.to_radians().cos();
+        let to_local = |p: &[f64; 2]| {
+            let dlon = (p[0] - lon0_deg + 540.0).rem_euclid(360.0) - 180.0;
+            [dlon * k_east, (p[1] - lat0_deg) * M_PER_DEG]
+        };
+
+        let mut walls = Vec::new();
+        for b in &self.buildings {
+            let pts: Vec<[f64; 2]> = b.ring.iter().map(to_local).collect();
+            if point_in_polygon([0.0, 0.0], &pts) {
+                continue;
+            }
+            for i in 0..pts.len() {
+                let a = pts[i];
+                let c = pts[(i + 1) % pts.len()];
+                if (a[0] - c[0]).hypot(a[1] - c[1]) > 1e-3 {
+                    walls.push(Wall { a, b: c, height_m: b.height_m });
+                }
+            }
+        }
+        LocalScene { walls }
+    }
+}
+
+/// Building height from GeoJSON properties, if any.
+fn feature_height(feat: &serde_json::Value) -> Option<f64> {
+    let props = feat.get("properties")?;
+    let num = |key: &str| {
+        props.get(key).and_then(|v| {
+            v.as_f64()
+                .or_else(|| v.as_str().and_then(|s| s.trim().trim_end_matches('m').trim().parse().ok()))
+        })
+    };
+    num("height")
+        .or_else(|| num("levels").map(|l| l * LEVEL_HEIGHT_M))
+        .or_else(|| num("building:levels").map(|l| l * LEVEL_HEIGHT_M))
+        .filter(|h| h.is_finite() && *h > 0.0)
+}
+
+// ---------------------------------------------------------------------------
+// Ray casting
+// ---------------------------------------------------------------------------
+
+impl LocalScene {
+    pub fn is_empty(&self) -> bool {
+        self.walls.is_empty()
+    }
+
+    /// Classify the line of sight to a satellite at `(az_deg, el_deg)` seen
+    /// from an antenna `antenna_h_m` above street level.
+    ///
+    /// Blocked rays are tested for a single specular bounce off any wall; the
+    /// reflection with the shortest excess path wins. Both legs — satellite
+    /// to wall and wall to antenna — must clear every other wall.
+    pub fn classify(&self, antenna_h_m: f64, az_deg: f64, el_deg: f64) -> LosResult {
+        if el_deg <= 0.0 || self.is_empty() {
+            return LosResult::CLEAR;
+        }
+        let az = az_deg.to_radians();
+        let dir = [az.sin(), az.cos()];
+        let tan_el = el_deg.to_radians().tan();
+
+        if self.first_block([0.0, 0.0], dir, antenna_h_m, tan_el, f64::INFINITY, None).is_none() {
+            return LosResult::CLEAR;
+        }
+
+        let cos_el = el_deg.to_radians().cos();
+        let best_excess = self
+            .walls
+            .iter()
+            .enumerate()
+            .filter_map(|(i, w)| self.reflection_excess(i, w, dir, cos_el, antenna_h_m, tan_el))
+            .min_by(f64::total_cmp);
+
+        match best_excess {
+            Some(excess_path_m) => LosResult {
+                state: LosState::NlosReflected,
+                c_n0_penalty_db: REFLECTION_LOSS_DB,
+                excess_path_m,
+            },
+            None => LosResult {
+                state: LosState::Blocked,
+                c_n0_penalty_db: BLOCKAGE_LOSS_DB,
+                excess_path_m: 0.0,
+            },
+        }
+    }
+
+    /// Distance to the first wall that the ray from `origin` at height `h0`
+    /// (horizontal direction `dir`, climbing at `tan_el`) strikes below its
+    /// roof line, within `max_t`. Wall index `skip` is ignored.
+    fn first_block(
+        &self,
+        origin: [f64; 2],
+        dir: [f64; 2],
+        h0: f64,
+        tan_el: f64,
+        max_t: f64,
+        skip: Option<usize>,
+    ) -> Option<f64> {
+        self.walls
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != skip)
+            .filter_map(|(_, w)| {
+                let t = ray_segment(origin, dir, w.a, w.b)?;
+                (t < max_t && h0 + t * tan_el < w.height_m).then_some(t)
+            })
+            .min_by(f64::total_cmp)
+    }
+
+    /// Excess path (m) of a specular reflection off wall `wi` (`w`), or
+    /// `None` if the geometry does not produce a clear reflected path.
+    fn reflection_excess(
+        &self,
+        wi: usize,
+        w: &Wall,
+        dir: [f64; 2],
+        cos_el: f64,
+        h0: f64,
+        tan_el: f64,
+    ) -> Option<f64> {
+        // Unit normal of the wall's vertical plane
+        let (ex, ny) = (w.b[0] - w.a[0], w.b[1] - w.a[1]);
+        let len = ex.hypot(ny);
+        let n = [-ny / len, ex / len];
+
+        // Signed distance observer → wall plane; satellite must illuminate
+        // the face that looks at the observer.
+        let dist = -(w.a[0] * n[0] + w.a[1] * n[1]);
+        let d_dot_n = dir[0] * n[0] + dir[1] * n[1];
+        if dist * d_dot_n <= 0.0 {
+            return None;
+        }
+
+        // Arrival direction after mirroring the horizontal component
+        let refl = [dir[0] - 2.0 * d_dot_n * n[0], dir[1] - 2.0 * d_dot_n * n[1]];
+        let t = ray_segment([0.0, 0.0], refl, w.a, w.b)?;
+        let z = h0 + t * tan_el;
+        if z >= w.height_m {
+            return None;
+        }
+        // Leg from antenna to reflection point must itself be unobstructed
+        if self.first_block([0.0, 0.0], refl, h0, tan_el, t - 1e-6, None).is_some() {
+            return None;
+        }
+        // …and so must the incoming leg from the reflection point up to the
+        // satellite (horizontal direction `dir`, still climbing at `tan_el`)
+        let p = [t * refl[0], t * refl[1]];
+        if self.first_block(p, dir, z, tan_el, f64::INFINITY, Some(wi)).is_some() {
+            return None;
+        }
+
+        // Plane-wave image method: Δ = 2·|dist|·|n·d̂|, with n·d̂ = d_dot_n·cos(el)
+        Some(2.0 * dist.abs() * (d_dot_n * cos_el).abs())
+    }
+}
+
+/// Distance `t > 0` along `origin + t·dir` to segment `[a, b]`, if they cross.
+fn ray_segment(origin: [f64; 2], dir: [f64; 2], a: [f64; 2], b: [f64; 2]) -> Option<f64> {
+    let e = [b[0] - a[0], b[1] - a[1]];
+    let denom = dir[0] * e[1] - dir[1] * e[0];
+    if denom.abs() < 1e-12 {
+        return None; // parallel
+    }
+    let w = [a[0] - origin[0], a[1] - origin[1]];
+    let t = (w[0] * e[1] - w[1] * e[0]) / denom;
+    let u = (w[0] * dir[1] - w[1] * dir[0]) / denom;
+    (t > 1e-9 && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+/// Even-odd point-in-polygon test.
+fn point_in_polygon(p: [f64; 2], poly: &[[f64; 2]]) -> bool {
+    let mut inside = false;
+    let mut j = poly.len() - 1;
+    for i in 0..poly.len() {
+        let (a, b) = (poly[i], poly[j]);
+        if (a[1] > p[1]) != (b[1] > p[1])
+            && p[0] < (b[0] - a[0]) * (p[1] - a[1]) / (b[1] - a[1]) + a[0]
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 40 m tall, 20 m deep block spanning ±50 m East/West, starting 10 m
+    /// north of the observer at (0°, 0°).
+    fn north_block_json() -> String {
+        let m = 1.0 / M_PER_DEG;
+        format!(
+            r#"{{"type":"FeatureCollection","features":[{{"type":"Feature",
+            "properties":{{"height":40}},
+            "geometry":{{"type":"Polygon","coordinates":[[
+              [{w},{s}],[{e},{s}],[{e},{n}],[{w},{n}],[{w},{s}]]]}}}}]}}"#,
+            w = -50.0 * m,
+            e = 50.0 * m,
+            s = 10.0 * m,
+            n = 30.0 * m
+        )
+    }
+
+    #[test]
+    fn test_parse_geojson_heights() {
+        let scene = UrbanScene::from_geojson(&north_block_json()).unwrap();
+        assert_eq!(scene.buildings.len(), 1);
+        assert_eq!(scene.buildings[0].height_m, 40.0);
+
+        let levels = r#"{"features":[{"properties":{"building:levels":"4"},
+            "geometry":{"type":"Polygon","coordinates":[[[0,0],[0.001,0],[0.001,0.001]]]}}]}"#;
+        let scene = UrbanScene::from_geojson(levels).unwrap();
+        assert_eq!(scene.buildings[0].height_m, 12.0);
+
+        assert!(UrbanScene::from_geojson("{}").is_err());
+    }
+
+    #[test]
+    fn test_los_when_no_buildings_in_path() {
+        let local = UrbanScene::from_geojson(&north_block_json()).unwrap().localise(0.0, 0.0);
+        let r = local.classify(1.5, 180.0, 30.0); // looking south, away from the block
+        assert_eq!(r.state, LosState::Los);
+        assert_eq!(r.c_n0_penalty_db, 0.0);
+        // High elevation clears the 40 m roof 10 m away (needs el > ~76°)
+        assert_eq!(local.classify(1.5, 0.0, 80.0).state, LosState::Los);
+    }
+
+    #[test]
+    fn test_blocked_behind_building() {
+        let local = UrbanScene::from_geojson(&north_block_json()).unwrap().localise(0.0, 0.0);
+        let r = local.classify(1.5, 0.0, 30.0);
+        assert_eq!(r.state, LosState::Blocked);
+        assert_eq!(r.c_n0_penalty_db, BLOCKAGE_LOSS_DB);
+    }
+
+    /// Street canyon: a 40 m block 10 m north and a block of height `south_h`
+    /// 10 m south of the observer.
+    fn canyon(south_h: f64) -> LocalScene {
+        let m = 1.0 / M_PER_DEG;
+        let json = format!(
+            r#"{{"features":[
+            {{"properties":{{"height":40}},"geometry":{{"type":"Polygon","coordinates":[[
+              [{w},{s1}],[{e},{s1}],[{e},{n1}],[{w},{n1}]]]}}}},
+            {{"properties":{{"height":{south_h}}},"geometry":{{"type":"Polygon","coordinates":[[
+              [{w},{s2}],[{e},{s2}],[{e},{n2}],[{w},{n2}]]]}}}}]}}"#,
+            w = -50.0 * m, e = 50.0 * m,
+            s1 = 10.0 * m, n1 = 30.0 * m,
+            s2 = -30.0 * m, n2 = -10.0 * m
+        );
+        UrbanScene::from_geojson(&json).unwrap().localise(0.0, 0.0)
+    }
+
+    /// A southern satellite at 45° is hidden by a 20 m south block (direct
+    /// ray meets it 10 m away at 11.5 m) but reflects off the north block's
+    /// south-facing wall: the mirrored ray hits that wall at 11.5 m, and the
+    /// incoming leg from there passes the south block at 31.5 m, over its roof.
+    #[test]
+    fn test_nlos_reflection_excess() {
+        let local = canyon(20.0);
+        let r = local.classify(1.5, 180.0, 45.0);
+        assert_eq!(r.state, LosState::NlosReflected);
+        // Δ = 2 · 10 m · cos(45°) ≈ 14.14 m
+        assert!((r.excess_path_m - 14.142).abs() < 0.01, "excess={}", r.excess_path_m);
+        assert_eq!(r.c_n0_penalty_db, REFLECTION_LOSS_DB);
+
+        // A 40 m south block also stops the incoming leg (31.5 m < 40 m):
+        // there is no clear reflection, only blockage.
+        assert_eq!(canyon(40.0).classify(1.5, 180.0, 45.0).state, LosState::Blocked);
+    }
+
+    #[test]
+    fn test_observer_inside_footprint_ignored() {
+        let local = UrbanScene::from_geojson(&north_block_json()).unwrap().localise(0.0002, 0.0);
+        assert!(local.is_empty());
+    }
+
+    #[test]
+    fn test_ray_segment() {
+        let t = ray_segment([0.0, 0.0], [0.0, 1.0], [-1.0, 5.0], [1.0, 5.0]);
+        assert!((t.unwrap() - 5.0).abs() < 1e-12);
+        assert!(ray_segment([0.0, 0.0], [0.0, -1.0], [-1.0, 5.0], [1.0, 5.0]).is_none());
+    }
+}
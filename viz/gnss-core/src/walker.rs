@@ -0,0 +1,351 @@
+// walker.rs — Walker Delta / Star constellation generator
+//
+// Responsibilities:
+//   - Parse Walker i:t/p/f notation ("56:24/3/1": inclination, total
+//     satellites, planes, relative phasing)
+//   - Deserialize user-defined constellations from JSON: altitude, colour,
+//     pattern, reporting constellation, disabled planes
+//   - Lay out planes and slots and propagate the circular orbits (with J2
+//     nodal regression) to ECEF at any epoch
+//
+// Pure math — no wasm_bindgen exports, no three-d. The viewer keeps the list of
+// generated constellations and feeds their positions into the sky data.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::classify::OrbitClass;
+use crate::coords;
+use crate::tles::{CONSTELLATION_COUNT, CONSTELLATION_OTHER};
+
+/// Spherical Earth radius used for altitude → orbit radius (matches coords.rs).
+const EARTH_R_KM: f64 = 6371.0;
+/// Gravitational parameter μ (km³/s²).
+const MU: f64 = 398600.4418;
+/// Second zonal harmonic and its reference (equatorial) radius.
+const J2: f64 = 1.08263e-3;
+const J2_RE_KM: f64 = 6378.137;
+
+/// Upper bound on a constellation's total t, so a typo cannot allocate
+/// (and propagate) billions of slots.
+pub const MAX_TOTAL: u32 = 10_000;
+
+// ---------------------------------------------------------------------------
+// Definition
+// ---------------------------------------------------------------------------
+
+/// Delta spreads the ascending nodes over 360°, Star over 180° (polar
+/// constellations, where co-rotating planes meet at the seam).
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Pattern {
+    #[default]
+    Delta,
+    Star,
+}
+
+/// One user-defined constellation.
+///
+/// JSON shape: `{ "name": "LEO-PNT", "notation": "87.9:240/12/1",
+///   "alt_km": 1100, "pattern": "star", "color": [255, 255, 255] }`.
+/// `notation` may be replaced by explicit `inc_deg` / `total` / `planes` /
+/// `phasing` fields; `notation` wins when both are given.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WalkerDef {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notation: Option<String>,
+    pub inc_deg: f64,
+    /// Total satellites t (before disabling planes).
+    pub total: u32,
+    /// Number of equally spaced orbital planes p.
+    pub planes: u32,
+    /// Relative phasing f: slot offset between adjacent planes is f·360°/t.
+    pub phasing: u32,
+    pub alt_km: f64,
+    pub pattern: Pattern,
+    /// RAAN of plane 0, degrees.
+    pub raan_offset_deg: f64,
+    pub color: [u8; 3],
+    /// CONSTELLATION_* index the satellites report as — selects the signal
+    /// plan for C/N0 and the NMEA talker. Defaults to OTHER.
+    pub constellation: u8,
+    /// Plane indices (0-based) left empty, e.g. a degraded constellation.
+    pub disabled_planes: Vec<u32>,
+    /// Reference epoch (Unix s) for slot phases and node regression; the
+    /// sim epoch at the time of adding when absent.
+    pub epoch_unix: Option<f64>,
+}
+
+impl Default for WalkerDef {
+    fn default() -> Self {
+        WalkerDef {
+            name: "Walker".to_string(),
+            notation: None,
+            inc_deg: 55.0,
+            total: 24,
+            planes: 6,
+            phasing: 1,
+            alt_km: 20200.0,
+            pattern: Pattern::Delta,
+            raan_offset_deg: 0.0,
+            color: [255, 255, 255],
+            constellation: CONSTELLATION_OTHER,
+            disabled_planes: Vec::new(),
+            epoch_unix: None,
+        }
+    }
+}
+
+/// `"56:24/3/1"` → `(56.0, 24, 3, 1)`.
+pub fn parse_notation(s: &str) -> Result<(f64, u32, u32, u32), String> {
+    let err = || format!("bad Walker notation {s:?}, expected i:t/p/f");
+    let (inc, rest) = s.trim().split_once(':').ok_or_else(err)?;
+    let mut parts = rest.split('/').map(|p| p.trim().parse::<u32>());
+    let (Some(Ok(t)), Some(Ok(p)), Some(Ok(f)), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err(err());
+    };
+    let inc: f64 = inc.trim().trim_end_matches('°').parse().map_err(|_| err())?;
+    Ok((inc, t, p, f))
+}
+
+// ---------------------------------------------------------------------------
+// Generated constellation
+// ---------------------------------------------------------------------------
+
+/// One occupied slot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slot {
+    pub plane: u32,
+    pub slot: u32,
+    raan0_rad: f64,
+    /// Argument of latitude at the reference epoch.
+    u0_rad: f64,
+}
+
+/// JSON view of one generated constellation.
+#[derive(Serialize)]
+pub struct WalkerSummary<'a> {
+    #[serde(flatten)]
+    pub def: &'a WalkerDef,
+    pub enabled: bool,
+    /// Occupied slots (t minus disabled planes).
+    pub satellites: usize,
+    pub orbit_class: OrbitClass,
+    pub period_min: f64,
+}
+
+/// A validated Walker constellation with its slot layout.
+#[derive(Clone, Debug)]
+pub struct Walker {
+    pub def: WalkerDef,
+    pub enabled: bool,
+    pub slots: Vec<Slot>,
+    epoch_unix: f64,
+    inc_rad: f64,
+    radius_km: f64,
+    mean_motion_rad_s: f64,
+    node_rate_rad_s: f64,
+}
+
+impl Walker {
+    /// Validate `def` and lay out its slots. `default_epoch` is used when
+    /// the definition carries no `epoch_unix`.
+    pub fn new(mut def: WalkerDef, default_epoch: f64) -> Result<Self, String> {
+        if let Some(n) = def.notation.as_deref() {
+            (def.inc_deg, def.total, def.planes, def.phasing) = parse_notation(n)?;
+        }
+        if def.planes == 0 || def.total == 0 || !def.total.is_multiple_of(def.planes) {
+            return Err(format!("Walker {}: t = {} is not a multiple of p = {}", def.name, def.total, def.planes));
+        }
+        if def.total > MAX_TOTAL {
+            return Err(format!("Walker {}: t = {} exceeds {MAX_TOTAL} satellites", def.name, def.total));
+        }
+        if def.phasing >= def.planes {
+            return Err(format!("Walker {}: phasing f = {} must be below p = {}", def.name, def.phasing, def.planes));
+        }
+        if !(100.0..=50_000.0).contains(&def.alt_km) || !(0.0..=180.0).contains(&def.inc_deg) {
+            return Err(format!("Walker {}: altitude or inclination out of range", def.name));
+        }
+        if !def.raan_offset_deg.is_finite() {
+            return Err(format!("Walker {}: RAAN offset must be finite", def.name));
+        }
+        if (def.constellation as usize) >= CONSTELLATION_COUNT {
+            def.constellation = CONSTELLATION_OTHER;
+        }
+
+        let per_plane = def.total / def.planes;
+        let spread = match def.pattern {
+            Pattern::Delta => 2.0 * PI,
+            Pattern::Star => PI,
+        };
+        let d_raan = spread / def.planes as f64;
+        let d_slot = 2.0 * PI / per_plane as f64;
+        let d_phase = 2.0 * PI * def.phasing as f64 / def.total as f64;
+        let raan_offset = def.raan_offset_deg.to_radians();
+        let slots = (0..def.planes)
+            .filter(|p| !def.disabled_planes.contains(p))
+            .flat_map(|p| {
+                (0..per_plane).map(move |s| Slot {
+                    plane: p,
+                    slot: s,
+                    raan0_rad: raan_offset + p as f64 * d_raan,
+                    u0_rad: s as f64 * d_slot + p as f64 * d_phase,
+                })
+            })
+            .collect();
+
+        let radius_km = EARTH_R_KM + def.alt_km;
+        let inc_rad = def.inc_deg.to_radians();
+        let mean_motion_rad_s = (MU / radius_km.powi(3)).sqrt();
+        // dΩ/dt = −3/2 · n · J2 · (R_E/a)² · cos i
+        let node_rate_rad_s = -1.5 * mean_motion_rad_s * J2 * (J2_RE_KM / radius_km).powi(2) * inc_rad.cos();
+        Ok(Walker {
+            epoch_unix: def.epoch_unix.unwrap_or(default_epoch),
+            def,
+            enabled: true,
+            slots,
+            inc_rad,
+            radius_km,
+            mean_motion_rad_s,
+            node_rate_rad_s,
+        })
+    }
+
+    /// Parse one `WalkerDef` from JSON and build it.
+    pub fn from_json(json: &str, default_epoch: f64) -> Result<Self, String> {
+        let def: WalkerDef = serde_json::from_str(json).map_err(|e| format!("Walker JSON parse error: {e}"))?;
+        Walker::new(def, default_epoch)
+    }
+
+    /// The definition with its reference epoch filled in — rebuilding from
+    /// it reproduces the same slot phases whenever it is added.
+    pub fn resolved_def(&self) -> WalkerDef {
+        WalkerDef { epoch_unix: Some(self.epoch_unix), ..self.def.clone() }
+    }
+
+    /// Revolutions per day, for `OrbitClass::from_elements`.
+    pub fn mean_motion_rev_day(&self) -> f64 {
+        self.mean_motion_rad_s * 86400.0 / (2.0 * PI)
+    }
+
+    pub fn orbit_class(&self) -> OrbitClass {
+        OrbitClass::from_elements(self.mean_motion_rev_day(), self.def.inc_deg)
+    }
+
+    /// Definition plus derived fields, for `get_walkers`.
+    pub fn summary(&self) -> WalkerSummary<'_> {
+        WalkerSummary {
+            def: &self.def,
+            enabled: self.enabled,
+            satellites: self.slots.len(),
+            orbit_class: self.orbit_class(),
+            period_min: 2.0 * PI / self.mean_motion_rad_s / 60.0,
+        }
+    }
+
+    /// Display label of slot `i`: "LEO-PNT P03-S07" (1-based).
+    pub fn slot_label(&self, i: usize) -> String {
+        let s = &self.slots[i];
+        format!("{} P{:02}-S{:02}", self.def.name, s.plane + 1, s.slot + 1)
+    }
+
+    /// ECEF positions (km) of every occupied slot at `unix_s`, in slot order.
+    pub fn positions_ecef(&self, unix_s: f64) -> Vec<[f64; 3]> {
+        let gmst = coords::gmst_rad(unix_s);
+        self.slots.iter().map(|s| coords::teme_to_ecef(self.slot_eci(s, unix_s), gmst)).collect()
+    }
+
+    /// ECEF position (km) of slot `i` at `unix_s`.
+    pub fn slot_ecef(&self, i: usize, unix_s: f64) -> [f64; 3] {
+        coords::teme_to_ecef(self.slot_eci(&self.slots[i], unix_s), coords::gmst_rad(unix_s))
+    }
+
+    fn slot_eci(&self, s: &Slot, unix_s: f64) -> [f64; 3] {
+        let dt = unix_s - self.epoch_unix;
+        let (si, ci) = self.inc_rad.sin_cos();
+        let raan = s.raan0_rad + self.node_rate_rad_s * dt;
+        let u = s.u0_rad + self.mean_motion_rad_s * dt;
+        let (so, co) = raan.sin_cos();
+        let (su, cu) = u.sin_cos();
+        let r = self.radius_km;
+        [r * (co * cu - so * su * ci), r * (so * cu + co * su * ci), r * su * si]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn norm(v: [f64; 3]) -> f64 {
+        (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+    }
+
+    /// Galileo reference constellation 56°:24/3/1.
+    #[test]
+    fn test_parse_notation() {
+        assert_eq!(parse_notation("56:24/3/1"), Ok((56.0, 24, 3, 1)));
+        assert_eq!(parse_notation(" 87.9° : 240 / 12 / 1 "), Ok((87.9, 240, 12, 1)));
+        assert!(parse_notation("56:24/3").is_err());
+        assert!(parse_notation("56-24/3/1").is_err());
+        assert!(parse_notation("56:24/3/1/0").is_err());
+    }
+
+    /// Slot layout: node spacing 360°/p (Delta) or 180°/p (Star), and
+    /// inter-plane phase f·360°/t.
+    #[test]
+    fn test_slot_layout() {
+        let def = WalkerDef { notation: Some("56:24/3/1".into()), ..Default::default() };
+        let w = Walker::new(def, 0.0).unwrap();
+        assert_eq!(w.slots.len(), 24);
+        let s = w.slots[8]; // plane 1, slot 0
+        assert_eq!((s.plane, s.slot), (1, 0));
+        assert!((s.raan0_rad.to_degrees() - 120.0).abs() < 1e-9);
+        assert!((s.u0_rad.to_degrees() - 15.0).abs() < 1e-9);
+
+        let star = WalkerDef { notation: Some("86.4:66/6/2".into()), pattern: Pattern::Star, alt_km: 780.0, ..Default::default() };
+        let w = Walker::new(star, 0.0).unwrap();
+        assert!((w.slots[11].raan0_rad.to_degrees() - 30.0).abs() < 1e-9);
+        assert_eq!(w.orbit_class(), OrbitClass::Meo);
+    }
+
+    /// Degraded GPS: 55:24/6 with two planes removed leaves 16 satellites.
+    #[test]
+    fn test_disabled_planes_and_validation() {
+        let def = WalkerDef { disabled_planes: vec![1, 4], ..Default::default() };
+        let w = Walker::new(def, 0.0).unwrap();
+        assert_eq!(w.slots.len(), 16);
+        assert!(w.slots.iter().all(|s| s.plane != 1 && s.plane != 4));
+        assert_eq!(w.slot_label(4), "Walker P03-S01");
+
+        assert!(Walker::new(WalkerDef { total: 25, ..Default::default() }, 0.0).is_err());
+        assert!(Walker::new(WalkerDef { phasing: 6, ..Default::default() }, 0.0).is_err());
+        assert!(Walker::new(WalkerDef { alt_km: 10.0, ..Default::default() }, 0.0).is_err());
+        let huge = WalkerDef { notation: Some("56:4000000000/1/0".into()), ..Default::default() };
+        assert!(Walker::new(huge, 0.0).is_err());
+        assert!(Walker::new(WalkerDef { raan_offset_deg: f64::NAN, ..Default::default() }, 0.0).is_err());
+        assert!(Walker::from_json(r#"{"notation": "56:24/3/1", "alt_km": 23222}"#, 0.0).is_ok());
+    }
+
+    /// Positions stay on the orbit sphere, and a full period returns each
+    /// satellite to the same inertial place (ECEF rotates by Earth's spin).
+    #[test]
+    fn test_positions_on_orbit() {
+        let w = Walker::from_json(r#"{"notation": "55:24/6/1", "alt_km": 20200}"#, 0.0).unwrap();
+        let p0 = w.positions_ecef(0.0);
+        assert_eq!(p0.len(), 24);
+        for p in &p0 {
+            assert!((norm(*p) - 26571.0).abs() < 1e-6);
+        }
+        // Slot 0 of plane 0 starts on the ascending node: z = 0
+        assert!(p0[0][2].abs() < 1e-6);
+        // GPS-like orbit ≈ 2 rev/day
+        assert!((w.mean_motion_rev_day() - 2.0).abs() < 0.02);
+    }
+}
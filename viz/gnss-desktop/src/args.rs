@@ -0,0 +1,248 @@
+// args.rs — Command-line parsing for the `gnss-desktop` viewer
+//
+// Responsibilities:
+//   - Parse the data files to load (elements, borders, horizon, buildings,
+//     catalog, Walker definitions) and the initial observer / clock / view
+//   - Parse time arguments (ISO 8601, "YYYY-DDD.FFF", Unix seconds, "now")
+//
+// Hand-rolled like the `gnss` CLI; options accept both `--key value` and
+// `--key=value`. Everything is optional — with no elements file the viewer
+// shows the Phase-1 Keplerian constellations.
+
+use gnss_core::tles;
+
+pub const USAGE: &str = "\
+usage: gnss-desktop [options]
+
+data (local files):
+  -e, --elements FILE   Celestrak OMM JSON or two/three-line TLE text
+      --borders FILE    country border segments JSON (assets/data/borders-110m.json)
+      --horizon FILE    per-azimuth horizon profile (JSON or CSV)
+      --buildings FILE  building footprints GeoJSON for urban-canyon masking
+      --catalog FILE    PRN / SVN / health catalog JSON
+      --walker FILE     Walker constellation definition JSON (repeatable)
+
+view:
+      --lat DEG         observer latitude, degrees north (default 0)
+      --lon DEG         observer longitude, degrees east (default 0)
+  -t, --time T          start epoch: ISO 8601, YYYY-DDD.FFF, Unix seconds or now (default)
+      --warp X          time acceleration (default 120)
+      --mask DEG        elevation mask in degrees (default 5)
+      --camera AZ,EL,D  camera azimuth and elevation in degrees, distance in
+                        Earth radii (default 17,24,19)
+      --cone            show the elevation-mask cone
+      --size WxH        window or image size in pixels (default 1280x800)
+      --borderless      no window decorations, for wall displays
+
+offscreen:
+      --png FILE        render one frame without a window, write it as PNG, exit
+  -h, --help            show this help
+";
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    pub elements: Option<String>,
+    pub borders: Option<String>,
+    pub horizon: Option<String>,
+    pub buildings: Option<String>,
+    pub catalog: Option<String>,
+    pub walkers: Vec<String>,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    /// `None` = now.
+    pub time_unix: Option<f64>,
+    pub warp: f64,
+    pub mask_deg: f64,
+    /// Camera azimuth / elevation (degrees) and distance (Earth radii).
+    pub camera: Option<[f64; 3]>,
+    pub cone: bool,
+    pub size: (u32, u32),
+    pub borderless: bool,
+    /// Render one frame offscreen to this PNG instead of opening a window.
+    pub png: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            elements: None,
+            borders: None,
+            horizon: None,
+            buildings: None,
+            catalog: None,
+            walkers: Vec::new(),
+            lat_deg: 0.0,
+            lon_deg: 0.0,
+            time_unix: None,
+            warp: 120.0,
+            mask_deg: 5.0,
+            camera: None,
+            cone: false,
+            size: (1280, 800),
+            borderless: false,
+            png: None,
+        }
+    }
+}
+
+/// What the command line asked for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parsed {
+    Help,
+    Run(Box<Options>),
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+/// Parse the arguments after the program name.
+pub fn parse(args: &[String]) -> Result<Parsed, String> {
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        return Ok(Parsed::Help);
+    }
+    let mut opts = Options::default();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let (key, inline) = match arg.split_once('=') {
+            Some((k, v)) if k.starts_with("--") => (k, Some(v.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        // Flags take no value
+        match key {
+            "--borderless" => {
+                opts.borderless = true;
+                continue;
+            }
+            "--cone" => {
+                opts.cone = true;
+                continue;
+            }
+            _ => {}
+        }
+        let value = match inline {
+            Some(v) => v,
+            None => rest.next().cloned().ok_or_else(|| format!("{key} needs a value"))?,
+        };
+        let number = |v: &str| v.parse::<f64>().map_err(|_| format!("{key}: '{v}' is not a number"));
+        match key {
+            "-e" | "--elements" => opts.elements = Some(value),
+            "--borders" => opts.borders = Some(value),
+            "--horizon" => opts.horizon = Some(value),
+            "--buildings" => opts.buildings = Some(value),
+            "--catalog" => opts.catalog = Some(value),
+            "--walker" => opts.walkers.push(value),
+            "--lat" => opts.lat_deg = number(&value)?,
+            "--lon" => opts.lon_deg = number(&value)?,
+            "-t" | "--time" => opts.time_unix = parse_time(&value)?,
+            "--warp" => opts.warp = number(&value)?,
+            "--mask" => opts.mask_deg = number(&value)?,
+            "--camera" => opts.camera = Some(parse_camera(&value)?),
+            "--size" => opts.size = parse_size(&value)?,
+            "--png" => opts.png = Some(value),
+            _ => return Err(format!("unknown option '{key}'")),
+        }
+    }
+    if !(-90.0..=90.0).contains(&opts.lat_deg) {
+        return Err(format!("--lat {} is outside ±90°", opts.lat_deg));
+    }
+    if opts.warp.is_nan() || opts.warp < 0.0 {
+        return Err("--warp must not be negative".to_string());
+    }
+    Ok(Parsed::Run(Box::new(opts)))
+}
+
+/// Parse a time argument to Unix seconds; `Ok(None)` means now.
+pub fn parse_time(s: &str) -> Result<Option<f64>, String> {
+    if s == "now" {
+        return Ok(None);
+    }
+    if let Ok(unix) = s.parse::<f64>() {
+        return Ok(Some(unix));
+    }
+    tles::parse_epoch(s.trim_end_matches('Z'))
+        .map(|(_, _, unix)| Some(unix))
+        .ok_or_else(|| format!("cannot parse time '{s}'"))
+}
+
+/// "17,24,19" → azimuth °, elevation °, distance in Earth radii.
+fn parse_camera(s: &str) -> Result<[f64; 3], String> {
+    let parts = s
+        .split(',')
+        .map(|p| p.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| format!("--camera: expected AZ,EL,DIST, got '{s}'"))?;
+    match parts[..] {
+        [az, el, dist] if (-90.0..=90.0).contains(&el) && dist > 1.0 => Ok([az, el, dist]),
+        _ => Err(format!("--camera: expected AZ,EL,DIST with |EL| ≤ 90 and DIST > 1, got '{s}'")),
+    }
+}
+
+/// "1920x1080" → (1920, 1080).
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let (w, h) = s.split_once(['x', 'X']).ok_or_else(|| format!("--size: expected WxH, got '{s}'"))?;
+    match (w.parse::<u32>(), h.parse::<u32>()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(format!("--size: expected WxH, got '{s}'")),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    /// No arguments is a valid run with defaults; files and view options parse.
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(parse(&[]), Ok(Parsed::Run(Box::default())));
+        let Ok(Parsed::Run(o)) = parse(&args(
+            "-e gnss.tle --borders=b.json --walker a.json --walker b.json --lat 41.85 --lon -87.65 --size 1920x1080 --borderless",
+        )) else {
+            panic!("expected options");
+        };
+        assert_eq!(o.elements.as_deref(), Some("gnss.tle"));
+        assert_eq!(o.borders.as_deref(), Some("b.json"));
+        assert_eq!(o.walkers, vec!["a.json", "b.json"]);
+        assert_eq!((o.lat_deg, o.lon_deg), (41.85, -87.65));
+        assert_eq!(o.size, (1920, 1080));
+        assert!(o.borderless);
+
+        let Ok(Parsed::Run(o)) = parse(&args("--png out.png --camera 30,-10,4.5 --cone")) else {
+            panic!("expected options");
+        };
+        assert_eq!(o.png.as_deref(), Some("out.png"));
+        assert_eq!(o.camera, Some([30.0, -10.0, 4.5]));
+        assert!(o.cone && !o.borderless);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&args("--lat 95")).is_err());
+        assert!(parse(&args("--size 1920")).is_err());
+        assert!(parse(&args("--warp fast")).is_err());
+        assert!(parse(&args("--elements")).is_err());
+        assert!(parse(&args("--fullscreen")).is_err());
+        assert!(parse(&args("--camera 0,95,10")).is_err());
+        assert!(parse(&args("--camera 0,10")).is_err());
+        assert_eq!(parse(&args("-e x --help")), Ok(Parsed::Help));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("now"), Ok(None));
+        assert_eq!(parse_time("1970-01-02T00:00:00Z"), Ok(Some(86400.0)));
+        assert!(parse_time("soon").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+// controls.rs — Keyboard control surface for the desktop viewer
+//
+// Responsibilities:
+//   - Map key presses to viewer actions (clock, warp, constellation and
+//     overlay toggles)
+//   - Track the toggle state the browser HUD would hold and push changes
+//     through the same setters the HUD calls on the wasm module
+//
+// The scene itself never sees key events; it only reads `STATE`.
+
+use gnss_constellation as viewer;
+use gnss_core::tles;
+use three_d::{Event, Key};
+
+pub const KEYS: &str = "\
+keys: space pause · ↑/↓ warp ×2/÷2 · ←/→ ∓1 h · n now · 1-8 constellations · h highlight
+      v visible only · c elevation cone · b borders · r orbit rings · g ground tracks
+      e ECEF axes · i ECI axes · p point sprites · q/esc quit";
+
+const WARP_MIN: f64 = 1.0;
+const WARP_MAX: f64 = 1e9;
+const STEP_S: f64 = 3600.0;
+
+/// One user command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    TogglePause,
+    WarpUp,
+    WarpDown,
+    StepBack,
+    StepForward,
+    JumpToNow,
+    /// Constellation index, 0 = GPS … 7 = SBAS.
+    ToggleConstellation(usize),
+    CycleHighlight,
+    ToggleVisibleOnly,
+    ToggleCone,
+    ToggleBorders,
+    ToggleRings,
+    ToggleGroundTracks,
+    ToggleEcefAxes,
+    ToggleEciAxes,
+    TogglePointSprites,
+    Quit,
+}
+
+/// Key → action; `None` for unbound keys.
+pub fn action(key: Key) -> Option<Action> {
+    use Action::*;
+    let num = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8];
+    if let Some(i) = num.iter().position(|k| *k == key) {
+        return Some(ToggleConstellation(i));
+    }
+    Some(match key {
+        Key::Space => TogglePause,
+        Key::ArrowUp => WarpUp,
+        Key::ArrowDown => WarpDown,
+        Key::ArrowLeft => StepBack,
+        Key::ArrowRight => StepForward,
+        Key::N => JumpToNow,
+        Key::H => CycleHighlight,
+        Key::V => ToggleVisibleOnly,
+        Key::C => ToggleCone,
+        Key::B => ToggleBorders,
+        Key::R => ToggleRings,
+        Key::G => ToggleGroundTracks,
+        Key::E => ToggleEcefAxes,
+        Key::I => ToggleEciAxes,
+        Key::P => TogglePointSprites,
+        Key::Q | Key::Escape => Quit,
+        _ => return None,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Control state
+// ---------------------------------------------------------------------------
+
+/// The viewer settings the keyboard can change, mirrored here because the
+/// wasm API exposes setters only. Defaults match `GnssState`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Controls {
+    pub paused: bool,
+    pub warp: f64,
+    pub constellations: [bool; tles::CONSTELLATION_COUNT],
+    /// -1 = none, else a constellation index.
+    pub highlighted: i32,
+    pub visible_only: bool,
+    pub cone: bool,
+    pub borders: bool,
+    pub rings: bool,
+    pub ground_tracks: bool,
+    pub ecef_axes: bool,
+    pub eci_axes: bool,
+    pub point_sprites: bool,
+}
+
+impl Controls {
+    pub fn new(warp: f64) -> Self {
+        Controls {
+            paused: false,
+            warp,
+            constellations: [true; tles::CONSTELLATION_COUNT],
+            highlighted: -1,
+            visible_only: false,
+            cone: false,
+            borders: true,
+            rings: true,
+            ground_tracks: true,
+            ecef_axes: false,
+            eci_axes: false,
+            point_sprites: false,
+        }
+    }
+
+    /// Handle unhandled key presses in `events`. Returns a status line for
+    /// each applied action and whether the user asked to quit.
+    pub fn handle_events(&mut self, events: &mut [Event], now_unix: f64) -> (Vec<String>, bool) {
+        let mut status = Vec::new();
+        let mut quit = false;
+        for event in events.iter_mut() {
+            let Event::KeyPress { kind, handled, .. } = event else { continue };
+            if *handled {
+                continue;
+            }
+            let Some(a) = action(*kind) else { continue };
+            *handled = true;
+            if a == Action::Quit {
+                quit = true;
+            } else {
+                status.push(self.apply(a, now_unix));
+            }
+        }
+        (status, quit)
+    }
+
+    /// Update the mirrored state, push it to the viewer and describe it.
+    pub fn apply(&mut self, a: Action, now_unix: f64) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        match a {
+            Action::TogglePause => {
+                self.paused = !self.paused;
+                viewer::set_paused(self.paused);
+                (if self.paused { "paused" } else { "running" }).to_string()
+            }
+            Action::WarpUp | Action::WarpDown => {
+                let f = if a == Action::WarpUp { 2.0 } else { 0.5 };
+                self.warp = (self.warp * f).clamp(WARP_MIN, WARP_MAX);
+                viewer::set_time_warp(self.warp);
+                format!("warp {}×", self.warp)
+            }
+            Action::StepBack | Action::StepForward => {
+                let dt = if a == Action::StepBack { -STEP_S } else { STEP_S };
+                viewer::set_sim_epoch(viewer::get_sim_epoch() + dt);
+                format!("epoch {:+} h", dt / 3600.0)
+            }
+            Action::JumpToNow => {
+                viewer::set_sim_epoch(now_unix);
+                "epoch now".to_string()
+            }
+            Action::ToggleConstellation(i) => {
+                self.constellations[i] = !self.constellations[i];
+                viewer::toggle_constellation(i as u32, self.constellations[i]);
+                format!("{} {}", tles::CONSTELLATION_NAMES[i], on_off(self.constellations[i]))
+            }
+            Action::CycleHighlight => {
+                self.highlighted += 1;
+                if self.highlighted >= tles::CONSTELLATION_COUNT as i32 {
+                    self.highlighted = -1;
+                }
+                viewer::set_highlighted_constellation(self.highlighted);
+                match self.highlighted {
+                    -1 => "highlight off".to_string(),
+                    i => format!("highlight {}", tles::CONSTELLATION_NAMES[i as usize]),
+                }
+            }
+            Action::ToggleVisibleOnly => {
+                self.visible_only = !self.visible_only;
+                viewer::set_visible_only(self.visible_only);
+                format!("visible only {}", on_off(self.visible_only))
+            }
+            Action::ToggleCone => {
+                self.cone = !self.cone;
+                viewer::set_show_elev_cone(self.cone);
+                format!("elevation cone {}", on_off(self.cone))
+            }
+            Action::ToggleBorders => {
+                self.borders = !self.borders;
+                viewer::set_show_borders(self.borders);
+                format!("borders {}", on_off(self.borders))
+            }
+            Action::ToggleRings => {
+                self.rings = !self.rings;
+                viewer::set_show_inc_rings(self.rings);
+                format!("orbit rings {}", on_off(self.rings))
+            }
+            Action::ToggleGroundTracks => {
+                self.ground_tracks = !self.ground_tracks;
+                viewer::set_show_ground_tracks(self.ground_tracks);
+                format!("ground tracks {}", on_off(self.ground_tracks))
+            }
+            Action::ToggleEcefAxes => {
+                self.ecef_axes = !self.ecef_axes;
+                viewer::set_show_ecef_axes(self.ecef_axes);
+                format!("ECEF axes {}", on_off(self.ecef_axes))
+            }
+            Action::ToggleEciAxes => {
+                self.eci_axes = !self.eci_axes;
+                viewer::set_show_eci_axes(self.eci_axes);
+                format!("ECI axes {}", on_off(self.eci_axes))
+            }
+            Action::TogglePointSprites => {
+                self.point_sprites = !self.point_sprites;
+                viewer::set_point_sprites(self.point_sprites);
+                format!("point sprites {}", on_off(self.point_sprites))
+            }
+            Action::Quit => "quit".to_string(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Number keys map to constellations in index order.
+    #[test]
+    fn test_key_bindings() {
+        assert_eq!(action(Key::Num1), Some(Action::ToggleConstellation(0)));
+        assert_eq!(action(Key::Num8), Some(Action::ToggleConstellation(7)));
+        assert_eq!(action(Key::Num9), None);
+        assert_eq!(action(Key::Escape), Some(Action::Quit));
+        assert_eq!(action(Key::Z), None);
+    }
+
+    /// Warp doubles and halves within the HUD slider's range.
+    #[test]
+    fn test_warp_clamped() {
+        let mut c = Controls::new(2.0);
+        c.apply(Action::WarpDown, 0.0);
+        c.apply(Action::WarpDown, 0.0);
+        assert_eq!(c.warp, WARP_MIN);
+        c.warp = 6e8;
+        c.apply(Action::WarpUp, 0.0);
+        assert_eq!(c.warp, WARP_MAX);
+    }
+
+    /// Highlight cycles through every constellation and back to none.
+    #[test]
+    fn test_highlight_cycles() {
+        let mut c = Controls::new(120.0);
+        for i in 0..tles::CONSTELLATION_COUNT as i32 {
+            c.apply(Action::CycleHighlight, 0.0);
+            assert_eq!(c.highlighted, i);
+        }
+        assert_eq!(c.apply(Action::CycleHighlight, 0.0), "highlight off");
+    }
+
+    /// Clock keys move the viewer's sim epoch.
+    #[test]
+    fn test_clock_keys() {
+        let mut c = Controls::new(120.0);
+        c.apply(Action::JumpToNow, 1000.0);
+        assert_eq!(viewer::get_sim_epoch(), 1000.0);
+        c.apply(Action::StepForward, 0.0);
+        assert_eq!(viewer::get_sim_epoch(), 1000.0 + STEP_S);
+        assert_eq!(c.apply(Action::ToggleConstellation(2), 0.0), "Galileo off");
+    }
+}
@@ -0,0 +1,152 @@
+// main.rs — `gnss-desktop`: the constellation viewer in a native window
+//
+// Runs the same `gnss_constellation::scene::Scene` as the browser build in
+// a winit / OpenGL window, for ops-room wall displays:
+//
+//   gnss-desktop -e gnss.json --borders assets/data/borders-110m.json \
+//                --lat 41.85 --lon -87.65 --size 3840x2160 --borderless
+//
+// Data comes from local files instead of fetches; the keyboard replaces
+// the HUD (see controls.rs) and status changes are printed to stdout.
+// With `--png FILE` it renders one frame offscreen instead — no window or
+// display server needed, Mesa llvmpipe is enough:
+//
+//   gnss-desktop -e gnss.json --time 2024-01-15T12:00:00Z --cone --png sky.png
+
+mod args;
+mod controls;
+
+use std::process::ExitCode;
+
+use gnss_constellation as viewer;
+use three_d::{FrameOutput, Window, WindowSettings};
+use viewer::scene::CameraPose;
+
+use args::{Options, Parsed};
+use controls::Controls;
+
+fn main() -> ExitCode {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    let opts = match args::parse(&argv) {
+        Ok(Parsed::Run(opts)) => opts,
+        Ok(Parsed::Help) => {
+            print!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => return fail(&e),
+    };
+    if let Err(e) = load(&opts) {
+        return fail(&e);
+    }
+    let pose = opts
+        .camera
+        .map(|[az, el, dist]| CameraPose { az: az.to_radians(), el: el.to_radians(), dist })
+        .unwrap_or_default();
+
+    if let Some(path) = &opts.png {
+        let (w, h) = opts.size;
+        let written = viewer::headless::render_png(w, h, pose)
+            .and_then(|png| std::fs::write(path, png).map_err(|e| format!("{path}: {e}")));
+        return match written {
+            Ok(()) => {
+                println!("wrote {w}×{h} {path} at {}", utc(viewer::get_sim_epoch()));
+                ExitCode::SUCCESS
+            }
+            Err(e) => fail(&e),
+        };
+    }
+
+    let window = match Window::new(WindowSettings {
+        title: "gnss constellation".to_string(),
+        initial_size: Some(opts.size),
+        borderless: opts.borderless,
+        ..Default::default()
+    }) {
+        Ok(w) => w,
+        Err(e) => return fail(&format!("cannot open window: {e}")),
+    };
+
+    println!("{}", controls::KEYS);
+    let mut scene = viewer::scene::Scene::new(&window.gl(), window.viewport());
+    scene.pose = pose;
+    let mut controls = Controls::new(opts.warp);
+    controls.cone = opts.cone;
+    window.render_loop(move |mut frame_input| {
+        let (status, quit) = controls.handle_events(&mut frame_input.events, now_unix());
+        for line in status {
+            println!("{}  {line}", utc(viewer::get_sim_epoch()));
+        }
+        scene.handle_events(&mut frame_input.events);
+        scene.update(frame_input.elapsed_time, frame_input.viewport);
+        scene.render(&frame_input.screen());
+        FrameOutput { exit: quit, ..Default::default() }
+    });
+    ExitCode::SUCCESS
+}
+
+fn fail(e: &str) -> ExitCode {
+    eprintln!("gnss-desktop: {e}\nrun `gnss-desktop --help` for usage");
+    ExitCode::from(2)
+}
+
+/// Seed the viewer state from the options and the local data files.
+fn load(opts: &Options) -> Result<(), String> {
+    let read = |path: &str| std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"));
+
+    viewer::set_sim_epoch(opts.time_unix.unwrap_or_else(now_unix));
+    viewer::set_ground_location(opts.lat_deg, opts.lon_deg);
+    viewer::set_time_warp(opts.warp);
+    viewer::set_elev_mask(opts.mask_deg);
+    viewer::set_show_elev_cone(opts.cone);
+
+    if let Some(path) = &opts.elements {
+        let text = read(path)?;
+        // OMM JSON is an array; anything else is treated as TLE text
+        if text.trim_start().starts_with('[') {
+            viewer::inject_tles(&text);
+        } else {
+            viewer::inject_tle_text(&text);
+        }
+        match viewer::get_tle_count() {
+            0 => return Err(format!("{path}: no element sets found")),
+            n => println!("loaded {n} element sets from {path}"),
+        }
+    }
+    if let Some(path) = &opts.borders {
+        viewer::inject_borders(&read(path)?);
+    }
+    if let Some(path) = &opts.horizon {
+        if !viewer::inject_horizon_profile(&read(path)?) {
+            return Err(format!("{path}: invalid horizon profile"));
+        }
+    }
+    if let Some(path) = &opts.buildings {
+        if viewer::inject_buildings(&read(path)?) == 0 {
+            return Err(format!("{path}: no buildings found"));
+        }
+    }
+    if let Some(path) = &opts.catalog {
+        if viewer::inject_catalog(&read(path)?) == 0 {
+            return Err(format!("{path}: invalid catalog"));
+        }
+    }
+    for path in &opts.walkers {
+        if viewer::add_walker(&read(path)?) < 0 {
+            return Err(format!("{path}: invalid Walker definition"));
+        }
+    }
+    Ok(())
+}
+
+fn now_unix() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+fn utc(unix_s: f64) -> String {
+    chrono::DateTime::from_timestamp(unix_s.round() as i64, 0)
+        .map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_default()
+}